@@ -0,0 +1,10 @@
+-- Keys authenticating first-party event collection, sent as `api_secret`
+CREATE TABLE collect_keys (
+    id UUID PRIMARY KEY,
+    project_id UUID NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    name VARCHAR(255) NOT NULL,
+    secret VARCHAR(255) NOT NULL UNIQUE
+);
+
+-- Create index for faster lookups by project
+CREATE INDEX idx_collect_keys_project_id ON collect_keys(project_id);
@@ -0,0 +1,5 @@
+-- Bot and spam filtering settings of a project, defaults apply without a row
+CREATE TABLE traffic_filters (
+    project_id UUID PRIMARY KEY REFERENCES projects(id) ON DELETE CASCADE,
+    config JSONB NOT NULL
+);
@@ -0,0 +1,11 @@
+-- Reports run on a GA4 connector besides the standard one, e.g. funnels
+CREATE TABLE report_definitions (
+    id UUID PRIMARY KEY,
+    connector_id UUID NOT NULL REFERENCES connectors(id) ON DELETE CASCADE,
+    name VARCHAR(255) NOT NULL,
+    property_id VARCHAR(255) NOT NULL,
+    spec JSONB NOT NULL
+);
+
+-- Create index for faster lookups by connector
+CREATE INDEX idx_report_definitions_connector_id ON report_definitions(connector_id);
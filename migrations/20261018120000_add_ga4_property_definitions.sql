@@ -0,0 +1,52 @@
+-- Custom definitions, key events and data streams of the GA4 properties of a connector,
+-- refreshed from the Admin API on every sync. `name` is the Admin API resource name.
+CREATE TABLE ga4_custom_dimensions (
+    connector_id UUID NOT NULL REFERENCES connectors(id) ON DELETE CASCADE,
+    property_id VARCHAR(255) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    parameter_name VARCHAR(255) NOT NULL,
+    display_name VARCHAR(255) NOT NULL,
+    description TEXT NOT NULL,
+    scope VARCHAR(255) NOT NULL,
+    -- Display name before the latest rename
+    renamed_from VARCHAR(255),
+    -- No longer listed by the Admin API
+    archived BOOLEAN NOT NULL DEFAULT FALSE,
+    PRIMARY KEY (connector_id, name)
+);
+
+CREATE TABLE ga4_custom_metrics (
+    connector_id UUID NOT NULL REFERENCES connectors(id) ON DELETE CASCADE,
+    property_id VARCHAR(255) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    parameter_name VARCHAR(255) NOT NULL,
+    display_name VARCHAR(255) NOT NULL,
+    description TEXT NOT NULL,
+    measurement_unit VARCHAR(255) NOT NULL,
+    scope VARCHAR(255) NOT NULL,
+    renamed_from VARCHAR(255),
+    archived BOOLEAN NOT NULL DEFAULT FALSE,
+    PRIMARY KEY (connector_id, name)
+);
+
+CREATE TABLE ga4_key_events (
+    connector_id UUID NOT NULL REFERENCES connectors(id) ON DELETE CASCADE,
+    property_id VARCHAR(255) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    event_name VARCHAR(255) NOT NULL,
+    counting_method VARCHAR(255) NOT NULL,
+    custom BOOLEAN NOT NULL,
+    PRIMARY KEY (connector_id, name)
+);
+
+CREATE TABLE ga4_data_streams (
+    connector_id UUID NOT NULL REFERENCES connectors(id) ON DELETE CASCADE,
+    property_id VARCHAR(255) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    type VARCHAR(255) NOT NULL,
+    display_name VARCHAR(255) NOT NULL,
+    -- Measurement id of web streams, package name or bundle id of app streams
+    app_id VARCHAR(255),
+    default_uri VARCHAR(255),
+    PRIMARY KEY (connector_id, name)
+);
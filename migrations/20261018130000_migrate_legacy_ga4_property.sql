@@ -0,0 +1,27 @@
+-- GA4 connectors created before multi-property support kept their single selected property
+-- in `property_id`/`property_name`. Move it into `properties`, and keep it as
+-- `legacy_property_id` so the rows of their DuckDB store can be attributed to it.
+UPDATE connectors
+SET config = (config - 'property_id' - 'property_name') || jsonb_build_object(
+        'properties', jsonb_build_array(jsonb_build_object(
+            'property_id', legacy.property_id,
+            'property_name', COALESCE(config->>'property_name', legacy.property_id)
+        )),
+        'legacy_property_id', legacy.property_id
+    )
+FROM (
+    SELECT
+        id,
+        CASE
+            WHEN config->>'property_id' ~ '^[0-9]+$' THEN 'properties/' || (config->>'property_id')
+            ELSE config->>'property_id'
+        END AS property_id
+    FROM connectors
+    WHERE type = 'GA4' AND config->>'property_id' IS NOT NULL AND NOT config ? 'properties'
+) legacy
+WHERE connectors.id = legacy.id;
+
+-- Legacy connectors that never had a property selected
+UPDATE connectors
+SET config = config - 'property_id' - 'property_name'
+WHERE type = 'GA4' AND (config ? 'property_id' OR config ? 'property_name');
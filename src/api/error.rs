@@ -8,11 +8,30 @@ use serde::Serialize;
 #[derive(Debug, Serialize)]
 pub struct ErrorResponse {
     pub error: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
+}
+
+/// A validation failure on a single request field, addressed by its JSON path.
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
 }
 
 pub struct AppError {
     pub status: StatusCode,
     pub message: String,
+    pub fields: Vec<FieldError>,
 }
 
 impl AppError {
@@ -20,6 +39,7 @@ impl AppError {
         Self {
             status: StatusCode::INTERNAL_SERVER_ERROR,
             message: message.into(),
+            fields: Vec::new(),
         }
     }
 
@@ -27,6 +47,7 @@ impl AppError {
         Self {
             status: StatusCode::NOT_FOUND,
             message: message.into(),
+            fields: Vec::new(),
         }
     }
 
@@ -34,6 +55,7 @@ impl AppError {
         Self {
             status: StatusCode::BAD_REQUEST,
             message: message.into(),
+            fields: Vec::new(),
         }
     }
 
@@ -41,6 +63,15 @@ impl AppError {
         Self {
             status: StatusCode::CONFLICT,
             message: message.into(),
+            fields: Vec::new(),
+        }
+    }
+
+    pub fn validation(message: impl Into<String>, fields: Vec<FieldError>) -> Self {
+        Self {
+            status: StatusCode::UNPROCESSABLE_ENTITY,
+            message: message.into(),
+            fields,
         }
     }
 
@@ -48,13 +79,21 @@ impl AppError {
         Self {
             status: StatusCode::UNAUTHORIZED,
             message: message.into(),
+            fields: Vec::new(),
         }
     }
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        (self.status, Json(ErrorResponse { error: self.message })).into_response()
+        (
+            self.status,
+            Json(ErrorResponse {
+                error: self.message,
+                fields: self.fields,
+            }),
+        )
+            .into_response()
     }
 }
 
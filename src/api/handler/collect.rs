@@ -0,0 +1,182 @@
+use axum::{
+    extract::{ConnectInfo, DefaultBodyLimit, Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Json},
+    routing::{delete, get, post},
+    Router,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::LazyLock;
+use tracing::{debug, error, instrument, warn};
+use uuid::Uuid;
+
+use crate::api::error::AppError;
+use crate::models::collect_key::CollectKey;
+use crate::services::collect_service::{self, MeasurementPayload};
+use crate::services::crypto_service;
+use crate::services::traffic_filter_service::{self, FilterReason, Filtered};
+use crate::AppState;
+
+/// Measurement Protocol requests are capped at 130 KB.
+const MAX_PAYLOAD_BYTES: usize = 130 * 1024;
+
+/// Whether to take the client IP from `X-Forwarded-For`, when running behind a proxy.
+static TRUST_FORWARDED_FOR: LazyLock<bool> =
+    LazyLock::new(|| std::env::var("TRUST_FORWARDED_FOR").is_ok_and(|v| v == "true"));
+
+#[derive(Debug, Deserialize)]
+pub struct CollectParams {
+    /// Secret of one of the project's collect keys. `measurement_id` is ignored, events
+    /// are attributed to the key's project.
+    pub api_secret: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateCollectKeyRequest {
+    pub name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeleteMessage {
+    pub message: String,
+}
+
+/// The first `X-Forwarded-For` address when trusted, the peer address otherwise.
+fn client_ip(headers: &HeaderMap, peer: SocketAddr) -> IpAddr {
+    let forwarded = TRUST_FORWARDED_FOR
+        .then(|| headers.get("x-forwarded-for"))
+        .flatten()
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(',').next())
+        .and_then(|ip| ip.trim().parse().ok());
+
+    forwarded.unwrap_or(peer.ip())
+}
+
+/// Accepts a GA4 Measurement Protocol payload and queues its events.
+///
+/// Events are enriched with the location and device of the client, its IP is not kept,
+/// and run through the project's traffic filters.
+#[instrument(skip_all)]
+async fn collect(
+    State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Query(params): Query<CollectParams>,
+    Json(payload): Json<MeasurementPayload>,
+) -> Result<StatusCode, AppError> {
+    let secret = params
+        .api_secret
+        .ok_or_else(|| AppError::unauthorized("Missing api_secret"))?;
+
+    let key = state
+        .collect_key_repo
+        .find_by_secret(&secret)
+        .await?
+        .ok_or_else(|| {
+            warn!("Unknown api_secret");
+            AppError::unauthorized("Invalid api_secret")
+        })?;
+
+    let now = Utc::now();
+    let errors = collect_service::validate(&payload, now);
+    if !errors.is_empty() {
+        debug!(project_id = %key.project_id, error_count = errors.len(), "Rejected events");
+        return Err(AppError::validation("Invalid Measurement Protocol payload", errors));
+    }
+
+    let ip = client_ip(&headers, peer);
+    let user_agent = headers.get(header::USER_AGENT).and_then(|value| value.to_str().ok());
+    let enrichment = state.enricher.enrich(Some(ip), user_agent);
+
+    let filter = state.traffic_filter_repo.find_by_project(key.project_id).await?;
+    let request_reason = state.filter_lists.check_request(&filter, ip, user_agent);
+
+    let mut events = collect_service::collected_events(payload, key.id, now, enrichment);
+    for event in &mut events {
+        let reason = request_reason.or_else(|| {
+            traffic_filter_service::is_spam_referrer(&filter, event.page_referrer.as_deref())
+                .then_some(FilterReason::ReferrerSpam)
+        });
+        event.filtered = reason.map(|reason| Filtered {
+            reason,
+            mode: filter.mode,
+        });
+    }
+    debug!(project_id = %key.project_id, count = events.len(), "Collected events");
+
+    if state.event_buffer.push(key.project_id, events) {
+        let buffer = state.event_buffer.clone();
+        let project_id = key.project_id;
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = buffer.flush(project_id) {
+                error!(project_id = %project_id, error = %e, "Failed to flush events");
+            }
+        });
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn create_key(
+    State(state): State<AppState>,
+    Path(project_id): Path<Uuid>,
+    Json(payload): Json<CreateCollectKeyRequest>,
+) -> impl IntoResponse {
+    match state.project_repo.find_by_id(project_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Err(AppError::not_found("Project not found")),
+        Err(e) => return Err(AppError::from(e)),
+    }
+
+    let key = CollectKey {
+        id: Uuid::now_v7(),
+        project_id,
+        name: payload.name,
+        secret: crypto_service::random_token(24),
+    };
+
+    state
+        .collect_key_repo
+        .create(&key)
+        .await
+        .map(|k| (StatusCode::CREATED, Json(k)))
+        .map_err(AppError::from)
+}
+
+async fn list_keys(
+    State(state): State<AppState>,
+    Path(project_id): Path<Uuid>,
+) -> impl IntoResponse {
+    state
+        .collect_key_repo
+        .find_by_project(project_id)
+        .await
+        .map(Json)
+        .map_err(AppError::from)
+}
+
+async fn delete_key(
+    State(state): State<AppState>,
+    Path((project_id, id)): Path<(Uuid, Uuid)>,
+) -> impl IntoResponse {
+    match state.collect_key_repo.delete(project_id, id).await {
+        Ok(true) => Ok(Json(DeleteMessage {
+            message: "Collect key deleted successfully".to_string(),
+        })),
+        Ok(false) => Err(AppError::not_found("Collect key not found")),
+        Err(e) => Err(AppError::from(e)),
+    }
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/collect", post(collect).layer(DefaultBodyLimit::max(MAX_PAYLOAD_BYTES)))
+        // Path of the Measurement Protocol, so existing clients only change the host
+        .route("/mp/collect", post(collect).layer(DefaultBodyLimit::max(MAX_PAYLOAD_BYTES)))
+        .route("/projects/{project_id}/collect-keys", post(create_key))
+        .route("/projects/{project_id}/collect-keys", get(list_keys))
+        .route("/projects/{project_id}/collect-keys/{id}", delete(delete_key))
+}
@@ -6,10 +6,13 @@ use axum::{
     Router,
 };
 use serde::{Deserialize, Serialize};
+use tracing::{info, instrument};
 use uuid::Uuid;
 
 use crate::api::error::AppError;
 use crate::models::connector::{Connector, ConnectorType};
+use crate::services::storage_service::{self, TableSchema};
+use crate::sources::{AuthKind, Source, SyncRequest};
 use crate::AppState;
 
 #[derive(Debug, Deserialize)]
@@ -31,6 +34,75 @@ pub struct DeleteMessage {
     pub message: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct SelectStreamsRequest {
+    pub streams: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConnectorTypeInfo {
+    pub connector_type: ConnectorType,
+    pub display_name: &'static str,
+    pub auth: AuthKind,
+    pub config_schema: serde_json::Value,
+    pub storage_schema: Vec<TableSchema>,
+}
+
+/// Validates a config against the source of `connector_type`, filling in the `type` tag
+/// when the client left it out.
+fn validated_config(
+    state: &AppState,
+    connector_type: &ConnectorType,
+    mut config: serde_json::Value,
+) -> Result<serde_json::Value, AppError> {
+    let source = state.sources.get(connector_type).ok_or_else(|| {
+        AppError::bad_request(format!("No source registered for connector type {}", connector_type))
+    })?;
+
+    if let Some(object) = config.as_object_mut() {
+        object
+            .entry("type")
+            .or_insert_with(|| serde_json::to_value(connector_type).unwrap());
+    }
+
+    let errors = source.validate_config(&config);
+    if !errors.is_empty() {
+        return Err(AppError::validation(
+            format!("Invalid config for connector type {}", connector_type),
+            errors,
+        ));
+    }
+
+    source.prepare_config(config)
+}
+
+/// Selected GA4 properties of a config, absent ones counting as none.
+fn ga4_properties(config: &serde_json::Value) -> Vec<serde_json::Value> {
+    config
+        .get("properties")
+        .and_then(|p| p.as_array())
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// GA4 properties are checked against the Admin API when selected through
+/// `PUT .../streams`, so a raw config must leave them as they were.
+fn check_ga4_properties(
+    connector_type: &ConnectorType,
+    config: &serde_json::Value,
+    previous: Option<&serde_json::Value>,
+) -> Result<(), AppError> {
+    let previous = previous.map(ga4_properties).unwrap_or_default();
+
+    if *connector_type == ConnectorType::Ga4 && ga4_properties(config) != previous {
+        return Err(AppError::bad_request(
+            "GA4 properties must be selected through the connector streams endpoint",
+        ));
+    }
+
+    Ok(())
+}
+
 async fn create(
     State(state): State<AppState>,
     Path(project_id): Path<Uuid>,
@@ -42,12 +114,15 @@ async fn create(
         Err(e) => return Err(AppError::from(e)),
     }
 
+    let config = validated_config(&state, &payload.connector_type, payload.config)?;
+    check_ga4_properties(&payload.connector_type, &config, None)?;
+
     let connector = Connector {
         id: Uuid::now_v7(),
         project_id,
         name: payload.name,
         connector_type: payload.connector_type,
-        config: payload.config,
+        config,
     };
 
     state
@@ -108,12 +183,33 @@ async fn update(
         return Err(AppError::not_found("Connector not found in this project"));
     }
 
+    let connector_type = payload
+        .connector_type
+        .unwrap_or_else(|| existing.connector_type.clone());
+
+    // Stored data is laid out by the source, a new type could not read it back
+    if connector_type != existing.connector_type && storage_service::has_data(project_id, id) {
+        return Err(AppError::conflict(
+            "Cannot change the type of a connector that already has stored data",
+        ));
+    }
+
+    let previous_config = (connector_type == existing.connector_type).then(|| existing.config.clone());
+    let config = match payload.config {
+        Some(config) => validated_config(&state, &connector_type, config)?,
+        None if connector_type != existing.connector_type => {
+            validated_config(&state, &connector_type, existing.config)?
+        }
+        None => existing.config,
+    };
+    check_ga4_properties(&connector_type, &config, previous_config.as_ref())?;
+
     let updated = Connector {
         id: existing.id,
         project_id: existing.project_id,
         name: payload.name.unwrap_or(existing.name),
-        connector_type: payload.connector_type.unwrap_or(existing.connector_type),
-        config: payload.config.unwrap_or(existing.config),
+        connector_type,
+        config,
     };
 
     state
@@ -150,11 +246,115 @@ async fn delete_connector(
         .map_err(AppError::from)
 }
 
+async fn connector_types(State(state): State<AppState>) -> Json<Vec<ConnectorTypeInfo>> {
+    Json(
+        state
+            .sources
+            .all()
+            .into_iter()
+            .map(|source| ConnectorTypeInfo {
+                connector_type: source.connector_type(),
+                display_name: source.display_name(),
+                auth: source.auth_kind(),
+                config_schema: source.config_schema(),
+                storage_schema: source.storage_schema(),
+            })
+            .collect(),
+    )
+}
+
+async fn config_schema(
+    State(state): State<AppState>,
+    Path(connector_type): Path<ConnectorType>,
+) -> impl IntoResponse {
+    state
+        .sources
+        .get(&connector_type)
+        .map(|source| Json(source.config_schema()))
+        .ok_or_else(|| AppError::not_found("Unknown connector type"))
+}
+
+/// Loads a connector of the project together with the source handling its type.
+async fn find_with_source(
+    state: &AppState,
+    project_id: Uuid,
+    id: Uuid,
+) -> Result<(Connector, std::sync::Arc<dyn Source>), AppError> {
+    let connector = match state.connector_repo.find_by_id(id).await {
+        Ok(Some(c)) => c,
+        Ok(None) => return Err(AppError::not_found("Connector not found")),
+        Err(e) => return Err(AppError::from(e)),
+    };
+
+    if connector.project_id != project_id {
+        return Err(AppError::not_found("Connector not found in this project"));
+    }
+
+    let source = state.sources.get(&connector.connector_type).ok_or_else(|| {
+        AppError::bad_request(format!(
+            "No source registered for connector type {}",
+            connector.connector_type
+        ))
+    })?;
+
+    Ok((connector, source))
+}
+
+#[instrument(skip(state), fields(project_id = %project_id, connector_id = %id))]
+async fn streams(
+    State(state): State<AppState>,
+    Path((project_id, id)): Path<(Uuid, Uuid)>,
+) -> impl IntoResponse {
+    let (connector, source) = find_with_source(&state, project_id, id).await?;
+
+    source.discover(&state, &connector).await.map(Json)
+}
+
+#[instrument(skip(state, payload), fields(project_id = %project_id, connector_id = %id))]
+async fn select_streams(
+    State(state): State<AppState>,
+    Path((project_id, id)): Path<(Uuid, Uuid)>,
+    Json(payload): Json<SelectStreamsRequest>,
+) -> impl IntoResponse {
+    let (connector, source) = find_with_source(&state, project_id, id).await?;
+
+    info!(count = payload.streams.len(), "Selecting streams");
+    source
+        .select_streams(&state, &connector, payload.streams)
+        .await
+        .map(Json)
+}
+
+#[instrument(skip(state, payload), fields(project_id = %project_id, connector_id = %id))]
+async fn sync(
+    State(state): State<AppState>,
+    Path((project_id, id)): Path<(Uuid, Uuid)>,
+    Json(payload): Json<SyncRequest>,
+) -> impl IntoResponse {
+    let (connector, source) = find_with_source(&state, project_id, id).await?;
+
+    info!(connector_type = %connector.connector_type, "Starting sync");
+    let result = source.sync(&state, &connector, payload).await?;
+
+    info!(
+        record_count = result.record_count,
+        inserted = result.inserted_count,
+        updated = result.updated_count,
+        "Sync completed"
+    );
+    Ok::<_, AppError>(Json(result))
+}
+
 pub fn routes() -> Router<AppState> {
     Router::new()
+        .route("/connector-types", get(connector_types))
+        .route("/connector-types/{connector_type}/schema", get(config_schema))
         .route("/projects/{project_id}/connectors", post(create))
         .route("/projects/{project_id}/connectors", get(list))
         .route("/projects/{project_id}/connectors/{id}", get(get_by_id))
         .route("/projects/{project_id}/connectors/{id}", put(update))
         .route("/projects/{project_id}/connectors/{id}", delete(delete_connector))
+        .route("/projects/{project_id}/connectors/{id}/streams", get(streams))
+        .route("/projects/{project_id}/connectors/{id}/streams", put(select_streams))
+        .route("/projects/{project_id}/connectors/{id}/sync", post(sync))
 }
@@ -0,0 +1,156 @@
+use axum::{
+    extract::{DefaultBodyLimit, Multipart, Path, State},
+    response::Json,
+    routing::post,
+    Router,
+};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, error, info, instrument, warn};
+use uuid::Uuid;
+
+use crate::api::error::AppError;
+use crate::models::connector::{ConnectorDetails, ConnectorType};
+use crate::services::upload_service::{self, FileFormat, ImportMode, ImportResult};
+use crate::sources::parse_config;
+use crate::AppState;
+
+const MAX_UPLOAD_BYTES: usize = 200 * 1024 * 1024;
+
+/// Removes the temporary copies of an upload once the import is done.
+struct TempFiles(Vec<PathBuf>);
+
+impl Drop for TempFiles {
+    fn drop(&mut self) {
+        for path in &self.0 {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// Loads a CSV or Excel file into the connector's DuckDB store.
+///
+/// Multipart fields: `file` (required), `mode` (`append` or `replace`, defaults to `append`)
+/// and `column_types` (JSON object of column type overrides, on top of the connector's).
+#[instrument(skip(state, multipart), fields(project_id = %project_id, connector_id = %id))]
+async fn upload(
+    State(state): State<AppState>,
+    Path((project_id, id)): Path<(Uuid, Uuid)>,
+    mut multipart: Multipart,
+) -> Result<Json<ImportResult>, AppError> {
+    let connector = state
+        .connector_repo
+        .find_by_id(id)
+        .await?
+        .filter(|c| c.project_id == project_id)
+        .ok_or_else(|| AppError::not_found("Connector not found in this project"))?;
+
+    if connector.connector_type != ConnectorType::FileUpload {
+        warn!("Connector is not a file upload connector");
+        return Err(AppError::bad_request("Connector is not a file upload connector"));
+    }
+
+    let ConnectorDetails::FileUpload { table, mut column_types } = parse_config(&connector)? else {
+        return Err(AppError::internal("Invalid connector config"));
+    };
+
+    let mut mode = ImportMode::default();
+    let mut upload: Option<(String, PathBuf)> = None;
+    let mut temp_files = TempFiles(Vec::new());
+
+    while let Some(mut field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| AppError::bad_request(format!("Invalid multipart body: {}", e)))?
+    {
+        match field.name() {
+            Some("file") => {
+                let file_name = field.file_name().unwrap_or_default().to_string();
+                // Keep the extension, calamine picks the workbook reader from it
+                let extension = std::path::Path::new(&file_name)
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .unwrap_or("bin")
+                    .to_ascii_lowercase();
+                let path = std::env::temp_dir().join(format!("discoveo-upload-{}.{}", Uuid::now_v7(), extension));
+                temp_files.0.push(path.clone());
+
+                let mut file = tokio::fs::File::create(&path).await.map_err(|e| {
+                    error!(error = %e, "Failed to create temporary file");
+                    AppError::internal("Failed to store upload")
+                })?;
+
+                while let Some(chunk) = field
+                    .chunk()
+                    .await
+                    .map_err(|e| AppError::bad_request(format!("Failed to read upload: {}", e)))?
+                {
+                    file.write_all(&chunk).await.map_err(|e| {
+                        error!(error = %e, "Failed to write temporary file");
+                        AppError::internal("Failed to store upload")
+                    })?;
+                }
+                file.flush().await.map_err(|e| {
+                    error!(error = %e, "Failed to write temporary file");
+                    AppError::internal("Failed to store upload")
+                })?;
+
+                upload = Some((file_name, path));
+            }
+            Some("mode") => {
+                let text = field.text().await.unwrap_or_default();
+                mode = serde_json::from_value(serde_json::Value::String(text.trim().to_lowercase()))
+                    .map_err(|_| AppError::bad_request("mode must be 'append' or 'replace'"))?;
+            }
+            Some("column_types") => {
+                let text = field.text().await.unwrap_or_default();
+                let overrides: BTreeMap<String, String> = serde_json::from_str(&text)
+                    .map_err(|_| AppError::bad_request("column_types must be a JSON object of column to type"))?;
+                column_types.extend(overrides);
+            }
+            other => debug!(field = ?other, "Ignoring multipart field"),
+        }
+    }
+
+    let (file_name, path) = upload.ok_or_else(|| AppError::bad_request("Missing 'file' field"))?;
+
+    let format = FileFormat::from_file_name(&file_name).ok_or_else(|| {
+        warn!(file_name = %file_name, "Unsupported file type");
+        AppError::bad_request("Unsupported file type, expected a CSV or Excel file")
+    })?;
+
+    upload_service::validate(&table, &column_types).map_err(AppError::bad_request)?;
+
+    info!(file_name = %file_name, table = %table, mode = ?mode, "Importing upload");
+
+    // Parsing the workbook and loading up to MAX_UPLOAD_BYTES into DuckDB is blocking work
+    let result = tokio::task::spawn_blocking(move || {
+        let csv_path = match format {
+            FileFormat::Csv => path,
+            FileFormat::Excel => {
+                let csv_path = path.with_extension("converted.csv");
+                temp_files.0.push(csv_path.clone());
+                upload_service::excel_to_csv(&path, &csv_path)?;
+                csv_path
+            }
+        };
+
+        upload_service::import_csv(project_id, id, &table, &csv_path, &column_types, mode)
+    })
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Upload import task failed");
+        AppError::internal("Failed to import upload")
+    })?
+    .map_err(AppError::bad_request)?;
+
+    Ok(Json(result))
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route(
+        "/projects/{project_id}/connectors/{id}/files",
+        post(upload).layer(DefaultBodyLimit::max(MAX_UPLOAD_BYTES)),
+    )
+}
@@ -5,38 +5,40 @@ use axum::{
     Router,
 };
 use chrono::{DateTime, Utc};
-use oauth2::{AuthorizationCode, CsrfToken, Scope, TokenResponse, reqwest::async_http_client};
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 use tracing::{debug, error, info, instrument, warn};
 use uuid::Uuid;
 
-use crate::api::error::AppError;
-use crate::models::connector::{Connector, ConnectorDetails, ConnectorType};
-use crate::services::{ga4_service, storage_service};
+use crate::api::error::{AppError, FieldError};
+use crate::api::handler::google;
+use crate::api::oauth::{AuthParams, AuthUrlResponse};
+use crate::models::connector::{Connector, ConnectorDetails, ConnectorType, Ga4Property};
+use crate::models::ga4_definition::{CustomDimension, CustomMetric, DataStream, KeyEvent, ReportFieldIssue};
+use crate::services::ga4_service::{self, RealtimeReport};
+use crate::services::oauth_service;
+use crate::services::realtime_service::{self, RealtimeCache};
+use crate::services::storage_service;
+use crate::sources::ga4::{self as ga4_source, Ga4Source};
+use crate::sources::google::fresh_access_token;
+use crate::sources::{Source, SyncRequest, SyncResult, parse_config};
 use crate::AppState;
 
-#[derive(Debug, Deserialize)]
-pub struct OAuthCallbackParams {
-    pub code: String,
-    pub state: Option<String>,
-}
-
 #[derive(Debug, Serialize)]
-pub struct AuthUrlResponse {
-    pub auth_url: String,
-}
-
-#[derive(Debug, Serialize)]
-pub struct StatusResponse {
+pub struct ConnectorStatus {
+    pub connector_id: Uuid,
+    pub name: String,
     pub connected: bool,
-    pub connector_id: Option<Uuid>,
+    pub token_expired: bool,
+    pub has_refresh_token: bool,
     pub expires_at: Option<DateTime<Utc>>,
+    pub properties: Vec<Ga4Property>,
 }
 
 #[derive(Debug, Serialize)]
-pub struct CallbackResponse {
-    pub connector_id: Uuid,
-    pub message: String,
+pub struct ProjectStatusResponse {
+    pub connected: bool,
+    pub connectors: Vec<ConnectorStatus>,
 }
 
 #[derive(Debug, Serialize)]
@@ -52,356 +54,250 @@ pub struct GA4Property {
 }
 
 #[derive(Debug, Deserialize)]
-pub struct SelectPropertyRequest {
-    pub property_id: String,
-    pub property_name: String,
+pub struct SelectPropertiesRequest {
+    /// Either bare ids (`123456`) or resource names (`properties/123456`).
+    pub property_ids: Vec<String>,
 }
 
 #[derive(Debug, Serialize)]
-pub struct SelectPropertyResponse {
+pub struct SelectPropertiesResponse {
     pub connector_id: Uuid,
-    pub property_id: String,
-    pub property_name: String,
+    pub properties: Vec<Ga4Property>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct PullDataRequest {
     #[serde(default)]
     pub start_date: Option<chrono::NaiveDate>,
+    /// Subset of the selected properties to sync; all of them when omitted.
+    #[serde(default)]
+    pub property_ids: Option<Vec<String>>,
 }
 
-#[derive(Debug, Serialize)]
-pub struct PullDataResponse {
-    pub record_count: usize,
-    pub inserted_count: usize,
-    pub updated_count: usize,
+#[derive(Debug, Deserialize)]
+pub struct RealtimeQuery {
+    /// Defaults to the connector's first selected property.
+    #[serde(default)]
+    pub property_id: Option<String>,
+    /// Comma-separated realtime dimensions, none by default.
+    #[serde(default)]
+    pub dimensions: Option<String>,
+    /// Comma-separated realtime metrics, `activeUsers` by default.
+    #[serde(default)]
+    pub metrics: Option<String>,
+    /// Window up to now, 29 minutes by default.
+    #[serde(default)]
+    pub minutes_ago: Option<u32>,
+    /// Also appends freshly fetched rows to the `ga4_realtime_snapshots` table.
+    #[serde(default)]
+    pub snapshot: bool,
 }
 
 #[derive(Debug, Deserialize)]
-struct GoogleAccountSummariesResponse {
-    #[serde(rename = "accountSummaries", default)]
-    account_summaries: Vec<AccountSummary>,
+pub struct DefinitionsQuery {
+    /// Limits the response to one selected property; all of them when omitted.
+    #[serde(default)]
+    pub property_id: Option<String>,
+    /// Fetches the definitions from the Admin API first instead of serving the latest sync's.
+    #[serde(default)]
+    pub refresh: bool,
 }
 
-#[derive(Debug, Deserialize)]
-struct AccountSummary {
-    #[serde(rename = "propertySummaries", default)]
-    property_summaries: Vec<PropertySummary>,
+#[derive(Debug, Serialize)]
+pub struct ApiField<T> {
+    /// Name to use in report definitions, e.g. `customEvent:plan`.
+    pub api_name: String,
+    #[serde(flatten)]
+    pub definition: T,
 }
 
-#[derive(Debug, Deserialize)]
-struct PropertySummary {
-    #[serde(default)]
-    property: String,
-    #[serde(rename = "displayName", default)]
-    display_name: String,
-    #[serde(rename = "propertyType", default)]
-    property_type: Option<String>,
+#[derive(Debug, Serialize)]
+pub struct PropertyDefinitionsResponse {
+    pub custom_dimensions: Vec<ApiField<CustomDimension>>,
+    pub custom_metrics: Vec<ApiField<CustomMetric>>,
+    pub key_events: Vec<ApiField<KeyEvent>>,
+    pub data_streams: Vec<DataStream>,
+    /// Report definitions using renamed or archived custom dimensions and metrics.
+    pub report_issues: Vec<ReportFieldIssue>,
 }
 
-#[instrument(skip(state), fields(project_id = %project_id))]
+fn split_names(names: Option<&str>, default: &[&str]) -> Vec<String> {
+    match names {
+        Some(names) => names
+            .split(',')
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+            .map(str::to_string)
+            .collect(),
+        None => default.iter().map(|n| n.to_string()).collect(),
+    }
+}
+
+#[instrument(skip(state, params), fields(project_id = %project_id))]
 async fn auth(
     State(state): State<AppState>,
     Path(project_id): Path<Uuid>,
+    Query(params): Query<AuthParams>,
 ) -> impl IntoResponse {
     info!("Generating GA4 auth URL");
 
-    match state.project_repo.find_by_id(project_id).await {
-        Ok(Some(_)) => debug!("Project found"),
-        Ok(None) => {
-            warn!("Project not found");
-            return Err(AppError::not_found("Project not found"));
-        }
-        Err(e) => {
-            error!(error = %e, "Database error");
-            return Err(AppError::from(e));
-        }
-    }
-
-    let (auth_url, _) = state
-        .oauth_client
-        .authorize_url(|| CsrfToken::new(project_id.to_string()))
-        // Admin API (for listing properties)
-        .add_scope(Scope::new(
-            "https://www.googleapis.com/auth/analytics.readonly".to_string(),
-        ))
-        // Data API (for running reports)
-        .add_scope(Scope::new(
-            "https://www.googleapis.com/auth/analytics".to_string(),
-        ))
-        .add_extra_param("access_type", "offline")
-        .add_extra_param("prompt", "consent")
-        .url();
+    let auth_url = google::build_auth_url(&state, project_id, ConnectorType::Ga4, params).await?;
 
     debug!(auth_url = %auth_url, "Generated auth URL");
-    Ok(Json(AuthUrlResponse {
-        auth_url: auth_url.to_string(),
-    }))
+    Ok::<_, AppError>(Json(AuthUrlResponse { auth_url }))
 }
 
-#[instrument(skip(state), fields(project_id = %project_id))]
+#[instrument(skip(state, params), fields(project_id = %project_id))]
 async fn auth_redirect(
     State(state): State<AppState>,
     Path(project_id): Path<Uuid>,
+    Query(params): Query<AuthParams>,
 ) -> impl IntoResponse {
     info!("Redirecting to GA4 auth");
 
-    match state.project_repo.find_by_id(project_id).await {
-        Ok(Some(_)) => {}
-        Ok(None) => {
-            warn!("Project not found");
-            return Err(AppError::not_found("Project not found"));
-        }
-        Err(e) => {
-            error!(error = %e, "Database error");
-            return Err(AppError::from(e));
-        }
-    }
-
-    let (auth_url, _) = state
-        .oauth_client
-        .authorize_url(|| CsrfToken::new(project_id.to_string()))
-        // Admin API (for listing properties)
-        .add_scope(Scope::new(
-            "https://www.googleapis.com/auth/analytics.readonly".to_string(),
-        ))
-        // Data API (for running reports)
-        .add_scope(Scope::new(
-            "https://www.googleapis.com/auth/analytics".to_string(),
-        ))
-        .add_extra_param("access_type", "offline")
-        .add_extra_param("prompt", "consent")
-        .url();
+    let auth_url = google::build_auth_url(&state, project_id, ConnectorType::Ga4, params).await?;
 
     debug!(auth_url = %auth_url, "Redirecting to Google OAuth");
-    Ok(Redirect::temporary(auth_url.as_str()))
+    Ok::<_, AppError>(Redirect::temporary(&auth_url))
 }
 
-#[instrument(skip(state, params), fields(has_code = params.code.len() > 0, has_state = params.state.is_some()))]
-async fn callback(
-    State(state): State<AppState>,
-    Query(params): Query<OAuthCallbackParams>,
-) -> impl IntoResponse {
-    info!("Processing GA4 OAuth callback");
-
-    let project_id = params
-        .state
-        .as_ref()
-        .and_then(|s| Uuid::parse_str(s).ok())
-        .ok_or_else(|| {
-            error!("Invalid or missing state parameter");
-            AppError::bad_request("Invalid or missing state parameter (project_id)")
-        })?;
-
-    debug!(project_id = %project_id, "Extracted project_id from state");
-
-    match state.project_repo.find_by_id(project_id).await {
-        Ok(Some(_)) => debug!("Project verified"),
-        Ok(None) => {
-            warn!(project_id = %project_id, "Project not found");
-            return Err(AppError::not_found("Project not found"));
-        }
-        Err(e) => {
-            error!(error = %e, "Database error");
-            return Err(AppError::from(e));
-        }
-    }
-
-    debug!("Exchanging authorization code for tokens");
-    let token = state
-        .oauth_client
-        .exchange_code(AuthorizationCode::new(params.code))
-        .request_async(async_http_client)
+/// Loads a connector and checks that it is a GA4 connector of the given project.
+pub(crate) async fn find_ga4_connector(
+    state: &AppState,
+    project_id: Uuid,
+    connector_id: Uuid,
+) -> Result<Connector, AppError> {
+    let connector = state
+        .connector_repo
+        .find_by_id(connector_id)
         .await
         .map_err(|e| {
-            error!(error = %e, "Failed to exchange code for tokens");
-            AppError::bad_request(format!("Failed to exchange code: {}", e))
+            error!(error = %e, "Database error");
+            AppError::from(e)
+        })?
+        .ok_or_else(|| {
+            warn!("Connector not found");
+            AppError::not_found("Connector not found")
         })?;
 
-    let expires_at = token
-        .expires_in()
-        .map(|d| Utc::now() + chrono::Duration::seconds(d.as_secs() as i64));
+    if connector.project_id != project_id {
+        warn!("Connector belongs to different project");
+        return Err(AppError::not_found("Connector not found in this project"));
+    }
 
-    debug!(
-        expires_at = ?expires_at,
-        has_refresh_token = token.refresh_token().is_some(),
-        "Token exchange successful"
-    );
+    if connector.connector_type != ConnectorType::Ga4 {
+        warn!("Connector is not GA4 type");
+        return Err(AppError::bad_request("Connector is not a GA4 connector"));
+    }
 
-    let config = ConnectorDetails::Ga4 {
-        access_token: token.access_token().secret().clone(),
-        refresh_token: token.refresh_token().map(|rt| rt.secret().clone()),
-        expires_at,
-        token_type: "Bearer".to_string(),
-        property_id: None,
-        property_name: None,
-    };
+    Ok(connector)
+}
 
-    let connector = Connector {
-        id: Uuid::now_v7(),
-        project_id,
-        name: "GA4 Connector".to_string(),
-        connector_type: ConnectorType::Ga4,
-        config: serde_json::to_value(&config).unwrap(),
+fn connector_status(connector: &Connector) -> Result<ConnectorStatus, AppError> {
+    let config = parse_config(connector)?;
+    let tokens = config
+        .oauth_tokens()
+        .ok_or_else(|| AppError::internal("Invalid connector config"))?;
+
+    let expires_at = tokens.expires_at;
+    let token_expired = oauth_service::is_token_expired(expires_at);
+    let has_refresh_token = tokens.refresh_token.is_some();
+    let properties = match config {
+        ConnectorDetails::Ga4 { properties, .. } => properties,
+        _ => Vec::new(),
     };
 
-    debug!(connector_id = %connector.id, "Creating connector");
-    state
-        .connector_repo
-        .create(&connector)
-        .await
-        .map(|c| {
-            info!(connector_id = %c.id, "GA4 connector created successfully");
-            Json(CallbackResponse {
-                connector_id: c.id,
-                message: "Successfully connected to GA4".to_string(),
-            })
-        })
-        .map_err(|e| {
-            error!(error = %e, "Failed to create connector");
-            AppError::from(e)
-        })
+    Ok(ConnectorStatus {
+        connector_id: connector.id,
+        name: connector.name.clone(),
+        connected: !token_expired || has_refresh_token,
+        token_expired,
+        has_refresh_token,
+        expires_at,
+        properties,
+    })
 }
 
 #[instrument(skip(state), fields(project_id = %project_id))]
-async fn status(
+async fn project_status(
     State(state): State<AppState>,
     Path(project_id): Path<Uuid>,
-) -> Result<Json<StatusResponse>, AppError> {
-    debug!("Checking GA4 connection status");
+) -> Result<Json<ProjectStatusResponse>, AppError> {
+    debug!("Checking GA4 connection status for project");
 
     let connectors = state
         .connector_repo
         .find_by_project_and_type(project_id, ConnectorType::Ga4)
         .await?;
 
-    let Some(connector) = connectors.first() else {
-        debug!("No GA4 connector found");
-        return Ok(Json(StatusResponse {
-            connected: false,
-            connector_id: None,
-            expires_at: None,
-        }));
-    };
+    let connectors = connectors
+        .iter()
+        .map(connector_status)
+        .collect::<Result<Vec<_>, _>>()?;
 
-    let config: ConnectorDetails = serde_json::from_value(connector.config.clone())
-        .map_err(|_| AppError::internal("Invalid connector config"))?;
+    debug!(count = connectors.len(), "Status check complete");
+
+    Ok(Json(ProjectStatusResponse {
+        connected: connectors.iter().any(|c| c.connected),
+        connectors,
+    }))
+}
+
+#[instrument(skip(state), fields(project_id = %project_id, connector_id = %connector_id))]
+async fn status(
+    State(state): State<AppState>,
+    Path((project_id, connector_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<ConnectorStatus>, AppError> {
+    debug!("Checking GA4 connection status");
 
-    let ConnectorDetails::Ga4 { expires_at, .. } = config;
-    let is_expired = expires_at.map(|exp| exp < Utc::now()).unwrap_or(false);
+    let connector = find_ga4_connector(&state, project_id, connector_id).await?;
+    let status = connector_status(&connector)?;
 
     debug!(
-        connector_id = %connector.id,
-        is_expired = is_expired,
-        expires_at = ?expires_at,
+        token_expired = status.token_expired,
+        expires_at = ?status.expires_at,
         "Status check complete"
     );
 
-    Ok(Json(StatusResponse {
-        connected: !is_expired,
-        connector_id: Some(connector.id),
-        expires_at,
-    }))
+    Ok(Json(status))
 }
 
-#[instrument(skip(state), fields(project_id = %project_id))]
+#[instrument(skip(state), fields(project_id = %project_id, connector_id = %connector_id))]
 async fn disconnect(
     State(state): State<AppState>,
-    Path(project_id): Path<Uuid>,
+    Path((project_id, connector_id)): Path<(Uuid, Uuid)>,
 ) -> Result<Json<DisconnectResponse>, AppError> {
     info!("Disconnecting GA4");
 
-    let connectors = state
-        .connector_repo
-        .find_by_project_and_type(project_id, ConnectorType::Ga4)
-        .await?;
-
-    let Some(connector) = connectors.first() else {
-        debug!("No GA4 connector found");
-        return Ok(Json(DisconnectResponse {
-            message: "No GA4 connector found for this project".to_string(),
-        }));
-    };
+    let connector = find_ga4_connector(&state, project_id, connector_id).await?;
 
-    debug!(connector_id = %connector.id, "Deleting connector");
+    debug!("Deleting connector");
     state.connector_repo.delete(connector.id).await?;
 
-    info!(connector_id = %connector.id, "GA4 disconnected successfully");
+    info!("GA4 disconnected successfully");
     Ok(Json(DisconnectResponse {
         message: "Successfully disconnected from GA4".to_string(),
     }))
 }
 
-#[instrument(skip(state), fields(project_id = %project_id))]
+#[instrument(skip(state), fields(project_id = %project_id, connector_id = %connector_id))]
 async fn properties(
     State(state): State<AppState>,
-    Path(project_id): Path<Uuid>,
-) -> impl IntoResponse {
+    Path((project_id, connector_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<Vec<GA4Property>>, AppError> {
     info!("Fetching GA4 properties");
 
-    let connectors = state
-        .connector_repo
-        .find_by_project_and_type(project_id, ConnectorType::Ga4)
-        .await
-        .map_err(AppError::from)?;
-
-    let connector = connectors
-        .first()
-        .ok_or_else(|| {
-            warn!("No GA4 connector found");
-            AppError::unauthorized("Not connected to GA4. Please authenticate first.")
-        })?;
-
-    debug!(connector_id = %connector.id, "Found GA4 connector");
-
-    let config: ConnectorDetails = serde_json::from_value(connector.config.clone())
-        .map_err(|_| AppError::internal("Invalid connector config"))?;
-
-    let ConnectorDetails::Ga4 { access_token, expires_at, .. } = config;
-
-    if let Some(exp) = expires_at {
-        if exp < Utc::now() {
-            warn!(expires_at = ?exp, "Token expired");
-            return Err(AppError::unauthorized("Token expired. Please re-authenticate."));
-        }
-    }
-
-    debug!("Calling Google Analytics Admin API");
-    let client = reqwest::Client::new();
-    let response = client
-        .get("https://analyticsadmin.googleapis.com/v1beta/accountSummaries")
-        .bearer_auth(&access_token)
-        .send()
-        .await
-        .map_err(|e| {
-            error!(error = %e, "Failed to connect to GA4 API");
-            AppError::internal(format!("Failed to connect to GA4 API: {}", e))
-        })?;
+    let connector = find_ga4_connector(&state, project_id, connector_id).await?;
+    let access_token = fresh_access_token(&state, &connector).await?;
 
-    if !response.status().is_success() {
-        let status = response.status();
-        let error_text = response.text().await.unwrap_or_default();
-        error!(status = %status, error = %error_text, "GA4 API error");
-        return Err(AppError::internal(format!("GA4 API error: {} - {}", status, error_text)));
-    }
-
-    let data: GoogleAccountSummariesResponse = response
-        .json()
+    let properties: Vec<GA4Property> = ga4_service::list_properties(&access_token)
         .await
-        .map_err(|e| {
-            error!(error = %e, "Failed to parse GA4 response");
-            AppError::internal(format!("Failed to parse GA4 response: {}", e))
-        })?;
-
-    let properties: Vec<GA4Property> = data
-        .account_summaries
+        .map_err(AppError::internal)?
         .into_iter()
-        .flat_map(|account| {
-            account.property_summaries.into_iter().map(|prop| GA4Property {
-                name: prop.property,
-                display_name: prop.display_name,
-                property_type: prop.property_type,
-            })
+        .map(|prop| GA4Property {
+            name: prop.property,
+            display_name: prop.display_name,
+            property_type: prop.property_type,
         })
         .collect();
 
@@ -410,68 +306,46 @@ async fn properties(
 }
 
 #[instrument(skip(state, payload), fields(project_id = %project_id, connector_id = %connector_id))]
-async fn select_property(
+async fn select_properties(
     State(state): State<AppState>,
     Path((project_id, connector_id)): Path<(Uuid, Uuid)>,
-    Json(payload): Json<SelectPropertyRequest>,
-) -> impl IntoResponse {
-    info!(
-        property_id = %payload.property_id,
-        property_name = %payload.property_name,
-        "Selecting GA4 property"
-    );
-
-    let connector = match state.connector_repo.find_by_id(connector_id).await {
-        Ok(Some(c)) => c,
-        Ok(None) => {
-            warn!("Connector not found");
-            return Err(AppError::not_found("Connector not found"));
-        }
-        Err(e) => {
-            error!(error = %e, "Database error");
-            return Err(AppError::from(e));
-        }
-    };
+    Json(payload): Json<SelectPropertiesRequest>,
+) -> Result<Json<SelectPropertiesResponse>, AppError> {
+    info!(count = payload.property_ids.len(), "Selecting GA4 properties");
 
-    if connector.project_id != project_id {
-        warn!("Connector belongs to different project");
-        return Err(AppError::not_found("Connector not found in this project"));
-    }
-
-    let config: ConnectorDetails = serde_json::from_value(connector.config.clone())
-        .map_err(|_| AppError::internal("Invalid connector config"))?;
+    let connector = find_ga4_connector(&state, project_id, connector_id).await?;
 
-    let ConnectorDetails::Ga4 { access_token, refresh_token, expires_at, token_type, .. } = config;
+    let properties = Ga4Source
+        .select_properties(&state, &connector, &payload.property_ids)
+        .await?;
 
-    let updated_config = ConnectorDetails::Ga4 {
-        access_token,
-        refresh_token,
-        expires_at,
-        token_type,
-        property_id: Some(payload.property_id.clone()),
-        property_name: Some(payload.property_name.clone()),
-    };
+    Ok(Json(SelectPropertiesResponse {
+        connector_id,
+        properties,
+    }))
+}
 
-    let updated_connector = Connector {
-        id: connector.id,
-        project_id: connector.project_id,
-        name: connector.name,
-        connector_type: connector.connector_type,
-        config: serde_json::to_value(&updated_config).unwrap(),
-    };
+/// Accepts ids as `properties/<number>` or `<number>`, like property selection does.
+fn normalize_property_ids(raw_ids: Vec<String>) -> Result<Vec<String>, AppError> {
+    let mut errors = Vec::new();
+    let mut property_ids = Vec::with_capacity(raw_ids.len());
+
+    for (i, raw) in raw_ids.iter().enumerate() {
+        match ga4_service::normalize_property_id(raw) {
+            Some(property_id) => property_ids.push(property_id),
+            None => errors.push(FieldError::new(
+                format!("property_ids[{}]", i),
+                "expected 'properties/<number>' or '<number>'",
+            )),
+        }
+    }
 
-    state
-        .connector_repo
-        .update(&updated_connector)
-        .await
-        .map_err(AppError::from)?;
+    if !errors.is_empty() {
+        warn!(count = errors.len(), "Invalid property ids");
+        return Err(AppError::validation("Invalid property ids", errors));
+    }
 
-    info!("Property selected successfully");
-    Ok(Json(SelectPropertyResponse {
-        connector_id,
-        property_id: payload.property_id,
-        property_name: payload.property_name,
-    }))
+    Ok(property_ids)
 }
 
 #[instrument(skip(state, payload), fields(project_id = %project_id, connector_id = %connector_id))]
@@ -479,126 +353,190 @@ async fn pull_data(
     State(state): State<AppState>,
     Path((project_id, connector_id)): Path<(Uuid, Uuid)>,
     Json(payload): Json<PullDataRequest>,
-) -> impl IntoResponse {
+) -> Result<Json<SyncResult>, AppError> {
     info!("Starting GA4 data pull");
 
-    // Get the specific connector
-    let connector = state
-        .connector_repo
-        .find_by_id(connector_id)
-        .await
-        .map_err(AppError::from)?
-        .ok_or_else(|| {
-            warn!("Connector not found");
-            AppError::not_found("Connector not found")
-        })?;
+    let connector = find_ga4_connector(&state, project_id, connector_id).await?;
 
-    // Verify connector belongs to project
-    if connector.project_id != project_id {
-        warn!("Connector belongs to different project");
-        return Err(AppError::not_found("Connector not found in this project"));
-    }
-
-    // Verify it's a GA4 connector
-    if connector.connector_type != ConnectorType::Ga4 {
-        warn!("Connector is not GA4 type");
-        return Err(AppError::bad_request("Connector is not a GA4 connector"));
-    }
+    // Streams are compared to the stored `properties/<number>` ids
+    let streams = payload.property_ids.map(normalize_property_ids).transpose()?;
 
-    let config: ConnectorDetails = serde_json::from_value(connector.config.clone())
-        .map_err(|_| AppError::internal("Invalid connector config"))?;
+    let request = SyncRequest {
+        start_date: payload.start_date,
+        streams,
+    };
 
-    let ConnectorDetails::Ga4 {
-        mut access_token,
-        refresh_token,
-        expires_at,
-        token_type,
-        property_id,
-        property_name,
-    } = config;
-
-    // Check and refresh token if expired
-    if ga4_service::is_token_expired(expires_at) {
-        let refresh_token_str = refresh_token.as_ref().ok_or_else(|| {
-            error!("Token expired and no refresh token available");
-            AppError::unauthorized("Token expired and no refresh token. Please re-authenticate.")
-        })?;
+    let result = Ga4Source.sync(&state, &connector, request).await?;
 
-        let new_token = ga4_service::refresh_token(&state.oauth_client, refresh_token_str)
-            .await
-            .map_err(AppError::internal)?;
-
-        // Update connector in database
-        let updated_config = ConnectorDetails::Ga4 {
-            access_token: new_token.access_token.clone(),
-            refresh_token: new_token.refresh_token.or(refresh_token),
-            expires_at: new_token.expires_at,
-            token_type,
-            property_id: property_id.clone(),
-            property_name,
-        };
-
-        let updated_connector = Connector {
-            id: connector.id,
-            project_id: connector.project_id,
-            name: connector.name.clone(),
-            connector_type: connector.connector_type.clone(),
-            config: serde_json::to_value(&updated_config).unwrap(),
-        };
-
-        state
-            .connector_repo
-            .update(&updated_connector)
-            .await
-            .map_err(AppError::from)?;
-
-        info!("Connector updated with refreshed token");
-        access_token = new_token.access_token;
-    }
+    info!(
+        property_count = result.streams.len(),
+        record_count = result.record_count,
+        inserted = result.inserted_count,
+        updated = result.updated_count,
+        "Data pull completed"
+    );
 
-    // Check property is selected
-    let property_id = property_id.ok_or_else(|| {
-        warn!("No property selected");
-        AppError::bad_request("No GA4 property selected. Please select a property first.")
-    })?;
+    Ok(Json(result))
+}
 
-    // Calculate start date: use provided, or get incremental start date
-    let start_date = payload.start_date.unwrap_or_else(|| {
-        storage_service::get_incremental_start_date(project_id, connector_id)
-    });
+/// What is happening right now on a property, from the Realtime API.
+#[instrument(skip(state, query), fields(project_id = %project_id, connector_id = %connector_id))]
+async fn realtime(
+    State(state): State<AppState>,
+    Path((project_id, connector_id)): Path<(Uuid, Uuid)>,
+    Query(query): Query<RealtimeQuery>,
+) -> Result<Json<RealtimeReport>, AppError> {
+    let dimensions = split_names(query.dimensions.as_deref(), &[]);
+    let metrics = split_names(query.metrics.as_deref(), &["activeUsers"]);
+    let minutes_ago = query.minutes_ago.unwrap_or(29);
+
+    let errors = ga4_service::validate_realtime(&dimensions, &metrics, minutes_ago);
+    if !errors.is_empty() {
+        return Err(AppError::validation("Invalid realtime request", errors));
+    }
 
-    debug!(
-        property_id = %property_id,
-        start_date = %start_date,
-        "Pulling data for property"
-    );
+    let connector = find_ga4_connector(&state, project_id, connector_id).await?;
 
-    // Pull data from GA4
-    let pull_params = ga4_service::PullParams {
-        property_id,
-        access_token,
-        start_date: Some(start_date),
+    let selected = match parse_config(&connector)? {
+        ConnectorDetails::Ga4 { properties, .. } => properties,
+        _ => Vec::new(),
+    };
+    let property_id = match &query.property_id {
+        Some(raw) => {
+            let property_id = ga4_service::normalize_property_id(raw)
+                .ok_or_else(|| AppError::bad_request(format!("Invalid property id {}", raw)))?;
+            if !selected.iter().any(|p| p.property_id == property_id) {
+                return Err(AppError::bad_request(format!(
+                    "Property {} is not selected on this connector",
+                    property_id
+                )));
+            }
+            property_id
+        }
+        None => selected
+            .first()
+            .map(|p| p.property_id.clone())
+            .ok_or_else(|| AppError::bad_request("No GA4 property selected. Please select a property first."))?,
     };
 
-    let records = ga4_service::pull(pull_params)
+    let key = RealtimeCache::key(&property_id, &dimensions, &metrics, minutes_ago);
+    if let Some(report) = state.realtime_cache.get(connector_id, &key) {
+        debug!("Serving cached realtime report");
+        return Ok(Json(report.as_ref().clone()));
+    }
+
+    // Same token handling as syncs
+    let access_token = fresh_access_token(&state, &connector).await?;
+
+    let report = ga4_service::run_realtime_report(ga4_service::RealtimeParams {
+        property_id: &property_id,
+        access_token: &access_token,
+        dimensions: &dimensions,
+        metrics: &metrics,
+        minutes_ago,
+    })
+    .await
+    .map_err(AppError::internal)?;
+    let report = Arc::new(report);
+
+    state.realtime_cache.insert(connector_id, key, report.clone());
+
+    if query.snapshot {
+        let snapshot = report.clone();
+        let count = tokio::task::spawn_blocking(move || {
+            let conn = storage_service::open_store(project_id, connector_id, storage_service::GA4_STORE_FILE)?;
+            realtime_service::snapshot(&conn, &snapshot)
+        })
         .await
+        .map_err(|e| {
+            error!(error = %e, "Realtime snapshot task failed");
+            AppError::internal("Realtime snapshot task failed")
+        })?
         .map_err(AppError::internal)?;
+        debug!(count = count, "Realtime snapshot stored");
+    }
 
-    // Store with upsert
-    let result = storage_service::store(project_id, connector_id, records)
-        .map_err(AppError::internal)?;
+    Ok(Json(report.as_ref().clone()))
+}
 
-    info!(
-        record_count = result.record_count,
-        inserted = result.inserted_count,
-        updated = result.updated_count,
-        "Data pull completed"
-    );
+/// Custom dimensions and metrics, key events and data streams of the selected properties,
+/// for the report builder to offer custom fields by their display names.
+#[instrument(skip(state, query), fields(project_id = %project_id, connector_id = %connector_id))]
+async fn definitions(
+    State(state): State<AppState>,
+    Path((project_id, connector_id)): Path<(Uuid, Uuid)>,
+    Query(query): Query<DefinitionsQuery>,
+) -> Result<Json<PropertyDefinitionsResponse>, AppError> {
+    let connector = find_ga4_connector(&state, project_id, connector_id).await?;
 
-    Ok(Json(PullDataResponse {
-        record_count: result.record_count,
-        inserted_count: result.inserted_count,
-        updated_count: result.updated_count,
+    let selected = match parse_config(&connector)? {
+        ConnectorDetails::Ga4 { properties, .. } => properties,
+        _ => Vec::new(),
+    };
+    let property_ids: Vec<String> = match &query.property_id {
+        Some(raw) => {
+            let property_id = ga4_service::normalize_property_id(raw)
+                .ok_or_else(|| AppError::bad_request(format!("Invalid property id {}", raw)))?;
+            if !selected.iter().any(|p| p.property_id == property_id) {
+                return Err(AppError::bad_request(format!(
+                    "Property {} is not selected on this connector",
+                    property_id
+                )));
+            }
+            vec![property_id]
+        }
+        None => selected.into_iter().map(|p| p.property_id).collect(),
+    };
+
+    if query.refresh {
+        let access_token = fresh_access_token(&state, &connector).await?;
+        for property_id in &property_ids {
+            ga4_source::refresh_property_definitions(&state, &connector, &access_token, property_id).await?;
+        }
+    }
+
+    let mut stored = state.ga4_definition_repo.find_by_connector(connector_id).await?;
+    // Properties deselected since their last sync are left out
+    stored.custom_dimensions.retain(|d| property_ids.contains(&d.property_id));
+    stored.custom_metrics.retain(|m| property_ids.contains(&m.property_id));
+    stored.key_events.retain(|k| property_ids.contains(&k.property_id));
+    stored.data_streams.retain(|d| property_ids.contains(&d.property_id));
+
+    let report_issues = state
+        .report_definition_repo
+        .find_by_connector(connector_id)
+        .await?
+        .iter()
+        .flat_map(|definition| stored.report_issues(definition))
+        .collect();
+
+    Ok(Json(PropertyDefinitionsResponse {
+        custom_dimensions: stored
+            .custom_dimensions
+            .into_iter()
+            .map(|d| ApiField {
+                api_name: d.api_name(),
+                definition: d,
+            })
+            .collect(),
+        custom_metrics: stored
+            .custom_metrics
+            .into_iter()
+            .map(|m| ApiField {
+                api_name: m.api_name(),
+                definition: m,
+            })
+            .collect(),
+        key_events: stored
+            .key_events
+            .into_iter()
+            .map(|k| ApiField {
+                api_name: k.api_name(),
+                definition: k,
+            })
+            .collect(),
+        data_streams: stored.data_streams,
+        report_issues,
     }))
 }
 
@@ -606,10 +544,14 @@ pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/projects/{project_id}/connectors/ga4/auth", get(auth))
         .route("/projects/{project_id}/connectors/ga4/auth/redirect", get(auth_redirect))
-        .route("/projects/{project_id}/connectors/ga4/status", get(status))
-        .route("/projects/{project_id}/connectors/ga4/disconnect", get(disconnect))
-        .route("/projects/{project_id}/connectors/ga4/properties", get(properties))
-        .route("/projects/{project_id}/connectors/ga4/{connector_id}/property", put(select_property))
+        .route("/projects/{project_id}/connectors/ga4/status", get(project_status))
+        .route("/projects/{project_id}/connectors/ga4/{connector_id}/status", get(status))
+        .route("/projects/{project_id}/connectors/ga4/{connector_id}/disconnect", get(disconnect).post(disconnect))
+        .route("/projects/{project_id}/connectors/ga4/{connector_id}/properties", get(properties))
+        .route("/projects/{project_id}/connectors/ga4/{connector_id}/properties", put(select_properties))
         .route("/projects/{project_id}/connectors/ga4/{connector_id}/pull", post(pull_data))
-        .route("/connectors/ga4/callback", get(callback))
+        .route("/projects/{project_id}/connectors/ga4/{connector_id}/realtime", get(realtime))
+        .route("/projects/{project_id}/connectors/ga4/{connector_id}/definitions", get(definitions))
+        // Redirect URI registered with Google before other Google connectors existed
+        .route("/connectors/ga4/callback", get(google::callback))
 }
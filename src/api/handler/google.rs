@@ -0,0 +1,230 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::{IntoResponse, Json, Redirect, Response},
+    routing::get,
+    Router,
+};
+use oauth2::{CsrfToken, Scope};
+use std::sync::Arc;
+use tracing::{debug, error, info, instrument, warn};
+use uuid::Uuid;
+
+use crate::api::error::AppError;
+use crate::api::oauth::{AuthParams, AuthUrlResponse, OAuthCallbackParams, OAuthState, callback_response};
+use crate::models::connector::{Connector, ConnectorType, OAuthTokens};
+use crate::services::oauth_service;
+use crate::sources::{AuthKind, Source};
+use crate::AppState;
+
+/// Source of a connector type that authenticates through Google OAuth.
+fn google_source(
+    state: &AppState,
+    connector_type: &ConnectorType,
+) -> Result<(Arc<dyn Source>, Vec<&'static str>), AppError> {
+    let source = state.sources.get(connector_type).ok_or_else(|| {
+        AppError::bad_request(format!("No source registered for connector type {}", connector_type))
+    })?;
+
+    match source.auth_kind() {
+        AuthKind::GoogleOAuth { scopes } => Ok((source, scopes)),
+        _ => Err(AppError::bad_request(format!(
+            "Connector type {} does not use Google OAuth",
+            connector_type
+        ))),
+    }
+}
+
+/// Validates the project and `return_to`, then builds the Google consent URL.
+pub async fn build_auth_url(
+    state: &AppState,
+    project_id: Uuid,
+    connector_type: ConnectorType,
+    params: AuthParams,
+) -> Result<String, AppError> {
+    let (_, scopes) = google_source(state, &connector_type)?;
+
+    match state.project_repo.find_by_id(project_id).await {
+        Ok(Some(_)) => debug!("Project found"),
+        Ok(None) => {
+            warn!("Project not found");
+            return Err(AppError::not_found("Project not found"));
+        }
+        Err(e) => {
+            error!(error = %e, "Database error");
+            return Err(AppError::from(e));
+        }
+    }
+
+    if let Some(return_to) = &params.return_to
+        && state.return_url_allowlist.check(return_to).is_none()
+    {
+        warn!(return_to = %return_to, "return_to is not allowlisted");
+        return Err(AppError::bad_request("return_to is not an allowed URL"));
+    }
+
+    let oauth_state = OAuthState {
+        project_id,
+        connector_type,
+        return_to: params.return_to,
+    };
+
+    let (auth_url, _) = state
+        .oauth_client
+        .authorize_url(|| CsrfToken::new(oauth_state.encode()))
+        .add_scopes(scopes.iter().map(|scope| Scope::new(scope.to_string())))
+        .add_extra_param("access_type", "offline")
+        .add_extra_param("prompt", "consent")
+        .url();
+
+    Ok(auth_url.to_string())
+}
+
+#[instrument(skip(state, params), fields(project_id = %project_id, connector_type = %connector_type))]
+async fn auth(
+    State(state): State<AppState>,
+    Path((project_id, connector_type)): Path<(Uuid, ConnectorType)>,
+    Query(params): Query<AuthParams>,
+) -> impl IntoResponse {
+    info!("Generating Google auth URL");
+
+    let auth_url = build_auth_url(&state, project_id, connector_type, params).await?;
+
+    debug!(auth_url = %auth_url, "Generated auth URL");
+    Ok::<_, AppError>(Json(AuthUrlResponse { auth_url }))
+}
+
+#[instrument(skip(state, params), fields(project_id = %project_id, connector_type = %connector_type))]
+async fn auth_redirect(
+    State(state): State<AppState>,
+    Path((project_id, connector_type)): Path<(Uuid, ConnectorType)>,
+    Query(params): Query<AuthParams>,
+) -> impl IntoResponse {
+    info!("Redirecting to Google auth");
+
+    let auth_url = build_auth_url(&state, project_id, connector_type, params).await?;
+
+    debug!(auth_url = %auth_url, "Redirecting to Google OAuth");
+    Ok::<_, AppError>(Redirect::temporary(&auth_url))
+}
+
+#[instrument(skip(state, params), fields(has_code = params.code.is_some(), has_state = params.state.is_some()))]
+pub async fn callback(
+    State(state): State<AppState>,
+    Query(params): Query<OAuthCallbackParams>,
+) -> Response {
+    info!("Processing Google OAuth callback");
+
+    let Some(oauth_state) = params.state.as_deref().and_then(OAuthState::decode) else {
+        error!("Invalid or missing state parameter");
+        return AppError::bad_request("Invalid or missing state parameter").into_response();
+    };
+
+    debug!(
+        project_id = %oauth_state.project_id,
+        connector_type = %oauth_state.connector_type,
+        "Extracted project_id from state"
+    );
+
+    // The state comes back from the browser, so the target is checked again
+    let return_to = oauth_state
+        .return_to
+        .as_deref()
+        .and_then(|url| state.return_url_allowlist.check(url));
+
+    let result = create_connector_from_callback(&state, &oauth_state, params).await;
+
+    let display_name = state
+        .sources
+        .get(&oauth_state.connector_type)
+        .map(|s| s.display_name())
+        .unwrap_or("Google");
+
+    callback_response(return_to, result, format!("Successfully connected to {}", display_name))
+}
+
+/// Exchanges the authorization code and stores a new connector.
+/// Errors carry a short code that is passed back to the frontend.
+async fn create_connector_from_callback(
+    state: &AppState,
+    oauth_state: &OAuthState,
+    params: OAuthCallbackParams,
+) -> Result<Uuid, (&'static str, AppError)> {
+    let project_id = oauth_state.project_id;
+
+    if let Some(error) = params.error {
+        warn!(error = %error, "Authorization denied by user or Google");
+        return Err(("access_denied", AppError::bad_request(format!("Authorization failed: {}", error))));
+    }
+
+    let code = params.code.ok_or_else(|| {
+        error!("Missing authorization code");
+        ("missing_code", AppError::bad_request("Missing authorization code"))
+    })?;
+
+    let (source, _) = google_source(state, &oauth_state.connector_type)
+        .map_err(|e| ("unsupported_connector_type", e))?;
+
+    match state.project_repo.find_by_id(project_id).await {
+        Ok(Some(_)) => debug!("Project verified"),
+        Ok(None) => {
+            warn!(project_id = %project_id, "Project not found");
+            return Err(("project_not_found", AppError::not_found("Project not found")));
+        }
+        Err(e) => {
+            error!(error = %e, "Database error");
+            return Err(("internal_error", AppError::from(e)));
+        }
+    }
+
+    debug!("Exchanging authorization code for tokens");
+    let token = oauth_service::exchange_code(&state.oauth_client, code)
+        .await
+        .map_err(|e| ("token_exchange_failed", AppError::bad_request(e)))?;
+
+    debug!(
+        expires_at = ?token.expires_at,
+        has_refresh_token = token.refresh_token.is_some(),
+        "Token exchange successful"
+    );
+
+    let tokens = OAuthTokens {
+        access_token: token.access_token,
+        refresh_token: token.refresh_token,
+        expires_at: token.expires_at,
+        token_type: "Bearer".to_string(),
+    };
+
+    let config = source.oauth_config(tokens).ok_or_else(|| {
+        error!("Source has no OAuth config");
+        ("internal_error", AppError::internal("Connector type cannot be created through OAuth"))
+    })?;
+
+    let connector = Connector {
+        id: Uuid::now_v7(),
+        project_id,
+        name: format!("{} Connector", source.display_name()),
+        connector_type: oauth_state.connector_type.clone(),
+        config: serde_json::to_value(&config).unwrap(),
+    };
+
+    debug!(connector_id = %connector.id, "Creating connector");
+    state
+        .connector_repo
+        .create(&connector)
+        .await
+        .map(|c| {
+            info!(connector_id = %c.id, connector_type = %c.connector_type, "Connector created successfully");
+            c.id
+        })
+        .map_err(|e| {
+            error!(error = %e, "Failed to create connector");
+            ("internal_error", AppError::from(e))
+        })
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/projects/{project_id}/connectors/google/{connector_type}/auth", get(auth))
+        .route("/projects/{project_id}/connectors/google/{connector_type}/auth/redirect", get(auth_redirect))
+        .route("/connectors/google/callback", get(callback))
+}
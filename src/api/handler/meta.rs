@@ -0,0 +1,207 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::{IntoResponse, Json, Redirect, Response},
+    routing::get,
+    Router,
+};
+use oauth2::{CsrfToken, Scope};
+use tracing::{debug, error, info, instrument, warn};
+use uuid::Uuid;
+
+use crate::api::error::AppError;
+use crate::api::oauth::{AuthParams, AuthUrlResponse, OAuthCallbackParams, OAuthState, callback_response};
+use crate::models::connector::{Connector, ConnectorType, OAuthTokens};
+use crate::services::meta_ads_service::{self, MetaApp};
+use crate::services::oauth_service;
+use crate::sources::AuthKind;
+use crate::AppState;
+
+fn meta_app(state: &AppState) -> Result<&MetaApp, AppError> {
+    state.meta_app.as_deref().ok_or_else(|| {
+        warn!("Meta app is not configured");
+        AppError::bad_request("Meta Ads is not configured on this server")
+    })
+}
+
+/// Validates the project and `return_to`, then builds the Facebook Login dialog URL.
+async fn build_auth_url(state: &AppState, project_id: Uuid, params: AuthParams) -> Result<String, AppError> {
+    let app = meta_app(state)?;
+
+    let scopes = match state.sources.get(&ConnectorType::MetaAds).map(|s| s.auth_kind()) {
+        Some(AuthKind::MetaOAuth { scopes }) => scopes,
+        _ => return Err(AppError::internal("Meta Ads source is not registered")),
+    };
+
+    match state.project_repo.find_by_id(project_id).await {
+        Ok(Some(_)) => debug!("Project found"),
+        Ok(None) => {
+            warn!("Project not found");
+            return Err(AppError::not_found("Project not found"));
+        }
+        Err(e) => {
+            error!(error = %e, "Database error");
+            return Err(AppError::from(e));
+        }
+    }
+
+    if let Some(return_to) = &params.return_to
+        && state.return_url_allowlist.check(return_to).is_none()
+    {
+        warn!(return_to = %return_to, "return_to is not allowlisted");
+        return Err(AppError::bad_request("return_to is not an allowed URL"));
+    }
+
+    let oauth_state = OAuthState {
+        project_id,
+        connector_type: ConnectorType::MetaAds,
+        return_to: params.return_to,
+    };
+
+    let (auth_url, _) = app
+        .client
+        .authorize_url(|| CsrfToken::new(oauth_state.encode()))
+        .add_scopes(scopes.iter().map(|scope| Scope::new(scope.to_string())))
+        .url();
+
+    Ok(auth_url.to_string())
+}
+
+#[instrument(skip(state, params), fields(project_id = %project_id))]
+async fn auth(
+    State(state): State<AppState>,
+    Path(project_id): Path<Uuid>,
+    Query(params): Query<AuthParams>,
+) -> impl IntoResponse {
+    info!("Generating Meta auth URL");
+
+    let auth_url = build_auth_url(&state, project_id, params).await?;
+
+    debug!(auth_url = %auth_url, "Generated auth URL");
+    Ok::<_, AppError>(Json(AuthUrlResponse { auth_url }))
+}
+
+#[instrument(skip(state, params), fields(project_id = %project_id))]
+async fn auth_redirect(
+    State(state): State<AppState>,
+    Path(project_id): Path<Uuid>,
+    Query(params): Query<AuthParams>,
+) -> impl IntoResponse {
+    info!("Redirecting to Meta auth");
+
+    let auth_url = build_auth_url(&state, project_id, params).await?;
+
+    debug!(auth_url = %auth_url, "Redirecting to Facebook Login");
+    Ok::<_, AppError>(Redirect::temporary(&auth_url))
+}
+
+#[instrument(skip(state, params), fields(has_code = params.code.is_some(), has_state = params.state.is_some()))]
+async fn callback(
+    State(state): State<AppState>,
+    Query(params): Query<OAuthCallbackParams>,
+) -> Response {
+    info!("Processing Meta OAuth callback");
+
+    let Some(oauth_state) = params.state.as_deref().and_then(OAuthState::decode) else {
+        error!("Invalid or missing state parameter");
+        return AppError::bad_request("Invalid or missing state parameter").into_response();
+    };
+
+    if oauth_state.connector_type != ConnectorType::MetaAds {
+        error!(connector_type = %oauth_state.connector_type, "Unexpected connector type in state");
+        return AppError::bad_request("Invalid or missing state parameter").into_response();
+    }
+
+    // The state comes back from the browser, so the target is checked again
+    let return_to = oauth_state
+        .return_to
+        .as_deref()
+        .and_then(|url| state.return_url_allowlist.check(url));
+
+    let result = create_connector_from_callback(&state, oauth_state.project_id, params).await;
+
+    callback_response(return_to, result, "Successfully connected to Meta Ads".to_string())
+}
+
+/// Exchanges the authorization code for a long-lived token and stores a new connector.
+async fn create_connector_from_callback(
+    state: &AppState,
+    project_id: Uuid,
+    params: OAuthCallbackParams,
+) -> Result<Uuid, (&'static str, AppError)> {
+    if let Some(error) = params.error {
+        warn!(error = %error, "Authorization denied by user or Meta");
+        return Err(("access_denied", AppError::bad_request(format!("Authorization failed: {}", error))));
+    }
+
+    let code = params.code.ok_or_else(|| {
+        error!("Missing authorization code");
+        ("missing_code", AppError::bad_request("Missing authorization code"))
+    })?;
+
+    let app = meta_app(state).map_err(|e| ("internal_error", e))?;
+
+    match state.project_repo.find_by_id(project_id).await {
+        Ok(Some(_)) => debug!("Project verified"),
+        Ok(None) => {
+            warn!(project_id = %project_id, "Project not found");
+            return Err(("project_not_found", AppError::not_found("Project not found")));
+        }
+        Err(e) => {
+            error!(error = %e, "Database error");
+            return Err(("internal_error", AppError::from(e)));
+        }
+    }
+
+    debug!("Exchanging authorization code for tokens");
+    let short_lived = oauth_service::exchange_code(&app.client, code)
+        .await
+        .map_err(|e| ("token_exchange_failed", AppError::bad_request(e)))?;
+
+    let token = meta_ads_service::exchange_long_lived_token(app, &short_lived.access_token)
+        .await
+        .map_err(|e| ("token_exchange_failed", AppError::bad_request(e)))?;
+
+    debug!(expires_at = ?token.expires_at, "Token exchange successful");
+
+    let tokens = OAuthTokens {
+        access_token: token.access_token,
+        refresh_token: None,
+        expires_at: token.expires_at,
+        token_type: "Bearer".to_string(),
+    };
+
+    let config = state
+        .sources
+        .get(&ConnectorType::MetaAds)
+        .and_then(|source| source.oauth_config(tokens))
+        .ok_or_else(|| ("internal_error", AppError::internal("Meta Ads source is not registered")))?;
+
+    let connector = Connector::new(
+        Uuid::now_v7(),
+        project_id,
+        "Meta Ads Connector".to_string(),
+        ConnectorType::MetaAds,
+        config,
+    );
+
+    debug!(connector_id = %connector.id, "Creating connector");
+    state
+        .connector_repo
+        .create(&connector)
+        .await
+        .map(|c| {
+            info!(connector_id = %c.id, "Meta Ads connector created successfully");
+            c.id
+        })
+        .map_err(|e| {
+            error!(error = %e, "Failed to create connector");
+            ("internal_error", AppError::from(e))
+        })
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/projects/{project_id}/connectors/meta/auth", get(auth))
+        .route("/projects/{project_id}/connectors/meta/auth/redirect", get(auth_redirect))
+        .route("/connectors/meta/callback", get(callback))
+}
@@ -1,3 +1,10 @@
+pub mod collect;
 pub mod connector;
+pub mod file;
 pub mod ga4;
+pub mod google;
+pub mod meta;
 pub mod project;
+pub mod report;
+pub mod session;
+pub mod traffic_filter;
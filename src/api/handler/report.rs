@@ -0,0 +1,290 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+    routing::{delete, get, post, put},
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, instrument, warn};
+use uuid::Uuid;
+
+use crate::api::error::{AppError, FieldError};
+use crate::api::handler::ga4::find_ga4_connector;
+use crate::models::connector::{Connector, ConnectorDetails, Ga4Property};
+use crate::models::report_definition::{ReportDefinition, ReportSpec};
+use crate::services::{ga4_service, report_service, storage_service};
+use crate::sources::ga4::{self as ga4_source, run_reports};
+use crate::sources::google::fresh_access_token;
+use crate::sources::{StreamSyncResult, parse_config};
+use crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct ReportDefinitionRequest {
+    pub name: String,
+    /// Either a bare id (`123456`) or a resource name (`properties/123456`).
+    pub property_id: String,
+    pub spec: ReportSpec,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RunReportRequest {
+    /// Defaults to an incremental run from the latest stored date.
+    #[serde(default)]
+    pub start_date: Option<chrono::NaiveDate>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeleteReportResponse {
+    pub message: String,
+}
+
+fn selected_properties(connector: &Connector) -> Result<Vec<Ga4Property>, AppError> {
+    match parse_config(connector)? {
+        ConnectorDetails::Ga4 { properties, .. } => Ok(properties),
+        _ => Err(AppError::bad_request("Connector is not a GA4 connector")),
+    }
+}
+
+/// Drops the stored results of a definition, off the async runtime.
+async fn delete_results(project_id: Uuid, connector_id: Uuid, report_id: Uuid) -> Result<usize, AppError> {
+    tokio::task::spawn_blocking(move || {
+        if !storage_service::has_data(project_id, connector_id) {
+            return Ok(0);
+        }
+        let conn = storage_service::open_store(project_id, connector_id, storage_service::GA4_STORE_FILE)?;
+        report_service::delete_results(&conn, report_id)
+    })
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Report results task failed");
+        AppError::internal("Report results task failed")
+    })?
+    .map_err(AppError::internal)
+}
+
+/// Checks the request, then its fields against the property's metadata, and returns the
+/// normalized property id.
+async fn validate(
+    state: &AppState,
+    connector: &Connector,
+    payload: &ReportDefinitionRequest,
+) -> Result<String, AppError> {
+    let mut errors = Vec::new();
+
+    if payload.name.trim().is_empty() {
+        errors.push(FieldError::new("name", "must not be empty"));
+    }
+
+    let property_id = ga4_service::normalize_property_id(&payload.property_id);
+    match &property_id {
+        None => errors.push(FieldError::new("property_id", "must be 'properties/<number>' or '<number>'")),
+        Some(property_id) => {
+            if !selected_properties(connector)?
+                .iter()
+                .any(|p| &p.property_id == property_id)
+            {
+                errors.push(FieldError::new("property_id", "is not selected on this connector"));
+            }
+        }
+    }
+
+    match &payload.spec {
+        ReportSpec::Funnel(spec) => errors.extend(ga4_service::validate_funnel(spec)),
+        ReportSpec::Standard(spec) => errors.extend(ga4_service::validate_report(&spec.dimensions, &spec.metrics, None)),
+        ReportSpec::Pivot(spec) => errors.extend(ga4_service::validate_report(
+            &spec.dimensions,
+            &spec.metrics,
+            Some(&spec.pivots),
+        )),
+    }
+
+    let (dimension_filter, metric_filter) = payload.spec.filters();
+    if let Some(filter) = dimension_filter {
+        errors.extend(ga4_service::validate_filter(filter, "spec.dimension_filter", false));
+    }
+    if let Some(filter) = metric_filter {
+        errors.extend(ga4_service::validate_filter(filter, "spec.metric_filter", true));
+    }
+
+    let property_id = match property_id {
+        Some(property_id) if errors.is_empty() => property_id,
+        _ => return Err(AppError::validation("Invalid report definition", errors)),
+    };
+
+    let access_token = fresh_access_token(state, connector).await?;
+    let metadata = ga4_service::get_metadata(&access_token, &property_id)
+        .await
+        .map_err(AppError::internal)?;
+
+    let errors = ga4_service::validate_against_metadata(&payload.spec, &metadata);
+    if !errors.is_empty() {
+        return Err(AppError::validation("Invalid report definition", errors));
+    }
+
+    Ok(property_id)
+}
+
+async fn find_definition(
+    state: &AppState,
+    connector_id: Uuid,
+    report_id: Uuid,
+) -> Result<ReportDefinition, AppError> {
+    match state.report_definition_repo.find_by_id(report_id).await? {
+        Some(definition) if definition.connector_id == connector_id => Ok(definition),
+        _ => {
+            warn!("Report definition not found");
+            Err(AppError::not_found("Report definition not found"))
+        }
+    }
+}
+
+async fn list_reports(
+    State(state): State<AppState>,
+    Path((project_id, connector_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<Vec<ReportDefinition>>, AppError> {
+    find_ga4_connector(&state, project_id, connector_id).await?;
+
+    let definitions = state.report_definition_repo.find_by_connector(connector_id).await?;
+    Ok(Json(definitions))
+}
+
+#[instrument(skip(state, payload), fields(project_id = %project_id, connector_id = %connector_id))]
+async fn create_report(
+    State(state): State<AppState>,
+    Path((project_id, connector_id)): Path<(Uuid, Uuid)>,
+    Json(payload): Json<ReportDefinitionRequest>,
+) -> Result<(StatusCode, Json<ReportDefinition>), AppError> {
+    let connector = find_ga4_connector(&state, project_id, connector_id).await?;
+    let property_id = validate(&state, &connector, &payload).await?;
+
+    let definition = state
+        .report_definition_repo
+        .create(&ReportDefinition {
+            id: Uuid::now_v7(),
+            connector_id,
+            name: payload.name.trim().to_string(),
+            property_id,
+            spec: payload.spec,
+        })
+        .await?;
+
+    info!(report_id = %definition.id, "Report definition created");
+    Ok((StatusCode::CREATED, Json(definition)))
+}
+
+async fn get_report(
+    State(state): State<AppState>,
+    Path((project_id, connector_id, report_id)): Path<(Uuid, Uuid, Uuid)>,
+) -> Result<Json<ReportDefinition>, AppError> {
+    find_ga4_connector(&state, project_id, connector_id).await?;
+
+    let definition = find_definition(&state, connector_id, report_id).await?;
+    Ok(Json(definition))
+}
+
+/// Replaces a definition. Results stored under a different spec or property are dropped,
+/// those of other filters are kept under their own fingerprint.
+#[instrument(skip(state, payload), fields(project_id = %project_id, connector_id = %connector_id, report_id = %report_id))]
+async fn update_report(
+    State(state): State<AppState>,
+    Path((project_id, connector_id, report_id)): Path<(Uuid, Uuid, Uuid)>,
+    Json(payload): Json<ReportDefinitionRequest>,
+) -> Result<Json<ReportDefinition>, AppError> {
+    let connector = find_ga4_connector(&state, project_id, connector_id).await?;
+    let existing = find_definition(&state, connector_id, report_id).await?;
+    let property_id = validate(&state, &connector, &payload).await?;
+
+    let changed =
+        existing.spec.without_filters() != payload.spec.without_filters() || existing.property_id != property_id;
+
+    let definition = state
+        .report_definition_repo
+        .update(&ReportDefinition {
+            id: report_id,
+            connector_id,
+            name: payload.name.trim().to_string(),
+            property_id,
+            spec: payload.spec,
+        })
+        .await?;
+
+    if changed {
+        delete_results(project_id, connector_id, report_id).await?;
+    }
+
+    info!(results_dropped = changed, "Report definition updated");
+    Ok(Json(definition))
+}
+
+#[instrument(skip(state), fields(project_id = %project_id, connector_id = %connector_id, report_id = %report_id))]
+async fn delete_report(
+    State(state): State<AppState>,
+    Path((project_id, connector_id, report_id)): Path<(Uuid, Uuid, Uuid)>,
+) -> Result<Json<DeleteReportResponse>, AppError> {
+    find_ga4_connector(&state, project_id, connector_id).await?;
+
+    if !state.report_definition_repo.delete(connector_id, report_id).await? {
+        return Err(AppError::not_found("Report definition not found"));
+    }
+
+    delete_results(project_id, connector_id, report_id).await?;
+
+    info!("Report definition deleted");
+    Ok(Json(DeleteReportResponse {
+        message: "Report definition deleted successfully".to_string(),
+    }))
+}
+
+/// Runs a definition now, outside of connector syncs.
+#[instrument(skip(state, payload), fields(project_id = %project_id, connector_id = %connector_id, report_id = %report_id))]
+async fn run(
+    State(state): State<AppState>,
+    Path((project_id, connector_id, report_id)): Path<(Uuid, Uuid, Uuid)>,
+    Json(payload): Json<RunReportRequest>,
+) -> Result<Json<StreamSyncResult>, AppError> {
+    let connector = find_ga4_connector(&state, project_id, connector_id).await?;
+    let definition = find_definition(&state, connector_id, report_id).await?;
+    let properties = selected_properties(&connector)?;
+
+    // Funnels take one request per day
+    if let (ReportSpec::Funnel(_), Some(start_date)) = (&definition.spec, payload.start_date) {
+        let time_zone = properties
+            .iter()
+            .find(|p| p.property_id == definition.property_id)
+            .and_then(|p| p.time_zone.as_deref());
+        if start_date < ga4_source::earliest_funnel_date(ga4_service::today_in(time_zone)) {
+            return Err(AppError::validation(
+                "Invalid report run",
+                vec![FieldError::new(
+                    "start_date",
+                    format!("funnel reports go back at most {} days", ga4_source::MAX_FUNNEL_DAYS),
+                )],
+            ));
+        }
+    }
+
+    let access_token = fresh_access_token(&state, &connector).await?;
+    let result = run_reports(
+        &connector,
+        &access_token,
+        std::slice::from_ref(&definition),
+        &properties,
+        payload.start_date,
+    )
+    .await
+    .pop()
+    .unwrap_or_else(|| Err(AppError::internal("Report definition was not run")))?;
+
+    Ok(Json(result))
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/projects/{project_id}/connectors/ga4/{connector_id}/reports", get(list_reports))
+        .route("/projects/{project_id}/connectors/ga4/{connector_id}/reports", post(create_report))
+        .route("/projects/{project_id}/connectors/ga4/{connector_id}/reports/{report_id}", get(get_report))
+        .route("/projects/{project_id}/connectors/ga4/{connector_id}/reports/{report_id}", put(update_report))
+        .route("/projects/{project_id}/connectors/ga4/{connector_id}/reports/{report_id}", delete(delete_report))
+        .route("/projects/{project_id}/connectors/ga4/{connector_id}/reports/{report_id}/run", post(run))
+}
@@ -0,0 +1,125 @@
+use axum::{
+    extract::{Path, State},
+    response::Json,
+    routing::post,
+    Router,
+};
+use serde::Deserialize;
+use tracing::{error, instrument, warn};
+use uuid::Uuid;
+
+use crate::api::error::AppError;
+use crate::models::connector::ConnectorType;
+use crate::services::session_service::{self, SessionizeResult};
+use crate::services::{collect_service, database_service, storage_service, upload_service};
+use crate::AppState;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SessionizeRequest {
+    /// Inactivity timeout, 30 minutes by default as in GA4.
+    #[serde(default)]
+    pub timeout_minutes: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConnectorSessionizeRequest {
+    /// Event table of the connector's store.
+    pub table: String,
+    #[serde(default)]
+    pub timeout_minutes: Option<u32>,
+}
+
+fn timeout_minutes(timeout_minutes: Option<u32>) -> Result<u32, AppError> {
+    match timeout_minutes.unwrap_or(session_service::DEFAULT_TIMEOUT_MINUTES) {
+        minutes @ 1..=session_service::MAX_TIMEOUT_MINUTES => Ok(minutes),
+        _ => Err(AppError::bad_request(format!(
+            "timeout_minutes must be between 1 and {}",
+            session_service::MAX_TIMEOUT_MINUTES
+        ))),
+    }
+}
+
+/// Runs a sessionize pass off the async runtime, it holds the store for its whole duration.
+async fn run_blocking(
+    work: impl FnOnce() -> Result<SessionizeResult, AppError> + Send + 'static,
+) -> Result<SessionizeResult, AppError> {
+    tokio::task::spawn_blocking(work).await.map_err(|e| {
+        error!(error = %e, "Sessionize task failed");
+        AppError::internal("Sessionize task failed")
+    })?
+}
+
+/// Builds sessions from the events collected for the project.
+#[instrument(skip(state, payload), fields(project_id = %project_id))]
+async fn sessionize_events(
+    State(state): State<AppState>,
+    Path(project_id): Path<Uuid>,
+    payload: Option<Json<SessionizeRequest>>,
+) -> Result<Json<SessionizeResult>, AppError> {
+    let Json(payload) = payload.unwrap_or_default();
+    let timeout = timeout_minutes(payload.timeout_minutes)?;
+
+    match state.project_repo.find_by_id(project_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Err(AppError::not_found("Project not found")),
+        Err(e) => return Err(AppError::from(e)),
+    }
+
+    let buffer = state.event_buffer.clone();
+    run_blocking(move || {
+        // Queued events belong to the sessions being built
+        buffer.flush(project_id).map_err(AppError::internal)?;
+
+        buffer
+            .with_store(project_id, |conn| {
+                session_service::sessionize(conn, collect_service::EVENTS_TABLE, timeout)
+            })
+            .map_err(AppError::bad_request)
+    })
+    .await
+    .map(Json)
+}
+
+/// Builds sessions from an event table loaded into a file upload or database connector.
+#[instrument(skip(state, payload), fields(project_id = %project_id, connector_id = %id))]
+async fn sessionize_connector(
+    State(state): State<AppState>,
+    Path((project_id, id)): Path<(Uuid, Uuid)>,
+    Json(payload): Json<ConnectorSessionizeRequest>,
+) -> Result<Json<SessionizeResult>, AppError> {
+    let timeout = timeout_minutes(payload.timeout_minutes)?;
+
+    let connector = state
+        .connector_repo
+        .find_by_id(id)
+        .await?
+        .filter(|c| c.project_id == project_id)
+        .ok_or_else(|| AppError::not_found("Connector not found in this project"))?;
+
+    let store_file = match connector.connector_type {
+        ConnectorType::FileUpload => upload_service::STORE_FILE,
+        ConnectorType::Database => database_service::STORE_FILE,
+        _ => {
+            warn!(connector_type = %connector.connector_type, "Connector has no event tables");
+            return Err(AppError::bad_request(
+                "Sessions can only be built from file upload or database connectors",
+            ));
+        }
+    };
+
+    run_blocking(move || {
+        let conn = storage_service::open_store(project_id, id, store_file).map_err(AppError::internal)?;
+        session_service::sessionize(&conn, &payload.table, timeout).map_err(AppError::bad_request)
+    })
+    .await
+    .map(Json)
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/projects/{project_id}/sessions", post(sessionize_events))
+        .route(
+            "/projects/{project_id}/connectors/{id}/sessions",
+            post(sessionize_connector),
+        )
+}
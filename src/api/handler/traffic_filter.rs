@@ -0,0 +1,169 @@
+use axum::{
+    extract::{Path, State},
+    response::Json,
+    routing::{get, put},
+    Router,
+};
+use serde::Serialize;
+use tracing::{error, info, instrument, warn};
+use uuid::Uuid;
+
+use crate::api::error::{AppError, FieldError};
+use crate::models::connector::ConnectorType;
+use crate::models::traffic_filter::TrafficFilter;
+use crate::services::storage_service;
+use crate::services::traffic_filter_service::{self, FilteredEventCount, FilteredGa4Count};
+use crate::AppState;
+
+#[derive(Debug, Serialize)]
+pub struct Ga4FilterStats {
+    pub connector_id: Uuid,
+    pub rows: Vec<FilteredGa4Count>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FilterStats {
+    /// Collected events caught by the filters.
+    pub events: Vec<FilteredEventCount>,
+    /// GA4 rows matching a spam combo, per GA4 connector.
+    pub ga4: Vec<Ga4FilterStats>,
+}
+
+async fn ensure_project(state: &AppState, project_id: Uuid) -> Result<(), AppError> {
+    match state.project_repo.find_by_id(project_id).await {
+        Ok(Some(_)) => Ok(()),
+        Ok(None) => Err(AppError::not_found("Project not found")),
+        Err(e) => Err(AppError::from(e)),
+    }
+}
+
+/// Runs store work off the async runtime, DuckDB and the event store lock both block.
+async fn run_blocking<T: Send + 'static>(
+    work: impl FnOnce() -> Result<T, AppError> + Send + 'static,
+) -> Result<T, AppError> {
+    tokio::task::spawn_blocking(work).await.map_err(|e| {
+        error!(error = %e, "Traffic filter task failed");
+        AppError::internal("Traffic filter task failed")
+    })?
+}
+
+fn validate(filter: &TrafficFilter) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+
+    for (i, domain) in filter.spam_referrers.iter().enumerate() {
+        if domain.trim().is_empty() || domain.contains('/') {
+            errors.push(FieldError::new(format!("spam_referrers[{}]", i), "must be a domain name"));
+        }
+    }
+    for (i, combo) in filter.ga4_spam_combos.iter().enumerate() {
+        if combo.is_empty() {
+            errors.push(FieldError::new(
+                format!("ga4_spam_combos[{}]", i),
+                "must set browser, operating_system or screen_resolution",
+            ));
+        }
+    }
+
+    errors
+}
+
+async fn get_filter(
+    State(state): State<AppState>,
+    Path(project_id): Path<Uuid>,
+) -> Result<Json<TrafficFilter>, AppError> {
+    ensure_project(&state, project_id).await?;
+
+    let filter = state.traffic_filter_repo.find_by_project(project_id).await?;
+    Ok(Json(filter))
+}
+
+/// Replaces the project's filters and rebuilds the filter views of its GA4 stores.
+#[instrument(skip(state, payload), fields(project_id = %project_id))]
+async fn update_filter(
+    State(state): State<AppState>,
+    Path(project_id): Path<Uuid>,
+    Json(payload): Json<TrafficFilter>,
+) -> Result<Json<TrafficFilter>, AppError> {
+    ensure_project(&state, project_id).await?;
+
+    let errors = validate(&payload);
+    if !errors.is_empty() {
+        return Err(AppError::validation("Invalid traffic filter", errors));
+    }
+
+    let filter = state.traffic_filter_repo.save(project_id, &payload).await?;
+
+    let connectors = state
+        .connector_repo
+        .find_by_project_and_type(project_id, ConnectorType::Ga4)
+        .await?;
+
+    let views_filter = filter.clone();
+    run_blocking(move || {
+        for connector in connectors {
+            if !storage_service::has_data(project_id, connector.id) {
+                continue;
+            }
+            let conn = storage_service::open_store(project_id, connector.id, storage_service::GA4_STORE_FILE)
+                .map_err(AppError::internal)?;
+            traffic_filter_service::create_ga4_views(&conn, &views_filter).map_err(AppError::internal)?;
+        }
+        Ok(())
+    })
+    .await?;
+
+    info!(mode = %filter.mode, "Traffic filter updated");
+    Ok(Json(filter))
+}
+
+/// How much traffic the filters caught, and why.
+#[instrument(skip(state), fields(project_id = %project_id))]
+async fn stats(
+    State(state): State<AppState>,
+    Path(project_id): Path<Uuid>,
+) -> Result<Json<FilterStats>, AppError> {
+    ensure_project(&state, project_id).await?;
+
+    let buffer = state.event_buffer.clone();
+    let events = run_blocking(move || {
+        buffer
+            .with_store(project_id, traffic_filter_service::filtered_event_counts)
+            .map_err(AppError::internal)
+    })
+    .await?;
+
+    let connectors = state
+        .connector_repo
+        .find_by_project_and_type(project_id, ConnectorType::Ga4)
+        .await?;
+
+    let ga4 = run_blocking(move || {
+        let mut ga4 = Vec::new();
+        for connector in connectors {
+            if !storage_service::has_data(project_id, connector.id) {
+                continue;
+            }
+            let conn = storage_service::open_store(project_id, connector.id, storage_service::GA4_STORE_FILE)
+                .map_err(AppError::internal)?;
+
+            match traffic_filter_service::filtered_ga4_counts(&conn) {
+                Ok(rows) => ga4.push(Ga4FilterStats {
+                    connector_id: connector.id,
+                    rows,
+                }),
+                Err(e) => warn!(connector_id = %connector.id, error = %e, "Failed to count GA4 spam rows"),
+            }
+        }
+        Ok(ga4)
+    })
+    .await?;
+
+    Ok(Json(FilterStats { events, ga4 }))
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/projects/{project_id}/traffic-filter", get(get_filter))
+        .route("/projects/{project_id}/traffic-filter", put(update_filter))
+        .route("/projects/{project_id}/traffic-filter/stats", get(stats))
+}
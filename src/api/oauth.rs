@@ -0,0 +1,111 @@
+use axum::response::{IntoResponse, Json, Redirect, Response};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::api::error::AppError;
+use crate::models::connector::ConnectorType;
+
+#[derive(Debug, Deserialize)]
+pub struct AuthParams {
+    /// Frontend URL to send the user back to once the OAuth flow completes.
+    pub return_to: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OAuthCallbackParams {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuthUrlResponse {
+    pub auth_url: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CallbackResponse {
+    pub connector_id: Uuid,
+    pub message: String,
+}
+
+/// Data carried through the OAuth round trip in the `state` parameter.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthState {
+    pub project_id: Uuid,
+    /// Type of the connector to create once the user has consented.
+    #[serde(default = "default_connector_type")]
+    pub connector_type: ConnectorType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub return_to: Option<String>,
+}
+
+impl OAuthState {
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    pub fn decode(raw: &str) -> Option<Self> {
+        let bytes = URL_SAFE_NO_PAD.decode(raw).ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+}
+
+/// States issued before other Google connectors existed were always for GA4.
+fn default_connector_type() -> ConnectorType {
+    ConnectorType::Ga4
+}
+
+/// Origins the OAuth callback is allowed to redirect back to.
+#[derive(Debug, Clone, Default)]
+pub struct ReturnUrlAllowlist {
+    origins: Vec<String>,
+}
+
+impl ReturnUrlAllowlist {
+    /// Parses a comma-separated list of origins, e.g. `http://localhost:5173,https://app.example.com`.
+    pub fn parse(raw: &str) -> Self {
+        let origins = raw
+            .split(',')
+            .filter_map(|origin| Url::parse(origin.trim()).ok())
+            .map(|url| url.origin().ascii_serialization())
+            .collect();
+
+        Self { origins }
+    }
+
+    /// Returns the parsed URL when its origin is allowlisted.
+    pub fn check(&self, return_to: &str) -> Option<Url> {
+        let url = Url::parse(return_to).ok()?;
+        let origin = url.origin().ascii_serialization();
+
+        self.origins.contains(&origin).then_some(url)
+    }
+}
+
+/// Sends the browser back to `return_to` with the outcome of the callback, or answers
+/// with JSON when there is no allowlisted target. Errors carry a short code for the frontend.
+pub fn callback_response(
+    return_to: Option<Url>,
+    result: Result<Uuid, (&'static str, AppError)>,
+    message: String,
+) -> Response {
+    match (result, return_to) {
+        (Ok(connector_id), Some(mut url)) => {
+            url.query_pairs_mut()
+                .append_pair("status", "connected")
+                .append_pair("connector_id", &connector_id.to_string());
+            Redirect::to(url.as_str()).into_response()
+        }
+        (Ok(connector_id), None) => Json(CallbackResponse { connector_id, message }).into_response(),
+        (Err((code, _)), Some(mut url)) => {
+            url.query_pairs_mut()
+                .append_pair("status", "error")
+                .append_pair("error", code);
+            Redirect::to(url.as_str()).into_response()
+        }
+        (Err((_, e)), None) => e.into_response(),
+    }
+}
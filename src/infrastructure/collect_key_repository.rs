@@ -0,0 +1,93 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::collect_key::CollectKey;
+
+#[derive(Clone)]
+pub struct CollectKeyRepository {
+    pool: PgPool,
+}
+
+impl CollectKeyRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(&self, key: &CollectKey) -> Result<CollectKey, sqlx::Error> {
+        let row = sqlx::query!(
+            r#"
+            INSERT INTO collect_keys (id, project_id, name, secret)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id, project_id, name, secret
+            "#,
+            key.id,
+            key.project_id,
+            key.name,
+            key.secret,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(CollectKey {
+            id: row.id,
+            project_id: row.project_id,
+            name: row.name,
+            secret: row.secret,
+        })
+    }
+
+    pub async fn find_by_project(&self, project_id: Uuid) -> Result<Vec<CollectKey>, sqlx::Error> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, project_id, name, secret
+            FROM collect_keys
+            WHERE project_id = $1
+            "#,
+            project_id,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|r| CollectKey {
+                id: r.id,
+                project_id: r.project_id,
+                name: r.name,
+                secret: r.secret,
+            })
+            .collect())
+    }
+
+    pub async fn find_by_secret(&self, secret: &str) -> Result<Option<CollectKey>, sqlx::Error> {
+        let row = sqlx::query!(
+            r#"
+            SELECT id, project_id, name, secret
+            FROM collect_keys
+            WHERE secret = $1
+            "#,
+            secret,
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|r| CollectKey {
+            id: r.id,
+            project_id: r.project_id,
+            name: r.name,
+            secret: r.secret,
+        }))
+    }
+
+    pub async fn delete(&self, project_id: Uuid, id: Uuid) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            "DELETE FROM collect_keys WHERE id = $1 AND project_id = $2",
+            id,
+            project_id,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
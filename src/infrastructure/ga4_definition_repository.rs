@@ -0,0 +1,373 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::ga4_definition::{CustomDimension, CustomMetric, DataStream, KeyEvent, PropertyDefinitions};
+
+#[derive(Clone)]
+pub struct Ga4DefinitionRepository {
+    pool: PgPool,
+}
+
+impl Ga4DefinitionRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Definitions of every property synced on the connector, archived ones included.
+    pub async fn find_by_connector(&self, connector_id: Uuid) -> Result<PropertyDefinitions, sqlx::Error> {
+        let custom_dimensions = sqlx::query_as!(
+            CustomDimension,
+            r#"
+            SELECT connector_id, property_id, name, parameter_name, display_name, description, scope,
+                   renamed_from, archived
+            FROM ga4_custom_dimensions
+            WHERE connector_id = $1
+            ORDER BY property_id, display_name
+            "#,
+            connector_id,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let custom_metrics = sqlx::query_as!(
+            CustomMetric,
+            r#"
+            SELECT connector_id, property_id, name, parameter_name, display_name, description,
+                   measurement_unit, scope, renamed_from, archived
+            FROM ga4_custom_metrics
+            WHERE connector_id = $1
+            ORDER BY property_id, display_name
+            "#,
+            connector_id,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let key_events = sqlx::query_as!(
+            KeyEvent,
+            r#"
+            SELECT connector_id, property_id, name, event_name, counting_method, custom
+            FROM ga4_key_events
+            WHERE connector_id = $1
+            ORDER BY property_id, event_name
+            "#,
+            connector_id,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let data_streams = sqlx::query_as!(
+            DataStream,
+            r#"
+            SELECT connector_id, property_id, name, type AS stream_type, display_name, app_id, default_uri
+            FROM ga4_data_streams
+            WHERE connector_id = $1
+            ORDER BY property_id, display_name
+            "#,
+            connector_id,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(PropertyDefinitions {
+            custom_dimensions,
+            custom_metrics,
+            key_events,
+            data_streams,
+        })
+    }
+
+    /// Replaces the stored definitions of a property with those just fetched. Custom dimensions
+    /// and metrics that disappeared are kept as archived, and renames remember the previous
+    /// display name. Key events and data streams that disappeared are dropped.
+    pub async fn replace_property(
+        &self,
+        connector_id: Uuid,
+        property_id: &str,
+        definitions: &PropertyDefinitions,
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        for d in &definitions.custom_dimensions {
+            sqlx::query!(
+                r#"
+                INSERT INTO ga4_custom_dimensions
+                    (connector_id, property_id, name, parameter_name, display_name, description, scope)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                ON CONFLICT (connector_id, name) DO UPDATE SET
+                    property_id = EXCLUDED.property_id,
+                    parameter_name = EXCLUDED.parameter_name,
+                    display_name = EXCLUDED.display_name,
+                    description = EXCLUDED.description,
+                    scope = EXCLUDED.scope,
+                    renamed_from = CASE
+                        WHEN ga4_custom_dimensions.display_name <> EXCLUDED.display_name
+                        THEN ga4_custom_dimensions.display_name
+                        ELSE ga4_custom_dimensions.renamed_from
+                    END,
+                    archived = FALSE
+                "#,
+                connector_id,
+                property_id,
+                d.name,
+                d.parameter_name,
+                d.display_name,
+                d.description,
+                d.scope,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        let names: Vec<String> = definitions.custom_dimensions.iter().map(|d| d.name.clone()).collect();
+        sqlx::query!(
+            r#"
+            UPDATE ga4_custom_dimensions
+            SET archived = TRUE
+            WHERE connector_id = $1 AND property_id = $2 AND NOT (name = ANY($3))
+            "#,
+            connector_id,
+            property_id,
+            &names,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        for m in &definitions.custom_metrics {
+            sqlx::query!(
+                r#"
+                INSERT INTO ga4_custom_metrics
+                    (connector_id, property_id, name, parameter_name, display_name, description,
+                     measurement_unit, scope)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                ON CONFLICT (connector_id, name) DO UPDATE SET
+                    property_id = EXCLUDED.property_id,
+                    parameter_name = EXCLUDED.parameter_name,
+                    display_name = EXCLUDED.display_name,
+                    description = EXCLUDED.description,
+                    measurement_unit = EXCLUDED.measurement_unit,
+                    scope = EXCLUDED.scope,
+                    renamed_from = CASE
+                        WHEN ga4_custom_metrics.display_name <> EXCLUDED.display_name
+                        THEN ga4_custom_metrics.display_name
+                        ELSE ga4_custom_metrics.renamed_from
+                    END,
+                    archived = FALSE
+                "#,
+                connector_id,
+                property_id,
+                m.name,
+                m.parameter_name,
+                m.display_name,
+                m.description,
+                m.measurement_unit,
+                m.scope,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        let names: Vec<String> = definitions.custom_metrics.iter().map(|m| m.name.clone()).collect();
+        sqlx::query!(
+            r#"
+            UPDATE ga4_custom_metrics
+            SET archived = TRUE
+            WHERE connector_id = $1 AND property_id = $2 AND NOT (name = ANY($3))
+            "#,
+            connector_id,
+            property_id,
+            &names,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            "DELETE FROM ga4_key_events WHERE connector_id = $1 AND property_id = $2",
+            connector_id,
+            property_id,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        for k in &definitions.key_events {
+            sqlx::query!(
+                r#"
+                INSERT INTO ga4_key_events (connector_id, property_id, name, event_name, counting_method, custom)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                "#,
+                connector_id,
+                property_id,
+                k.name,
+                k.event_name,
+                k.counting_method,
+                k.custom,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        sqlx::query!(
+            "DELETE FROM ga4_data_streams WHERE connector_id = $1 AND property_id = $2",
+            connector_id,
+            property_id,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        for d in &definitions.data_streams {
+            sqlx::query!(
+                r#"
+                INSERT INTO ga4_data_streams
+                    (connector_id, property_id, name, type, display_name, app_id, default_uri)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                "#,
+                connector_id,
+                property_id,
+                d.name,
+                d.stream_type,
+                d.display_name,
+                d.app_id,
+                d.default_uri,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROPERTY: &str = "properties/123";
+
+    async fn connector(pool: &PgPool) -> Uuid {
+        let (project_id, connector_id) = (Uuid::now_v7(), Uuid::now_v7());
+        sqlx::query("INSERT INTO projects (id, name) VALUES ($1, 'Project')")
+            .bind(project_id)
+            .execute(pool)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO connectors (id, project_id, name, type, config) VALUES ($1, $2, 'GA4', 'GA4', '{}')")
+            .bind(connector_id)
+            .bind(project_id)
+            .execute(pool)
+            .await
+            .unwrap();
+        connector_id
+    }
+
+    fn dimension(connector_id: Uuid, id: &str, display_name: &str) -> CustomDimension {
+        CustomDimension {
+            connector_id,
+            property_id: PROPERTY.to_string(),
+            name: format!("{}/customDimensions/{}", PROPERTY, id),
+            parameter_name: id.to_string(),
+            display_name: display_name.to_string(),
+            description: String::new(),
+            scope: "EVENT".to_string(),
+            renamed_from: None,
+            archived: false,
+        }
+    }
+
+    fn metric(connector_id: Uuid, id: &str, display_name: &str) -> CustomMetric {
+        CustomMetric {
+            connector_id,
+            property_id: PROPERTY.to_string(),
+            name: format!("{}/customMetrics/{}", PROPERTY, id),
+            parameter_name: id.to_string(),
+            display_name: display_name.to_string(),
+            description: String::new(),
+            measurement_unit: "STANDARD".to_string(),
+            scope: "EVENT".to_string(),
+            renamed_from: None,
+            archived: false,
+        }
+    }
+
+    /// (parameter name, display name, renamed from, archived) of the stored dimensions.
+    async fn dimensions(repo: &Ga4DefinitionRepository, connector_id: Uuid) -> Vec<(String, String, Option<String>, bool)> {
+        let mut dimensions: Vec<_> = repo
+            .find_by_connector(connector_id)
+            .await
+            .unwrap()
+            .custom_dimensions
+            .into_iter()
+            .map(|d| (d.parameter_name, d.display_name, d.renamed_from, d.archived))
+            .collect();
+        dimensions.sort();
+        dimensions
+    }
+
+    #[sqlx::test]
+    async fn remembers_renames_and_archives_missing_fields(pool: PgPool) {
+        let repo = Ga4DefinitionRepository::new(pool.clone());
+        let connector_id = connector(&pool).await;
+
+        let first = PropertyDefinitions {
+            custom_dimensions: vec![dimension(connector_id, "plan", "Plan"), dimension(connector_id, "tier", "Tier")],
+            custom_metrics: vec![metric(connector_id, "levels", "Levels")],
+            ..PropertyDefinitions::default()
+        };
+        repo.replace_property(connector_id, PROPERTY, &first).await.unwrap();
+
+        let second = PropertyDefinitions {
+            custom_dimensions: vec![dimension(connector_id, "plan", "Pricing plan")],
+            ..PropertyDefinitions::default()
+        };
+        repo.replace_property(connector_id, PROPERTY, &second).await.unwrap();
+
+        assert_eq!(
+            dimensions(&repo, connector_id).await,
+            vec![
+                ("plan".to_string(), "Pricing plan".to_string(), Some("Plan".to_string()), false),
+                ("tier".to_string(), "Tier".to_string(), None, true),
+            ]
+        );
+        let metrics = repo.find_by_connector(connector_id).await.unwrap().custom_metrics;
+        assert_eq!(metrics.len(), 1);
+        assert!(metrics[0].archived);
+
+        // Syncing again without changes keeps the previous name, a field coming back is restored
+        let third = PropertyDefinitions {
+            custom_dimensions: vec![dimension(connector_id, "plan", "Pricing plan"), dimension(connector_id, "tier", "Tier")],
+            ..PropertyDefinitions::default()
+        };
+        repo.replace_property(connector_id, PROPERTY, &third).await.unwrap();
+
+        assert_eq!(
+            dimensions(&repo, connector_id).await,
+            vec![
+                ("plan".to_string(), "Pricing plan".to_string(), Some("Plan".to_string()), false),
+                ("tier".to_string(), "Tier".to_string(), None, false),
+            ]
+        );
+    }
+
+    #[sqlx::test]
+    async fn leaves_other_properties_alone(pool: PgPool) {
+        let repo = Ga4DefinitionRepository::new(pool.clone());
+        let connector_id = connector(&pool).await;
+
+        let other = PropertyDefinitions {
+            custom_dimensions: vec![CustomDimension {
+                property_id: "properties/456".to_string(),
+                name: "properties/456/customDimensions/plan".to_string(),
+                ..dimension(connector_id, "plan", "Plan")
+            }],
+            ..PropertyDefinitions::default()
+        };
+        repo.replace_property(connector_id, "properties/456", &other).await.unwrap();
+        repo.replace_property(connector_id, PROPERTY, &PropertyDefinitions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            dimensions(&repo, connector_id).await,
+            vec![("plan".to_string(), "Plan".to_string(), None, false)]
+        );
+    }
+}
@@ -1,2 +1,6 @@
+pub mod collect_key_repository;
 pub mod connector_repository;
+pub mod ga4_definition_repository;
 pub mod project_repository;
+pub mod report_definition_repository;
+pub mod traffic_filter_repository;
@@ -0,0 +1,135 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::report_definition::{ReportDefinition, ReportSpec};
+
+#[derive(Clone)]
+pub struct ReportDefinitionRepository {
+    pool: PgPool,
+}
+
+fn parse_spec(spec: serde_json::Value) -> Result<ReportSpec, sqlx::Error> {
+    serde_json::from_value(spec).map_err(|e| sqlx::Error::Decode(Box::new(e)))
+}
+
+impl ReportDefinitionRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(&self, definition: &ReportDefinition) -> Result<ReportDefinition, sqlx::Error> {
+        let spec = serde_json::to_value(&definition.spec).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+
+        let row = sqlx::query!(
+            r#"
+            INSERT INTO report_definitions (id, connector_id, name, property_id, spec)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, connector_id, name, property_id, spec
+            "#,
+            definition.id,
+            definition.connector_id,
+            definition.name,
+            definition.property_id,
+            spec,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(ReportDefinition {
+            id: row.id,
+            connector_id: row.connector_id,
+            name: row.name,
+            property_id: row.property_id,
+            spec: parse_spec(row.spec)?,
+        })
+    }
+
+    pub async fn find_by_id(&self, id: Uuid) -> Result<Option<ReportDefinition>, sqlx::Error> {
+        let row = sqlx::query!(
+            r#"
+            SELECT id, connector_id, name, property_id, spec
+            FROM report_definitions
+            WHERE id = $1
+            "#,
+            id,
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|r| {
+            Ok(ReportDefinition {
+                id: r.id,
+                connector_id: r.connector_id,
+                name: r.name,
+                property_id: r.property_id,
+                spec: parse_spec(r.spec)?,
+            })
+        })
+        .transpose()
+    }
+
+    pub async fn find_by_connector(&self, connector_id: Uuid) -> Result<Vec<ReportDefinition>, sqlx::Error> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, connector_id, name, property_id, spec
+            FROM report_definitions
+            WHERE connector_id = $1
+            ORDER BY name
+            "#,
+            connector_id,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|r| {
+                Ok(ReportDefinition {
+                    id: r.id,
+                    connector_id: r.connector_id,
+                    name: r.name,
+                    property_id: r.property_id,
+                    spec: parse_spec(r.spec)?,
+                })
+            })
+            .collect()
+    }
+
+    pub async fn update(&self, definition: &ReportDefinition) -> Result<ReportDefinition, sqlx::Error> {
+        let spec = serde_json::to_value(&definition.spec).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+
+        let row = sqlx::query!(
+            r#"
+            UPDATE report_definitions
+            SET name = $2, property_id = $3, spec = $4
+            WHERE id = $1
+            RETURNING id, connector_id, name, property_id, spec
+            "#,
+            definition.id,
+            definition.name,
+            definition.property_id,
+            spec,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(ReportDefinition {
+            id: row.id,
+            connector_id: row.connector_id,
+            name: row.name,
+            property_id: row.property_id,
+            spec: parse_spec(row.spec)?,
+        })
+    }
+
+    pub async fn delete(&self, connector_id: Uuid, id: Uuid) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            "DELETE FROM report_definitions WHERE connector_id = $1 AND id = $2",
+            connector_id,
+            id,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
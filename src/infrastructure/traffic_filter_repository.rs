@@ -0,0 +1,53 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::traffic_filter::TrafficFilter;
+
+#[derive(Clone)]
+pub struct TrafficFilterRepository {
+    pool: PgPool,
+}
+
+impl TrafficFilterRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// The project's settings, the defaults when it has none.
+    pub async fn find_by_project(&self, project_id: Uuid) -> Result<TrafficFilter, sqlx::Error> {
+        let row = sqlx::query!(
+            r#"
+            SELECT config
+            FROM traffic_filters
+            WHERE project_id = $1
+            "#,
+            project_id,
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match row {
+            Some(r) => serde_json::from_value(r.config).map_err(|e| sqlx::Error::Decode(Box::new(e))),
+            None => Ok(TrafficFilter::default()),
+        }
+    }
+
+    pub async fn save(&self, project_id: Uuid, filter: &TrafficFilter) -> Result<TrafficFilter, sqlx::Error> {
+        let config = serde_json::to_value(filter).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+
+        let row = sqlx::query!(
+            r#"
+            INSERT INTO traffic_filters (project_id, config)
+            VALUES ($1, $2)
+            ON CONFLICT (project_id) DO UPDATE SET config = EXCLUDED.config
+            RETURNING config
+            "#,
+            project_id,
+            config,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        serde_json::from_value(row.config).map_err(|e| sqlx::Error::Decode(Box::new(e)))
+    }
+}
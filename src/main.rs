@@ -2,23 +2,50 @@ mod api;
 mod infrastructure;
 mod models;
 mod services;
+mod sources;
 
 use axum::{routing::get, Router};
-use oauth2::{AuthUrl, ClientId, ClientSecret, RedirectUrl, TokenUrl, basic::BasicClient};
+use oauth2::{AuthType, AuthUrl, ClientId, ClientSecret, RedirectUrl, TokenUrl, basic::BasicClient};
 use sqlx::postgres::PgPoolOptions;
+use std::net::SocketAddr;
 use std::sync::Arc;
 use tower_http::cors::{Any, CorsLayer};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use crate::api::handler::{connector, ga4, project};
+use crate::api::handler::{collect, connector, file, ga4, google, meta, project, report, session, traffic_filter};
+use crate::api::oauth::ReturnUrlAllowlist;
+use crate::infrastructure::collect_key_repository::CollectKeyRepository;
 use crate::infrastructure::connector_repository::ConnectorRepository;
+use crate::infrastructure::ga4_definition_repository::Ga4DefinitionRepository;
 use crate::infrastructure::project_repository::ProjectRepository;
+use crate::infrastructure::report_definition_repository::ReportDefinitionRepository;
+use crate::infrastructure::traffic_filter_repository::TrafficFilterRepository;
+use crate::services::collect_service::EventBuffer;
+use crate::services::enrichment_service::Enricher;
+use crate::services::traffic_filter_service::FilterLists;
+use crate::services::meta_ads_service::{self, MetaApp};
+use crate::services::realtime_service::RealtimeCache;
+use crate::sources::SourceRegistry;
 
 #[derive(Clone)]
 pub struct AppState {
     pub oauth_client: Arc<BasicClient>,
+    /// Unset when no Meta app credentials are configured.
+    pub meta_app: Option<Arc<MetaApp>>,
     pub connector_repo: ConnectorRepository,
     pub project_repo: ProjectRepository,
+    pub collect_key_repo: CollectKeyRepository,
+    pub traffic_filter_repo: TrafficFilterRepository,
+    pub report_definition_repo: ReportDefinitionRepository,
+    pub ga4_definition_repo: Ga4DefinitionRepository,
+    /// Collected events not yet written to the project event stores.
+    pub event_buffer: Arc<EventBuffer>,
+    pub enricher: Arc<Enricher>,
+    pub filter_lists: Arc<FilterLists>,
+    /// Realtime reports fetched in the last seconds, per GA4 connector.
+    pub realtime_cache: Arc<RealtimeCache>,
+    pub return_url_allowlist: Arc<ReturnUrlAllowlist>,
+    pub sources: Arc<SourceRegistry>,
 }
 
 async fn health() -> &'static str {
@@ -42,6 +69,33 @@ fn create_oauth_client() -> BasicClient {
     .set_redirect_uri(RedirectUrl::new(redirect_url).unwrap())
 }
 
+fn create_meta_app() -> Option<MetaApp> {
+    let (Ok(app_id), Ok(app_secret)) = (std::env::var("META_APP_ID"), std::env::var("META_APP_SECRET")) else {
+        tracing::warn!("META_APP_ID or META_APP_SECRET not set, Meta Ads OAuth disabled");
+        return None;
+    };
+    let redirect_url = std::env::var("META_REDIRECT_URL")
+        .unwrap_or_else(|_| "http://localhost:3000/connectors/meta/callback".to_string());
+    let api_base_url = std::env::var("META_API_BASE_URL")
+        .unwrap_or_else(|_| meta_ads_service::DEFAULT_API_BASE_URL.to_string());
+
+    let client = BasicClient::new(
+        ClientId::new(app_id.clone()),
+        Some(ClientSecret::new(app_secret.clone())),
+        AuthUrl::new("https://www.facebook.com/v21.0/dialog/oauth".to_string()).unwrap(),
+        Some(TokenUrl::new(format!("{}/oauth/access_token", api_base_url)).unwrap()),
+    )
+    .set_auth_type(AuthType::RequestBody)
+    .set_redirect_uri(RedirectUrl::new(redirect_url).unwrap());
+
+    Some(MetaApp {
+        client,
+        app_id,
+        app_secret,
+        api_base_url,
+    })
+}
+
 #[tokio::main]
 async fn main() {
     dotenvy::dotenv().ok();
@@ -70,19 +124,59 @@ async fn main() {
 
     let state = AppState {
         oauth_client: Arc::new(create_oauth_client()),
+        meta_app: create_meta_app().map(Arc::new),
         connector_repo: ConnectorRepository::new(pool.clone()),
-        project_repo: ProjectRepository::new(pool),
+        project_repo: ProjectRepository::new(pool.clone()),
+        collect_key_repo: CollectKeyRepository::new(pool.clone()),
+        traffic_filter_repo: TrafficFilterRepository::new(pool.clone()),
+        report_definition_repo: ReportDefinitionRepository::new(pool.clone()),
+        ga4_definition_repo: Ga4DefinitionRepository::new(pool),
+        event_buffer: Arc::new(EventBuffer::default()),
+        enricher: Arc::new(Enricher::from_env()),
+        filter_lists: Arc::new(FilterLists::from_env()),
+        realtime_cache: Arc::new(RealtimeCache::default()),
+        return_url_allowlist: Arc::new(ReturnUrlAllowlist::parse(
+            &std::env::var("OAUTH_RETURN_URL_ALLOWLIST").unwrap_or_default(),
+        )),
+        sources: Arc::new(SourceRegistry::new()),
     };
 
+    let event_buffer = state.event_buffer.clone();
+    tokio::spawn(event_buffer.clone().run());
+
     let app = Router::new()
         .route("/health", get(health))
         .merge(project::routes())
         .merge(connector::routes())
+        .merge(file::routes())
         .merge(ga4::routes())
+        .merge(report::routes())
+        .merge(google::routes())
+        .merge(meta::routes())
+        .merge(collect::routes())
+        .merge(session::routes())
+        .merge(traffic_filter::routes())
         .layer(cors)
         .with_state(state);
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
     println!("Server running on http://localhost:3000");
-    axum::serve(listener, app).await.unwrap();
+    // Peer addresses are needed to enrich collected events
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(shutdown_signal())
+        .await
+        .unwrap();
+
+    // Write what is still buffered before exiting
+    tracing::info!("Flushing buffered events");
+    tokio::task::spawn_blocking(move || event_buffer.flush_all())
+        .await
+        .unwrap();
+}
+
+async fn shutdown_signal() {
+    tokio::signal::ctrl_c()
+        .await
+        .expect("Failed to install Ctrl+C handler");
+    tracing::info!("Shutting down");
 }
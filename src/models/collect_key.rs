@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Authenticates event collection for a project.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct CollectKey {
+    pub id: Uuid,
+    pub project_id: Uuid,
+    pub name: String,
+    /// Sent by clients as the Measurement Protocol `api_secret`.
+    pub secret: String,
+}
@@ -1,27 +1,375 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::types::JsonValue;
+use std::collections::BTreeMap;
 use strum::{Display, EnumString};
 use uuid::Uuid;
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Display, EnumString, sqlx::Type)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Display, EnumString, sqlx::Type)]
 #[sqlx(type_name = "VARCHAR", rename_all = "SCREAMING_SNAKE_CASE")]
 #[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
 pub enum ConnectorType {
     Ga4,
+    SearchConsole,
+    GoogleAds,
+    MetaAds,
+    Stripe,
+    FileUpload,
+    Database,
+    RestApi,
+    Plausible,
+    Matomo,
+    Ga4Export,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum ConnectorDetails {
     Ga4 {
-        access_token: String,
-        refresh_token: Option<String>,
-        expires_at: Option<DateTime<Utc>>,
-        token_type: String,
-        property_id: Option<String>,
-        property_name: Option<String>,
+        #[serde(flatten)]
+        tokens: OAuthTokens,
+        #[serde(default)]
+        properties: Vec<Ga4Property>,
+        /// Single property selected before multi-property support, which the rows of an
+        /// older store belong to.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        legacy_property_id: Option<String>,
     },
+    SearchConsole {
+        #[serde(flatten)]
+        tokens: OAuthTokens,
+        /// Selected site URLs, e.g. `https://example.com/` or `sc-domain:example.com`.
+        #[serde(default)]
+        sites: Vec<String>,
+    },
+    GoogleAds {
+        #[serde(flatten)]
+        tokens: OAuthTokens,
+        /// Google Ads API developer token, required before any API call.
+        #[serde(default)]
+        developer_token: Option<String>,
+        /// Manager account to access client accounts through.
+        #[serde(default)]
+        login_customer_id: Option<String>,
+        #[serde(default)]
+        customers: Vec<GoogleAdsCustomer>,
+    },
+    MetaAds {
+        #[serde(flatten)]
+        tokens: OAuthTokens,
+        #[serde(default)]
+        ad_accounts: Vec<MetaAdAccount>,
+        /// Insights breakdowns applied at every level, e.g. `["age", "gender"]`.
+        #[serde(default)]
+        breakdowns: Vec<String>,
+    },
+    Stripe {
+        /// Secret or restricted (read-only) key, encrypted with `crypto_service` before the
+        /// config is stored.
+        api_key: String,
+        /// Overrides the API base URL, e.g. `http://localhost:12111` for stripe-mock.
+        #[serde(default)]
+        base_url: Option<String>,
+    },
+    FileUpload {
+        /// DuckDB table the uploaded files are loaded into.
+        #[serde(default = "default_upload_table")]
+        table: String,
+        /// Column type overrides applied to every upload, e.g. `{"amount": "DOUBLE"}`.
+        #[serde(default)]
+        column_types: BTreeMap<String, String>,
+    },
+    Database {
+        engine: DatabaseEngine,
+        host: String,
+        /// Defaults to the engine's standard port.
+        #[serde(default)]
+        port: Option<u16>,
+        database: String,
+        user: String,
+        /// Encrypted with `crypto_service` before the config is stored.
+        password: String,
+        /// Tables replicated as is.
+        #[serde(default)]
+        tables: Vec<DatabaseTable>,
+        /// Custom queries, each replicated into a table of its name.
+        #[serde(default)]
+        queries: Vec<DatabaseQuery>,
+    },
+    RestApi {
+        /// Prefix of every endpoint path, e.g. `https://api.example.com`.
+        base_url: String,
+        #[serde(default)]
+        auth: RestAuth,
+        /// Extra headers sent with every request.
+        #[serde(default)]
+        headers: BTreeMap<String, String>,
+        endpoints: Vec<RestEndpoint>,
+    },
+    Plausible {
+        /// Instance URL, e.g. `https://plausible.example.com` when self-hosted.
+        #[serde(default = "default_plausible_url")]
+        base_url: String,
+        /// Stats API key, encrypted before the config is stored.
+        api_key: String,
+        /// Site domains as registered in Plausible, e.g. `example.com`.
+        #[serde(default)]
+        sites: Vec<String>,
+    },
+    Matomo {
+        /// Instance URL, e.g. `https://matomo.example.com`.
+        base_url: String,
+        /// Encrypted before the config is stored.
+        token_auth: String,
+        /// Selected site ids.
+        #[serde(default)]
+        sites: Vec<String>,
+    },
+    Ga4Export {
+        /// Glob of the exported `events_*` files, NDJSON or Parquet, e.g.
+        /// `ga4/events_*.json.gz` under `GA4_EXPORT_ROOT` or `s3://bucket/ga4/*.parquet`
+        /// under one of `GA4_EXPORT_REMOTE_PREFIXES`.
+        path: String,
+    },
+}
+
+fn default_plausible_url() -> String {
+    "https://plausible.io".to_string()
+}
+
+fn default_upload_table() -> String {
+    "uploads".to_string()
+}
+
+impl ConnectorDetails {
+    pub fn connector_type(&self) -> ConnectorType {
+        match self {
+            ConnectorDetails::Ga4 { .. } => ConnectorType::Ga4,
+            ConnectorDetails::SearchConsole { .. } => ConnectorType::SearchConsole,
+            ConnectorDetails::GoogleAds { .. } => ConnectorType::GoogleAds,
+            ConnectorDetails::MetaAds { .. } => ConnectorType::MetaAds,
+            ConnectorDetails::Stripe { .. } => ConnectorType::Stripe,
+            ConnectorDetails::FileUpload { .. } => ConnectorType::FileUpload,
+            ConnectorDetails::Database { .. } => ConnectorType::Database,
+            ConnectorDetails::RestApi { .. } => ConnectorType::RestApi,
+            ConnectorDetails::Plausible { .. } => ConnectorType::Plausible,
+            ConnectorDetails::Matomo { .. } => ConnectorType::Matomo,
+            ConnectorDetails::Ga4Export { .. } => ConnectorType::Ga4Export,
+        }
+    }
+
+    /// OAuth tokens of connectors that authenticate through an OAuth provider.
+    pub fn oauth_tokens(&self) -> Option<&OAuthTokens> {
+        match self {
+            ConnectorDetails::Ga4 { tokens, .. }
+            | ConnectorDetails::SearchConsole { tokens, .. }
+            | ConnectorDetails::GoogleAds { tokens, .. }
+            | ConnectorDetails::MetaAds { tokens, .. } => Some(tokens),
+            ConnectorDetails::Stripe { .. }
+            | ConnectorDetails::FileUpload { .. }
+            | ConnectorDetails::Database { .. }
+            | ConnectorDetails::RestApi { .. }
+            | ConnectorDetails::Plausible { .. }
+            | ConnectorDetails::Matomo { .. }
+            | ConnectorDetails::Ga4Export { .. } => None,
+        }
+    }
+
+    pub fn oauth_tokens_mut(&mut self) -> Option<&mut OAuthTokens> {
+        match self {
+            ConnectorDetails::Ga4 { tokens, .. }
+            | ConnectorDetails::SearchConsole { tokens, .. }
+            | ConnectorDetails::GoogleAds { tokens, .. }
+            | ConnectorDetails::MetaAds { tokens, .. } => Some(tokens),
+            ConnectorDetails::Stripe { .. }
+            | ConnectorDetails::FileUpload { .. }
+            | ConnectorDetails::Database { .. }
+            | ConnectorDetails::RestApi { .. }
+            | ConnectorDetails::Plausible { .. }
+            | ConnectorDetails::Matomo { .. }
+            | ConnectorDetails::Ga4Export { .. } => None,
+        }
+    }
+}
+
+/// OAuth2 tokens, stored flattened in the connector config.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthTokens {
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub token_type: String,
+}
+
+/// A GA4 property selected for syncing on a connector.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ga4Property {
+    pub property_id: String,
+    pub property_name: String,
+    #[serde(default)]
+    pub time_zone: Option<String>,
+    #[serde(default)]
+    pub currency_code: Option<String>,
+}
+
+/// A Google Ads customer account selected for syncing on a connector.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GoogleAdsCustomer {
+    /// Ten digit id without dashes.
+    pub customer_id: String,
+    pub customer_name: String,
+    #[serde(default)]
+    pub time_zone: Option<String>,
+    #[serde(default)]
+    pub currency_code: Option<String>,
+}
+
+/// A Meta ad account selected for syncing on a connector.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetaAdAccount {
+    /// `act_` prefixed id.
+    pub account_id: String,
+    pub account_name: String,
+    #[serde(default)]
+    pub time_zone: Option<String>,
+    #[serde(default)]
+    pub currency_code: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DatabaseEngine {
+    Postgres,
+    Mysql,
+}
+
+/// A table of an external database replicated by a database connector.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DatabaseTable {
+    /// `schema.table`, the schema being the database name on MySQL.
+    pub name: String,
+    /// Monotonic column, e.g. `updated_at`, to only copy new rows; full refresh when unset.
+    #[serde(default)]
+    pub cursor_column: Option<String>,
+    /// Columns identifying a row, to update rows copied again instead of duplicating them.
+    #[serde(default)]
+    pub primary_key: Vec<String>,
+}
+
+/// A custom query replicated by a database connector, in the source database's dialect.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DatabaseQuery {
+    /// DuckDB table the results are written to.
+    pub name: String,
+    pub sql: String,
+    #[serde(default)]
+    pub cursor_column: Option<String>,
+    #[serde(default)]
+    pub primary_key: Vec<String>,
+}
+
+/// How a REST API connector authenticates its requests. Tokens, passwords, key values and
+/// client secrets are encrypted before the config is stored.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RestAuth {
+    #[default]
+    None,
+    Bearer { token: String },
+    Basic { username: String, password: String },
+    /// Key sent in a header, e.g. `X-Api-Key`.
+    ApiKey { header: String, value: String },
+    /// Bearer token obtained with the OAuth2 client credentials grant at the start of a sync.
+    #[serde(rename = "oauth_client_credentials")]
+    OAuthClientCredentials {
+        token_url: String,
+        client_id: String,
+        client_secret: String,
+        #[serde(default)]
+        scopes: Vec<String>,
+    },
+}
+
+/// An endpoint of a REST API connector, stored in the table of its name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RestEndpoint {
+    pub name: String,
+    /// Appended to the base URL, e.g. `/v1/orders`.
+    pub path: String,
+    /// Query parameters sent with every request.
+    #[serde(default)]
+    pub params: BTreeMap<String, String>,
+    /// JSONPath of the records in a response, e.g. `$.data[*]`; the response itself when unset.
+    #[serde(default)]
+    pub records_path: Option<String>,
+    #[serde(default)]
+    pub pagination: RestPagination,
+    pub fields: Vec<RestField>,
+    /// Fields identifying a record, rows are upserted on them.
+    pub primary_key: Vec<String>,
+    #[serde(default)]
+    pub cursor: Option<RestCursor>,
+}
+
+/// A column of an endpoint's table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RestField {
+    pub name: String,
+    /// JSONPath within a record, `$.<name>` when unset.
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default = "default_rest_field_type")]
+    pub data_type: String,
+}
+
+fn default_rest_field_type() -> String {
+    "VARCHAR".to_string()
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RestPagination {
+    /// A single request.
+    #[default]
+    None,
+    /// Stops at the first page shorter than `limit`.
+    Offset {
+        #[serde(default = "default_offset_param")]
+        offset_param: String,
+        #[serde(default = "default_limit_param")]
+        limit_param: String,
+        #[serde(default = "default_page_limit")]
+        limit: u32,
+    },
+    /// Sends the cursor found at `next_cursor_path` of a response with the next request.
+    Cursor {
+        cursor_param: String,
+        next_cursor_path: String,
+    },
+    /// Follows the `rel="next"` URL of the `Link` header.
+    LinkHeader,
+}
+
+fn default_offset_param() -> String {
+    "offset".to_string()
+}
+
+fn default_limit_param() -> String {
+    "limit".to_string()
+}
+
+fn default_page_limit() -> u32 {
+    100
+}
+
+/// Incremental sync of an endpoint: the highest value of `field` seen so far is sent as
+/// the `param` query parameter, so the API only returns newer records.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RestCursor {
+    /// One of the endpoint's fields, e.g. `updated_at`.
+    pub field: String,
+    /// e.g. `updated_since`.
+    pub param: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
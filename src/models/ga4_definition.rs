@@ -0,0 +1,291 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::report_definition::ReportDefinition;
+
+/// Custom dimension of a GA4 property, as of the connector's latest sync.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomDimension {
+    pub connector_id: Uuid,
+    pub property_id: String,
+    /// Resource name, `properties/{id}/customDimensions/{id}`.
+    pub name: String,
+    pub parameter_name: String,
+    pub display_name: String,
+    pub description: String,
+    /// `EVENT`, `USER` or `ITEM`.
+    pub scope: String,
+    /// Display name before the latest rename.
+    pub renamed_from: Option<String>,
+    /// Archived in GA4, reports using it fail.
+    pub archived: bool,
+}
+
+/// Custom metric of a GA4 property, as of the connector's latest sync.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomMetric {
+    pub connector_id: Uuid,
+    pub property_id: String,
+    /// Resource name, `properties/{id}/customMetrics/{id}`.
+    pub name: String,
+    pub parameter_name: String,
+    pub display_name: String,
+    pub description: String,
+    /// e.g. `STANDARD`, `CURRENCY` or `SECONDS`.
+    pub measurement_unit: String,
+    pub scope: String,
+    pub renamed_from: Option<String>,
+    pub archived: bool,
+}
+
+/// Event counted as a key event (conversion) on a GA4 property.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyEvent {
+    pub connector_id: Uuid,
+    pub property_id: String,
+    pub name: String,
+    pub event_name: String,
+    /// `ONCE_PER_EVENT` or `ONCE_PER_SESSION`.
+    pub counting_method: String,
+    /// Created by the property's users rather than built in, e.g. `purchase`.
+    pub custom: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DataStream {
+    pub connector_id: Uuid,
+    pub property_id: String,
+    pub name: String,
+    /// `WEB_DATA_STREAM`, `ANDROID_APP_DATA_STREAM` or `IOS_APP_DATA_STREAM`.
+    #[serde(rename = "type")]
+    pub stream_type: String,
+    pub display_name: String,
+    /// Measurement id of web streams, package name or bundle id of app streams.
+    pub app_id: Option<String>,
+    pub default_uri: Option<String>,
+}
+
+/// Everything synced from the Admin API for one property.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PropertyDefinitions {
+    pub custom_dimensions: Vec<CustomDimension>,
+    pub custom_metrics: Vec<CustomMetric>,
+    pub key_events: Vec<KeyEvent>,
+    pub data_streams: Vec<DataStream>,
+}
+
+/// Custom field a report definition uses that was renamed or archived in GA4.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReportFieldIssue {
+    pub report_id: Uuid,
+    pub report_name: String,
+    /// Data API name used by the definition, e.g. `customEvent:plan`.
+    pub field: String,
+    pub display_name: String,
+    pub issue: FieldIssue,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FieldIssue {
+    /// The report fails until the field is removed from it.
+    Archived,
+    /// Still works, the API name does not change, but labels shown to users do.
+    Renamed { from: String },
+}
+
+impl PropertyDefinitions {
+    /// Renamed or archived custom dimensions and metrics used by a definition of this property.
+    pub fn report_issues(&self, definition: &ReportDefinition) -> Vec<ReportFieldIssue> {
+        let (dimensions, metrics) = definition.spec.fields();
+        let issue = |archived: bool, renamed_from: &Option<String>| match renamed_from {
+            _ if archived => Some(FieldIssue::Archived),
+            Some(from) => Some(FieldIssue::Renamed { from: from.clone() }),
+            None => None,
+        };
+
+        let dimension_issues = self
+            .custom_dimensions
+            .iter()
+            .filter(|d| d.property_id == definition.property_id && dimensions.contains(&d.api_name().as_str()))
+            .filter_map(|d| Some((d.api_name(), &d.display_name, issue(d.archived, &d.renamed_from)?)));
+        let metric_issues = self
+            .custom_metrics
+            .iter()
+            .filter(|m| m.property_id == definition.property_id && metrics.contains(&m.api_name().as_str()))
+            .filter_map(|m| Some((m.api_name(), &m.display_name, issue(m.archived, &m.renamed_from)?)));
+
+        dimension_issues
+            .chain(metric_issues)
+            .map(|(field, display_name, issue)| ReportFieldIssue {
+                report_id: definition.id,
+                report_name: definition.name.clone(),
+                field,
+                display_name: display_name.clone(),
+                issue,
+            })
+            .collect()
+    }
+}
+
+impl CustomDimension {
+    /// Name used in Data API requests, e.g. `customEvent:plan`.
+    pub fn api_name(&self) -> String {
+        let prefix = match self.scope.as_str() {
+            "USER" => "customUser",
+            "ITEM" => "customItem",
+            _ => "customEvent",
+        };
+        format!("{}:{}", prefix, self.parameter_name)
+    }
+}
+
+impl CustomMetric {
+    /// Name used in Data API requests, e.g. `customEvent:levels_unlocked`.
+    pub fn api_name(&self) -> String {
+        format!("customEvent:{}", self.parameter_name)
+    }
+}
+
+impl KeyEvent {
+    /// Metric counting the key event, e.g. `keyEvents:purchase`.
+    pub fn api_name(&self) -> String {
+        format!("keyEvents:{}", self.event_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROPERTY: &str = "properties/123";
+
+    fn dimension(parameter_name: &str, scope: &str) -> CustomDimension {
+        CustomDimension {
+            connector_id: Uuid::nil(),
+            property_id: PROPERTY.to_string(),
+            name: format!("{}/customDimensions/{}", PROPERTY, parameter_name),
+            parameter_name: parameter_name.to_string(),
+            display_name: parameter_name.to_string(),
+            description: String::new(),
+            scope: scope.to_string(),
+            renamed_from: None,
+            archived: false,
+        }
+    }
+
+    fn metric(parameter_name: &str) -> CustomMetric {
+        CustomMetric {
+            connector_id: Uuid::nil(),
+            property_id: PROPERTY.to_string(),
+            name: format!("{}/customMetrics/{}", PROPERTY, parameter_name),
+            parameter_name: parameter_name.to_string(),
+            display_name: parameter_name.to_string(),
+            description: String::new(),
+            measurement_unit: "STANDARD".to_string(),
+            scope: "EVENT".to_string(),
+            renamed_from: None,
+            archived: false,
+        }
+    }
+
+    fn definition(spec: serde_json::Value) -> ReportDefinition {
+        ReportDefinition {
+            id: Uuid::nil(),
+            connector_id: Uuid::nil(),
+            name: "Plans".to_string(),
+            property_id: PROPERTY.to_string(),
+            spec: serde_json::from_value(spec).unwrap(),
+        }
+    }
+
+    #[test]
+    fn prefixes_api_names_by_scope() {
+        assert_eq!(dimension("plan", "EVENT").api_name(), "customEvent:plan");
+        assert_eq!(dimension("tier", "USER").api_name(), "customUser:tier");
+        assert_eq!(dimension("color", "ITEM").api_name(), "customItem:color");
+        assert_eq!(metric("levels").api_name(), "customEvent:levels");
+    }
+
+    #[test]
+    fn reports_archived_and_renamed_fields_in_use() {
+        let definitions = PropertyDefinitions {
+            custom_dimensions: vec![
+                CustomDimension {
+                    archived: true,
+                    renamed_from: Some("Old plan".to_string()),
+                    ..dimension("plan", "EVENT")
+                },
+                CustomDimension {
+                    display_name: "Tier".to_string(),
+                    renamed_from: Some("Level".to_string()),
+                    ..dimension("tier", "USER")
+                },
+                // Renamed too, but not used by the report
+                CustomDimension {
+                    renamed_from: Some("Colour".to_string()),
+                    ..dimension("color", "ITEM")
+                },
+                dimension("source", "EVENT"),
+            ],
+            custom_metrics: vec![CustomMetric {
+                archived: true,
+                ..metric("levels")
+            }],
+            ..PropertyDefinitions::default()
+        };
+        let report = definition(serde_json::json!({
+            "kind": "standard",
+            "dimensions": ["customEvent:plan", "customEvent:source"],
+            "metrics": ["sessions"],
+            // Fields only referenced from filters count as used
+            "dimension_filter": { "type": "string", "field_name": "customUser:tier", "value": "gold" },
+            "metric_filter": { "type": "numeric", "field_name": "customEvent:levels", "operation": "greater_than", "value": 1.0 },
+        }));
+
+        let issues: Vec<(String, FieldIssue)> = definitions
+            .report_issues(&report)
+            .into_iter()
+            .map(|issue| (issue.field, issue.issue))
+            .collect();
+        assert_eq!(
+            issues,
+            vec![
+                ("customEvent:plan".to_string(), FieldIssue::Archived),
+                (
+                    "customUser:tier".to_string(),
+                    FieldIssue::Renamed {
+                        from: "Level".to_string()
+                    }
+                ),
+                ("customEvent:levels".to_string(), FieldIssue::Archived),
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_fields_of_other_properties_and_scopes() {
+        let definitions = PropertyDefinitions {
+            custom_dimensions: vec![
+                CustomDimension {
+                    property_id: "properties/456".to_string(),
+                    archived: true,
+                    ..dimension("plan", "EVENT")
+                },
+                // Same parameter, user scoped: not the field the report uses
+                CustomDimension {
+                    archived: true,
+                    ..dimension("plan", "USER")
+                },
+            ],
+            ..PropertyDefinitions::default()
+        };
+        let report = definition(serde_json::json!({
+            "kind": "standard",
+            "dimensions": ["customEvent:plan"],
+            "metrics": ["sessions"],
+        }));
+
+        assert!(definitions.report_issues(&report).is_empty());
+    }
+}
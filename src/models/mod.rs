@@ -1,2 +1,6 @@
+pub mod collect_key;
 pub mod connector;
+pub mod ga4_definition;
 pub mod project;
+pub mod report_definition;
+pub mod traffic_filter;
@@ -0,0 +1,251 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A report run on a GA4 property on every sync, stored by date.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReportDefinition {
+    pub id: Uuid,
+    pub connector_id: Uuid,
+    pub name: String,
+    /// `properties/{id}`, one of the connector's selected properties.
+    pub property_id: String,
+    pub spec: ReportSpec,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ReportSpec {
+    /// Data API `runFunnelReport`.
+    Funnel(FunnelSpec),
+    /// Data API `runReport`, batched with the other standard reports of the property.
+    Standard(StandardSpec),
+    /// Data API `runPivotReport`.
+    Pivot(PivotSpec),
+}
+
+/// Dimensions and metrics of a flat report, broken down by `date` on top of them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StandardSpec {
+    #[serde(default)]
+    pub dimensions: Vec<String>,
+    pub metrics: Vec<String>,
+    #[serde(default)]
+    pub dimension_filter: Option<FilterExpression>,
+    #[serde(default)]
+    pub metric_filter: Option<FilterExpression>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PivotSpec {
+    pub dimensions: Vec<String>,
+    pub metrics: Vec<String>,
+    /// Each dimension appears in exactly one pivot. Results are flattened back into rows.
+    pub pivots: Vec<Pivot>,
+    #[serde(default)]
+    pub dimension_filter: Option<FilterExpression>,
+    #[serde(default)]
+    pub metric_filter: Option<FilterExpression>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pivot {
+    pub field_names: Vec<String>,
+    /// Number of value combinations kept, 100 by default.
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunnelSpec {
+    pub steps: Vec<FunnelStep>,
+    /// Open funnels let users enter at any step, closed ones only at the first.
+    #[serde(default)]
+    pub is_open_funnel: bool,
+    /// Dimension splitting each step, e.g. `deviceCategory`.
+    #[serde(default)]
+    pub breakdown_dimension: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunnelStep {
+    pub name: String,
+    pub filter: FunnelStepFilter,
+    /// Whether the step must directly follow the previous one rather than eventually.
+    #[serde(default)]
+    pub is_directly_followed_by: bool,
+    /// Maximum time since the previous step, in seconds.
+    #[serde(default)]
+    pub within_seconds_of_prior_step: Option<u64>,
+}
+
+/// What users do to complete a step.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FunnelStepFilter {
+    /// Any of the events.
+    Event { event_names: Vec<String> },
+    /// A page view whose path matches.
+    Page {
+        page_path: String,
+        #[serde(default)]
+        match_type: PathMatch,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PathMatch {
+    #[default]
+    Exact,
+    BeginsWith,
+    Contains,
+    FullRegexp,
+}
+
+/// Data API filter expression, applied to dimensions before aggregation or to metrics after it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FilterExpression {
+    And {
+        expressions: Vec<FilterExpression>,
+    },
+    Or {
+        expressions: Vec<FilterExpression>,
+    },
+    Not {
+        expression: Box<FilterExpression>,
+    },
+    String {
+        field_name: String,
+        value: String,
+        #[serde(default)]
+        match_type: StringMatch,
+        #[serde(default)]
+        case_sensitive: bool,
+    },
+    InList {
+        field_name: String,
+        values: Vec<String>,
+        #[serde(default)]
+        case_sensitive: bool,
+    },
+    Numeric {
+        field_name: String,
+        operation: NumericOperation,
+        value: f64,
+    },
+    /// Inclusive on both ends.
+    Between {
+        field_name: String,
+        from_value: f64,
+        to_value: f64,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StringMatch {
+    #[default]
+    Exact,
+    BeginsWith,
+    EndsWith,
+    Contains,
+    FullRegexp,
+    PartialRegexp,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NumericOperation {
+    Equal,
+    LessThan,
+    LessThanOrEqual,
+    GreaterThan,
+    GreaterThanOrEqual,
+}
+
+impl ReportSpec {
+    /// Dimension and metric filters, for the kinds that take them.
+    pub fn filters(&self) -> (Option<&FilterExpression>, Option<&FilterExpression>) {
+        match self {
+            Self::Funnel(_) => (None, None),
+            Self::Standard(spec) => (spec.dimension_filter.as_ref(), spec.metric_filter.as_ref()),
+            Self::Pivot(spec) => (spec.dimension_filter.as_ref(), spec.metric_filter.as_ref()),
+        }
+    }
+
+    /// The spec with its filters removed, to tell filter changes from other changes.
+    pub fn without_filters(&self) -> Self {
+        let mut spec = self.clone();
+        match &mut spec {
+            Self::Funnel(_) => {}
+            Self::Standard(StandardSpec {
+                dimension_filter,
+                metric_filter,
+                ..
+            })
+            | Self::Pivot(PivotSpec {
+                dimension_filter,
+                metric_filter,
+                ..
+            }) => {
+                *dimension_filter = None;
+                *metric_filter = None;
+            }
+        }
+        spec
+    }
+
+    /// Field names used by the dimension filter, then by the metric filter.
+    pub fn filter_fields(&self) -> (Vec<&str>, Vec<&str>) {
+        let (dimension_filter, metric_filter) = self.filters();
+        let mut dimensions = Vec::new();
+        let mut metrics = Vec::new();
+        if let Some(filter) = dimension_filter {
+            filter.collect_fields(&mut dimensions);
+        }
+        if let Some(filter) = metric_filter {
+            filter.collect_fields(&mut metrics);
+        }
+        (dimensions, metrics)
+    }
+
+    /// Every dimension then every metric the spec uses, filters included.
+    pub fn fields(&self) -> (Vec<&str>, Vec<&str>) {
+        let (mut dimensions, mut metrics) = self.filter_fields();
+        match self {
+            Self::Funnel(spec) => dimensions.extend(spec.breakdown_dimension.as_deref()),
+            Self::Standard(StandardSpec {
+                dimensions: spec_dimensions,
+                metrics: spec_metrics,
+                ..
+            })
+            | Self::Pivot(PivotSpec {
+                dimensions: spec_dimensions,
+                metrics: spec_metrics,
+                ..
+            }) => {
+                dimensions.extend(spec_dimensions.iter().map(String::as_str));
+                metrics.extend(spec_metrics.iter().map(String::as_str));
+            }
+        }
+        (dimensions, metrics)
+    }
+}
+
+impl FilterExpression {
+    fn collect_fields<'a>(&'a self, fields: &mut Vec<&'a str>) {
+        match self {
+            Self::And { expressions } | Self::Or { expressions } => {
+                for expression in expressions {
+                    expression.collect_fields(fields);
+                }
+            }
+            Self::Not { expression } => expression.collect_fields(fields),
+            Self::String { field_name, .. }
+            | Self::InList { field_name, .. }
+            | Self::Numeric { field_name, .. }
+            | Self::Between { field_name, .. } => fields.push(field_name),
+        }
+    }
+}
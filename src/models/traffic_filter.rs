@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+use strum::Display;
+
+/// What happens to traffic matching a filter.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, Display)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum FilterMode {
+    /// Kept, with the reason in `filter_reason`.
+    #[default]
+    Flag,
+    /// Discarded, only counted.
+    Drop,
+}
+
+/// GA4 rows with these values are known bot or spam traffic. Unset fields match anything.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Ga4SpamCombo {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub browser: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operating_system: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub screen_resolution: Option<String>,
+}
+
+/// Bot and spam filtering settings of a project.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrafficFilter {
+    #[serde(default)]
+    pub mode: FilterMode,
+    /// Crawlers and automated browsers. HTTP libraries pass, collected events being sent
+    /// server to server.
+    #[serde(default = "enabled")]
+    pub bot_user_agents: bool,
+    /// IPs in the ranges of `DATACENTER_IP_RANGES_PATH`.
+    #[serde(default = "enabled")]
+    pub datacenter_ips: bool,
+    /// Referrers on the built-in spam list or `spam_referrers`.
+    #[serde(default = "enabled")]
+    pub referrer_spam: bool,
+    /// Domains filtered on top of the built-in list, subdomains included.
+    #[serde(default)]
+    pub spam_referrers: Vec<String>,
+    /// Built-in GA4 spam combos.
+    #[serde(default = "enabled")]
+    pub ga4_spam: bool,
+    /// GA4 spam combos on top of the built-in ones.
+    #[serde(default)]
+    pub ga4_spam_combos: Vec<Ga4SpamCombo>,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+impl Default for TrafficFilter {
+    fn default() -> Self {
+        Self {
+            mode: FilterMode::default(),
+            bot_user_agents: true,
+            datacenter_ips: true,
+            referrer_spam: true,
+            spam_referrers: Vec::new(),
+            ga4_spam: true,
+            ga4_spam_combos: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,607 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use duckdb::Connection;
+use duckdb::types::{TimeUnit, Value};
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Map;
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex, PoisonError};
+use std::time::Duration;
+use tracing::{debug, error, info};
+use uuid::Uuid;
+
+use super::enrichment_service::Enrichment;
+use super::storage_service::{self, TableSchema};
+use super::traffic_filter_service::{self, Filtered};
+use crate::api::error::FieldError;
+use crate::models::traffic_filter::FilterMode;
+
+pub const STORE_FILE: &str = "events.duckdb";
+pub const EVENTS_TABLE: &str = "events";
+
+/// Events of a project written at once when the periodic flush has not run yet.
+const BATCH_SIZE: usize = 1000;
+const FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+/// Events kept per project while flushing fails, the oldest are dropped beyond it.
+const MAX_PENDING: usize = 100_000;
+
+// Measurement Protocol limits
+const MAX_EVENTS: usize = 25;
+const MAX_PARAMS: usize = 25;
+const MAX_EVENT_NAME_LENGTH: usize = 40;
+const MAX_PARAM_NAME_LENGTH: usize = 40;
+const MAX_PARAM_VALUE_LENGTH: usize = 100;
+const MAX_USER_PROPERTIES: usize = 25;
+const MAX_USER_PROPERTY_NAME_LENGTH: usize = 24;
+const MAX_USER_PROPERTY_VALUE_LENGTH: usize = 36;
+const MAX_BACKDATE: TimeDelta = TimeDelta::hours(72);
+/// Tolerated client clock skew for timestamps in the future.
+const MAX_CLOCK_SKEW: TimeDelta = TimeDelta::minutes(5);
+
+const RESERVED_PREFIXES: [&str; 5] = ["_", "firebase_", "ga_", "google_", "gtag."];
+const RESERVED_EVENT_NAMES: [&str; 27] = [
+    "ad_activeview",
+    "ad_click",
+    "ad_exposure",
+    "ad_query",
+    "ad_reward",
+    "adunit_exposure",
+    "app_background",
+    "app_clear_data",
+    "app_exception",
+    "app_remove",
+    "app_store_refund",
+    "app_update",
+    "app_upgrade",
+    "dynamic_link_app_open",
+    "dynamic_link_app_update",
+    "dynamic_link_first_open",
+    "error",
+    "first_open",
+    "first_visit",
+    "in_app_purchase",
+    "notification_dismiss",
+    "notification_foreground",
+    "notification_open",
+    "notification_receive",
+    "os_update",
+    "session_start",
+    "user_engagement",
+];
+
+static NAME: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[A-Za-z][A-Za-z0-9_]*$").unwrap());
+
+/// Request body of the GA4 Measurement Protocol.
+#[derive(Debug, Deserialize)]
+pub struct MeasurementPayload {
+    #[serde(default)]
+    pub client_id: Option<String>,
+    #[serde(default)]
+    pub user_id: Option<String>,
+    #[serde(default)]
+    pub timestamp_micros: Option<i64>,
+    /// `{"name": {"value": ...}}`
+    #[serde(default)]
+    pub user_properties: Map<String, serde_json::Value>,
+    #[serde(default)]
+    pub events: Vec<MeasurementEvent>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MeasurementEvent {
+    pub name: String,
+    #[serde(default)]
+    pub params: Map<String, serde_json::Value>,
+    /// Overrides the request timestamp for this event.
+    #[serde(default)]
+    pub timestamp_micros: Option<i64>,
+}
+
+/// A validated event waiting to be written.
+#[derive(Debug, Clone)]
+pub struct CollectedEvent {
+    pub event_id: Uuid,
+    pub collect_key_id: Uuid,
+    pub received_at: DateTime<Utc>,
+    pub event_timestamp: DateTime<Utc>,
+    pub event_name: String,
+    pub client_id: String,
+    pub user_id: Option<String>,
+    pub session_id: Option<String>,
+    pub page_location: Option<String>,
+    pub page_referrer: Option<String>,
+    pub params: serde_json::Value,
+    pub user_properties: serde_json::Value,
+    pub enrichment: Enrichment,
+    /// Set when a traffic filter caught the event.
+    pub filtered: Option<Filtered>,
+}
+
+/// Append-only table of collected events, in the project's event store.
+pub fn events_schema() -> TableSchema {
+    TableSchema::new(
+        EVENTS_TABLE,
+        &[
+            ("event_id", "VARCHAR"),
+            ("collect_key_id", "VARCHAR"),
+            ("received_at", "TIMESTAMP"),
+            ("event_timestamp", "TIMESTAMP"),
+            // `YYYYMMDD` in UTC, like `ga4_records.date`
+            ("event_date", "VARCHAR"),
+            ("event_name", "VARCHAR"),
+            ("client_id", "VARCHAR"),
+            ("user_id", "VARCHAR"),
+            ("session_id", "VARCHAR"),
+            ("page_location", "VARCHAR"),
+            ("page_referrer", "VARCHAR"),
+            ("params", "VARCHAR"),
+            ("user_properties", "VARCHAR"),
+            // Derived from the request IP and User-Agent, with GA4's values
+            ("country", "VARCHAR"),
+            ("country_id", "VARCHAR"),
+            ("city", "VARCHAR"),
+            ("browser", "VARCHAR"),
+            ("operating_system", "VARCHAR"),
+            ("device_category", "VARCHAR"),
+            // Traffic filter that flagged the event, dropped events are not stored
+            ("filter_reason", "VARCHAR"),
+        ],
+        &[],
+    )
+}
+
+fn timestamp_value(timestamp: DateTime<Utc>) -> Value {
+    Value::Timestamp(TimeUnit::Microsecond, timestamp.timestamp_micros())
+}
+
+fn text_value(value: &Option<String>) -> Value {
+    value.clone().map(Value::Text).unwrap_or(Value::Null)
+}
+
+impl CollectedEvent {
+    fn row(&self) -> Vec<Value> {
+        vec![
+            Value::Text(self.event_id.to_string()),
+            Value::Text(self.collect_key_id.to_string()),
+            timestamp_value(self.received_at),
+            timestamp_value(self.event_timestamp),
+            Value::Text(self.event_timestamp.format("%Y%m%d").to_string()),
+            Value::Text(self.event_name.clone()),
+            Value::Text(self.client_id.clone()),
+            text_value(&self.user_id),
+            text_value(&self.session_id),
+            text_value(&self.page_location),
+            text_value(&self.page_referrer),
+            Value::Text(self.params.to_string()),
+            Value::Text(self.user_properties.to_string()),
+            Value::Text(self.enrichment.country.clone()),
+            Value::Text(self.enrichment.country_id.clone()),
+            Value::Text(self.enrichment.city.clone()),
+            Value::Text(self.enrichment.browser.clone()),
+            Value::Text(self.enrichment.operating_system.clone()),
+            Value::Text(self.enrichment.device_category.clone()),
+            self.filtered
+                .map(|filtered| Value::Text(filtered.reason.to_string()))
+                .unwrap_or(Value::Null),
+        ]
+    }
+
+    fn is_dropped(&self) -> bool {
+        self.filtered.is_some_and(|filtered| filtered.mode == FilterMode::Drop)
+    }
+}
+
+/// Rows of `filtered_events` counting the filtered events per day, reason and mode.
+fn filtered_counts(events: &[CollectedEvent]) -> Vec<Vec<Value>> {
+    let mut counts: HashMap<(String, String, String), i64> = HashMap::new();
+    for event in events {
+        if let Some(filtered) = event.filtered {
+            let key = (
+                event.event_timestamp.format("%Y%m%d").to_string(),
+                filtered.reason.to_string(),
+                filtered.mode.to_string(),
+            );
+            *counts.entry(key).or_default() += 1;
+        }
+    }
+
+    counts
+        .into_iter()
+        .map(|((date, reason, mode), count)| {
+            vec![Value::Text(date), Value::Text(reason), Value::Text(mode), Value::BigInt(count)]
+        })
+        .collect()
+}
+
+fn check_name(errors: &mut Vec<FieldError>, field: String, name: &str, max_length: usize) {
+    if !NAME.is_match(name) {
+        errors.push(FieldError::new(field, "must start with a letter and contain only letters, digits and underscores"));
+    } else if name.len() > max_length {
+        errors.push(FieldError::new(field, format!("must be at most {} characters", max_length)));
+    } else if RESERVED_PREFIXES.iter().any(|p| name.starts_with(p)) {
+        errors.push(FieldError::new(field, "uses a reserved prefix"));
+    }
+}
+
+fn check_timestamp(errors: &mut Vec<FieldError>, field: &str, micros: Option<i64>, now: DateTime<Utc>) {
+    let Some(micros) = micros else {
+        return;
+    };
+
+    match DateTime::from_timestamp_micros(micros) {
+        Some(timestamp) if timestamp < now - MAX_BACKDATE => {
+            errors.push(FieldError::new(field, "must be within the last 72 hours"));
+        }
+        Some(timestamp) if timestamp > now + MAX_CLOCK_SKEW => {
+            errors.push(FieldError::new(field, "must not be in the future"));
+        }
+        Some(_) => {}
+        None => errors.push(FieldError::new(field, "is not a valid timestamp")),
+    }
+}
+
+/// Checks a payload against the Measurement Protocol limits.
+pub fn validate(payload: &MeasurementPayload, now: DateTime<Utc>) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+
+    if payload.client_id.as_deref().is_none_or(str::is_empty) {
+        errors.push(FieldError::new("client_id", "is required"));
+    }
+
+    check_timestamp(&mut errors, "timestamp_micros", payload.timestamp_micros, now);
+
+    if payload.events.is_empty() {
+        errors.push(FieldError::new("events", "must contain at least one event"));
+    } else if payload.events.len() > MAX_EVENTS {
+        errors.push(FieldError::new("events", format!("must contain at most {} events", MAX_EVENTS)));
+    }
+
+    for (i, event) in payload.events.iter().enumerate() {
+        let path = format!("events[{}]", i);
+
+        check_name(&mut errors, format!("{}.name", path), &event.name, MAX_EVENT_NAME_LENGTH);
+        if RESERVED_EVENT_NAMES.contains(&event.name.as_str()) {
+            errors.push(FieldError::new(format!("{}.name", path), "is a reserved event name"));
+        }
+
+        check_timestamp(&mut errors, &format!("{}.timestamp_micros", path), event.timestamp_micros, now);
+
+        if event.params.len() > MAX_PARAMS {
+            errors.push(FieldError::new(format!("{}.params", path), format!("must have at most {} parameters", MAX_PARAMS)));
+        }
+
+        for (name, value) in &event.params {
+            let field = format!("{}.params.{}", path, name);
+            check_name(&mut errors, field.clone(), name, MAX_PARAM_NAME_LENGTH);
+
+            match value {
+                serde_json::Value::String(s) if s.chars().count() > MAX_PARAM_VALUE_LENGTH => {
+                    errors.push(FieldError::new(field, format!("must be at most {} characters", MAX_PARAM_VALUE_LENGTH)));
+                }
+                serde_json::Value::String(_) | serde_json::Value::Number(_) | serde_json::Value::Bool(_) => {}
+                // Ecommerce events carry their products in `items`
+                serde_json::Value::Array(items) if name == "items" && items.iter().all(|i| i.is_object()) => {}
+                _ => errors.push(FieldError::new(field, "must be a string, number or boolean")),
+            }
+        }
+    }
+
+    if payload.user_properties.len() > MAX_USER_PROPERTIES {
+        errors.push(FieldError::new(
+            "user_properties",
+            format!("must have at most {} properties", MAX_USER_PROPERTIES),
+        ));
+    }
+
+    for (name, property) in &payload.user_properties {
+        let field = format!("user_properties.{}", name);
+        check_name(&mut errors, field.clone(), name, MAX_USER_PROPERTY_NAME_LENGTH);
+
+        match property.get("value") {
+            Some(serde_json::Value::String(s)) if s.chars().count() > MAX_USER_PROPERTY_VALUE_LENGTH => {
+                errors.push(FieldError::new(
+                    format!("{}.value", field),
+                    format!("must be at most {} characters", MAX_USER_PROPERTY_VALUE_LENGTH),
+                ));
+            }
+            Some(serde_json::Value::String(_) | serde_json::Value::Number(_) | serde_json::Value::Bool(_)) => {}
+            _ => errors.push(FieldError::new(format!("{}.value", field), "must be a string, number or boolean")),
+        }
+    }
+
+    errors
+}
+
+fn param_string(params: &Map<String, serde_json::Value>, name: &str) -> Option<String> {
+    match params.get(name)? {
+        serde_json::Value::String(s) => Some(s.clone()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// Turns a validated payload into events to store, each with the request's enrichment.
+pub fn collected_events(
+    payload: MeasurementPayload,
+    collect_key_id: Uuid,
+    received_at: DateTime<Utc>,
+    enrichment: Enrichment,
+) -> Vec<CollectedEvent> {
+    let client_id = payload.client_id.unwrap_or_default();
+    let user_properties = serde_json::Value::Object(payload.user_properties);
+    let request_timestamp = payload.timestamp_micros.and_then(DateTime::from_timestamp_micros);
+
+    payload
+        .events
+        .into_iter()
+        .map(|event| CollectedEvent {
+            event_id: Uuid::now_v7(),
+            collect_key_id,
+            received_at,
+            event_timestamp: event
+                .timestamp_micros
+                .and_then(DateTime::from_timestamp_micros)
+                .or(request_timestamp)
+                .unwrap_or(received_at),
+            client_id: client_id.clone(),
+            user_id: payload.user_id.clone(),
+            session_id: param_string(&event.params, "session_id"),
+            page_location: param_string(&event.params, "page_location"),
+            page_referrer: param_string(&event.params, "page_referrer"),
+            event_name: event.name,
+            params: serde_json::Value::Object(event.params),
+            user_properties: user_properties.clone(),
+            enrichment: enrichment.clone(),
+            filtered: None,
+        })
+        .collect()
+}
+
+/// Collected events waiting in memory, written to each project's event store in batches.
+#[derive(Default)]
+pub struct EventBuffer {
+    pending: Mutex<HashMap<Uuid, Vec<CollectedEvent>>>,
+    /// A DuckDB file takes a single writer, every access to event stores goes through it.
+    store_lock: Mutex<()>,
+}
+
+impl EventBuffer {
+    /// Queues events, returning whether the project's batch is full and should be flushed.
+    pub fn push(&self, project_id: Uuid, events: Vec<CollectedEvent>) -> bool {
+        let mut pending = self.pending.lock().unwrap_or_else(PoisonError::into_inner);
+        let queue = pending.entry(project_id).or_default();
+        queue.extend(events);
+        queue.len() >= BATCH_SIZE
+    }
+
+    /// Runs `f` on the project's event store, one caller at a time.
+    pub fn with_store<T>(
+        &self,
+        project_id: Uuid,
+        f: impl FnOnce(&Connection) -> Result<T, String>,
+    ) -> Result<T, String> {
+        let _guard = self.store_lock.lock().unwrap_or_else(PoisonError::into_inner);
+        let conn = storage_service::open_project_store(project_id, STORE_FILE)?;
+        f(&conn)
+    }
+
+    /// Writes the pending events of a project. They are queued again if the write fails.
+    pub fn flush(&self, project_id: Uuid) -> Result<usize, String> {
+        let events = self
+            .pending
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&project_id)
+            .unwrap_or_default();
+
+        if events.is_empty() {
+            return Ok(0);
+        }
+
+        let rows = events.iter().filter(|e| !e.is_dropped()).map(CollectedEvent::row).collect();
+        let counts = filtered_counts(&events);
+        let result = self.with_store(project_id, |conn| {
+            let count = storage_service::append_rows(conn, &events_schema(), rows)?;
+            if !counts.is_empty() {
+                storage_service::append_rows(conn, &traffic_filter_service::filtered_events_schema(), counts)?;
+            }
+            Ok(count)
+        });
+
+        match &result {
+            Ok(count) => debug!(project_id = %project_id, count = count, "Flushed events"),
+            Err(_) => self.requeue(project_id, events),
+        }
+
+        result
+    }
+
+    fn requeue(&self, project_id: Uuid, mut events: Vec<CollectedEvent>) {
+        let mut pending = self.pending.lock().unwrap_or_else(PoisonError::into_inner);
+        let queue = pending.entry(project_id).or_default();
+
+        // Keep the original order, newer events may have arrived meanwhile
+        events.append(queue);
+        if events.len() > MAX_PENDING {
+            let dropped = events.len() - MAX_PENDING;
+            error!(project_id = %project_id, dropped = dropped, "Event buffer full, dropping oldest events");
+            events.drain(..dropped);
+        }
+        *queue = events;
+    }
+
+    /// Writes the pending events of every project.
+    pub fn flush_all(&self) {
+        let project_ids: Vec<Uuid> = self
+            .pending
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .keys()
+            .copied()
+            .collect();
+
+        for project_id in project_ids {
+            if let Err(e) = self.flush(project_id) {
+                error!(project_id = %project_id, error = %e, "Failed to flush events");
+            }
+        }
+    }
+
+    /// Flushes every `FLUSH_INTERVAL` until the process exits.
+    pub async fn run(self: Arc<Self>) {
+        info!(interval_secs = FLUSH_INTERVAL.as_secs(), "Starting event flusher");
+        let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+
+        loop {
+            interval.tick().await;
+            let buffer = self.clone();
+            if let Err(e) = tokio::task::spawn_blocking(move || buffer.flush_all()).await {
+                error!(error = %e, "Event flush task failed");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2026-10-18T12:00:00Z").unwrap().to_utc()
+    }
+
+    /// Fields `validate` reports errors on, in order.
+    fn invalid_fields(payload: serde_json::Value) -> Vec<String> {
+        let payload: MeasurementPayload = serde_json::from_value(payload).unwrap();
+        validate(&payload, now()).into_iter().map(|e| e.field).collect()
+    }
+
+    fn event(event: serde_json::Value) -> serde_json::Value {
+        json!({ "client_id": "123.456", "events": [event] })
+    }
+
+    fn micros(offset: TimeDelta) -> i64 {
+        (now() + offset).timestamp_micros()
+    }
+
+    #[test]
+    fn accepts_a_valid_payload() {
+        let payload = json!({
+            "client_id": "123.456",
+            "timestamp_micros": micros(TimeDelta::zero()),
+            "user_properties": { "plan": { "value": "pro" } },
+            "events": [{
+                "name": "purchase",
+                "params": {
+                    "currency": "EUR",
+                    "value": 12.5,
+                    "items": [{ "item_id": "sku-1", "quantity": 2 }],
+                },
+            }],
+        });
+
+        assert!(invalid_fields(payload).is_empty());
+    }
+
+    #[test]
+    fn requires_a_client_id_and_events() {
+        assert_eq!(invalid_fields(json!({ "client_id": "", "events": [] })), ["client_id", "events"]);
+
+        let events: Vec<_> = (0..=MAX_EVENTS).map(|_| json!({ "name": "page_view" })).collect();
+        assert_eq!(invalid_fields(json!({ "client_id": "123.456", "events": events })), ["events"]);
+    }
+
+    #[test]
+    fn rejects_reserved_names() {
+        assert_eq!(invalid_fields(event(json!({ "name": "session_start" }))), ["events[0].name"]);
+        assert_eq!(invalid_fields(event(json!({ "name": "ga_custom" }))), ["events[0].name"]);
+        assert_eq!(invalid_fields(event(json!({ "name": "1st_event" }))), ["events[0].name"]);
+        assert_eq!(
+            invalid_fields(event(json!({ "name": "page_view", "params": { "firebase_screen": "home" } }))),
+            ["events[0].params.firebase_screen"]
+        );
+        assert_eq!(
+            invalid_fields(json!({
+                "client_id": "123.456",
+                "user_properties": { "_plan": { "value": "pro" } },
+                "events": [{ "name": "page_view" }],
+            })),
+            ["user_properties._plan"]
+        );
+    }
+
+    #[test]
+    fn checks_name_and_value_lengths() {
+        let name = "a".repeat(MAX_EVENT_NAME_LENGTH);
+        assert!(invalid_fields(event(json!({ "name": name }))).is_empty());
+        let name = "a".repeat(MAX_EVENT_NAME_LENGTH + 1);
+        assert_eq!(invalid_fields(event(json!({ "name": name }))), ["events[0].name"]);
+
+        let value = "a".repeat(MAX_PARAM_VALUE_LENGTH);
+        assert!(invalid_fields(event(json!({ "name": "page_view", "params": { "page_title": value } }))).is_empty());
+        let value = "a".repeat(MAX_PARAM_VALUE_LENGTH + 1);
+        assert_eq!(
+            invalid_fields(event(json!({ "name": "page_view", "params": { "page_title": value } }))),
+            ["events[0].params.page_title"]
+        );
+    }
+
+    #[test]
+    fn accepts_timestamps_within_the_backdate_and_skew_window() {
+        for offset in [-MAX_BACKDATE, MAX_CLOCK_SKEW] {
+            let payload = json!({
+                "client_id": "123.456",
+                "timestamp_micros": micros(offset),
+                "events": [{ "name": "page_view", "timestamp_micros": micros(offset) }],
+            });
+            assert!(invalid_fields(payload).is_empty());
+        }
+    }
+
+    #[test]
+    fn rejects_timestamps_outside_the_window() {
+        let microsecond = TimeDelta::microseconds(1);
+        for offset in [-MAX_BACKDATE - microsecond, MAX_CLOCK_SKEW + microsecond] {
+            let payload = json!({
+                "client_id": "123.456",
+                "timestamp_micros": micros(offset),
+                "events": [{ "name": "page_view", "timestamp_micros": micros(offset) }],
+            });
+            assert_eq!(invalid_fields(payload), ["timestamp_micros", "events[0].timestamp_micros"]);
+        }
+
+        let payload = event(json!({ "name": "page_view", "timestamp_micros": i64::MAX }));
+        assert_eq!(invalid_fields(payload), ["events[0].timestamp_micros"]);
+    }
+
+    #[test]
+    fn only_accepts_arrays_of_objects_as_items() {
+        let items = |items: serde_json::Value| event(json!({ "name": "purchase", "params": { "items": items } }));
+
+        assert!(invalid_fields(items(json!([]))).is_empty());
+        assert!(invalid_fields(items(json!([{ "item_id": "sku-1" }]))).is_empty());
+        assert_eq!(invalid_fields(items(json!(["sku-1"]))), ["events[0].params.items"]);
+        assert_eq!(invalid_fields(items(json!({ "item_id": "sku-1" }))), ["events[0].params.items"]);
+
+        let payload = event(json!({ "name": "page_view", "params": { "tags": [{ "name": "a" }] } }));
+        assert_eq!(invalid_fields(payload), ["events[0].params.tags"]);
+    }
+
+    #[test]
+    fn requires_scalar_user_property_values() {
+        let payload = |value: serde_json::Value| {
+            json!({
+                "client_id": "123.456",
+                "user_properties": { "plan": value },
+                "events": [{ "name": "page_view" }],
+            })
+        };
+
+        assert!(invalid_fields(payload(json!({ "value": 3 }))).is_empty());
+        assert_eq!(invalid_fields(payload(json!({}))), ["user_properties.plan.value"]);
+        assert_eq!(invalid_fields(payload(json!({ "value": ["pro"] }))), ["user_properties.plan.value"]);
+        let value = "a".repeat(MAX_USER_PROPERTY_VALUE_LENGTH + 1);
+        assert_eq!(invalid_fields(payload(json!({ "value": value }))), ["user_properties.plan.value"]);
+    }
+}
@@ -0,0 +1,74 @@
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::{Engine, engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD}};
+use std::sync::LazyLock;
+use tracing::warn;
+
+/// Marks config values encrypted by `encrypt`, followed by base64 of nonce and ciphertext.
+const PREFIX: &str = "enc:v1:";
+const NONCE_LEN: usize = 12;
+
+/// Key from `CONFIG_ENCRYPTION_KEY`, 32 bytes encoded as base64.
+static CIPHER: LazyLock<Option<Aes256Gcm>> = LazyLock::new(|| {
+    let encoded = std::env::var("CONFIG_ENCRYPTION_KEY").ok()?;
+
+    match STANDARD.decode(encoded.trim()) {
+        Ok(key) if key.len() == 32 => Some(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key))),
+        _ => {
+            warn!("CONFIG_ENCRYPTION_KEY must be 32 bytes encoded as base64, secrets cannot be stored");
+            None
+        }
+    }
+});
+
+fn cipher() -> Result<&'static Aes256Gcm, String> {
+    CIPHER
+        .as_ref()
+        .ok_or_else(|| "CONFIG_ENCRYPTION_KEY is not configured".to_string())
+}
+
+pub fn is_encrypted(value: &str) -> bool {
+    value.starts_with(PREFIX)
+}
+
+/// Encrypts a secret before it is written to a connector config.
+pub fn encrypt(plaintext: &str) -> Result<String, String> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher()?
+        .encrypt(&nonce, plaintext.as_bytes())
+        .map_err(|_| "Failed to encrypt secret".to_string())?;
+
+    let mut payload = nonce.to_vec();
+    payload.extend_from_slice(&ciphertext);
+
+    Ok(format!("{}{}", PREFIX, STANDARD.encode(payload)))
+}
+
+/// Decrypts a value produced by `encrypt`.
+pub fn decrypt(value: &str) -> Result<String, String> {
+    let encoded = value
+        .strip_prefix(PREFIX)
+        .ok_or_else(|| "Secret is not encrypted".to_string())?;
+    let payload = STANDARD
+        .decode(encoded)
+        .map_err(|_| "Encrypted secret is not valid base64".to_string())?;
+
+    if payload.len() <= NONCE_LEN {
+        return Err("Encrypted secret is truncated".to_string());
+    }
+
+    let (nonce, ciphertext) = payload.split_at(NONCE_LEN);
+    let plaintext = cipher()?
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Failed to decrypt secret, was CONFIG_ENCRYPTION_KEY changed?".to_string())?;
+
+    String::from_utf8(plaintext).map_err(|_| "Decrypted secret is not UTF-8".to_string())
+}
+
+/// Random URL-safe token of `bytes` random bytes, e.g. for API keys.
+pub fn random_token(bytes: usize) -> String {
+    let mut buffer = vec![0u8; bytes];
+    OsRng.fill_bytes(&mut buffer);
+    URL_SAFE_NO_PAD.encode(buffer)
+}
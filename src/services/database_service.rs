@@ -0,0 +1,424 @@
+use duckdb::Connection;
+use tracing::{debug, info};
+
+use super::storage_service::StorageResult;
+use crate::models::connector::DatabaseEngine;
+
+pub const STORE_FILE: &str = "database.duckdb";
+
+/// Name the external database is attached under in the connector's DuckDB.
+const SOURCE_ALIAS: &str = "source";
+const STAGING_TABLE: &str = "replication_staging";
+
+pub struct Credentials<'a> {
+    pub engine: DatabaseEngine,
+    pub host: &'a str,
+    pub port: Option<u16>,
+    pub database: &'a str,
+    pub user: &'a str,
+    /// Decrypted password.
+    pub password: &'a str,
+}
+
+/// What to copy into which table of the store, see `replicate`.
+pub struct Replication<'a> {
+    /// Relation to read from, built with `table_source` or `query_source`.
+    pub source: String,
+    /// Store table written to.
+    pub table: String,
+    pub cursor_column: Option<&'a str>,
+    pub primary_key: &'a [String],
+    /// Cursor value to start from instead of the highest one already stored, only for
+    /// cursor columns checked with `is_temporal`.
+    pub since: Option<String>,
+}
+
+fn extension(engine: DatabaseEngine) -> &'static str {
+    match engine {
+        DatabaseEngine::Postgres => "postgres",
+        DatabaseEngine::Mysql => "mysql",
+    }
+}
+
+pub fn default_port(engine: DatabaseEngine) -> u16 {
+    match engine {
+        DatabaseEngine::Postgres => 5432,
+        DatabaseEngine::Mysql => 3306,
+    }
+}
+
+/// Key/value connection string understood by both the postgres and mysql extensions.
+fn connection_string(credentials: &Credentials) -> String {
+    let database_key = match credentials.engine {
+        DatabaseEngine::Postgres => "dbname",
+        DatabaseEngine::Mysql => "database",
+    };
+    let port = credentials.port.unwrap_or(default_port(credentials.engine)).to_string();
+
+    [
+        ("host", credentials.host),
+        ("port", port.as_str()),
+        (database_key, credentials.database),
+        ("user", credentials.user),
+        ("password", credentials.password),
+    ]
+    .iter()
+    .map(|(key, value)| format!("{}={}", key, connection_value(value)))
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+fn connection_value(value: &str) -> String {
+    if !value.is_empty() && !value.contains([' ', '\'', '\\']) {
+        return value.to_string();
+    }
+
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+fn sql_string(value: &str) -> String {
+    value.replace('\'', "''")
+}
+
+pub fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Loads the DuckDB extension of an engine.
+///
+/// Extensions are not downloaded at request time, they have to be installed on the server
+/// beforehand, e.g. with `duckdb -c "INSTALL postgres; INSTALL mysql;"`.
+pub fn load_extension(conn: &Connection, engine: DatabaseEngine) -> Result<(), String> {
+    let extension = extension(engine);
+
+    conn.execute_batch(&format!("LOAD {};", extension)).map_err(|e| {
+        format!(
+            "The {0} DuckDB extension is not installed on the server, install it with `INSTALL {0}`: {1}",
+            extension, e
+        )
+    })
+}
+
+/// Attaches the external database read-only, once its extension is loaded.
+pub fn attach(conn: &Connection, credentials: &Credentials) -> Result<(), String> {
+    let extension = extension(credentials.engine);
+
+    debug!(host = %credentials.host, database = %credentials.database, "Attaching database");
+    conn.execute_batch(&format!(
+        "ATTACH '{}' AS {} (TYPE {}, READ_ONLY)",
+        sql_string(&connection_string(credentials)),
+        SOURCE_ALIAS,
+        extension
+    ))
+    .map_err(|e| format!("Failed to connect to the database: {}", e))
+}
+
+/// Tables and views of the attached database as `schema.table`.
+pub fn list_tables(conn: &Connection) -> Result<Vec<String>, String> {
+    let mut statement = conn
+        .prepare(
+            "SELECT table_schema || '.' || table_name FROM information_schema.tables \
+             WHERE table_catalog = ? AND table_schema NOT IN ('information_schema', 'pg_catalog') \
+             ORDER BY 1",
+        )
+        .map_err(|e| format!("Failed to list tables: {}", e))?;
+
+    statement
+        .query_map([SOURCE_ALIAS], |row| row.get(0))
+        .and_then(|rows| rows.collect::<Result<Vec<String>, _>>())
+        .map_err(|e| format!("Failed to list tables: {}", e))
+}
+
+/// Relation reading a `schema.table` of the attached database.
+pub fn table_source(name: &str) -> Result<String, String> {
+    let (schema, table) = name
+        .split_once('.')
+        .ok_or_else(|| format!("Table '{}' must be given as schema.table", name))?;
+
+    Ok(format!(
+        "{}.{}.{}",
+        SOURCE_ALIAS,
+        quote_identifier(schema),
+        quote_identifier(table)
+    ))
+}
+
+/// Relation running a query on the attached database, in its own SQL dialect.
+pub fn query_source(engine: DatabaseEngine, sql: &str) -> String {
+    format!("{}_query('{}', '{}')", extension(engine), SOURCE_ALIAS, sql_string(sql))
+}
+
+/// Store table name for a replicated `schema.table`, e.g. `public_orders`.
+pub fn table_destination(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect()
+}
+
+fn table_exists(conn: &Connection, table: &str) -> Result<bool, String> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM information_schema.tables WHERE table_catalog = current_database() AND table_name = ?",
+        [table],
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|count| count > 0)
+    .map_err(|e| format!("Failed to inspect tables: {}", e))
+}
+
+/// Whether `column` of `source` holds dates or timestamps, the only cursors a start date
+/// can be compared with.
+pub fn is_temporal(conn: &Connection, source: &str, column: &str) -> Result<bool, String> {
+    let data_type: String = conn
+        .query_row(
+            &format!("SELECT column_type FROM (DESCRIBE SELECT * FROM {}) WHERE column_name = ?", source),
+            [column],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to read the type of cursor column {}: {}", column, e))?;
+
+    Ok(data_type == "DATE" || data_type.starts_with("TIMESTAMP"))
+}
+
+fn count(conn: &Connection, sql: &str) -> Result<usize, String> {
+    conn.query_row(sql, [], |row| row.get::<_, i64>(0))
+        .map(|count| count as usize)
+        .map_err(|e| format!("Failed to count rows: {}", e))
+}
+
+/// Deletes the stored rows sharing a primary key with staged ones, returning their count.
+fn replace_by_key(conn: &Connection, table: &str, primary_key: &[String]) -> Result<usize, String> {
+    let matches = primary_key
+        .iter()
+        .map(|column| {
+            let column = quote_identifier(column);
+            format!("{0}.{1} = {2}.{1}", table, column, STAGING_TABLE)
+        })
+        .collect::<Vec<_>>()
+        .join(" AND ");
+
+    let replaced_count = count(
+        conn,
+        &format!(
+            "SELECT COUNT(*) FROM {} WHERE EXISTS (SELECT 1 FROM {} WHERE {})",
+            STAGING_TABLE, table, matches
+        ),
+    )?;
+
+    conn.execute_batch(&format!("DELETE FROM {} USING {} WHERE {}", table, STAGING_TABLE, matches))
+        .map_err(|e| format!("Failed to replace rows: {}", e))?;
+    Ok(replaced_count)
+}
+
+/// Deletes the stored rows at or past `since`, which are all read again, returning their count.
+fn replace_since(conn: &Connection, table: &str, cursor_column: &str, since: &str) -> Result<usize, String> {
+    let filter = format!("{} >= '{}'", quote_identifier(cursor_column), sql_string(since));
+    let replaced_count = count(conn, &format!("SELECT COUNT(*) FROM {} WHERE {}", table, filter))?;
+
+    conn.execute_batch(&format!("DELETE FROM {} WHERE {}", table, filter))
+        .map_err(|e| format!("Failed to replace rows: {}", e))?;
+    Ok(replaced_count)
+}
+
+/// Copies rows from the attached database into a store table.
+///
+/// Without a cursor column the table is rebuilt on every run. With one, only rows at or
+/// past the highest stored cursor value are read; rows sharing a primary key with stored
+/// ones replace them, otherwise they are appended. Starting from `since` without a primary
+/// key replaces every stored row at or past it.
+pub fn replicate(conn: &mut Connection, replication: Replication) -> Result<StorageResult, String> {
+    let table = quote_identifier(&replication.table);
+    let exists = table_exists(conn, &replication.table)?;
+
+    let cursor = match replication.cursor_column {
+        Some(column) if replication.since.is_some() => Some((column, replication.since.clone())),
+        Some(column) if exists => {
+            let max: Option<String> = conn
+                .query_row(
+                    &format!("SELECT MAX({})::VARCHAR FROM {}", quote_identifier(column), table),
+                    [],
+                    |row| row.get(0),
+                )
+                .map_err(|e| format!("Failed to read cursor: {}", e))?;
+            Some((column, max))
+        }
+        _ => None,
+    };
+
+    let filter = match &cursor {
+        Some((column, Some(value))) => {
+            // Rows at the cursor value are read again when they can be matched by key
+            let operator = if replication.primary_key.is_empty() && replication.since.is_none() {
+                ">"
+            } else {
+                ">="
+            };
+            format!(" WHERE {} {} '{}'", quote_identifier(column), operator, sql_string(value))
+        }
+        _ => String::new(),
+    };
+
+    debug!(table = %replication.table, filter = %filter, "Reading source rows");
+    conn.execute_batch(&format!(
+        "CREATE OR REPLACE TEMP TABLE {} AS SELECT * FROM {}{}",
+        STAGING_TABLE, replication.source, filter
+    ))
+    .map_err(|e| format!("Failed to read from the database: {}", e))?;
+
+    let record_count = count(conn, &format!("SELECT COUNT(*) FROM {}", STAGING_TABLE))?;
+
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+
+    let updated_count = if !exists || cursor.is_none() {
+        tx.execute_batch(&format!("CREATE OR REPLACE TABLE {} AS SELECT * FROM {}", table, STAGING_TABLE))
+            .map_err(|e| format!("Failed to write table: {}", e))?;
+        0
+    } else {
+        let updated_count = if !replication.primary_key.is_empty() {
+            replace_by_key(&tx, &table, replication.primary_key)?
+        } else if let (Some((column, _)), Some(since)) = (&cursor, &replication.since) {
+            // Rows read again cannot be matched without a key, the ones they replace can only
+            // be told apart by the cursor
+            replace_since(&tx, &table, column, since)?.min(record_count)
+        } else {
+            0
+        };
+
+        tx.execute_batch(&format!("INSERT INTO {} BY NAME SELECT * FROM {}", table, STAGING_TABLE))
+            .map_err(|e| format!("Failed to write table: {}", e))?;
+        updated_count
+    };
+
+    tx.commit().map_err(|e| format!("Failed to commit: {}", e))?;
+
+    conn.execute_batch(&format!("DROP TABLE IF EXISTS {}", STAGING_TABLE))
+        .map_err(|e| format!("Failed to drop staging table: {}", e))?;
+
+    info!(table = %replication.table, record_count = record_count, updated = updated_count, "Table replicated");
+
+    Ok(StorageResult {
+        record_count,
+        inserted_count: record_count - updated_count,
+        updated_count,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A store whose `orders` table stands in for an attached source table.
+    fn store() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE orders (id INTEGER, status VARCHAR, updated_at TIMESTAMP);
+             INSERT INTO orders VALUES
+                 (1, 'paid', '2026-10-01 10:00:00'),
+                 (2, 'paid', '2026-10-02 10:00:00');",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn replication<'a>(cursor_column: Option<&'a str>, primary_key: &'a [String]) -> Replication<'a> {
+        Replication {
+            source: "orders".to_string(),
+            table: "public_orders".to_string(),
+            cursor_column,
+            primary_key,
+            since: None,
+        }
+    }
+
+    fn statuses(conn: &Connection) -> Vec<(i32, String)> {
+        let mut statement = conn.prepare("SELECT id, status FROM public_orders ORDER BY id").unwrap();
+        statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn upserts_rows_past_the_cursor_by_primary_key() {
+        let mut conn = store();
+        let primary_key = ["id".to_string()];
+
+        let first = replicate(&mut conn, replication(Some("updated_at"), &primary_key)).unwrap();
+        assert_eq!((first.record_count, first.inserted_count), (2, 2));
+
+        conn.execute_batch(
+            "UPDATE orders SET status = 'refunded', updated_at = '2026-10-03 10:00:00' WHERE id = 1;
+             INSERT INTO orders VALUES (3, 'paid', '2026-10-03 11:00:00');",
+        )
+        .unwrap();
+
+        let second = replicate(&mut conn, replication(Some("updated_at"), &primary_key)).unwrap();
+        // Order 2 sits at the previous cursor value and is read again
+        assert_eq!((second.record_count, second.inserted_count, second.updated_count), (3, 1, 2));
+        assert_eq!(
+            statuses(&conn),
+            [(1, "refunded".to_string()), (2, "paid".to_string()), (3, "paid".to_string())]
+        );
+    }
+
+    #[test]
+    fn only_date_and_timestamp_cursors_take_a_start_date() {
+        let conn = store();
+
+        assert!(is_temporal(&conn, "orders", "updated_at").unwrap());
+        assert!(!is_temporal(&conn, "orders", "id").unwrap());
+        assert!(is_temporal(&conn, "orders", "missing").is_err());
+    }
+
+    #[test]
+    fn replaces_rows_since_the_start_date_without_primary_key() {
+        let mut conn = store();
+
+        replicate(&mut conn, replication(Some("updated_at"), &[])).unwrap();
+        conn.execute_batch("UPDATE orders SET status = 'refunded' WHERE id = 2;").unwrap();
+
+        let mut since = replication(Some("updated_at"), &[]);
+        since.since = Some("2026-10-02".to_string());
+        let second = replicate(&mut conn, since).unwrap();
+
+        assert_eq!((second.record_count, second.inserted_count, second.updated_count), (1, 0, 1));
+        assert_eq!(statuses(&conn), [(1, "paid".to_string()), (2, "refunded".to_string())]);
+    }
+
+    #[test]
+    fn appends_rows_past_the_cursor_without_primary_key() {
+        let mut conn = store();
+
+        replicate(&mut conn, replication(Some("updated_at"), &[])).unwrap();
+        conn.execute_batch("INSERT INTO orders VALUES (3, 'paid', '2026-10-03 11:00:00');")
+            .unwrap();
+        let second = replicate(&mut conn, replication(Some("updated_at"), &[])).unwrap();
+
+        assert_eq!((second.record_count, second.inserted_count), (1, 1));
+        assert_eq!(statuses(&conn).len(), 3);
+    }
+
+    #[test]
+    fn rebuilds_tables_without_cursor() {
+        let mut conn = store();
+
+        replicate(&mut conn, replication(None, &[])).unwrap();
+        conn.execute_batch("DELETE FROM orders WHERE id = 1;").unwrap();
+        let second = replicate(&mut conn, replication(None, &[])).unwrap();
+
+        assert_eq!(second.record_count, 1);
+        assert_eq!(statuses(&conn), [(2, "paid".to_string())]);
+    }
+
+    #[test]
+    fn quotes_names_and_connection_values() {
+        assert_eq!(table_source("public.orders").unwrap(), r#"source."public"."orders""#);
+        assert!(table_source("orders").is_err());
+        assert_eq!(table_destination("Sales.Order Items"), "sales_order_items");
+        assert_eq!(connection_value("secret"), "secret");
+        assert_eq!(connection_value("it's a secret"), r"'it\'s a secret'");
+        assert_eq!(connection_value(""), "''");
+    }
+}
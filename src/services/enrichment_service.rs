@@ -0,0 +1,211 @@
+use maxminddb::{geoip2, Reader};
+use std::net::IpAddr;
+use tracing::{info, warn};
+use woothee::parser::{Parser, WootheeResult};
+
+use crate::sources::countries;
+
+/// GA4's value for dimensions it could not determine.
+const NOT_SET: &str = "(not set)";
+
+/// Location and device of the client that sent an event, in GA4's vocabulary so collected
+/// events line up with `ga4_records`.
+#[derive(Debug, Clone)]
+pub struct Enrichment {
+    /// Country name as GA4 reports it, e.g. `United States` or `Bosnia & Herzegovina`.
+    pub country: String,
+    /// ISO 3166-1 alpha-2 code, e.g. `US`.
+    pub country_id: String,
+    pub city: String,
+    pub browser: String,
+    pub operating_system: String,
+    /// `desktop`, `mobile`, `tablet` or `smart tv`.
+    pub device_category: String,
+}
+
+/// Derives an `Enrichment` from the client IP and User-Agent of a request.
+pub struct Enricher {
+    /// MaxMind City database, GeoLite2 or GeoIP2. Unset when none is configured.
+    geoip: Option<Reader<Vec<u8>>>,
+    parser: Parser,
+}
+
+impl Enricher {
+    /// Loads the database at `GEOIP_DATABASE_PATH`, leaving location `(not set)` without it.
+    pub fn from_env() -> Self {
+        let geoip = match std::env::var("GEOIP_DATABASE_PATH") {
+            Ok(path) => match Reader::open_readfile(&path) {
+                Ok(reader) => {
+                    info!(path = %path, database_type = %reader.metadata.database_type, "GeoIP database loaded");
+                    Some(reader)
+                }
+                Err(e) => {
+                    warn!(path = %path, error = %e, "Failed to load GeoIP database, location enrichment disabled");
+                    None
+                }
+            },
+            Err(_) => {
+                warn!("GEOIP_DATABASE_PATH not set, location enrichment disabled");
+                None
+            }
+        };
+
+        Self {
+            geoip,
+            parser: Parser::new(),
+        }
+    }
+
+    pub fn enrich(&self, ip: Option<IpAddr>, user_agent: Option<&str>) -> Enrichment {
+        let (country, country_id, city) = ip.map(|ip| self.locate(ip)).unwrap_or_default();
+        let user_agent = user_agent.unwrap_or_default();
+        let parsed = self.parser.parse(user_agent);
+
+        Enrichment {
+            country: country.unwrap_or_else(|| NOT_SET.to_string()),
+            country_id: country_id.unwrap_or_else(|| NOT_SET.to_string()),
+            city: city.unwrap_or_else(|| NOT_SET.to_string()),
+            browser: parsed.as_ref().map_or(NOT_SET, |p| browser(p)).to_string(),
+            operating_system: parsed.as_ref().map_or(NOT_SET, |p| operating_system(p)).to_string(),
+            device_category: parsed
+                .as_ref()
+                .map_or(NOT_SET, |p| device_category(p, user_agent))
+                .to_string(),
+        }
+    }
+
+    /// Country name, country code and city name of the IP, as far as the database knows.
+    /// Countries are named after GA4 by their code, MaxMind's English name being the fallback.
+    fn locate(&self, ip: IpAddr) -> (Option<String>, Option<String>, Option<String>) {
+        let Some(reader) = &self.geoip else {
+            return (None, None, None);
+        };
+
+        let Ok(record) = reader.lookup::<geoip2::City>(ip) else {
+            return (None, None, None);
+        };
+
+        let english = |names: Option<&std::collections::BTreeMap<&str, &str>>| {
+            names.and_then(|names| names.get("en")).map(|name| name.to_string())
+        };
+
+        let country = record.country.as_ref();
+        let country_id = country.and_then(|c| c.iso_code);
+        (
+            country_id
+                .and_then(countries::ga4_name)
+                .map(str::to_string)
+                .or_else(|| english(country.and_then(|c| c.names.as_ref()))),
+            country_id.map(str::to_string),
+            english(record.city.as_ref().and_then(|c| c.names.as_ref())),
+        )
+    }
+}
+
+fn is_ios(result: &WootheeResult) -> bool {
+    matches!(result.os, "iPhone" | "iPad" | "iPod" | "iOS")
+}
+
+fn browser<'a>(result: &WootheeResult<'a>) -> &'a str {
+    match result.name {
+        "SamsungBrowser" => "Samsung Internet",
+        "Yandex Browser" => "YaBrowser",
+        "Webview" | "Google Search App" if is_ios(result) => "Safari (in-app)",
+        "Webview" | "Google Search App" => "Android Webview",
+        woothee::woothee::VALUE_UNKNOWN => NOT_SET,
+        name => name,
+    }
+}
+
+fn operating_system<'a>(result: &WootheeResult<'a>) -> &'a str {
+    match result.os {
+        os if os.starts_with("Windows Phone") => "Windows Phone",
+        os if os.starts_with("Windows") => "Windows",
+        "Mac OSX" | "Mac OS Classic" => "Macintosh",
+        "iPhone" | "iPad" | "iPod" | "iOS" => "iOS",
+        "ChromeOS" => "Chrome OS",
+        "BlackBerry 10" => "BlackBerry",
+        woothee::woothee::VALUE_UNKNOWN => NOT_SET,
+        os => os,
+    }
+}
+
+fn device_category(result: &WootheeResult, user_agent: &str) -> &'static str {
+    // Android tablets leave `Mobile` out of their User-Agent
+    if result.os == "iPad" || (result.os == "Android" && !user_agent.contains("Mobile")) {
+        return "tablet";
+    }
+
+    match result.category {
+        "pc" => "desktop",
+        "smartphone" | "mobilephone" => "mobile",
+        "appliance" => "smart tv",
+        _ => NOT_SET,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enrich(user_agent: &str) -> (String, String, String) {
+        let enricher = Enricher {
+            geoip: None,
+            parser: Parser::new(),
+        };
+        let enrichment = enricher.enrich(None, Some(user_agent));
+        (enrichment.browser, enrichment.operating_system, enrichment.device_category)
+    }
+
+    #[test]
+    fn maps_user_agents_to_ga4_values() {
+        let cases = [
+            (
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/118.0.0.0 Safari/537.36",
+                ("Chrome", "Windows", "desktop"),
+            ),
+            (
+                "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.0 Safari/605.1.15",
+                ("Safari", "Macintosh", "desktop"),
+            ),
+            (
+                "Mozilla/5.0 (Linux; Android 13; Pixel 7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/118.0.0.0 Mobile Safari/537.36",
+                ("Chrome", "Android", "mobile"),
+            ),
+            (
+                "Mozilla/5.0 (Linux; Android 13; SM-X700) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/118.0.0.0 Safari/537.36",
+                ("Chrome", "Android", "tablet"),
+            ),
+            (
+                "Mozilla/5.0 (iPad; CPU OS 17_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.0 Mobile/15E148 Safari/604.1",
+                ("Safari", "iOS", "tablet"),
+            ),
+            (
+                "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.0 Mobile/15E148 Safari/604.1",
+                ("Safari", "iOS", "mobile"),
+            ),
+            (
+                "Mozilla/5.0 (Linux; Android 13; SM-S911B) AppleWebKit/537.36 (KHTML, like Gecko) SamsungBrowser/23.0 Chrome/115.0.0.0 Mobile Safari/537.36",
+                ("Samsung Internet", "Android", "mobile"),
+            ),
+            (
+                "Mozilla/5.0 (Linux; Android 13; Pixel 7 Build/TQ3A.230901.001; wv) AppleWebKit/537.36 (KHTML, like Gecko) Version/4.0 Chrome/118.0.0.0 Mobile Safari/537.36",
+                ("Android Webview", "Android", "mobile"),
+            ),
+            (
+                "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Mobile/15E148",
+                ("Safari (in-app)", "iOS", "mobile"),
+            ),
+            ("", ("(not set)", "(not set)", "(not set)")),
+        ];
+
+        for (user_agent, (browser, operating_system, device_category)) in cases {
+            assert_eq!(
+                enrich(user_agent),
+                (browser.to_string(), operating_system.to_string(), device_category.to_string()),
+                "{}",
+                user_agent
+            );
+        }
+    }
+}
@@ -0,0 +1,632 @@
+use duckdb::Connection;
+use std::path::{Component, Path, PathBuf};
+use tracing::{debug, info, warn};
+
+use super::storage_service::TableSchema;
+
+pub const STORE_FILE: &str = "ga4_export.duckdb";
+pub const EVENTS_TABLE: &str = "ga4_export_events";
+/// Files already imported, so syncs only pick up new ones.
+pub const FILES_TABLE: &str = "ga4_export_files";
+
+const RAW_TABLE: &str = "ga4_export_raw";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Ndjson,
+    Parquet,
+}
+
+impl ExportFormat {
+    pub fn from_file_name(file: &str) -> Option<Self> {
+        let file = file.to_lowercase();
+        let file = file.strip_suffix(".gz").unwrap_or(&file);
+
+        if file.ends_with(".parquet") {
+            Some(Self::Parquet)
+        } else if file.ends_with(".json") || file.ends_with(".ndjson") || file.ends_with(".jsonl") {
+            Some(Self::Ndjson)
+        } else {
+            None
+        }
+    }
+}
+
+/// Where export files may be read from: a directory on the server and remote prefixes,
+/// e.g. `s3://analytics-exports/ga4/`. Everything else is rejected so a connector cannot
+/// read arbitrary server files or make the server fetch arbitrary URLs.
+#[derive(Debug, Clone, Default)]
+pub struct ExportLocations {
+    root: Option<PathBuf>,
+    remote_prefixes: Vec<String>,
+}
+
+impl ExportLocations {
+    pub fn new(root: Option<&Path>, remote_prefixes: &str) -> Self {
+        let root = root.and_then(|root| match root.canonicalize() {
+            Ok(root) => Some(root),
+            Err(e) => {
+                warn!(root = %root.display(), error = %e, "GA4 export root is not accessible, local paths disabled");
+                None
+            }
+        });
+
+        let remote_prefixes = remote_prefixes
+            .split(',')
+            .map(str::trim)
+            .filter(|prefix| prefix.contains("://"))
+            .map(|prefix| format!("{}/", prefix.trim_end_matches('/')))
+            .collect();
+
+        Self { root, remote_prefixes }
+    }
+
+    /// Reads `GA4_EXPORT_ROOT` and the comma-separated `GA4_EXPORT_REMOTE_PREFIXES`.
+    pub fn from_env() -> Self {
+        let root = std::env::var("GA4_EXPORT_ROOT").ok().filter(|root| !root.is_empty());
+        let remote_prefixes = std::env::var("GA4_EXPORT_REMOTE_PREFIXES").unwrap_or_default();
+        Self::new(root.as_deref().map(Path::new), &remote_prefixes)
+    }
+
+    /// Resolves a connector `path` to the glob to read, relative paths being taken from
+    /// the root. Fails when it points outside the allowed locations.
+    pub fn resolve(&self, path: &str) -> Result<String, String> {
+        if path.contains("://") {
+            return self.resolve_remote(path);
+        }
+
+        let root = self
+            .root
+            .as_ref()
+            .ok_or("Local export paths are disabled, set GA4_EXPORT_ROOT")?;
+
+        let path = Path::new(path);
+        if path.components().any(|c| c == Component::ParentDir) {
+            return Err("Export path must not contain '..'".to_string());
+        }
+
+        // Canonicalize the part before the first wildcard, the rest is matched by the glob
+        let full = root.join(path);
+        let fixed: PathBuf = full.components().take_while(|c| !is_pattern(c)).collect();
+        let pattern: PathBuf = full.components().skip_while(|c| !is_pattern(c)).collect();
+
+        let fixed = fixed
+            .canonicalize()
+            .map_err(|e| format!("Export path {} is not accessible: {}", path.display(), e))?;
+        if !fixed.starts_with(root) {
+            return Err(format!("Export path must be under {}", root.display()));
+        }
+
+        Ok(fixed.join(pattern).to_string_lossy().into_owned())
+    }
+
+    fn resolve_remote(&self, path: &str) -> Result<String, String> {
+        if path.split('/').any(|segment| segment == "..") {
+            return Err("Export path must not contain '..'".to_string());
+        }
+
+        if !self.remote_prefixes.iter().any(|prefix| path.starts_with(prefix.as_str())) {
+            return Err("Export path is not under an allowed remote prefix".to_string());
+        }
+
+        Ok(path.to_string())
+    }
+
+    /// Whether a file matched by a resolved glob is allowed, i.e. was not reached through a
+    /// symlink leading out of the root.
+    pub fn contains(&self, file: &str) -> bool {
+        if file.contains("://") {
+            return self.resolve_remote(file).is_ok();
+        }
+
+        match (&self.root, Path::new(file).canonicalize()) {
+            (Some(root), Ok(file)) => file.starts_with(root),
+            _ => false,
+        }
+    }
+}
+
+fn is_pattern(component: &Component) -> bool {
+    component
+        .as_os_str()
+        .to_string_lossy()
+        .contains(['*', '?', '[', '{'])
+}
+
+/// One row per exported event. `event_params` and `user_properties` are JSON objects of
+/// key to value, whichever of `string_value`, `int_value`, `float_value` or `double_value`
+/// was set, e.g. `event_params->>'$.page_location'`.
+pub fn events_schema() -> TableSchema {
+    TableSchema::new(
+        EVENTS_TABLE,
+        &[
+            ("source_file", "VARCHAR"),
+            // `YYYYMMDD` in the property's time zone, like `ga4_records.date`
+            ("event_date", "VARCHAR"),
+            ("event_timestamp", "TIMESTAMP"),
+            ("event_name", "VARCHAR"),
+            ("user_pseudo_id", "VARCHAR"),
+            ("user_id", "VARCHAR"),
+            ("ga_session_id", "BIGINT"),
+            ("ga_session_number", "BIGINT"),
+            ("page_location", "VARCHAR"),
+            ("page_referrer", "VARCHAR"),
+            ("page_title", "VARCHAR"),
+            ("engagement_time_msec", "BIGINT"),
+            ("platform", "VARCHAR"),
+            ("stream_id", "VARCHAR"),
+            ("device_category", "VARCHAR"),
+            ("operating_system", "VARCHAR"),
+            ("browser", "VARCHAR"),
+            ("country", "VARCHAR"),
+            ("city", "VARCHAR"),
+            ("traffic_source", "VARCHAR"),
+            ("traffic_medium", "VARCHAR"),
+            ("traffic_campaign", "VARCHAR"),
+            ("event_value_in_usd", "DOUBLE"),
+            ("event_params", "JSON"),
+            ("user_properties", "JSON"),
+            ("ecommerce", "JSON"),
+            ("items", "JSON"),
+        ],
+        &[],
+    )
+}
+
+fn literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+fn create_tables(conn: &Connection) -> Result<(), String> {
+    let columns: Vec<String> = events_schema()
+        .columns
+        .iter()
+        .map(|c| format!("{} {}", c.name, c.data_type))
+        .collect();
+
+    conn.execute_batch(&format!(
+        r#"
+        CREATE TABLE IF NOT EXISTS {EVENTS_TABLE} ({columns});
+        CREATE TABLE IF NOT EXISTS {FILES_TABLE} (
+            file VARCHAR PRIMARY KEY,
+            imported_at TIMESTAMP,
+            row_count BIGINT
+        );
+        "#,
+        columns = columns.join(", ")
+    ))
+    .map_err(|e| format!("Failed to create tables: {}", e))
+}
+
+/// Loads httpfs for remote paths, with S3 credentials from the usual AWS sources.
+///
+/// The extension is not downloaded at request time, it has to be installed on the server
+/// beforehand, e.g. with `duckdb -c "INSTALL httpfs;"`.
+fn prepare_remote(conn: &Connection, path: &str) -> Result<(), String> {
+    if !path.contains("://") {
+        return Ok(());
+    }
+
+    conn.execute_batch("LOAD httpfs;").map_err(|e| {
+        format!(
+            "The httpfs DuckDB extension is not installed on the server, install it with `INSTALL httpfs`: {}",
+            e
+        )
+    })?;
+
+    if path.starts_with("s3://") {
+        let secret = "CREATE OR REPLACE SECRET ga4_export (TYPE s3, PROVIDER credential_chain);";
+        if let Err(e) = conn.execute_batch(secret) {
+            warn!(error = %e, "No S3 credentials found, reading anonymously");
+        }
+    }
+
+    Ok(())
+}
+
+/// Files matching the glob, with whether they were imported already.
+pub fn list_files(conn: &Connection, path: &str) -> Result<Vec<(String, bool)>, String> {
+    create_tables(conn)?;
+    prepare_remote(conn, path)?;
+
+    let mut statement = conn
+        .prepare(&format!(
+            "SELECT g.file, f.file IS NOT NULL FROM glob({}) g \
+             LEFT JOIN {FILES_TABLE} f ON f.file = g.file ORDER BY g.file",
+            literal(path)
+        ))
+        .map_err(|e| format!("Failed to list files: {}", e))?;
+
+    let files: Vec<(String, bool)> = statement
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .and_then(|rows| rows.collect())
+        .map_err(|e| format!("Failed to list files: {}", e))?;
+
+    Ok(files
+        .into_iter()
+        .filter(|(file, _)| ExportFormat::from_file_name(file).is_some())
+        .collect())
+}
+
+/// Whether the file comes from an intraday table, judged by its name alone.
+fn is_intraday(file: &str) -> bool {
+    Path::new(file)
+        .file_name()
+        .is_some_and(|name| name.to_string_lossy().contains("intraday"))
+}
+
+/// Object of the key/value list at `path`, e.g. `$.event_params`.
+fn params_object(path: &str) -> String {
+    format!(
+        r#"(
+            SELECT json_group_object(
+                p->>'$.key',
+                CASE
+                    WHEN p->>'$.value.string_value' IS NOT NULL THEN to_json(p->>'$.value.string_value')
+                    WHEN p->>'$.value.int_value' IS NOT NULL THEN to_json(TRY_CAST(p->>'$.value.int_value' AS BIGINT))
+                    WHEN p->>'$.value.double_value' IS NOT NULL THEN to_json(TRY_CAST(p->>'$.value.double_value' AS DOUBLE))
+                    WHEN p->>'$.value.float_value' IS NOT NULL THEN to_json(TRY_CAST(p->>'$.value.float_value' AS DOUBLE))
+                END
+            )
+            FROM unnest(json_extract(record, '{path}[*]')) AS params(p)
+            WHERE p->>'$.key' IS NOT NULL
+        )"#
+    )
+}
+
+/// Imports one exported file, replacing the rows of a previous import of it.
+///
+/// Rows read from the intraday table of a day are dropped once its daily table arrives.
+pub fn import_file(conn: &Connection, file: &str) -> Result<usize, String> {
+    let format = ExportFormat::from_file_name(file).ok_or_else(|| format!("Unsupported file {}", file))?;
+    create_tables(conn)?;
+    prepare_remote(conn, file)?;
+
+    // Both formats go through JSON so missing optional fields read as NULL
+    let reader = match format {
+        ExportFormat::Ndjson => format!(
+            "SELECT CAST(json AS JSON) AS record FROM read_json_objects({}, format = 'newline_delimited')",
+            literal(file)
+        ),
+        ExportFormat::Parquet => format!("SELECT to_json(p) AS record FROM read_parquet({}) p", literal(file)),
+    };
+
+    debug!(file = %file, format = ?format, "Reading GA4 export file");
+    conn.execute_batch(&format!("CREATE OR REPLACE TEMP TABLE {RAW_TABLE} AS {reader};"))
+        .map_err(|e| format!("Failed to read {}: {}", file, e))?;
+
+    let file_literal = literal(file);
+    let event_params = params_object("$.event_params");
+    let user_properties = params_object("$.user_properties");
+    let is_daily = !is_intraday(file);
+
+    let drop_intraday = if is_daily {
+        format!(
+            "DELETE FROM {EVENTS_TABLE} WHERE parse_filename(source_file) LIKE '%intraday%' \
+             AND event_date IN (SELECT DISTINCT record->>'$.event_date' FROM {RAW_TABLE});"
+        )
+    } else {
+        String::new()
+    };
+
+    conn.execute_batch(&format!(
+        r#"
+        BEGIN TRANSACTION;
+
+        DELETE FROM {EVENTS_TABLE} WHERE source_file = {file_literal};
+        {drop_intraday}
+
+        INSERT INTO {EVENTS_TABLE} BY NAME
+        SELECT
+            {file_literal} AS source_file,
+            record->>'$.event_date' AS event_date,
+            make_timestamp(TRY_CAST(record->>'$.event_timestamp' AS BIGINT)) AS event_timestamp,
+            record->>'$.event_name' AS event_name,
+            record->>'$.user_pseudo_id' AS user_pseudo_id,
+            record->>'$.user_id' AS user_id,
+            TRY_CAST(event_params->>'$.ga_session_id' AS BIGINT) AS ga_session_id,
+            TRY_CAST(event_params->>'$.ga_session_number' AS BIGINT) AS ga_session_number,
+            event_params->>'$.page_location' AS page_location,
+            event_params->>'$.page_referrer' AS page_referrer,
+            event_params->>'$.page_title' AS page_title,
+            TRY_CAST(event_params->>'$.engagement_time_msec' AS BIGINT) AS engagement_time_msec,
+            record->>'$.platform' AS platform,
+            record->>'$.stream_id' AS stream_id,
+            record->>'$.device.category' AS device_category,
+            record->>'$.device.operating_system' AS operating_system,
+            record->>'$.device.web_info.browser' AS browser,
+            record->>'$.geo.country' AS country,
+            record->>'$.geo.city' AS city,
+            record->>'$.traffic_source.source' AS traffic_source,
+            record->>'$.traffic_source.medium' AS traffic_medium,
+            record->>'$.traffic_source.name' AS traffic_campaign,
+            TRY_CAST(record->>'$.event_value_in_usd' AS DOUBLE) AS event_value_in_usd,
+            COALESCE(event_params, '{{}}') AS event_params,
+            COALESCE(user_properties, '{{}}') AS user_properties,
+            record->'$.ecommerce' AS ecommerce,
+            record->'$.items' AS items
+        FROM (
+            SELECT
+                record,
+                {event_params} AS event_params,
+                {user_properties} AS user_properties
+            FROM {RAW_TABLE}
+        ) flattened;
+
+        INSERT OR REPLACE INTO {FILES_TABLE}
+        SELECT {file_literal}, now()::TIMESTAMP, COUNT(*) FROM {RAW_TABLE};
+
+        COMMIT;
+        "#
+    ))
+    .map_err(|e| {
+        let _ = conn.execute_batch("ROLLBACK;");
+        format!("Failed to import {}: {}", file, e)
+    })?;
+
+    let row_count: i64 = conn
+        .query_row(&format!("SELECT COUNT(*) FROM {RAW_TABLE}"), [], |row| row.get(0))
+        .map_err(|e| format!("Failed to count rows: {}", e))?;
+
+    conn.execute_batch(&format!("DROP TABLE IF EXISTS {RAW_TABLE};"))
+        .map_err(|e| format!("Failed to drop temporary table: {}", e))?;
+
+    info!(file = %file, row_count = row_count, "GA4 export file imported");
+    Ok(row_count as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::fs;
+    use uuid::Uuid;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ga4_export_test_{}", Uuid::now_v7()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn resolves_paths_under_the_root() {
+        let root = temp_dir();
+        fs::create_dir_all(root.join("exports")).unwrap();
+        let locations = ExportLocations::new(Some(&root), "");
+        let root = root.canonicalize().unwrap();
+
+        let relative = locations.resolve("exports/events_*.json").unwrap();
+        assert_eq!(relative, root.join("exports/events_*.json").to_string_lossy());
+
+        let absolute = root.join("exports/*.parquet");
+        assert!(locations.resolve(&absolute.to_string_lossy()).is_ok());
+    }
+
+    #[test]
+    fn rejects_paths_outside_the_root() {
+        let root = temp_dir();
+        let locations = ExportLocations::new(Some(&root), "");
+
+        assert!(locations.resolve("../*.json").is_err());
+        assert!(locations.resolve("exports/../../*.json").is_err());
+        assert!(locations.resolve("/etc/*.json").is_err());
+        assert!(ExportLocations::new(None, "").resolve("events_*.json").is_err());
+    }
+
+    #[test]
+    fn rejects_symlinks_leaving_the_root() {
+        let root = temp_dir();
+        let outside = temp_dir();
+        fs::write(outside.join("events_1.json"), "{}").unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("link")).unwrap();
+        let locations = ExportLocations::new(Some(&root), "");
+
+        assert!(locations.resolve("link/*.json").is_err());
+        assert!(!locations.contains(&outside.join("events_1.json").to_string_lossy()));
+    }
+
+    #[test]
+    fn only_allows_remote_prefixes() {
+        let locations = ExportLocations::new(None, "s3://exports/ga4, https://cdn.example.com/ga4/");
+
+        assert!(locations.resolve("s3://exports/ga4/events_*.parquet").is_ok());
+        assert!(locations.resolve("https://cdn.example.com/ga4/events_1.json").is_ok());
+        assert!(locations.resolve("s3://exports/ga4-other/events_*.parquet").is_err());
+        assert!(locations.resolve("s3://exports/ga4/../private/*.parquet").is_err());
+        assert!(locations.resolve("http://169.254.169.254/latest/meta-data").is_err());
+    }
+
+    /// An exported event as BigQuery writes it, one per NDJSON line.
+    fn event(date: &str, name: &str, user: &str, page: &str) -> String {
+        json!({
+            "event_date": date,
+            "event_timestamp": "1760700000000000",
+            "event_name": name,
+            "user_pseudo_id": user,
+            "event_params": [
+                { "key": "page_location", "value": { "string_value": page } },
+                { "key": "ga_session_id", "value": { "int_value": "1760700000" } },
+                { "key": "engagement_time_msec", "value": { "int_value": "1200" } },
+                { "key": "percent", "value": { "double_value": 0.5 } },
+            ],
+            "user_properties": [{ "key": "plan", "value": { "string_value": "pro" } }],
+            "device": { "category": "desktop", "web_info": { "browser": "Firefox" } },
+            "geo": { "country": "France" },
+            "traffic_source": { "source": "google", "medium": "organic" },
+        })
+        .to_string()
+    }
+
+    fn write_export(dir: &Path, file: &str, events: &[String]) -> String {
+        let path = dir.join(file);
+        fs::write(&path, events.join("\n")).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    fn count(conn: &Connection, sql: &str) -> i64 {
+        conn.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    fn assert_flattened(conn: &Connection) {
+        let (page, session, engagement, percent, plan, device, browser, country): (
+            String,
+            i64,
+            i64,
+            f64,
+            String,
+            String,
+            String,
+            String,
+        ) = conn
+            .query_row(
+                &format!(
+                    "SELECT page_location, ga_session_id, engagement_time_msec, \
+                     CAST(event_params->>'$.percent' AS DOUBLE), user_properties->>'$.plan', \
+                     device_category, browser, country FROM {EVENTS_TABLE} WHERE event_name = 'page_view'"
+                ),
+                [],
+                |row| {
+                    Ok((
+                        row.get(0)?,
+                        row.get(1)?,
+                        row.get(2)?,
+                        row.get(3)?,
+                        row.get(4)?,
+                        row.get(5)?,
+                        row.get(6)?,
+                        row.get(7)?,
+                    ))
+                },
+            )
+            .unwrap();
+
+        assert_eq!(page, "https://example.com/");
+        assert_eq!(session, 1760700000);
+        assert_eq!(engagement, 1200);
+        assert_eq!(percent, 0.5);
+        assert_eq!(plan, "pro");
+        assert_eq!(device, "desktop");
+        assert_eq!(browser, "Firefox");
+        assert_eq!(country, "France");
+    }
+
+    #[test]
+    fn flattens_ndjson_events() {
+        let dir = temp_dir();
+        let conn = Connection::open_in_memory().unwrap();
+        let file = write_export(
+            &dir,
+            "events_20261017.json",
+            &[
+                event("20261017", "page_view", "a", "https://example.com/"),
+                event("20261017", "scroll", "a", "https://example.com/"),
+            ],
+        );
+
+        assert_eq!(import_file(&conn, &file).unwrap(), 2);
+        assert_flattened(&conn);
+    }
+
+    #[test]
+    fn flattens_parquet_events() {
+        let dir = temp_dir();
+        let conn = Connection::open_in_memory().unwrap();
+        let json = write_export(&dir, "source.json", &[event("20261017", "page_view", "a", "https://example.com/")]);
+        let file = dir.join("events_20261017.parquet").to_string_lossy().into_owned();
+        conn.execute_batch(&format!(
+            "COPY (SELECT * FROM read_json({})) TO {} (FORMAT parquet);",
+            literal(&json),
+            literal(&file)
+        ))
+        .unwrap();
+
+        assert_eq!(import_file(&conn, &file).unwrap(), 1);
+        assert_flattened(&conn);
+    }
+
+    #[test]
+    fn daily_file_replaces_intraday_rows() {
+        let dir = temp_dir();
+        let conn = Connection::open_in_memory().unwrap();
+        let intraday = write_export(
+            &dir,
+            "events_intraday_20261017.json",
+            &[
+                event("20261017", "page_view", "a", "https://example.com/"),
+                event("20261017", "page_view", "b", "https://example.com/"),
+            ],
+        );
+        let other_day = write_export(
+            &dir,
+            "events_intraday_20261018.json",
+            &[event("20261018", "page_view", "c", "https://example.com/")],
+        );
+        let daily = write_export(
+            &dir,
+            "events_20261017.json",
+            &[
+                event("20261017", "page_view", "a", "https://example.com/"),
+                event("20261017", "page_view", "b", "https://example.com/"),
+                event("20261017", "page_view", "d", "https://example.com/"),
+            ],
+        );
+
+        import_file(&conn, &intraday).unwrap();
+        import_file(&conn, &other_day).unwrap();
+        import_file(&conn, &daily).unwrap();
+
+        let day = format!("SELECT COUNT(*) FROM {EVENTS_TABLE} WHERE event_date = '20261017'");
+        assert_eq!(count(&conn, &day), 3);
+        let from_intraday = format!("{day} AND source_file LIKE '%intraday%'");
+        assert_eq!(count(&conn, &from_intraday), 0);
+        // Intraday rows of other days stay until their own daily file
+        let next_day = format!("SELECT COUNT(*) FROM {EVENTS_TABLE} WHERE event_date = '20261018'");
+        assert_eq!(count(&conn, &next_day), 1);
+    }
+
+    #[test]
+    fn judges_intraday_files_by_their_name() {
+        let dir = temp_dir().join("intraday_exports");
+        fs::create_dir_all(&dir).unwrap();
+        let conn = Connection::open_in_memory().unwrap();
+        let intraday = write_export(
+            &dir,
+            "events_intraday_20261017.json",
+            &[event("20261017", "page_view", "a", "https://example.com/")],
+        );
+        let daily = write_export(
+            &dir,
+            "events_20261017.json",
+            &[event("20261017", "page_view", "a", "https://example.com/")],
+        );
+
+        import_file(&conn, &intraday).unwrap();
+        import_file(&conn, &daily).unwrap();
+
+        let day = format!("SELECT COUNT(*) FROM {EVENTS_TABLE} WHERE event_date = '20261017'");
+        assert_eq!(count(&conn, &day), 1);
+        assert_eq!(count(&conn, &format!("{day} AND source_file = {}", literal(&daily))), 1);
+    }
+
+    #[test]
+    fn reimporting_a_file_replaces_its_rows() {
+        let dir = temp_dir();
+        let conn = Connection::open_in_memory().unwrap();
+        let file = write_export(
+            &dir,
+            "events_20261017.json",
+            &[
+                event("20261017", "page_view", "a", "https://example.com/"),
+                event("20261017", "page_view", "b", "https://example.com/"),
+            ],
+        );
+
+        import_file(&conn, &file).unwrap();
+        import_file(&conn, &file).unwrap();
+
+        assert_eq!(count(&conn, &format!("SELECT COUNT(*) FROM {EVENTS_TABLE}")), 2);
+        let recorded = format!("SELECT COUNT(*) FROM {FILES_TABLE} WHERE file = {} AND row_count = 2", literal(&file));
+        assert_eq!(count(&conn, &recorded), 1);
+        assert_eq!(list_files(&conn, &dir.join("events_*.json").to_string_lossy()).unwrap(), vec![(file, true)]);
+    }
+}
@@ -1,7 +1,16 @@
 use chrono::{DateTime, Duration, NaiveDate, Utc};
-use oauth2::{RefreshToken, TokenResponse, basic::BasicClient, reqwest::async_http_client};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use crate::api::error::FieldError;
+use crate::models::ga4_definition::{CustomDimension, CustomMetric, DataStream, KeyEvent, PropertyDefinitions};
+use crate::models::report_definition::{
+    FilterExpression, FunnelSpec, FunnelStepFilter, NumericOperation, PathMatch, Pivot, PivotSpec, ReportSpec,
+    StandardSpec, StringMatch,
+};
 
 // GA4 API request types
 #[derive(Debug, Serialize)]
@@ -10,6 +19,10 @@ struct RunReportRequest {
     date_ranges: Vec<DateRange>,
     dimensions: Vec<Dimension>,
     metrics: Vec<Metric>,
+    #[serde(rename = "dimensionFilter", skip_serializing_if = "Option::is_none")]
+    dimension_filter: Option<FilterExpressionRequest>,
+    #[serde(rename = "metricFilter", skip_serializing_if = "Option::is_none")]
+    metric_filter: Option<FilterExpressionRequest>,
     limit: i64,
     offset: i64,
 }
@@ -35,6 +48,10 @@ struct Metric {
 // GA4 API response types
 #[derive(Debug, Deserialize)]
 struct RunReportResponse {
+    #[serde(rename = "dimensionHeaders", default)]
+    dimension_headers: Vec<Header>,
+    #[serde(rename = "metricHeaders", default)]
+    metric_headers: Vec<Header>,
     #[serde(default)]
     rows: Vec<Row>,
     #[serde(rename = "rowCount", default)]
@@ -57,6 +74,7 @@ struct Value {
 // Flat record for storage
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GA4Record {
+    pub property_id: String,
     pub date: String,
     pub country: String,
     pub device_category: String,
@@ -69,24 +87,32 @@ pub struct GA4Record {
     pub screen_page_views: i64,
     pub bounce_rate: f64,
     pub average_session_duration: f64,
+    pub total_revenue: f64,
+    pub currency_code: String,
 }
 
 pub struct PullParams {
     pub property_id: String,
     pub access_token: String,
     pub start_date: Option<NaiveDate>,
+    /// IANA reporting time zone of the property; GA4 dates are expressed in it.
+    pub time_zone: Option<String>,
+    /// Currency of monetary metrics such as `totalRevenue`.
+    pub currency_code: Option<String>,
 }
 
 const PAGE_SIZE: i64 = 10000;
 
 pub async fn pull(params: PullParams) -> Result<Vec<GA4Record>, String> {
+    let end_date = today_in(params.time_zone.as_deref());
     let start_date = params
         .start_date
-        .unwrap_or_else(|| (Utc::now() - Duration::days(90)).date_naive());
-    let end_date = Utc::now().date_naive();
+        .unwrap_or_else(|| end_date - Duration::days(90));
+    let currency_code = params.currency_code.clone().unwrap_or_default();
 
     info!(
         property_id = %params.property_id,
+        time_zone = ?params.time_zone,
         start_date = %start_date,
         end_date = %end_date,
         "Pulling GA4 data"
@@ -98,7 +124,14 @@ pub async fn pull(params: PullParams) -> Result<Vec<GA4Record>, String> {
 
     loop {
         let request = build_request(&start_date, &end_date, offset);
-        let response = call_api(&params.property_id, &params.access_token, &request).await?;
+        let response: RunReportResponse = call_api(
+            DATA_API_BASE_URL,
+            &params.property_id,
+            &params.access_token,
+            "runReport",
+            &request,
+        )
+        .await?;
 
         if total_rows.is_none() {
             total_rows = Some(response.row_count);
@@ -106,7 +139,7 @@ pub async fn pull(params: PullParams) -> Result<Vec<GA4Record>, String> {
         }
 
         let page_count = response.rows.len();
-        let records = flatten(response);
+        let records = flatten(&params.property_id, &currency_code, response);
         all_records.extend(records);
 
         info!(
@@ -148,24 +181,31 @@ fn build_request(start_date: &NaiveDate, end_date: &NaiveDate, offset: i64) -> R
             Metric { name: "screenPageViews".to_string() },
             Metric { name: "bounceRate".to_string() },
             Metric { name: "averageSessionDuration".to_string() },
+            Metric { name: "totalRevenue".to_string() },
         ],
+        dimension_filter: None,
+        metric_filter: None,
         limit: PAGE_SIZE,
         offset,
     }
 }
 
-async fn call_api(
+pub const DATA_API_BASE_URL: &str = "https://analyticsdata.googleapis.com/v1beta";
+/// Funnel reports are only available in the alpha version.
+const DATA_API_ALPHA_BASE_URL: &str = "https://analyticsdata.googleapis.com/v1alpha";
+
+/// Calls a Data API method of the property, e.g. `runReport`.
+async fn call_api<T: serde::de::DeserializeOwned>(
+    base_url: &str,
     property_id: &str,
     access_token: &str,
-    request: &RunReportRequest,
-) -> Result<RunReportResponse, String> {
+    method: &str,
+    request: &impl Serialize,
+) -> Result<T, String> {
     let client = reqwest::Client::new();
-    let url = format!(
-        "https://analyticsdata.googleapis.com/v1beta/{}:runReport",
-        property_id
-    );
+    let url = format!("{}/{}:{}", base_url, property_id, method);
 
-    debug!("Calling GA4 Data API");
+    debug!(method = %method, "Calling GA4 Data API");
 
     let response = client
         .post(&url)
@@ -191,7 +231,7 @@ async fn call_api(
     })
 }
 
-fn flatten(response: RunReportResponse) -> Vec<GA4Record> {
+fn flatten(property_id: &str, currency_code: &str, response: RunReportResponse) -> Vec<GA4Record> {
     response
         .rows
         .into_iter()
@@ -200,18 +240,21 @@ fn flatten(response: RunReportResponse) -> Vec<GA4Record> {
             let metrics = &row.metric_values;
 
             GA4Record {
-                date: dims.get(0).map(|v| v.value.clone()).unwrap_or_default(),
+                property_id: property_id.to_string(),
+                date: dims.first().map(|v| v.value.clone()).unwrap_or_default(),
                 country: dims.get(1).map(|v| v.value.clone()).unwrap_or_default(),
                 device_category: dims.get(2).map(|v| v.value.clone()).unwrap_or_default(),
                 event_name: dims.get(3).map(|v| v.value.clone()).unwrap_or_default(),
                 browser: dims.get(4).map(|v| v.value.clone()).unwrap_or_default(),
                 operating_system: dims.get(5).map(|v| v.value.clone()).unwrap_or_default(),
                 screen_resolution: dims.get(6).map(|v| v.value.clone()).unwrap_or_default(),
-                active_users: parse_i64(metrics.get(0)),
+                active_users: parse_i64(metrics.first()),
                 sessions: parse_i64(metrics.get(1)),
                 screen_page_views: parse_i64(metrics.get(2)),
                 bounce_rate: parse_f64(metrics.get(3)),
                 average_session_duration: parse_f64(metrics.get(4)),
+                total_revenue: parse_f64(metrics.get(5)),
+                currency_code: currency_code.to_string(),
             }
         })
         .collect()
@@ -229,45 +272,1610 @@ fn parse_f64(value: Option<&Value>) -> f64 {
         .unwrap_or(0.0)
 }
 
-// Token refresh
-#[derive(Debug, Clone)]
-pub struct TokenInfo {
-    pub access_token: String,
-    pub refresh_token: Option<String>,
-    pub expires_at: Option<DateTime<Utc>>,
+// GA4 Realtime API types
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RunRealtimeReportRequest {
+    dimensions: Vec<Dimension>,
+    metrics: Vec<Metric>,
+    minute_ranges: Vec<MinuteRange>,
+    limit: i64,
 }
 
-pub fn is_token_expired(expires_at: Option<DateTime<Utc>>) -> bool {
-    expires_at.map(|exp| exp < Utc::now()).unwrap_or(false)
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MinuteRange {
+    start_minutes_ago: u32,
+    end_minutes_ago: u32,
 }
 
-pub async fn refresh_token(
-    oauth_client: &BasicClient,
-    refresh_token: &str,
-) -> Result<TokenInfo, String> {
-    warn!("Access token expired, refreshing...");
+#[derive(Debug, Deserialize)]
+struct Header {
+    name: String,
+}
 
-    let token = oauth_client
-        .exchange_refresh_token(&RefreshToken::new(refresh_token.to_string()))
-        .request_async(async_http_client)
-        .await
-        .map_err(|e| {
-            error!(error = %e, "Failed to refresh token");
-            format!("Failed to refresh token: {}", e)
-        })?;
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RunRealtimeReportResponse {
+    #[serde(default)]
+    dimension_headers: Vec<Header>,
+    #[serde(default)]
+    metric_headers: Vec<Header>,
+    #[serde(default)]
+    rows: Vec<Row>,
+    #[serde(default)]
+    row_count: i64,
+}
+
+/// Dimensions and metrics of the Realtime API schema, custom user dimensions aside.
+pub const REALTIME_DIMENSIONS: [&str; 15] = [
+    "appVersion",
+    "audienceId",
+    "audienceName",
+    "audienceResourceName",
+    "city",
+    "cityId",
+    "country",
+    "countryId",
+    "deviceCategory",
+    "eventName",
+    "minutesAgo",
+    "platform",
+    "streamId",
+    "streamName",
+    "unifiedScreenName",
+];
+pub const REALTIME_METRICS: [&str; 4] = ["activeUsers", "eventCount", "keyEvents", "screenPageViews"];
+/// Standard properties go back 29 minutes, GA4 360 ones 59.
+pub const MAX_MINUTES_AGO: u32 = 59;
+const REALTIME_ROW_LIMIT: i64 = 10000;
+
+pub struct RealtimeParams<'a> {
+    pub property_id: &'a str,
+    pub access_token: &'a str,
+    pub dimensions: &'a [String],
+    pub metrics: &'a [String],
+    /// Start of the window, up to now.
+    pub minutes_ago: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RealtimeRow {
+    pub dimensions: BTreeMap<String, String>,
+    pub metrics: BTreeMap<String, f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RealtimeReport {
+    pub property_id: String,
+    pub minutes_ago: u32,
+    pub row_count: i64,
+    pub rows: Vec<RealtimeRow>,
+    pub fetched_at: DateTime<Utc>,
+}
+
+/// Field errors of a realtime request, checked before spending quota on it.
+pub fn validate_realtime(dimensions: &[String], metrics: &[String], minutes_ago: u32) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+
+    for (i, dimension) in dimensions.iter().enumerate() {
+        if !REALTIME_DIMENSIONS.contains(&dimension.as_str()) && !dimension.starts_with("customUser:") {
+            errors.push(FieldError::new(format!("dimensions[{}]", i), "is not a realtime dimension"));
+        }
+    }
+    if metrics.is_empty() {
+        errors.push(FieldError::new("metrics", "must not be empty"));
+    }
+    for (i, metric) in metrics.iter().enumerate() {
+        if !REALTIME_METRICS.contains(&metric.as_str()) {
+            errors.push(FieldError::new(format!("metrics[{}]", i), "is not a realtime metric"));
+        }
+    }
+    if minutes_ago > MAX_MINUTES_AGO {
+        errors.push(FieldError::new(
+            "minutes_ago",
+            format!("must be at most {}", MAX_MINUTES_AGO),
+        ));
+    }
+
+    errors
+}
+
+/// Activity of the last `minutes_ago` minutes, broken down by the given dimensions.
+pub async fn run_realtime_report(params: RealtimeParams<'_>) -> Result<RealtimeReport, String> {
+    let request = RunRealtimeReportRequest {
+        dimensions: params
+            .dimensions
+            .iter()
+            .map(|name| Dimension { name: name.clone() })
+            .collect(),
+        metrics: params
+            .metrics
+            .iter()
+            .map(|name| Metric { name: name.clone() })
+            .collect(),
+        minute_ranges: vec![MinuteRange {
+            start_minutes_ago: params.minutes_ago,
+            end_minutes_ago: 0,
+        }],
+        limit: REALTIME_ROW_LIMIT,
+    };
+
+    let response: RunRealtimeReportResponse = call_api(
+        DATA_API_BASE_URL,
+        params.property_id,
+        params.access_token,
+        "runRealtimeReport",
+        &request,
+    )
+    .await?;
+
+    let rows = response
+        .rows
+        .into_iter()
+        .map(|row| RealtimeRow {
+            dimensions: response
+                .dimension_headers
+                .iter()
+                .zip(row.dimension_values)
+                .map(|(header, value)| (header.name.clone(), value.value))
+                .collect(),
+            metrics: response
+                .metric_headers
+                .iter()
+                .zip(row.metric_values)
+                .map(|(header, value)| (header.name.clone(), value.value.parse().unwrap_or(0.0)))
+                .collect(),
+        })
+        .collect();
+
+    debug!(property_id = %params.property_id, row_count = response.row_count, "Fetched realtime report");
+
+    Ok(RealtimeReport {
+        property_id: params.property_id.to_string(),
+        minutes_ago: params.minutes_ago,
+        row_count: response.row_count,
+        rows,
+        fetched_at: Utc::now(),
+    })
+}
+
+// GA4 Funnel API types
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RunFunnelReportRequest {
+    date_ranges: Vec<DateRange>,
+    funnel: Funnel,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    funnel_breakdown: Option<FunnelBreakdown>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Funnel {
+    is_open_funnel: bool,
+    steps: Vec<FunnelStepRequest>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FunnelStepRequest {
+    name: String,
+    is_directly_followed_by: bool,
+    /// Duration such as `300s`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    within_duration_from_prior_step: Option<String>,
+    filter_expression: FunnelFilterExpression,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+enum FunnelFilterExpression {
+    OrGroup(FunnelFilterExpressionList),
+    FunnelEventFilter(FunnelEventFilter),
+    FunnelFieldFilter(FunnelFieldFilter),
+}
+
+#[derive(Debug, Serialize)]
+struct FunnelFilterExpressionList {
+    expressions: Vec<FunnelFilterExpression>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FunnelEventFilter {
+    event_name: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FunnelFieldFilter {
+    field_name: String,
+    string_filter: StringFilter,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StringFilter {
+    match_type: &'static str,
+    value: String,
+    case_sensitive: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FunnelBreakdown {
+    breakdown_dimension: Dimension,
+    limit: i64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RunFunnelReportResponse {
+    #[serde(default)]
+    funnel_table: Option<FunnelSubReport>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FunnelSubReport {
+    #[serde(default)]
+    dimension_headers: Vec<Header>,
+    #[serde(default)]
+    metric_headers: Vec<Header>,
+    #[serde(default)]
+    rows: Vec<Row>,
+}
+
+/// GA4 caps funnels at 10 steps.
+pub const MAX_FUNNEL_STEPS: usize = 10;
+const FUNNEL_BREAKDOWN_LIMIT: i64 = 100;
+/// Breakdown value of the rows summing up all breakdown values.
+pub const FUNNEL_TOTAL: &str = "(total)";
+
+pub struct FunnelParams<'a> {
+    pub property_id: &'a str,
+    pub access_token: &'a str,
+    pub spec: &'a FunnelSpec,
+    pub date: NaiveDate,
+}
+
+/// One step of a funnel on one day, for one breakdown value.
+#[derive(Debug, Clone, Serialize)]
+pub struct FunnelRow {
+    pub date: NaiveDate,
+    /// 1-based position of the step.
+    pub step_number: i32,
+    pub step_name: String,
+    /// Value of the breakdown dimension, or `FUNNEL_TOTAL`.
+    pub breakdown: String,
+    pub active_users: i64,
+    pub completion_rate: f64,
+    pub abandonments: i64,
+    pub abandonment_rate: f64,
+}
+
+/// Field errors of a funnel definition, checked before spending quota on it.
+pub fn validate_funnel(spec: &FunnelSpec) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+
+    if spec.steps.is_empty() {
+        errors.push(FieldError::new("spec.steps", "must not be empty"));
+    }
+    if spec.steps.len() > MAX_FUNNEL_STEPS {
+        errors.push(FieldError::new(
+            "spec.steps",
+            format!("must have at most {} steps", MAX_FUNNEL_STEPS),
+        ));
+    }
+    for (i, step) in spec.steps.iter().enumerate() {
+        if step.name.trim().is_empty() {
+            errors.push(FieldError::new(format!("spec.steps[{}].name", i), "must not be empty"));
+        }
+        match &step.filter {
+            FunnelStepFilter::Event { event_names } => {
+                if event_names.is_empty() || event_names.iter().any(|n| n.trim().is_empty()) {
+                    errors.push(FieldError::new(
+                        format!("spec.steps[{}].filter.event_names", i),
+                        "must list non-empty event names",
+                    ));
+                }
+            }
+            FunnelStepFilter::Page { page_path, .. } => {
+                if page_path.trim().is_empty() {
+                    errors.push(FieldError::new(
+                        format!("spec.steps[{}].filter.page_path", i),
+                        "must not be empty",
+                    ));
+                }
+            }
+        }
+        if i == 0 && step.within_seconds_of_prior_step.is_some() {
+            errors.push(FieldError::new(
+                "spec.steps[0].within_seconds_of_prior_step",
+                "must not be set on the first step",
+            ));
+        }
+    }
+    if spec.breakdown_dimension.as_deref().is_some_and(|d| d.trim().is_empty()) {
+        errors.push(FieldError::new("spec.breakdown_dimension", "must not be empty"));
+    }
+
+    errors
+}
+
+fn funnel_step_filter(filter: &FunnelStepFilter) -> FunnelFilterExpression {
+    match filter {
+        FunnelStepFilter::Event { event_names } => {
+            let mut expressions: Vec<FunnelFilterExpression> = event_names
+                .iter()
+                .map(|name| {
+                    FunnelFilterExpression::FunnelEventFilter(FunnelEventFilter {
+                        event_name: name.clone(),
+                    })
+                })
+                .collect();
+
+            if expressions.len() == 1 {
+                expressions.remove(0)
+            } else {
+                FunnelFilterExpression::OrGroup(FunnelFilterExpressionList { expressions })
+            }
+        }
+        FunnelStepFilter::Page { page_path, match_type } => {
+            FunnelFilterExpression::FunnelFieldFilter(FunnelFieldFilter {
+                field_name: "unifiedPagePathScreen".to_string(),
+                string_filter: StringFilter {
+                    match_type: match match_type {
+                        PathMatch::Exact => "EXACT",
+                        PathMatch::BeginsWith => "BEGINS_WITH",
+                        PathMatch::Contains => "CONTAINS",
+                        PathMatch::FullRegexp => "FULL_REGEXP",
+                    },
+                    value: page_path.clone(),
+                    case_sensitive: false,
+                },
+            })
+        }
+    }
+}
+
+/// Runs a funnel over a single day, so results can be stored by date.
+pub async fn run_funnel_report(params: FunnelParams<'_>) -> Result<Vec<FunnelRow>, String> {
+    let date = params.date.format("%Y-%m-%d").to_string();
+    let request = RunFunnelReportRequest {
+        date_ranges: vec![DateRange {
+            start_date: date.clone(),
+            end_date: date,
+        }],
+        funnel: Funnel {
+            is_open_funnel: params.spec.is_open_funnel,
+            steps: params
+                .spec
+                .steps
+                .iter()
+                .map(|step| FunnelStepRequest {
+                    name: step.name.clone(),
+                    is_directly_followed_by: step.is_directly_followed_by,
+                    within_duration_from_prior_step: step.within_seconds_of_prior_step.map(|s| format!("{}s", s)),
+                    filter_expression: funnel_step_filter(&step.filter),
+                })
+                .collect(),
+        },
+        funnel_breakdown: params.spec.breakdown_dimension.as_ref().map(|name| FunnelBreakdown {
+            breakdown_dimension: Dimension { name: name.clone() },
+            limit: FUNNEL_BREAKDOWN_LIMIT,
+        }),
+    };
+
+    let response: RunFunnelReportResponse = call_api(
+        DATA_API_ALPHA_BASE_URL,
+        params.property_id,
+        params.access_token,
+        "runFunnelReport",
+        &request,
+    )
+    .await?;
+
+    let Some(table) = response.funnel_table else {
+        return Ok(Vec::new());
+    };
+
+    let metric_index = |name: &str| table.metric_headers.iter().position(|h| h.name == name);
+    let active_users = metric_index("activeUsers");
+    let completion_rate = metric_index("funnelStepCompletionRate");
+    let abandonments = metric_index("funnelStepAbandonments");
+    let abandonment_rate = metric_index("funnelStepAbandonmentRate");
+    let has_breakdown = table.dimension_headers.len() > 1;
+
+    let rows: Vec<FunnelRow> = table
+        .rows
+        .iter()
+        .map(|row| {
+            let metric = |index: Option<usize>| index.and_then(|i| row.metric_values.get(i));
+            // Steps come back as "1. Name"
+            let step = row.dimension_values.first().map(|v| v.value.as_str()).unwrap_or_default();
+            let (step_number, step_name) = match step.split_once(". ") {
+                Some((number, name)) => (number.parse().unwrap_or(0), name.to_string()),
+                None => (0, step.to_string()),
+            };
+            let breakdown = match row.dimension_values.get(1) {
+                Some(value) if has_breakdown && value.value != "RESERVED_TOTAL" => value.value.clone(),
+                _ => FUNNEL_TOTAL.to_string(),
+            };
+
+            FunnelRow {
+                date: params.date,
+                step_number,
+                step_name,
+                breakdown,
+                active_users: parse_i64(metric(active_users)),
+                completion_rate: parse_f64(metric(completion_rate)),
+                abandonments: parse_i64(metric(abandonments)),
+                abandonment_rate: parse_f64(metric(abandonment_rate)),
+            }
+        })
+        .collect();
+
+    debug!(property_id = %params.property_id, date = %params.date, row_count = rows.len(), "Fetched funnel report");
+    Ok(rows)
+}
+
+// GA4 batch and pivot API types
+#[derive(Debug, Serialize)]
+struct BatchRunReportsRequest {
+    requests: Vec<RunReportRequest>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchRunReportsResponse {
+    #[serde(default)]
+    reports: Vec<RunReportResponse>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RunPivotReportRequest {
+    date_ranges: Vec<DateRange>,
+    dimensions: Vec<Dimension>,
+    metrics: Vec<Metric>,
+    pivots: Vec<PivotRequest>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dimension_filter: Option<FilterExpressionRequest>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metric_filter: Option<FilterExpressionRequest>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PivotRequest {
+    field_names: Vec<String>,
+    limit: i64,
+}
+
+/// `batchRunReports` takes at most 5 requests.
+const MAX_BATCH_REQUESTS: usize = 5;
+/// GA4 caps requests at 9 dimensions and 10 metrics; `date` takes one dimension.
+pub const MAX_REPORT_DIMENSIONS: usize = 8;
+pub const MAX_REPORT_METRICS: usize = 10;
+const DEFAULT_PIVOT_LIMIT: u32 = 100;
+
+/// One row of a standard or pivot report on one day.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportRow {
+    pub date: NaiveDate,
+    /// Dimension values by name, `date` aside.
+    pub dimensions: BTreeMap<String, String>,
+    pub metrics: BTreeMap<String, f64>,
+}
+
+/// Field errors of a standard (no pivots) or pivot report, checked before spending quota on it.
+pub fn validate_report(dimensions: &[String], metrics: &[String], pivots: Option<&[Pivot]>) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+
+    if dimensions.len() > MAX_REPORT_DIMENSIONS {
+        errors.push(FieldError::new(
+            "spec.dimensions",
+            format!("must have at most {} dimensions", MAX_REPORT_DIMENSIONS),
+        ));
+    }
+    for (i, dimension) in dimensions.iter().enumerate() {
+        if dimension.trim().is_empty() || dimension == "date" {
+            errors.push(FieldError::new(
+                format!("spec.dimensions[{}]", i),
+                "must be a dimension other than date, which is always added",
+            ));
+        }
+    }
+    if metrics.is_empty() || metrics.len() > MAX_REPORT_METRICS {
+        errors.push(FieldError::new(
+            "spec.metrics",
+            format!("must have between 1 and {} metrics", MAX_REPORT_METRICS),
+        ));
+    }
+    if metrics.iter().any(|m| m.trim().is_empty()) {
+        errors.push(FieldError::new("spec.metrics", "must not contain empty names"));
+    }
+
+    if let Some(pivots) = pivots {
+        if pivots.is_empty() {
+            errors.push(FieldError::new("spec.pivots", "must not be empty"));
+        }
+        for (i, pivot) in pivots.iter().enumerate() {
+            if pivot.field_names.is_empty() {
+                errors.push(FieldError::new(format!("spec.pivots[{}].field_names", i), "must not be empty"));
+            }
+            if let Some(unknown) = pivot.field_names.iter().find(|f| !dimensions.contains(f)) {
+                errors.push(FieldError::new(
+                    format!("spec.pivots[{}].field_names", i),
+                    format!("{} is not one of the dimensions", unknown),
+                ));
+            }
+            if pivot.limit == Some(0) {
+                errors.push(FieldError::new(format!("spec.pivots[{}].limit", i), "must be positive"));
+            }
+        }
+        for dimension in dimensions {
+            let count = pivots.iter().filter(|p| p.field_names.contains(dimension)).count();
+            if count != 1 {
+                errors.push(FieldError::new(
+                    "spec.pivots",
+                    format!("{} must appear in exactly one pivot", dimension),
+                ));
+            }
+        }
+    }
+
+    errors
+}
+
+/// `date` first, then the requested dimensions.
+fn report_dimensions(dimensions: &[String]) -> Vec<Dimension> {
+    std::iter::once("date")
+        .chain(dimensions.iter().map(String::as_str))
+        .map(|name| Dimension { name: name.to_string() })
+        .collect()
+}
+
+fn report_rows(response: RunReportResponse) -> Vec<ReportRow> {
+    let headers = &response.dimension_headers;
+    let metric_headers = &response.metric_headers;
+
+    response
+        .rows
+        .into_iter()
+        .filter_map(|row| {
+            let mut dimensions: BTreeMap<String, String> = headers
+                .iter()
+                .zip(row.dimension_values)
+                .map(|(header, value)| (header.name.clone(), value.value))
+                .collect();
+            let date = dimensions.remove("date")?;
+            let date = NaiveDate::parse_from_str(&date, "%Y%m%d").ok()?;
+
+            Some(ReportRow {
+                date,
+                dimensions,
+                metrics: metric_headers
+                    .iter()
+                    .zip(row.metric_values)
+                    .map(|(header, value)| (header.name.clone(), value.value.parse().unwrap_or(0.0)))
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+/// Runs standard reports of a property over the same dates, `MAX_BATCH_REQUESTS` per round
+/// trip, paging through each of them. Rows are returned in the order of `reports`.
+pub async fn batch_run_reports(
+    base_url: &str,
+    property_id: &str,
+    access_token: &str,
+    reports: &[&StandardSpec],
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<Vec<Vec<ReportRow>>, String> {
+    let mut results: Vec<Vec<ReportRow>> = vec![Vec::new(); reports.len()];
+    // Index of the report and offset of its next page
+    let mut pending: Vec<(usize, i64)> = (0..reports.len()).map(|i| (i, 0)).collect();
+
+    while !pending.is_empty() {
+        let batch: Vec<(usize, i64)> = pending.drain(..pending.len().min(MAX_BATCH_REQUESTS)).collect();
+
+        let request = BatchRunReportsRequest {
+            requests: batch
+                .iter()
+                .map(|&(i, offset)| RunReportRequest {
+                    date_ranges: vec![DateRange {
+                        start_date: start_date.format("%Y-%m-%d").to_string(),
+                        end_date: end_date.format("%Y-%m-%d").to_string(),
+                    }],
+                    dimensions: report_dimensions(&reports[i].dimensions),
+                    metrics: reports[i]
+                        .metrics
+                        .iter()
+                        .map(|name| Metric { name: name.clone() })
+                        .collect(),
+                    dimension_filter: reports[i].dimension_filter.as_ref().map(filter_expression),
+                    metric_filter: reports[i].metric_filter.as_ref().map(filter_expression),
+                    limit: PAGE_SIZE,
+                    offset,
+                })
+                .collect(),
+        };
+
+        let response: BatchRunReportsResponse =
+            call_api(base_url, property_id, access_token, "batchRunReports", &request).await?;
+
+        if response.reports.len() != batch.len() {
+            return Err(format!(
+                "GA4 batch returned {} reports for {} requests",
+                response.reports.len(),
+                batch.len()
+            ));
+        }
+
+        for ((i, offset), report) in batch.into_iter().zip(response.reports) {
+            let page_count = report.rows.len();
+            results[i].extend(report_rows(report));
+
+            if page_count == PAGE_SIZE as usize {
+                pending.push((i, offset + PAGE_SIZE));
+            }
+        }
+    }
+
+    debug!(
+        property_id = %property_id,
+        report_count = reports.len(),
+        start_date = %start_date,
+        end_date = %end_date,
+        "Fetched batched reports"
+    );
+    Ok(results)
+}
+
+/// Runs a pivot report, flattened to one row per combination of dimension values.
+///
+/// `date` is added as its own pivot covering every day of the range, the pivot limits
+/// bound the other combinations.
+pub async fn run_pivot_report(
+    base_url: &str,
+    property_id: &str,
+    access_token: &str,
+    spec: &PivotSpec,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<Vec<ReportRow>, String> {
+    let days = (end_date - start_date).num_days() + 1;
+
+    let request = RunPivotReportRequest {
+        date_ranges: vec![DateRange {
+            start_date: start_date.format("%Y-%m-%d").to_string(),
+            end_date: end_date.format("%Y-%m-%d").to_string(),
+        }],
+        dimensions: report_dimensions(&spec.dimensions),
+        metrics: spec.metrics.iter().map(|name| Metric { name: name.clone() }).collect(),
+        pivots: std::iter::once(PivotRequest {
+            field_names: vec!["date".to_string()],
+            limit: days,
+        })
+        .chain(spec.pivots.iter().map(|pivot| PivotRequest {
+            field_names: pivot.field_names.clone(),
+            limit: pivot.limit.unwrap_or(DEFAULT_PIVOT_LIMIT) as i64,
+        }))
+        .collect(),
+        dimension_filter: spec.dimension_filter.as_ref().map(filter_expression),
+        metric_filter: spec.metric_filter.as_ref().map(filter_expression),
+    };
+
+    let response: RunReportResponse =
+        call_api(base_url, property_id, access_token, "runPivotReport", &request).await?;
+
+    let rows = report_rows(response);
+    debug!(property_id = %property_id, row_count = rows.len(), "Fetched pivot report");
+    Ok(rows)
+}
+
+// GA4 filter and metadata API types
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+enum FilterExpressionRequest {
+    AndGroup(FilterExpressionList),
+    OrGroup(FilterExpressionList),
+    NotExpression(Box<FilterExpressionRequest>),
+    Filter(FilterRequest),
+}
+
+#[derive(Debug, Serialize)]
+struct FilterExpressionList {
+    expressions: Vec<FilterExpressionRequest>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FilterRequest {
+    field_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    string_filter: Option<StringFilter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    in_list_filter: Option<InListFilter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    numeric_filter: Option<NumericFilter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    between_filter: Option<BetweenFilter>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct InListFilter {
+    values: Vec<String>,
+    case_sensitive: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct NumericFilter {
+    operation: &'static str,
+    value: NumericValue,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BetweenFilter {
+    from_value: NumericValue,
+    to_value: NumericValue,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct NumericValue {
+    double_value: f64,
+}
+
+/// Dimensions and metrics a property can report on, custom definitions included.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Metadata {
+    #[serde(default)]
+    pub dimensions: Vec<MetadataField>,
+    #[serde(default)]
+    pub metrics: Vec<MetadataField>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetadataField {
+    /// Name used in requests, e.g. `customEvent:plan`.
+    pub api_name: String,
+    /// Name shown in the GA4 UI.
+    #[serde(default)]
+    pub ui_name: String,
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub custom_definition: bool,
+}
+
+/// Expressions nest at most this deep, like in the GA4 UI.
+const MAX_FILTER_DEPTH: usize = 5;
+
+fn filter_expression(filter: &FilterExpression) -> FilterExpressionRequest {
+    let field = |field_name: &str| FilterRequest {
+        field_name: field_name.to_string(),
+        string_filter: None,
+        in_list_filter: None,
+        numeric_filter: None,
+        between_filter: None,
+    };
+
+    match filter {
+        FilterExpression::And { expressions } => FilterExpressionRequest::AndGroup(FilterExpressionList {
+            expressions: expressions.iter().map(filter_expression).collect(),
+        }),
+        FilterExpression::Or { expressions } => FilterExpressionRequest::OrGroup(FilterExpressionList {
+            expressions: expressions.iter().map(filter_expression).collect(),
+        }),
+        FilterExpression::Not { expression } => {
+            FilterExpressionRequest::NotExpression(Box::new(filter_expression(expression)))
+        }
+        FilterExpression::String {
+            field_name,
+            value,
+            match_type,
+            case_sensitive,
+        } => FilterExpressionRequest::Filter(FilterRequest {
+            string_filter: Some(StringFilter {
+                match_type: match match_type {
+                    StringMatch::Exact => "EXACT",
+                    StringMatch::BeginsWith => "BEGINS_WITH",
+                    StringMatch::EndsWith => "ENDS_WITH",
+                    StringMatch::Contains => "CONTAINS",
+                    StringMatch::FullRegexp => "FULL_REGEXP",
+                    StringMatch::PartialRegexp => "PARTIAL_REGEXP",
+                },
+                value: value.clone(),
+                case_sensitive: *case_sensitive,
+            }),
+            ..field(field_name)
+        }),
+        FilterExpression::InList {
+            field_name,
+            values,
+            case_sensitive,
+        } => FilterExpressionRequest::Filter(FilterRequest {
+            in_list_filter: Some(InListFilter {
+                values: values.clone(),
+                case_sensitive: *case_sensitive,
+            }),
+            ..field(field_name)
+        }),
+        FilterExpression::Numeric {
+            field_name,
+            operation,
+            value,
+        } => FilterExpressionRequest::Filter(FilterRequest {
+            numeric_filter: Some(NumericFilter {
+                operation: match operation {
+                    NumericOperation::Equal => "EQUAL",
+                    NumericOperation::LessThan => "LESS_THAN",
+                    NumericOperation::LessThanOrEqual => "LESS_THAN_OR_EQUAL",
+                    NumericOperation::GreaterThan => "GREATER_THAN",
+                    NumericOperation::GreaterThanOrEqual => "GREATER_THAN_OR_EQUAL",
+                },
+                value: NumericValue { double_value: *value },
+            }),
+            ..field(field_name)
+        }),
+        FilterExpression::Between {
+            field_name,
+            from_value,
+            to_value,
+        } => FilterExpressionRequest::Filter(FilterRequest {
+            between_filter: Some(BetweenFilter {
+                from_value: NumericValue {
+                    double_value: *from_value,
+                },
+                to_value: NumericValue { double_value: *to_value },
+            }),
+            ..field(field_name)
+        }),
+    }
+}
+
+/// Structural errors of a filter tree at `path`, e.g. `spec.dimension_filter`. Metric filters
+/// only take numeric and between conditions.
+pub fn validate_filter(filter: &FilterExpression, path: &str, is_metric: bool) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    validate_filter_node(filter, path, is_metric, 1, &mut errors);
+    errors
+}
+
+fn validate_filter_node(
+    filter: &FilterExpression,
+    path: &str,
+    is_metric: bool,
+    depth: usize,
+    errors: &mut Vec<FieldError>,
+) {
+    if depth > MAX_FILTER_DEPTH {
+        errors.push(FieldError::new(
+            path,
+            format!("must not nest more than {} levels", MAX_FILTER_DEPTH),
+        ));
+        return;
+    }
+
+    match filter {
+        FilterExpression::And { expressions } | FilterExpression::Or { expressions } => {
+            if expressions.is_empty() {
+                errors.push(FieldError::new(format!("{}.expressions", path), "must not be empty"));
+            }
+            for (i, expression) in expressions.iter().enumerate() {
+                let path = format!("{}.expressions[{}]", path, i);
+                validate_filter_node(expression, &path, is_metric, depth + 1, errors);
+            }
+        }
+        FilterExpression::Not { expression } => {
+            let path = format!("{}.expression", path);
+            validate_filter_node(expression, &path, is_metric, depth + 1, errors);
+        }
+        FilterExpression::String {
+            field_name,
+            value,
+            match_type,
+            ..
+        } => {
+            if is_metric {
+                errors.push(FieldError::new(path, "metric filters only take numeric and between conditions"));
+            }
+            if matches!(match_type, StringMatch::FullRegexp | StringMatch::PartialRegexp)
+                && regex::Regex::new(value).is_err()
+            {
+                errors.push(FieldError::new(format!("{}.value", path), "is not a valid regular expression"));
+            }
+            validate_field_name(field_name, path, errors);
+        }
+        FilterExpression::InList { field_name, values, .. } => {
+            if is_metric {
+                errors.push(FieldError::new(path, "metric filters only take numeric and between conditions"));
+            }
+            if values.is_empty() {
+                errors.push(FieldError::new(format!("{}.values", path), "must not be empty"));
+            }
+            validate_field_name(field_name, path, errors);
+        }
+        FilterExpression::Numeric { field_name, value, .. } => {
+            if !value.is_finite() {
+                errors.push(FieldError::new(format!("{}.value", path), "must be a finite number"));
+            }
+            validate_field_name(field_name, path, errors);
+        }
+        FilterExpression::Between {
+            field_name,
+            from_value,
+            to_value,
+        } => {
+            if !from_value.is_finite() || !to_value.is_finite() || from_value > to_value {
+                errors.push(FieldError::new(
+                    path,
+                    "from_value and to_value must be finite, from_value at most to_value",
+                ));
+            }
+            validate_field_name(field_name, path, errors);
+        }
+    }
+}
+
+fn validate_field_name(field_name: &str, path: &str, errors: &mut Vec<FieldError>) {
+    if field_name.trim().is_empty() {
+        errors.push(FieldError::new(format!("{}.field_name", path), "must not be empty"));
+    }
+}
+
+/// Field errors of a definition's dimensions, metrics and filters that the property does
+/// not know about.
+pub fn validate_against_metadata(spec: &ReportSpec, metadata: &Metadata) -> Vec<FieldError> {
+    let is_dimension = |name: &str| metadata.dimensions.iter().any(|d| d.api_name == name);
+    let is_metric = |name: &str| metadata.metrics.iter().any(|m| m.api_name == name);
+    let mut errors = Vec::new();
+
+    let (dimensions, metrics): (Vec<&String>, Vec<&String>) = match spec {
+        ReportSpec::Funnel(spec) => (spec.breakdown_dimension.iter().collect(), Vec::new()),
+        ReportSpec::Standard(spec) => (spec.dimensions.iter().collect(), spec.metrics.iter().collect()),
+        ReportSpec::Pivot(spec) => (spec.dimensions.iter().collect(), spec.metrics.iter().collect()),
+    };
+    for dimension in dimensions.into_iter().filter(|d| !is_dimension(d)) {
+        errors.push(FieldError::new(
+            "spec.dimensions",
+            format!("{} is not a dimension of this property", dimension),
+        ));
+    }
+    for metric in metrics.into_iter().filter(|m| !is_metric(m)) {
+        errors.push(FieldError::new(
+            "spec.metrics",
+            format!("{} is not a metric of this property", metric),
+        ));
+    }
+
+    let (dimension_fields, metric_fields) = spec.filter_fields();
+    for field in dimension_fields.into_iter().filter(|f| !is_dimension(f)) {
+        errors.push(FieldError::new(
+            "spec.dimension_filter",
+            format!("{} is not a dimension of this property", field),
+        ));
+    }
+    for field in metric_fields.into_iter().filter(|f| !is_metric(f)) {
+        errors.push(FieldError::new(
+            "spec.metric_filter",
+            format!("{} is not a metric of this property", field),
+        ));
+    }
+
+    errors
+}
+
+/// Dimensions and metrics of the property, from the Data API.
+pub async fn get_metadata(access_token: &str, property_id: &str) -> Result<Metadata, String> {
+    let client = reqwest::Client::new();
+    let url = format!("{}/{}/metadata", DATA_API_BASE_URL, property_id);
+
+    debug!(property_id = %property_id, "Fetching GA4 metadata");
+    send_admin_request(client.get(&url).bearer_auth(access_token)).await
+}
+
+// GA4 Admin API types
+#[derive(Debug, Deserialize)]
+struct AccountSummariesResponse {
+    #[serde(rename = "accountSummaries", default)]
+    account_summaries: Vec<AccountSummary>,
+    #[serde(rename = "nextPageToken", default)]
+    next_page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AccountSummary {
+    #[serde(rename = "propertySummaries", default)]
+    property_summaries: Vec<PropertySummary>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PropertySummary {
+    #[serde(default)]
+    pub property: String,
+    #[serde(rename = "displayName", default)]
+    pub display_name: String,
+    #[serde(rename = "propertyType", default)]
+    pub property_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PropertyDetails {
+    #[serde(rename = "displayName", default)]
+    pub display_name: String,
+    #[serde(rename = "timeZone", default)]
+    pub time_zone: Option<String>,
+    #[serde(rename = "currencyCode", default)]
+    pub currency_code: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AdminCustomDimension {
+    name: String,
+    parameter_name: String,
+    display_name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    scope: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AdminCustomMetric {
+    name: String,
+    parameter_name: String,
+    display_name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    measurement_unit: String,
+    #[serde(default)]
+    scope: String,
+}
 
-    let expires_at = token
-        .expires_in()
-        .map(|d| Utc::now() + Duration::seconds(d.as_secs() as i64));
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AdminKeyEvent {
+    name: String,
+    event_name: String,
+    #[serde(default)]
+    counting_method: String,
+    #[serde(default)]
+    custom: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AdminDataStream {
+    name: String,
+    #[serde(rename = "type", default)]
+    stream_type: String,
+    #[serde(default)]
+    display_name: String,
+    #[serde(default)]
+    web_stream_data: Option<WebStreamData>,
+    #[serde(default)]
+    android_app_stream_data: Option<AndroidAppStreamData>,
+    #[serde(default)]
+    ios_app_stream_data: Option<IosAppStreamData>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WebStreamData {
+    #[serde(default)]
+    measurement_id: Option<String>,
+    #[serde(default)]
+    default_uri: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AndroidAppStreamData {
+    #[serde(default)]
+    package_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IosAppStreamData {
+    #[serde(default)]
+    bundle_id: Option<String>,
+}
+
+const ADMIN_API_BASE_URL: &str = "https://analyticsadmin.googleapis.com/v1beta";
+
+/// Current date in the property's reporting time zone, falling back to UTC
+/// when the zone is unknown.
+pub fn today_in(time_zone: Option<&str>) -> NaiveDate {
+    match time_zone.map(|tz| tz.parse::<Tz>()) {
+        Some(Ok(tz)) => Utc::now().with_timezone(&tz).date_naive(),
+        Some(Err(e)) => {
+            warn!(time_zone = ?time_zone, error = %e, "Unknown time zone, using UTC");
+            Utc::now().date_naive()
+        }
+        None => Utc::now().date_naive(),
+    }
+}
+
+/// Normalises a property id to the `properties/{id}` resource name.
+/// Accepts either the bare numeric id or the full resource name.
+pub fn normalize_property_id(raw: &str) -> Option<String> {
+    let raw = raw.trim();
+    let id = raw.strip_prefix("properties/").unwrap_or(raw);
+
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    Some(format!("properties/{}", id))
+}
+
+/// Lists every property the account can see, following pagination.
+pub async fn list_properties(access_token: &str) -> Result<Vec<PropertySummary>, String> {
+    let client = reqwest::Client::new();
+    let mut properties = Vec::new();
+    let mut page_token: Option<String> = None;
+
+    loop {
+        let mut request = client
+            .get(format!("{}/accountSummaries", ADMIN_API_BASE_URL))
+            .bearer_auth(access_token)
+            .query(&[("pageSize", "200")]);
+
+        if let Some(token) = &page_token {
+            request = request.query(&[("pageToken", token)]);
+        }
+
+        debug!("Calling Google Analytics Admin API");
+        let data: AccountSummariesResponse = send_admin_request(request).await?;
+
+        properties.extend(
+            data.account_summaries
+                .into_iter()
+                .flat_map(|account| account.property_summaries),
+        );
+
+        match data.next_page_token.filter(|t| !t.is_empty()) {
+            Some(token) => page_token = Some(token),
+            None => break,
+        }
+    }
+
+    info!(count = properties.len(), "Fetched GA4 properties");
+    Ok(properties)
+}
+
+/// Fetches a single property, including its reporting time zone and currency.
+pub async fn get_property(access_token: &str, property_id: &str) -> Result<PropertyDetails, String> {
+    let client = reqwest::Client::new();
+    let request = client
+        .get(format!("{}/{}", ADMIN_API_BASE_URL, property_id))
+        .bearer_auth(access_token);
+
+    debug!(property_id = %property_id, "Fetching GA4 property");
+    send_admin_request(request).await
+}
+
+/// Lists a collection of the property, e.g. `customDimensions`, following pagination.
+async fn list_property_collection<T: serde::de::DeserializeOwned>(
+    access_token: &str,
+    property_id: &str,
+    collection: &str,
+) -> Result<Vec<T>, String> {
+    let client = reqwest::Client::new();
+    let mut items = Vec::new();
+    let mut page_token: Option<String> = None;
+
+    loop {
+        let mut request = client
+            .get(format!("{}/{}/{}", ADMIN_API_BASE_URL, property_id, collection))
+            .bearer_auth(access_token)
+            .query(&[("pageSize", "200")]);
+
+        if let Some(token) = &page_token {
+            request = request.query(&[("pageToken", token)]);
+        }
+
+        debug!(property_id = %property_id, collection = %collection, "Calling Google Analytics Admin API");
+        let mut data: serde_json::Map<String, serde_json::Value> = send_admin_request(request).await?;
+
+        // Empty collections are returned without their field
+        if let Some(page) = data.remove(collection) {
+            let page: Vec<T> = serde_json::from_value(page).map_err(|e| {
+                error!(error = %e, collection = %collection, "Failed to parse GA4 response");
+                format!("Failed to parse GA4 response: {}", e)
+            })?;
+            items.extend(page);
+        }
+
+        match data
+            .get("nextPageToken")
+            .and_then(|t| t.as_str())
+            .filter(|t| !t.is_empty())
+        {
+            Some(token) => page_token = Some(token.to_string()),
+            None => break,
+        }
+    }
+
+    Ok(items)
+}
+
+/// Custom dimensions, custom metrics, key events and data streams of the property.
+pub async fn get_property_definitions(
+    connector_id: Uuid,
+    access_token: &str,
+    property_id: &str,
+) -> Result<PropertyDefinitions, String> {
+    let dimensions: Vec<AdminCustomDimension> =
+        list_property_collection(access_token, property_id, "customDimensions").await?;
+    let metrics: Vec<AdminCustomMetric> = list_property_collection(access_token, property_id, "customMetrics").await?;
+    let key_events: Vec<AdminKeyEvent> = list_property_collection(access_token, property_id, "keyEvents").await?;
+    let data_streams: Vec<AdminDataStream> =
+        list_property_collection(access_token, property_id, "dataStreams").await?;
+
+    let definitions = PropertyDefinitions {
+        custom_dimensions: dimensions
+            .into_iter()
+            .map(|d| CustomDimension {
+                connector_id,
+                property_id: property_id.to_string(),
+                name: d.name,
+                parameter_name: d.parameter_name,
+                display_name: d.display_name,
+                description: d.description,
+                scope: d.scope,
+                renamed_from: None,
+                archived: false,
+            })
+            .collect(),
+        custom_metrics: metrics
+            .into_iter()
+            .map(|m| CustomMetric {
+                connector_id,
+                property_id: property_id.to_string(),
+                name: m.name,
+                parameter_name: m.parameter_name,
+                display_name: m.display_name,
+                description: m.description,
+                measurement_unit: m.measurement_unit,
+                scope: m.scope,
+                renamed_from: None,
+                archived: false,
+            })
+            .collect(),
+        key_events: key_events
+            .into_iter()
+            .map(|k| KeyEvent {
+                connector_id,
+                property_id: property_id.to_string(),
+                name: k.name,
+                event_name: k.event_name,
+                counting_method: k.counting_method,
+                custom: k.custom,
+            })
+            .collect(),
+        data_streams: data_streams
+            .into_iter()
+            .map(|d| {
+                let app_id = d
+                    .web_stream_data
+                    .as_ref()
+                    .and_then(|w| w.measurement_id.clone())
+                    .or_else(|| d.android_app_stream_data.and_then(|a| a.package_name))
+                    .or_else(|| d.ios_app_stream_data.and_then(|i| i.bundle_id));
+
+                DataStream {
+                    connector_id,
+                    property_id: property_id.to_string(),
+                    name: d.name,
+                    stream_type: d.stream_type,
+                    display_name: d.display_name,
+                    app_id,
+                    default_uri: d.web_stream_data.and_then(|w| w.default_uri),
+                }
+            })
+            .collect(),
+    };
 
     info!(
-        expires_at = ?expires_at,
-        "Token refreshed successfully"
+        property_id = %property_id,
+        custom_dimensions = definitions.custom_dimensions.len(),
+        custom_metrics = definitions.custom_metrics.len(),
+        key_events = definitions.key_events.len(),
+        data_streams = definitions.data_streams.len(),
+        "Fetched GA4 property definitions"
     );
+    Ok(definitions)
+}
+
+async fn send_admin_request<T: serde::de::DeserializeOwned>(
+    request: reqwest::RequestBuilder,
+) -> Result<T, String> {
+    let response = request.send().await.map_err(|e| {
+        error!(error = %e, "Failed to connect to GA4 API");
+        format!("Failed to connect to GA4 API: {}", e)
+    })?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_text = response.text().await.unwrap_or_default();
+        error!(status = %status, error = %error_text, "GA4 API error");
+        return Err(format!("GA4 API error: {} - {}", status, error_text));
+    }
 
-    Ok(TokenInfo {
-        access_token: token.access_token().secret().clone(),
-        refresh_token: token.refresh_token().map(|rt| rt.secret().clone()),
-        expires_at,
+    response.json().await.map_err(|e| {
+        error!(error = %e, "Failed to parse GA4 response");
+        format!("Failed to parse GA4 response: {}", e)
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::test_server::serve;
+    use axum::extract::{Path, State};
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use serde_json::{json, Value as JsonValue};
+    use std::sync::{Arc, Mutex};
+
+    /// Requests of each `batchRunReports` call, as (first metric, offset).
+    type Batches = Arc<Mutex<Vec<Vec<(String, i64)>>>>;
+
+    fn report_response(metric: &str, row_count: usize) -> JsonValue {
+        let rows: Vec<JsonValue> = (0..row_count)
+            .map(|_| json!({ "dimensionValues": [{ "value": "20260101" }], "metricValues": [{ "value": "1" }] }))
+            .collect();
+        json!({ "dimensionHeaders": [{ "name": "date" }], "metricHeaders": [{ "name": metric }], "rows": rows })
+    }
+
+    /// Reports with a `paged` metric fill their first page, the others return one row.
+    async fn batch(
+        State(batches): State<Batches>,
+        Path(call): Path<String>,
+        Json(body): Json<JsonValue>,
+    ) -> Json<JsonValue> {
+        assert_eq!(call, "123:batchRunReports");
+
+        let requests: Vec<(String, i64)> = body["requests"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| (r["metrics"][0]["name"].as_str().unwrap().to_string(), r["offset"].as_i64().unwrap()))
+            .collect();
+        let reports: Vec<JsonValue> = requests
+            .iter()
+            .map(|(metric, offset)| {
+                let row_count = if metric == "paged" && *offset == 0 { PAGE_SIZE as usize } else { 1 };
+                report_response(metric, row_count)
+            })
+            .collect();
+
+        batches.lock().unwrap().push(requests);
+        Json(json!({ "reports": reports }))
+    }
+
+    async fn stub() -> (String, Batches) {
+        let batches = Batches::default();
+        let router = Router::new()
+            .route("/properties/{call}", post(batch))
+            .with_state(batches.clone());
+
+        (serve(router).await, batches)
+    }
+
+    fn standard(metric: &str) -> StandardSpec {
+        StandardSpec {
+            dimensions: Vec::new(),
+            metrics: vec![metric.to_string()],
+            dimension_filter: None,
+            metric_filter: None,
+        }
+    }
+
+    fn date(value: &str) -> NaiveDate {
+        value.parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn batches_reports_five_at_a_time() {
+        let (base_url, batches) = stub().await;
+        let specs: Vec<StandardSpec> = (0..7).map(|i| standard(&format!("m{}", i))).collect();
+        let reports: Vec<&StandardSpec> = specs.iter().collect();
+
+        let rows = batch_run_reports(&base_url, "properties/123", "token", &reports, date("2026-01-01"), date("2026-01-31"))
+            .await
+            .unwrap();
+
+        let sizes: Vec<usize> = batches.lock().unwrap().iter().map(Vec::len).collect();
+        assert_eq!(sizes, vec![5, 2]);
+        // Rows come back in the order of the reports
+        let metrics: Vec<&str> = rows.iter().map(|r| r[0].metrics.keys().next().unwrap().as_str()).collect();
+        assert_eq!(metrics, vec!["m0", "m1", "m2", "m3", "m4", "m5", "m6"]);
+    }
+
+    #[tokio::test]
+    async fn pages_reports_across_batches() {
+        let (base_url, batches) = stub().await;
+        let specs = [standard("paged"), standard("single")];
+        let reports: Vec<&StandardSpec> = specs.iter().collect();
+
+        let rows = batch_run_reports(&base_url, "properties/123", "token", &reports, date("2026-01-01"), date("2026-01-01"))
+            .await
+            .unwrap();
+
+        assert_eq!(rows[0].len(), PAGE_SIZE as usize + 1);
+        assert_eq!(rows[1].len(), 1);
+        assert_eq!(
+            *batches.lock().unwrap(),
+            vec![
+                vec![("paged".to_string(), 0), ("single".to_string(), 0)],
+                vec![("paged".to_string(), PAGE_SIZE)],
+            ]
+        );
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    fn fields(errors: Vec<FieldError>) -> Vec<String> {
+        errors.into_iter().map(|e| e.field).collect()
+    }
+
+    #[test]
+    fn accepts_realtime_requests_within_limits() {
+        let errors = validate_realtime(
+            &names(&["country", "customUser:plan"]),
+            &names(&["activeUsers", "eventCount"]),
+            MAX_MINUTES_AGO,
+        );
+        assert!(errors.is_empty());
+        assert!(validate_realtime(&[], &names(&["activeUsers"]), 0).is_empty());
+    }
+
+    #[test]
+    fn rejects_realtime_windows_past_the_limit() {
+        let errors = validate_realtime(&[], &names(&["activeUsers"]), MAX_MINUTES_AGO + 1);
+        assert_eq!(fields(errors), vec!["minutes_ago"]);
+    }
+
+    #[test]
+    fn rejects_fields_outside_the_realtime_schema() {
+        let errors = validate_realtime(&names(&["country", "sessionSource"]), &names(&["sessions"]), 29);
+        assert_eq!(fields(errors), vec!["dimensions[1]", "metrics[0]"]);
+
+        let errors = validate_realtime(&[], &[], 29);
+        assert_eq!(fields(errors), vec!["metrics"]);
+    }
+
+    fn string(field_name: &str) -> FilterExpression {
+        FilterExpression::String {
+            field_name: field_name.to_string(),
+            value: "organic".to_string(),
+            match_type: StringMatch::Exact,
+            case_sensitive: false,
+        }
+    }
+
+    fn between(from_value: f64, to_value: f64) -> FilterExpression {
+        FilterExpression::Between {
+            field_name: "sessions".to_string(),
+            from_value,
+            to_value,
+        }
+    }
+
+    fn nested(depth: usize) -> FilterExpression {
+        (1..depth).fold(string("sessionMedium"), |expression, _| FilterExpression::Not {
+            expression: Box::new(expression),
+        })
+    }
+
+    #[test]
+    fn limits_filter_nesting() {
+        assert!(validate_filter(&nested(MAX_FILTER_DEPTH), "spec.dimension_filter", false).is_empty());
+
+        let errors = validate_filter(&nested(MAX_FILTER_DEPTH + 1), "spec.dimension_filter", false);
+        assert_eq!(
+            fields(errors),
+            vec!["spec.dimension_filter.expression.expression.expression.expression.expression"]
+        );
+    }
+
+    #[test]
+    fn rejects_empty_filter_groups() {
+        let filter = FilterExpression::And {
+            expressions: vec![string("sessionMedium"), FilterExpression::Or { expressions: Vec::new() }],
+        };
+
+        let errors = validate_filter(&filter, "spec.dimension_filter", false);
+        assert_eq!(fields(errors), vec!["spec.dimension_filter.expressions[1].expressions"]);
+    }
+
+    #[test]
+    fn checks_between_bounds() {
+        assert!(validate_filter(&between(1.0, 10.0), "spec.metric_filter", true).is_empty());
+        assert!(validate_filter(&between(5.0, 5.0), "spec.metric_filter", true).is_empty());
+
+        for filter in [between(10.0, 1.0), between(f64::NEG_INFINITY, 1.0), between(1.0, f64::NAN)] {
+            assert_eq!(fields(validate_filter(&filter, "spec.metric_filter", true)), vec!["spec.metric_filter"]);
+        }
+    }
+
+    #[test]
+    fn only_takes_numeric_conditions_in_metric_filters() {
+        let errors = validate_filter(&string("sessions"), "spec.metric_filter", true);
+        assert_eq!(fields(errors), vec!["spec.metric_filter"]);
+    }
+
+    fn metadata(dimensions: &[&str], metrics: &[&str]) -> Metadata {
+        let field = |api_name: &&str| MetadataField {
+            api_name: api_name.to_string(),
+            ui_name: String::new(),
+            category: None,
+            custom_definition: false,
+        };
+        Metadata {
+            dimensions: dimensions.iter().map(field).collect(),
+            metrics: metrics.iter().map(field).collect(),
+        }
+    }
+
+    #[test]
+    fn checks_filter_fields_against_metadata() {
+        let metadata = metadata(&["country", "sessionMedium"], &["sessions"]);
+        let spec = |dimension_filter, metric_filter| {
+            ReportSpec::Standard(StandardSpec {
+                dimensions: vec!["country".to_string()],
+                metrics: vec!["sessions".to_string()],
+                dimension_filter: Some(dimension_filter),
+                metric_filter: Some(metric_filter),
+            })
+        };
+
+        let known = spec(string("sessionMedium"), between(1.0, 10.0));
+        assert!(validate_against_metadata(&known, &metadata).is_empty());
+
+        // Metrics are not dimensions and the other way round
+        let swapped = spec(
+            FilterExpression::Not {
+                expression: Box::new(string("sessions")),
+            },
+            FilterExpression::Numeric {
+                field_name: "customEvent:plan".to_string(),
+                operation: NumericOperation::GreaterThan,
+                value: 0.0,
+            },
+        );
+        let errors = validate_against_metadata(&swapped, &metadata);
+        let messages: Vec<(String, String)> = errors.into_iter().map(|e| (e.field, e.message)).collect();
+        assert_eq!(
+            messages,
+            vec![
+                (
+                    "spec.dimension_filter".to_string(),
+                    "sessions is not a dimension of this property".to_string()
+                ),
+                (
+                    "spec.metric_filter".to_string(),
+                    "customEvent:plan is not a metric of this property".to_string()
+                ),
+            ]
+        );
+    }
+}
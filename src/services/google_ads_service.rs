@@ -0,0 +1,218 @@
+use serde::Deserialize;
+use serde_json::json;
+use tracing::{debug, error, info};
+
+pub const DEFAULT_API_BASE_URL: &str = "https://googleads.googleapis.com/v18";
+
+/// Credentials sent with every Google Ads API call.
+pub struct Credentials<'a> {
+    pub access_token: &'a str,
+    pub developer_token: &'a str,
+    /// Manager account the client accounts are accessed through.
+    pub login_customer_id: Option<&'a str>,
+}
+
+// Google Ads API response types
+#[derive(Debug, Deserialize)]
+struct ListAccessibleCustomersResponse {
+    #[serde(rename = "resourceNames", default)]
+    resource_names: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchStreamBatch {
+    #[serde(default)]
+    results: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CustomerDetails {
+    pub descriptive_name: String,
+    pub time_zone: Option<String>,
+    pub currency_code: Option<String>,
+    pub manager: bool,
+}
+
+/// Normalises a customer id to its ten digits.
+/// Accepts `123-456-7890`, `1234567890` or `customers/1234567890`.
+pub fn normalize_customer_id(raw: &str) -> Option<String> {
+    let raw = raw.trim();
+    let id: String = raw
+        .strip_prefix("customers/")
+        .unwrap_or(raw)
+        .chars()
+        .filter(|c| *c != '-')
+        .collect();
+
+    if id.len() != 10 || !id.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    Some(id)
+}
+
+/// Lists the ids of the customers the user can access directly.
+pub async fn list_accessible_customers(base_url: &str, credentials: &Credentials<'_>) -> Result<Vec<String>, String> {
+    let request = authorized(
+        reqwest::Client::new().get(format!("{}/customers:listAccessibleCustomers", base_url.trim_end_matches('/'))),
+        credentials,
+    );
+
+    debug!("Calling Google Ads listAccessibleCustomers");
+    let data: ListAccessibleCustomersResponse = send(request).await?;
+
+    let customer_ids: Vec<String> = data
+        .resource_names
+        .iter()
+        .filter_map(|name| normalize_customer_id(name))
+        .collect();
+
+    info!(count = customer_ids.len(), "Fetched accessible Google Ads customers");
+    Ok(customer_ids)
+}
+
+/// Fetches the name, time zone and currency of a customer account.
+pub async fn get_customer(
+    base_url: &str,
+    credentials: &Credentials<'_>,
+    customer_id: &str,
+) -> Result<CustomerDetails, String> {
+    let query = "SELECT customer.id, customer.descriptive_name, customer.time_zone, customer.currency_code, customer.manager FROM customer";
+    let rows = search_stream(base_url, credentials, customer_id, query).await?;
+
+    let customer = rows
+        .first()
+        .and_then(|row| row.get("customer"))
+        .ok_or_else(|| format!("Customer {} not found", customer_id))?;
+
+    let text = |field: &str| customer.get(field).and_then(|v| v.as_str()).map(str::to_string);
+
+    Ok(CustomerDetails {
+        descriptive_name: text("descriptiveName").unwrap_or_else(|| customer_id.to_string()),
+        time_zone: text("timeZone"),
+        currency_code: text("currencyCode"),
+        manager: customer.get("manager").and_then(|v| v.as_bool()).unwrap_or(false),
+    })
+}
+
+/// Runs a GAQL query through `googleAds:searchStream` and returns every result row.
+pub async fn search_stream(
+    base_url: &str,
+    credentials: &Credentials<'_>,
+    customer_id: &str,
+    query: &str,
+) -> Result<Vec<serde_json::Value>, String> {
+    let url = format!(
+        "{}/customers/{}/googleAds:searchStream",
+        base_url.trim_end_matches('/'),
+        customer_id
+    );
+    let request = authorized(reqwest::Client::new().post(url), credentials).json(&json!({ "query": query }));
+
+    debug!(customer_id = %customer_id, query = %query, "Calling Google Ads searchStream");
+    let batches: Vec<SearchStreamBatch> = send(request).await?;
+
+    let rows: Vec<serde_json::Value> = batches.into_iter().flat_map(|b| b.results).collect();
+
+    debug!(customer_id = %customer_id, rows = rows.len(), "searchStream complete");
+    Ok(rows)
+}
+
+fn authorized(request: reqwest::RequestBuilder, credentials: &Credentials<'_>) -> reqwest::RequestBuilder {
+    let request = request
+        .bearer_auth(credentials.access_token)
+        .header("developer-token", credentials.developer_token);
+
+    match credentials.login_customer_id {
+        Some(login_customer_id) => request.header("login-customer-id", login_customer_id),
+        None => request,
+    }
+}
+
+async fn send<T: serde::de::DeserializeOwned>(request: reqwest::RequestBuilder) -> Result<T, String> {
+    let response = request.send().await.map_err(|e| {
+        error!(error = %e, "Failed to connect to Google Ads API");
+        format!("Failed to connect to Google Ads API: {}", e)
+    })?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_text = response.text().await.unwrap_or_default();
+        error!(status = %status, error = %error_text, "Google Ads API error");
+        return Err(format!("Google Ads API error: {} - {}", status, error_text));
+    }
+
+    response.json().await.map_err(|e| {
+        error!(error = %e, "Failed to parse Google Ads response");
+        format!("Failed to parse Google Ads response: {}", e)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::test_server::serve;
+    use axum::http::HeaderMap;
+    use axum::routing::{get, post};
+    use axum::{Json, Router};
+
+    fn credentials() -> Credentials<'static> {
+        Credentials {
+            access_token: "token",
+            developer_token: "developer",
+            login_customer_id: Some("1112223333"),
+        }
+    }
+
+    #[test]
+    fn normalizes_customer_ids() {
+        assert_eq!(normalize_customer_id("123-456-7890").as_deref(), Some("1234567890"));
+        assert_eq!(normalize_customer_id("customers/1234567890").as_deref(), Some("1234567890"));
+        assert_eq!(normalize_customer_id(" 1234567890 ").as_deref(), Some("1234567890"));
+    }
+
+    #[test]
+    fn rejects_ids_without_ten_digits() {
+        assert_eq!(normalize_customer_id("123456789"), None);
+        assert_eq!(normalize_customer_id("12345678901"), None);
+        assert_eq!(normalize_customer_id("123-456-789a"), None);
+        assert_eq!(normalize_customer_id(""), None);
+    }
+
+    #[tokio::test]
+    async fn lists_accessible_customers_as_ten_digit_ids() {
+        let router = Router::new().route(
+            "/customers:listAccessibleCustomers",
+            get(|| async {
+                Json(serde_json::json!({ "resourceNames": ["customers/1234567890", "customers/123", "customers/9876543210"] }))
+            }),
+        );
+        let base_url = serve(router).await;
+
+        let customers = list_accessible_customers(&base_url, &credentials()).await.unwrap();
+        assert_eq!(customers, ["1234567890", "9876543210"]);
+    }
+
+    #[tokio::test]
+    async fn search_stream_flattens_batches_and_sends_credentials() {
+        let router = Router::new().route(
+            "/customers/{customer}/googleAds:searchStream",
+            post(|headers: HeaderMap| async move {
+                assert_eq!(headers["authorization"], "Bearer token");
+                assert_eq!(headers["developer-token"], "developer");
+                assert_eq!(headers["login-customer-id"], "1112223333");
+                Json(serde_json::json!([
+                    { "results": [{ "campaign": { "id": "1" } }, { "campaign": { "id": "2" } }] },
+                    { "results": [{ "campaign": { "id": "3" } }] },
+                ]))
+            }),
+        );
+        let base_url = serve(router).await;
+
+        let rows = search_stream(&base_url, &credentials(), "1234567890", "SELECT campaign.id FROM campaign")
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[2]["campaign"]["id"], "3");
+    }
+}
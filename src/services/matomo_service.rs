@@ -0,0 +1,151 @@
+use chrono::{FixedOffset, NaiveDate, Utc};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use tracing::{debug, error, info};
+
+use crate::services::ga4_service;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Site {
+    #[serde(deserialize_with = "id_string")]
+    pub idsite: String,
+    pub name: String,
+    /// Reporting time zone, an IANA name or a fixed offset such as `UTC+5.5`.
+    #[serde(default)]
+    pub timezone: Option<String>,
+}
+
+/// Matomo returns ids as numbers or strings depending on the version.
+fn id_string<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    Ok(match Value::deserialize(deserializer)? {
+        Value::String(s) => s,
+        other => other.to_string(),
+    })
+}
+
+/// Calls a Reporting API method. The token is posted rather than put in the URL so it
+/// stays out of access logs. Labels are requested in English rather than in the token
+/// owner's language.
+async fn call(base_url: &str, token_auth: &str, method: &str, params: &[(&str, String)]) -> Result<Value, String> {
+    let url = format!("{}/index.php", base_url.trim_end_matches('/'));
+
+    let mut form: Vec<(&str, String)> = vec![
+        ("module", "API".to_string()),
+        ("method", method.to_string()),
+        ("format", "JSON".to_string()),
+        ("language", "en".to_string()),
+        ("token_auth", token_auth.to_string()),
+    ];
+    form.extend(params.iter().cloned());
+
+    debug!(method = %method, "Calling Matomo API");
+    let response = reqwest::Client::new()
+        .post(url)
+        .form(&form)
+        .send()
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to connect to Matomo API");
+            format!("Failed to connect to Matomo API: {}", e)
+        })?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_text = response.text().await.unwrap_or_default();
+        error!(status = %status, error = %error_text, "Matomo API error");
+        return Err(format!("Matomo API error: {} - {}", status, error_text));
+    }
+
+    let body: Value = response.json().await.map_err(|e| {
+        error!(error = %e, "Failed to parse Matomo response");
+        format!("Failed to parse Matomo response: {}", e)
+    })?;
+
+    // Errors come back with a 200 status
+    if body.get("result").and_then(Value::as_str) == Some("error") {
+        let message = body.get("message").and_then(Value::as_str).unwrap_or_default();
+        error!(error = %message, "Matomo API error");
+        return Err(format!("Matomo API error: {}", message));
+    }
+
+    Ok(body)
+}
+
+/// Current date in a site's reporting time zone, the one Matomo's days are cut in.
+pub fn today(timezone: Option<&str>) -> NaiveDate {
+    match timezone.and_then(utc_offset) {
+        Some(offset) => Utc::now().with_timezone(&offset).date_naive(),
+        None => ga4_service::today_in(timezone),
+    }
+}
+
+/// Fixed offset of a `UTC+5.5` or `UTC-3` time zone, which Matomo offers besides IANA names.
+fn utc_offset(timezone: &str) -> Option<FixedOffset> {
+    let hours: f64 = timezone.strip_prefix("UTC")?.parse().ok()?;
+    FixedOffset::east_opt((hours * 3600.0).round() as i32)
+}
+
+/// Lists the sites the token can view.
+pub async fn list_sites(base_url: &str, token_auth: &str) -> Result<Vec<Site>, String> {
+    let body = call(base_url, token_auth, "SitesManager.getSitesWithAtLeastViewAccess", &[]).await?;
+    let sites: Vec<Site> =
+        serde_json::from_value(body).map_err(|e| format!("Failed to parse Matomo sites: {}", e))?;
+
+    info!(count = sites.len(), "Fetched Matomo sites");
+    Ok(sites)
+}
+
+/// Runs a report for each day from `start_date` to `end_date`, keyed by day. Totals
+/// methods such as `API.get` give an object per day, breakdowns an array of rows.
+pub async fn daily(
+    base_url: &str,
+    token_auth: &str,
+    site_id: &str,
+    method: &str,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<BTreeMap<NaiveDate, Value>, String> {
+    let params = [
+        ("idSite", site_id.to_string()),
+        ("period", "day".to_string()),
+        ("date", format!("{},{}", start_date, end_date)),
+        ("filter_limit", "-1".to_string()),
+    ];
+
+    let body = call(base_url, token_auth, method, &params).await?;
+    let Value::Object(days) = body else {
+        return Err(format!("Unexpected Matomo response to {}", method));
+    };
+
+    Ok(days
+        .into_iter()
+        .filter_map(|(date, value)| {
+            NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+                .ok()
+                .map(|date| (date, value))
+        })
+        .collect())
+}
+
+/// Reads a metric that may be a number, a numeric string or a percentage like `"45%"`.
+pub fn number(row: &Value, metric: &str) -> f64 {
+    match row.get(metric) {
+        Some(Value::Number(n)) => n.as_f64().unwrap_or_default(),
+        Some(Value::String(s)) => s.trim_end_matches('%').parse().unwrap_or_default(),
+        _ => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_fixed_utc_offsets() {
+        assert_eq!(utc_offset("UTC+5.5"), FixedOffset::east_opt(5 * 3600 + 1800));
+        assert_eq!(utc_offset("UTC-3"), FixedOffset::west_opt(3 * 3600));
+        assert_eq!(utc_offset("UTC"), None);
+        assert_eq!(utc_offset("Europe/Paris"), None);
+    }
+}
@@ -0,0 +1,257 @@
+use chrono::{Duration, NaiveDate, Utc};
+use oauth2::basic::BasicClient;
+use serde::Deserialize;
+use serde_json::json;
+use tracing::{debug, error, info, warn};
+
+use super::oauth_service::TokenInfo;
+
+pub const DEFAULT_API_BASE_URL: &str = "https://graph.facebook.com/v21.0";
+
+/// Longer ranges are pulled through async report jobs, which Meta recommends for large accounts.
+const SYNC_MAX_DAYS: i64 = 7;
+const PAGE_SIZE: &str = "500";
+const JOB_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+const JOB_MAX_POLLS: usize = 120;
+
+/// Meta app credentials, needed on top of the OAuth client to get long-lived tokens.
+pub struct MetaApp {
+    pub client: BasicClient,
+    pub app_id: String,
+    pub app_secret: String,
+    pub api_base_url: String,
+}
+
+// Graph API response types
+#[derive(Debug, Deserialize)]
+struct Page<T> {
+    #[serde(default = "Vec::new")]
+    data: Vec<T>,
+    #[serde(default)]
+    paging: Option<Paging>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Paging {
+    #[serde(default)]
+    next: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AdAccount {
+    /// `act_` prefixed id.
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub currency: Option<String>,
+    #[serde(default)]
+    pub timezone_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LongLivedTokenResponse {
+    access_token: String,
+    #[serde(default)]
+    expires_in: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReportRun {
+    report_run_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReportRunStatus {
+    #[serde(default)]
+    async_status: String,
+    #[serde(default)]
+    async_percent_completion: i64,
+}
+
+pub struct InsightsQuery<'a> {
+    pub level: &'a str,
+    pub fields: Vec<&'a str>,
+    pub breakdowns: &'a [String],
+    pub since: NaiveDate,
+    pub until: NaiveDate,
+}
+
+impl InsightsQuery<'_> {
+    fn params(&self) -> Vec<(&'static str, String)> {
+        let mut params = vec![
+            ("level", self.level.to_string()),
+            ("time_increment", "1".to_string()),
+            (
+                "time_range",
+                json!({ "since": self.since.to_string(), "until": self.until.to_string() }).to_string(),
+            ),
+            ("fields", self.fields.join(",")),
+            ("limit", PAGE_SIZE.to_string()),
+        ];
+
+        if !self.breakdowns.is_empty() {
+            params.push(("breakdowns", self.breakdowns.join(",")));
+        }
+
+        params
+    }
+}
+
+/// Trades a short-lived user token for a long-lived one (about 60 days).
+/// Meta has no refresh tokens, users re-authenticate once it expires.
+pub async fn exchange_long_lived_token(app: &MetaApp, access_token: &str) -> Result<TokenInfo, String> {
+    let request = reqwest::Client::new()
+        .get(format!("{}/oauth/access_token", app.api_base_url.trim_end_matches('/')))
+        .query(&[
+            ("grant_type", "fb_exchange_token"),
+            ("client_id", &app.app_id),
+            ("client_secret", &app.app_secret),
+            ("fb_exchange_token", access_token),
+        ]);
+
+    debug!("Exchanging for a long-lived Meta token");
+    let token: LongLivedTokenResponse = send(request).await?;
+
+    Ok(TokenInfo {
+        access_token: token.access_token,
+        refresh_token: None,
+        expires_at: token.expires_in.map(|secs| Utc::now() + Duration::seconds(secs)),
+    })
+}
+
+/// Lists the ad accounts the user has access to, following pagination.
+pub async fn list_ad_accounts(base_url: &str, access_token: &str) -> Result<Vec<AdAccount>, String> {
+    let request = reqwest::Client::new()
+        .get(format!("{}/me/adaccounts", base_url.trim_end_matches('/')))
+        .bearer_auth(access_token)
+        .query(&[("fields", "id,name,currency,timezone_name"), ("limit", PAGE_SIZE)]);
+
+    let accounts = fetch_all(request, access_token).await?;
+
+    info!(count = accounts.len(), "Fetched Meta ad accounts");
+    Ok(accounts)
+}
+
+/// Pulls daily insights of an ad account, through an async report job for long ranges.
+pub async fn pull_insights(
+    base_url: &str,
+    access_token: &str,
+    account_id: &str,
+    query: &InsightsQuery<'_>,
+) -> Result<Vec<serde_json::Value>, String> {
+    let base_url = base_url.trim_end_matches('/');
+    let days = (query.until - query.since).num_days() + 1;
+
+    info!(
+        account_id = %account_id,
+        level = %query.level,
+        since = %query.since,
+        until = %query.until,
+        "Pulling Meta insights"
+    );
+
+    if days <= SYNC_MAX_DAYS {
+        let request = reqwest::Client::new()
+            .get(format!("{}/{}/insights", base_url, account_id))
+            .bearer_auth(access_token)
+            .query(&query.params());
+
+        return fetch_all(request, access_token).await;
+    }
+
+    let client = reqwest::Client::new();
+    let run: ReportRun = send(
+        client
+            .post(format!("{}/{}/insights", base_url, account_id))
+            .bearer_auth(access_token)
+            .form(&query.params()),
+    )
+    .await?;
+
+    debug!(report_run_id = %run.report_run_id, "Async report job started");
+
+    let mut polls = 0;
+    loop {
+        let status: ReportRunStatus = send(
+            client
+                .get(format!("{}/{}", base_url, run.report_run_id))
+                .bearer_auth(access_token)
+                .query(&[("fields", "async_status,async_percent_completion")]),
+        )
+        .await?;
+
+        match status.async_status.as_str() {
+            "Job Completed" => break,
+            "Job Failed" | "Job Skipped" => {
+                error!(report_run_id = %run.report_run_id, status = %status.async_status, "Async report job failed");
+                return Err(format!("Meta report job {}: {}", run.report_run_id, status.async_status));
+            }
+            _ => {}
+        }
+
+        polls += 1;
+        if polls >= JOB_MAX_POLLS {
+            warn!(report_run_id = %run.report_run_id, "Async report job timed out");
+            return Err(format!("Meta report job {} did not complete in time", run.report_run_id));
+        }
+
+        debug!(
+            report_run_id = %run.report_run_id,
+            status = %status.async_status,
+            percent = status.async_percent_completion,
+            "Waiting for async report job"
+        );
+        tokio::time::sleep(JOB_POLL_INTERVAL).await;
+    }
+
+    let request = client
+        .get(format!("{}/{}/insights", base_url, run.report_run_id))
+        .bearer_auth(access_token)
+        .query(&[("limit", PAGE_SIZE)]);
+
+    fetch_all(request, access_token).await
+}
+
+/// Follows `paging.next` links until the last page.
+async fn fetch_all<T: serde::de::DeserializeOwned>(
+    request: reqwest::RequestBuilder,
+    access_token: &str,
+) -> Result<Vec<T>, String> {
+    let client = reqwest::Client::new();
+    let mut items = Vec::new();
+    let mut page: Page<T> = send(request).await?;
+
+    loop {
+        items.extend(page.data);
+
+        match page.paging.and_then(|p| p.next) {
+            Some(next) => {
+                debug!(fetched = items.len(), "Fetching next page");
+                page = send(client.get(next).bearer_auth(access_token)).await?;
+            }
+            None => break,
+        }
+    }
+
+    Ok(items)
+}
+
+async fn send<T: serde::de::DeserializeOwned>(request: reqwest::RequestBuilder) -> Result<T, String> {
+    let response = request.send().await.map_err(|e| {
+        error!(error = %e, "Failed to connect to Meta API");
+        format!("Failed to connect to Meta API: {}", e)
+    })?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_text = response.text().await.unwrap_or_default();
+        error!(status = %status, error = %error_text, "Meta API error");
+        return Err(format!("Meta API error: {} - {}", status, error_text));
+    }
+
+    response.json().await.map_err(|e| {
+        error!(error = %e, "Failed to parse Meta response");
+        format!("Failed to parse Meta response: {}", e)
+    })
+}
@@ -1,2 +1,23 @@
+pub mod collect_service;
+pub mod crypto_service;
+pub mod database_service;
+pub mod enrichment_service;
+pub mod ga4_export_service;
 pub mod ga4_service;
+pub mod google_ads_service;
+pub mod matomo_service;
+pub mod meta_ads_service;
+pub mod oauth_service;
+pub mod plausible_service;
+pub mod realtime_service;
+pub mod report_service;
+pub mod rest_api_service;
+pub mod search_console_service;
+pub mod session_service;
 pub mod storage_service;
+pub mod stripe_service;
+pub mod traffic_filter_service;
+pub mod upload_service;
+
+#[cfg(test)]
+pub mod test_server;
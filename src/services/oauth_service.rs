@@ -0,0 +1,62 @@
+use chrono::{DateTime, Duration, Utc};
+use oauth2::{AuthorizationCode, RefreshToken, TokenResponse, basic::BasicClient, reqwest::async_http_client};
+use tracing::{error, info, warn};
+
+#[derive(Debug, Clone)]
+pub struct TokenInfo {
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+pub fn is_token_expired(expires_at: Option<DateTime<Utc>>) -> bool {
+    expires_at.map(|exp| exp < Utc::now()).unwrap_or(false)
+}
+
+pub async fn exchange_code(oauth_client: &BasicClient, code: String) -> Result<TokenInfo, String> {
+    let token = oauth_client
+        .exchange_code(AuthorizationCode::new(code))
+        .request_async(async_http_client)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to exchange code for tokens");
+            format!("Failed to exchange code: {}", e)
+        })?;
+
+    Ok(token_info(&token))
+}
+
+pub async fn refresh_token(
+    oauth_client: &BasicClient,
+    refresh_token: &str,
+) -> Result<TokenInfo, String> {
+    warn!("Access token expired, refreshing...");
+
+    let token = oauth_client
+        .exchange_refresh_token(&RefreshToken::new(refresh_token.to_string()))
+        .request_async(async_http_client)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to refresh token");
+            format!("Failed to refresh token: {}", e)
+        })?;
+
+    let token_info = token_info(&token);
+
+    info!(
+        expires_at = ?token_info.expires_at,
+        "Token refreshed successfully"
+    );
+
+    Ok(token_info)
+}
+
+fn token_info(token: &impl TokenResponse<oauth2::basic::BasicTokenType>) -> TokenInfo {
+    TokenInfo {
+        access_token: token.access_token().secret().clone(),
+        refresh_token: token.refresh_token().map(|rt| rt.secret().clone()),
+        expires_at: token
+            .expires_in()
+            .map(|d| Utc::now() + Duration::seconds(d.as_secs() as i64)),
+    }
+}
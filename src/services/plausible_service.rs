@@ -0,0 +1,144 @@
+use chrono::NaiveDate;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use tracing::{debug, error};
+
+const METRICS: &str = "visitors,visits,pageviews,bounce_rate,visit_duration";
+const BREAKDOWN_LIMIT: usize = 1000;
+
+/// Metric values as returned by the Stats API; bounce rate is a percentage.
+#[derive(Debug, Default, Deserialize)]
+pub struct PlausibleMetrics {
+    #[serde(default)]
+    pub visitors: Option<f64>,
+    #[serde(default)]
+    pub visits: Option<f64>,
+    #[serde(default)]
+    pub pageviews: Option<f64>,
+    #[serde(default)]
+    pub bounce_rate: Option<f64>,
+    #[serde(default)]
+    pub visit_duration: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AggregateResponse {
+    results: HashMap<String, AggregateValue>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AggregateValue {
+    value: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BreakdownResponse {
+    #[serde(default)]
+    results: Vec<serde_json::Map<String, Value>>,
+}
+
+async fn get<T: serde::de::DeserializeOwned>(
+    base_url: &str,
+    api_key: &str,
+    endpoint: &str,
+    query: &[(&str, String)],
+) -> Result<T, String> {
+    let url = format!("{}/api/v1/stats/{}", base_url.trim_end_matches('/'), endpoint);
+
+    let response = reqwest::Client::new()
+        .get(url)
+        .bearer_auth(api_key)
+        .query(query)
+        .send()
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to connect to Plausible API");
+            format!("Failed to connect to Plausible API: {}", e)
+        })?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_text = response.text().await.unwrap_or_default();
+        error!(status = %status, error = %error_text, "Plausible API error");
+        return Err(format!("Plausible API error: {} - {}", status, error_text));
+    }
+
+    response.json().await.map_err(|e| {
+        error!(error = %e, "Failed to parse Plausible response");
+        format!("Failed to parse Plausible response: {}", e)
+    })
+}
+
+/// Totals of a site for one day.
+pub async fn aggregate(
+    base_url: &str,
+    api_key: &str,
+    site_id: &str,
+    date: NaiveDate,
+) -> Result<PlausibleMetrics, String> {
+    let query = [
+        ("site_id", site_id.to_string()),
+        ("period", "day".to_string()),
+        ("date", date.to_string()),
+        ("metrics", METRICS.to_string()),
+    ];
+
+    let data: AggregateResponse = get(base_url, api_key, "aggregate", &query).await?;
+    let value = |metric: &str| data.results.get(metric).and_then(|v| v.value);
+
+    Ok(PlausibleMetrics {
+        visitors: value("visitors"),
+        visits: value("visits"),
+        pageviews: value("pageviews"),
+        bounce_rate: value("bounce_rate"),
+        visit_duration: value("visit_duration"),
+    })
+}
+
+/// Metrics of a site for one day by values of a property, e.g. `visit:country`,
+/// following page pagination.
+pub async fn breakdown(
+    base_url: &str,
+    api_key: &str,
+    site_id: &str,
+    date: NaiveDate,
+    property: &str,
+) -> Result<Vec<(String, PlausibleMetrics)>, String> {
+    // Rows are keyed by the property name without its `visit:` prefix
+    let key = property.rsplit(':').next().unwrap_or(property);
+    let mut rows = Vec::new();
+
+    for page in 1.. {
+        let query = [
+            ("site_id", site_id.to_string()),
+            ("period", "day".to_string()),
+            ("date", date.to_string()),
+            ("property", property.to_string()),
+            ("metrics", METRICS.to_string()),
+            ("limit", BREAKDOWN_LIMIT.to_string()),
+            ("page", page.to_string()),
+        ];
+
+        let data: BreakdownResponse = get(base_url, api_key, "breakdown", &query).await?;
+        let page_count = data.results.len();
+
+        for row in data.results {
+            let label = match row.get(key) {
+                Some(Value::String(s)) => s.clone(),
+                Some(other) => other.to_string(),
+                None => continue,
+            };
+            let metrics = serde_json::from_value(Value::Object(row))
+                .map_err(|e| format!("Failed to parse Plausible breakdown row: {}", e))?;
+            rows.push((label, metrics));
+        }
+
+        debug!(site_id = %site_id, property = %property, page = page, page_count = page_count, "Fetched breakdown page");
+        if page_count < BREAKDOWN_LIMIT {
+            break;
+        }
+    }
+
+    Ok(rows)
+}
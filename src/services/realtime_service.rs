@@ -0,0 +1,137 @@
+use chrono::Utc;
+use duckdb::Connection;
+use duckdb::types::{TimeUnit, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+use uuid::Uuid;
+
+use super::ga4_service::RealtimeReport;
+use super::storage_service::{self, TableSchema};
+
+/// Realtime reports are served from the cache for this long, in seconds.
+const CACHE_TTL_SECS: i64 = 10;
+
+pub const SNAPSHOTS_TABLE: &str = "ga4_realtime_snapshots";
+
+/// Recent realtime reports per connector and request, sparing quota when dashboards poll.
+#[derive(Default)]
+pub struct RealtimeCache {
+    entries: Mutex<HashMap<(Uuid, String), Arc<RealtimeReport>>>,
+}
+
+impl RealtimeCache {
+    /// Identifies a request, with dimensions and metrics in the order given.
+    pub fn key(property_id: &str, dimensions: &[String], metrics: &[String], minutes_ago: u32) -> String {
+        format!("{}|{}|{}|{}", property_id, dimensions.join(","), metrics.join(","), minutes_ago)
+    }
+
+    pub fn get(&self, connector_id: Uuid, key: &str) -> Option<Arc<RealtimeReport>> {
+        let entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        entries
+            .get(&(connector_id, key.to_string()))
+            .filter(|report| (Utc::now() - report.fetched_at).num_seconds() < CACHE_TTL_SECS)
+            .cloned()
+    }
+
+    pub fn insert(&self, connector_id: Uuid, key: String, report: Arc<RealtimeReport>) {
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        entries.retain(|_, cached| (Utc::now() - cached.fetched_at).num_seconds() < CACHE_TTL_SECS);
+        entries.insert((connector_id, key), report);
+    }
+}
+
+/// One row per realtime report row, as a time series of what was happening.
+pub fn snapshots_schema() -> TableSchema {
+    TableSchema::new(
+        SNAPSHOTS_TABLE,
+        &[
+            ("captured_at", "TIMESTAMP"),
+            ("property_id", "VARCHAR"),
+            ("minutes_ago", "INTEGER"),
+            // JSON objects of dimension name to value and metric name to value
+            ("dimensions", "VARCHAR"),
+            ("metrics", "VARCHAR"),
+        ],
+        &[],
+    )
+}
+
+/// Appends the rows of a report to the snapshot table.
+pub fn snapshot(conn: &Connection, report: &RealtimeReport) -> Result<usize, String> {
+    let captured_at = Value::Timestamp(TimeUnit::Microsecond, report.fetched_at.timestamp_micros());
+
+    let rows = report
+        .rows
+        .iter()
+        .map(|row| {
+            vec![
+                captured_at.clone(),
+                Value::Text(report.property_id.clone()),
+                Value::Int(report.minutes_ago as i32),
+                Value::Text(serde_json::to_string(&row.dimensions).unwrap_or_default()),
+                Value::Text(serde_json::to_string(&row.metrics).unwrap_or_default()),
+            ]
+        })
+        .collect();
+
+    storage_service::append_rows(conn, &snapshots_schema(), rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn report(age_secs: i64) -> Arc<RealtimeReport> {
+        Arc::new(RealtimeReport {
+            property_id: "123".to_string(),
+            minutes_ago: 29,
+            row_count: 0,
+            rows: Vec::new(),
+            fetched_at: Utc::now() - Duration::seconds(age_secs),
+        })
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn keys_requests_by_property_fields_and_window() {
+        let key = RealtimeCache::key("123", &names(&["country"]), &names(&["activeUsers"]), 29);
+
+        assert_eq!(key, RealtimeCache::key("123", &names(&["country"]), &names(&["activeUsers"]), 29));
+        assert_ne!(key, RealtimeCache::key("456", &names(&["country"]), &names(&["activeUsers"]), 29));
+        assert_ne!(key, RealtimeCache::key("123", &names(&["country"]), &names(&["activeUsers"]), 5));
+        assert_ne!(key, RealtimeCache::key("123", &names(&[]), &names(&["activeUsers"]), 29));
+        assert_ne!(
+            RealtimeCache::key("123", &names(&["country", "city"]), &names(&["activeUsers"]), 29),
+            RealtimeCache::key("123", &names(&["city", "country"]), &names(&["activeUsers"]), 29)
+        );
+    }
+
+    #[test]
+    fn serves_fresh_reports_per_connector() {
+        let cache = RealtimeCache::default();
+        let connector_id = Uuid::now_v7();
+        cache.insert(connector_id, "key".to_string(), report(0));
+
+        assert!(cache.get(connector_id, "key").is_some());
+        assert!(cache.get(connector_id, "other").is_none());
+        assert!(cache.get(Uuid::now_v7(), "key").is_none());
+    }
+
+    #[test]
+    fn expires_reports_after_the_ttl() {
+        let cache = RealtimeCache::default();
+        let connector_id = Uuid::now_v7();
+        cache.insert(connector_id, "stale".to_string(), report(CACHE_TTL_SECS));
+        assert!(cache.get(connector_id, "stale").is_none());
+
+        // Expired entries are dropped on the next insert
+        cache.insert(connector_id, "fresh".to_string(), report(0));
+        let entries = cache.entries.lock().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert!(entries.contains_key(&(connector_id, "fresh".to_string())));
+    }
+}
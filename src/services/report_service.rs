@@ -0,0 +1,188 @@
+use duckdb::Connection;
+use duckdb::types::Value;
+use sha2::{Digest, Sha256};
+use tracing::debug;
+use uuid::Uuid;
+
+use super::ga4_service::{FunnelRow, ReportRow};
+use crate::models::report_definition::ReportSpec;
+use super::storage_service::{self, StorageResult, TableSchema};
+
+pub const FUNNEL_RESULTS_TABLE: &str = "ga4_funnel_results";
+/// Results of standard and pivot report definitions.
+pub const REPORT_RESULTS_TABLE: &str = "ga4_report_results";
+
+/// One row per funnel report definition, day, step and breakdown value.
+pub fn funnel_results_schema() -> TableSchema {
+    TableSchema::new(
+        FUNNEL_RESULTS_TABLE,
+        &[
+            ("definition_id", "VARCHAR"),
+            ("property_id", "VARCHAR"),
+            ("date", "DATE"),
+            ("step_number", "INTEGER"),
+            ("step_name", "VARCHAR"),
+            ("breakdown", "VARCHAR"),
+            ("active_users", "BIGINT"),
+            ("completion_rate", "DOUBLE"),
+            ("abandonments", "BIGINT"),
+            ("abandonment_rate", "DOUBLE"),
+        ],
+        &["definition_id", "date", "step_number", "breakdown"],
+    )
+}
+
+/// One row per report definition, day and combination of dimension values.
+pub fn report_results_schema() -> TableSchema {
+    TableSchema::new(
+        REPORT_RESULTS_TABLE,
+        &[
+            ("definition_id", "VARCHAR"),
+            ("property_id", "VARCHAR"),
+            ("filter_fingerprint", "VARCHAR"),
+            ("date", "DATE"),
+            // JSON objects of dimension name to value and metric name to value
+            ("dimensions", "VARCHAR"),
+            ("metrics", "VARCHAR"),
+        ],
+        &["definition_id", "filter_fingerprint", "date", "dimensions"],
+    )
+}
+
+/// Identifies the dimension and metric filters of a spec, so rows pulled under different
+/// filters are kept apart. Stable across runs, unlike `std::hash`.
+pub fn filter_fingerprint(spec: &ReportSpec) -> String {
+    let filters = serde_json::to_string(&spec.filters()).unwrap_or_default();
+    let digest = Sha256::digest(filters.as_bytes());
+
+    digest[..8].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Upserts the rows of a standard or pivot report definition.
+pub fn store_report(
+    conn: &Connection,
+    definition_id: Uuid,
+    property_id: &str,
+    filter_fingerprint: &str,
+    rows: Vec<ReportRow>,
+) -> Result<StorageResult, String> {
+    let rows = rows
+        .into_iter()
+        .map(|row| {
+            vec![
+                Value::Text(definition_id.to_string()),
+                Value::Text(property_id.to_string()),
+                Value::Text(filter_fingerprint.to_string()),
+                storage_service::date_value(row.date),
+                Value::Text(serde_json::to_string(&row.dimensions).unwrap_or_default()),
+                Value::Text(serde_json::to_string(&row.metrics).unwrap_or_default()),
+            ]
+        })
+        .collect();
+
+    storage_service::upsert_rows(conn, &report_results_schema(), rows)
+}
+
+/// Upserts the funnel rows of a definition.
+pub fn store_funnel(
+    conn: &Connection,
+    definition_id: Uuid,
+    property_id: &str,
+    rows: Vec<FunnelRow>,
+) -> Result<StorageResult, String> {
+    let rows = rows
+        .into_iter()
+        .map(|row| {
+            vec![
+                Value::Text(definition_id.to_string()),
+                Value::Text(property_id.to_string()),
+                storage_service::date_value(row.date),
+                Value::Int(row.step_number),
+                Value::Text(row.step_name),
+                Value::Text(row.breakdown),
+                Value::BigInt(row.active_users),
+                Value::Double(row.completion_rate),
+                Value::BigInt(row.abandonments),
+                Value::Double(row.abandonment_rate),
+            ]
+        })
+        .collect();
+
+    storage_service::upsert_rows(conn, &funnel_results_schema(), rows)
+}
+
+/// Drops the stored results of a definition, once deleted or when its spec changed.
+pub fn delete_results(conn: &Connection, definition_id: Uuid) -> Result<usize, String> {
+    let mut count = 0;
+
+    for table in [FUNNEL_RESULTS_TABLE, REPORT_RESULTS_TABLE] {
+        let exists: bool = conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM duckdb_tables() WHERE table_name = ?",
+                [table],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("Failed to look up table: {}", e))?;
+        if !exists {
+            continue;
+        }
+
+        count += conn
+            .execute(
+                &format!("DELETE FROM {table} WHERE definition_id = ?"),
+                [definition_id.to_string()],
+            )
+            .map_err(|e| format!("Failed to delete report results: {}", e))?;
+    }
+
+    debug!(definition_id = %definition_id, count = count, "Report results deleted");
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(json: &str) -> ReportSpec {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn fingerprints_filters_regardless_of_key_order() {
+        let a = spec(
+            r#"{"kind": "standard", "dimensions": ["country"], "metrics": ["sessions"],
+                "dimension_filter": {"type": "string", "field_name": "sessionMedium", "value": "organic"}}"#,
+        );
+        let b = spec(
+            r#"{"dimension_filter": {"value": "organic", "field_name": "sessionMedium", "type": "string"},
+                "metrics": ["sessions"], "kind": "standard", "dimensions": ["country"]}"#,
+        );
+
+        assert_eq!(filter_fingerprint(&a), filter_fingerprint(&b));
+        assert_eq!(filter_fingerprint(&a).len(), 16);
+    }
+
+    #[test]
+    fn fingerprints_only_the_filters() {
+        let filtered = r#""dimension_filter": {"type": "string", "field_name": "sessionMedium", "value": "organic"}"#;
+        let base = spec(&format!(r#"{{"kind": "standard", "metrics": ["sessions"], {}}}"#, filtered));
+        let more_fields = spec(&format!(
+            r#"{{"kind": "standard", "dimensions": ["country"], "metrics": ["sessions", "bounceRate"], {}}}"#,
+            filtered
+        ));
+        let other_value = spec(
+            r#"{"kind": "standard", "metrics": ["sessions"],
+                "dimension_filter": {"type": "string", "field_name": "sessionMedium", "value": "cpc"}}"#,
+        );
+        let metric_filter = spec(
+            r#"{"kind": "standard", "metrics": ["sessions"],
+                "metric_filter": {"type": "string", "field_name": "sessionMedium", "value": "organic"}}"#,
+        );
+        let unfiltered = spec(r#"{"kind": "standard", "metrics": ["sessions"]}"#);
+
+        assert_eq!(filter_fingerprint(&base), filter_fingerprint(&more_fields));
+        assert_ne!(filter_fingerprint(&base), filter_fingerprint(&other_value));
+        assert_ne!(filter_fingerprint(&base), filter_fingerprint(&metric_filter));
+        assert_ne!(filter_fingerprint(&base), filter_fingerprint(&unfiltered));
+    }
+}
@@ -0,0 +1,303 @@
+use chrono::NaiveDate;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error, info};
+
+pub const DEFAULT_API_BASE_URL: &str = "https://www.googleapis.com/webmasters/v3";
+
+/// Search Console reports dates in Pacific Time.
+pub const TIME_ZONE: &str = "America/Los_Angeles";
+
+const DIMENSIONS: [&str; 4] = ["query", "page", "country", "device"];
+const ROW_LIMIT: i64 = 25000;
+
+// Search Console API request types
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchAnalyticsRequest {
+    start_date: String,
+    end_date: String,
+    dimensions: Vec<&'static str>,
+    #[serde(rename = "type")]
+    search_type: &'static str,
+    row_limit: i64,
+    start_row: i64,
+}
+
+// Search Console API response types
+#[derive(Debug, Deserialize)]
+struct SitesResponse {
+    #[serde(rename = "siteEntry", default)]
+    site_entry: Vec<Site>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Site {
+    pub site_url: String,
+    pub permission_level: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchAnalyticsResponse {
+    #[serde(default)]
+    rows: Vec<Row>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Row {
+    #[serde(default)]
+    keys: Vec<String>,
+    #[serde(default)]
+    clicks: f64,
+    #[serde(default)]
+    impressions: f64,
+    #[serde(default)]
+    ctr: f64,
+    #[serde(default)]
+    position: f64,
+}
+
+// Flat record for storage
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchConsoleRecord {
+    pub site_url: String,
+    pub date: NaiveDate,
+    pub query: String,
+    pub page: String,
+    pub country: String,
+    pub device: String,
+    pub clicks: i64,
+    pub impressions: i64,
+    pub ctr: f64,
+    pub position: f64,
+}
+
+/// Lists the sites the Google account has access to.
+pub async fn list_sites(base_url: &str, access_token: &str) -> Result<Vec<Site>, String> {
+    let url = format!("{}/sites", base_url.trim_end_matches('/'));
+
+    debug!("Calling Search Console sites API");
+    let response = reqwest::Client::new()
+        .get(url)
+        .bearer_auth(access_token)
+        .send()
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to connect to Search Console API");
+            format!("Failed to connect to Search Console API: {}", e)
+        })?;
+
+    let data: SitesResponse = parse_response(response).await?;
+
+    info!(count = data.site_entry.len(), "Fetched Search Console sites");
+    Ok(data.site_entry)
+}
+
+/// Pulls every day from `start_date` through `end_date` for a site. One query per day
+/// keeps each response well under the API row caps.
+pub async fn pull_days(
+    base_url: &str,
+    access_token: &str,
+    site_url: &str,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<Vec<SearchConsoleRecord>, String> {
+    let mut records = Vec::new();
+    for date in start_date.iter_days().take_while(|d| *d <= end_date) {
+        records.extend(pull_day(base_url, access_token, site_url, date).await?);
+    }
+
+    Ok(records)
+}
+
+/// Pulls one day of search analytics for a site, following row pagination.
+async fn pull_day(
+    base_url: &str,
+    access_token: &str,
+    site_url: &str,
+    date: NaiveDate,
+) -> Result<Vec<SearchConsoleRecord>, String> {
+    let url = query_url(base_url, site_url)?;
+    let client = reqwest::Client::new();
+
+    let mut records = Vec::new();
+    let mut start_row: i64 = 0;
+
+    loop {
+        let request = SearchAnalyticsRequest {
+            start_date: date.to_string(),
+            end_date: date.to_string(),
+            dimensions: DIMENSIONS.to_vec(),
+            search_type: "web",
+            row_limit: ROW_LIMIT,
+            start_row,
+        };
+
+        let response = client
+            .post(url.clone())
+            .bearer_auth(access_token)
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| {
+                error!(error = %e, "Failed to connect to Search Console API");
+                format!("Failed to connect to Search Console API: {}", e)
+            })?;
+
+        let data: SearchAnalyticsResponse = parse_response(response).await?;
+        let page_count = data.rows.len();
+
+        records.extend(data.rows.into_iter().map(|row| flatten(site_url, date, row)));
+
+        debug!(
+            site_url = %site_url,
+            date = %date,
+            start_row = start_row,
+            page_count = page_count,
+            "Fetched page"
+        );
+
+        if page_count < ROW_LIMIT as usize {
+            break;
+        }
+        start_row += ROW_LIMIT;
+    }
+
+    Ok(records)
+}
+
+/// `{base}/sites/{siteUrl}/searchAnalytics/query`, with the site URL percent-encoded
+/// as a single path segment.
+fn query_url(base_url: &str, site_url: &str) -> Result<Url, String> {
+    let mut url = Url::parse(base_url).map_err(|e| format!("Invalid Search Console base URL: {}", e))?;
+
+    url.path_segments_mut()
+        .map_err(|_| "Invalid Search Console base URL".to_string())?
+        .pop_if_empty()
+        .extend(["sites", site_url, "searchAnalytics", "query"]);
+
+    Ok(url)
+}
+
+fn flatten(site_url: &str, date: NaiveDate, row: Row) -> SearchConsoleRecord {
+    let key = |i: usize| row.keys.get(i).cloned().unwrap_or_default();
+
+    SearchConsoleRecord {
+        site_url: site_url.to_string(),
+        date,
+        query: key(0),
+        page: key(1),
+        country: key(2),
+        device: key(3),
+        clicks: row.clicks as i64,
+        impressions: row.impressions as i64,
+        ctr: row.ctr,
+        position: row.position,
+    }
+}
+
+async fn parse_response<T: serde::de::DeserializeOwned>(response: reqwest::Response) -> Result<T, String> {
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_text = response.text().await.unwrap_or_default();
+        error!(status = %status, error = %error_text, "Search Console API error");
+        return Err(format!("Search Console API error: {} - {}", status, error_text));
+    }
+
+    response.json().await.map_err(|e| {
+        error!(error = %e, "Failed to parse Search Console response");
+        format!("Failed to parse Search Console response: {}", e)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::test_server::serve;
+    use axum::extract::{Path, State};
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use serde_json::{Value, json};
+    use std::sync::{Arc, Mutex};
+
+    /// `(site, startDate, startRow)` of each query the stub received.
+    type Queries = Arc<Mutex<Vec<(String, String, i64)>>>;
+
+    /// Answers a full page of rows for the first page of `2026-10-01`, one row otherwise.
+    async fn query(
+        State(queries): State<Queries>,
+        Path(site): Path<String>,
+        Json(body): Json<Value>,
+    ) -> Json<Value> {
+        let date = body["startDate"].as_str().unwrap().to_string();
+        let start_row = body["startRow"].as_i64().unwrap();
+        queries.lock().unwrap().push((site, date.clone(), start_row));
+
+        let count = if date == "2026-10-01" && start_row == 0 { ROW_LIMIT } else { 1 };
+        let rows: Vec<_> = (0..count)
+            .map(|i| json!({ "keys": [format!("query {}", i), "/", "fra", "DESKTOP"], "clicks": 2.0, "impressions": 10.0 }))
+            .collect();
+
+        Json(json!({ "rows": rows }))
+    }
+
+    async fn stub() -> (String, Queries) {
+        let queries = Queries::default();
+        let router = Router::new()
+            .route("/sites/{site}/searchAnalytics/query", post(query))
+            .with_state(queries.clone());
+
+        (serve(router).await, queries)
+    }
+
+    fn date(value: &str) -> NaiveDate {
+        value.parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn follows_row_pagination() {
+        let (base_url, queries) = stub().await;
+
+        let site = "https://example.com/";
+        let records = pull_days(&base_url, "token", site, date("2026-10-01"), date("2026-10-01"))
+            .await
+            .unwrap();
+
+        assert_eq!(records.len(), ROW_LIMIT as usize + 1);
+        assert_eq!(
+            *queries.lock().unwrap(),
+            [
+                (site.to_string(), "2026-10-01".to_string(), 0),
+                (site.to_string(), "2026-10-01".to_string(), ROW_LIMIT),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn queries_each_day_of_the_range() {
+        let (base_url, queries) = stub().await;
+
+        let records = pull_days(&base_url, "token", "sc-domain:example.com", date("2026-10-02"), date("2026-10-04"))
+            .await
+            .unwrap();
+
+        let dates: Vec<_> = records.iter().map(|r| r.date.to_string()).collect();
+        assert_eq!(dates, ["2026-10-02", "2026-10-03", "2026-10-04"]);
+        assert_eq!(records[0].country, "fra");
+        assert_eq!((records[0].clicks, records[0].impressions), (2, 10));
+        assert!(queries.lock().unwrap().iter().all(|(site, _, row)| site == "sc-domain:example.com" && *row == 0));
+    }
+
+    #[tokio::test]
+    async fn pulls_nothing_when_the_range_is_empty() {
+        let (base_url, queries) = stub().await;
+
+        let records = pull_days(&base_url, "token", "sc-domain:example.com", date("2026-10-05"), date("2026-10-04"))
+            .await
+            .unwrap();
+
+        assert!(records.is_empty());
+        assert!(queries.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,464 @@
+use duckdb::Connection;
+use regex::Regex;
+use serde::Serialize;
+use std::sync::LazyLock;
+use tracing::{debug, info};
+
+pub const DEFAULT_TIMEOUT_MINUTES: u32 = 30;
+pub const MAX_TIMEOUT_MINUTES: u32 = 24 * 60;
+
+const WATERMARK_TABLE: &str = "sessionization_watermarks";
+/// Columns an event table needs to be sessionized.
+const REQUIRED_COLUMNS: [&str; 5] = ["client_id", "event_timestamp", "event_name", "page_location", "page_referrer"];
+
+static TABLE_NAME: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[a-z_][a-z0-9_]*$").unwrap());
+
+#[derive(Debug, Serialize)]
+pub struct SessionizeResult {
+    pub events_table: String,
+    pub sessions_table: String,
+    /// Events read by this run, including those of sessions reopened from the last run.
+    pub event_count: usize,
+    pub session_count: usize,
+    /// Latest arrival (`received_at`, or `event_timestamp` when the table has none)
+    /// processed so far.
+    pub watermark: Option<String>,
+}
+
+/// Name of the sessions table built from an events table, e.g. `events_sessions`.
+pub fn sessions_table(events_table: &str) -> String {
+    format!("{}_sessions", events_table)
+}
+
+/// Columns of the table, once checked it has the required ones.
+fn check_columns(conn: &Connection, events_table: &str) -> Result<Vec<String>, String> {
+    let mut statement = conn
+        .prepare("SELECT column_name FROM information_schema.columns WHERE table_name = ?")
+        .map_err(|e| format!("Failed to inspect table: {}", e))?;
+    let columns = statement
+        .query_map([events_table], |row| row.get::<_, String>(0))
+        .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| format!("Failed to inspect table: {}", e))?;
+
+    if columns.is_empty() {
+        return Err(format!("Table {} does not exist", events_table));
+    }
+
+    let missing: Vec<&str> = REQUIRED_COLUMNS
+        .into_iter()
+        .filter(|c| !columns.iter().any(|column| column == c))
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!("Table {} lacks columns {}", events_table, missing.join(", ")));
+    }
+
+    Ok(columns)
+}
+
+fn count(conn: &Connection, table: &str) -> Result<usize, String> {
+    conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get::<_, i64>(0))
+        .map(|count| count as usize)
+        .map_err(|e| format!("Failed to count rows: {}", e))
+}
+
+/// Groups the events of `events_table` into sessions per client id.
+///
+/// A session ends after `timeout_minutes` without events, at midnight (UTC) and when an
+/// event arrives with another campaign (`utm_*` or `gclid` in its page URL). Runs pick up
+/// from the watermark left by the previous one, on `received_at` when the table has it
+/// since events may arrive backdated. The sessions of a client that ended within the
+/// timeout of its earliest new event are rebuilt together with the new events, since
+/// those may extend, split or merge them.
+pub fn sessionize(conn: &Connection, events_table: &str, timeout_minutes: u32) -> Result<SessionizeResult, String> {
+    if !TABLE_NAME.is_match(events_table) {
+        return Err(format!("Invalid table name '{}'", events_table));
+    }
+    let columns = check_columns(conn, events_table)?;
+
+    let sessions = sessions_table(events_table);
+
+    conn.execute_batch(&format!(
+        r#"
+        CREATE TABLE IF NOT EXISTS {WATERMARK_TABLE} (
+            events_table VARCHAR PRIMARY KEY,
+            watermark TIMESTAMP
+        );
+        CREATE TABLE IF NOT EXISTS {sessions} (
+            session_id VARCHAR,
+            client_id VARCHAR,
+            session_date VARCHAR,
+            session_start TIMESTAMP,
+            session_end TIMESTAMP,
+            duration_seconds DOUBLE,
+            entry_page VARCHAR,
+            exit_page VARCHAR,
+            source VARCHAR,
+            medium VARCHAR,
+            campaign VARCHAR,
+            event_count BIGINT,
+            page_view_count BIGINT,
+            engaged BOOLEAN,
+            is_bounce BOOLEAN
+        );
+        "#
+    ))
+    .map_err(|e| format!("Failed to create sessions table: {}", e))?;
+
+    let watermark: Option<String> = conn
+        .query_row(
+            &format!("SELECT CAST(watermark AS VARCHAR) FROM {WATERMARK_TABLE} WHERE events_table = ?"),
+            [events_table],
+            |row| row.get(0),
+        )
+        .ok()
+        .flatten();
+
+    debug!(events_table = %events_table, watermark = ?watermark, "Sessionizing events");
+
+    conn.execute_batch("BEGIN TRANSACTION;")
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+
+    // Events flagged by a traffic filter are left out
+    let flagged = columns.iter().any(|c| c == "filter_reason");
+    let arrival = if columns.iter().any(|c| c == "received_at") {
+        "received_at"
+    } else {
+        "event_timestamp"
+    };
+
+    let batch = Batch {
+        events_table,
+        sessions: &sessions,
+        watermark: watermark.as_deref(),
+        arrival,
+        timeout_minutes,
+        flagged,
+    };
+    let result = build_sessions(conn, &batch);
+
+    match result {
+        Ok(()) => conn
+            .execute_batch("COMMIT;")
+            .map_err(|e| format!("Failed to commit: {}", e))?,
+        Err(e) => {
+            let _ = conn.execute_batch("ROLLBACK;");
+            return Err(e);
+        }
+    }
+
+    let event_count = count(conn, "sessionize_batch")?;
+    let session_count = count(conn, "sessionize_sessions")?;
+    let watermark: Option<String> = conn
+        .query_row(
+            &format!("SELECT CAST(watermark AS VARCHAR) FROM {WATERMARK_TABLE} WHERE events_table = ?"),
+            [events_table],
+            |row| row.get(0),
+        )
+        .ok()
+        .flatten();
+
+    conn.execute_batch(
+        "DROP TABLE IF EXISTS sessionize_batch; DROP TABLE IF EXISTS sessionize_sessions; \
+         DROP TABLE IF EXISTS sessionize_arrived; DROP TABLE IF EXISTS sessionize_reopened;",
+    )
+        .map_err(|e| format!("Failed to drop temporary tables: {}", e))?;
+
+    info!(events_table = %events_table, event_count = event_count, session_count = session_count, "Sessions built");
+
+    Ok(SessionizeResult {
+        events_table: events_table.to_string(),
+        sessions_table: sessions,
+        event_count,
+        session_count,
+        watermark,
+    })
+}
+
+/// What a sessionize run reads and writes.
+struct Batch<'a> {
+    events_table: &'a str,
+    sessions: &'a str,
+    watermark: Option<&'a str>,
+    /// Column the watermark is kept on.
+    arrival: &'a str,
+    timeout_minutes: u32,
+    flagged: bool,
+}
+
+fn build_sessions(conn: &Connection, batch: &Batch) -> Result<(), String> {
+    let Batch {
+        events_table,
+        sessions,
+        arrival,
+        ..
+    } = *batch;
+    let timeout = format!("INTERVAL {} MINUTE", batch.timeout_minutes);
+    let flagged_filter = if batch.flagged { "AND e.filter_reason IS NULL" } else { "" };
+
+    // Events arrived past the watermark, plus those of the sessions they may change
+    let (reopened_join, batch_filter) = match batch.watermark {
+        Some(watermark) => {
+            let watermark = format!("TIMESTAMP '{}'", watermark.replace('\'', "''"));
+            conn.execute_batch(&format!(
+                r#"
+                CREATE OR REPLACE TEMP TABLE sessionize_arrived AS
+                SELECT CAST(e.client_id AS VARCHAR) AS client_id, MIN(CAST(e.event_timestamp AS TIMESTAMP)) AS first_event
+                FROM {events_table} e
+                WHERE CAST(e.{arrival} AS TIMESTAMP) > {watermark} {flagged_filter}
+                GROUP BY 1;
+
+                CREATE OR REPLACE TEMP TABLE sessionize_reopened AS
+                SELECT a.client_id, LEAST(a.first_event, MIN(s.session_start)) AS since
+                FROM sessionize_arrived a
+                LEFT JOIN {sessions} s ON s.client_id = a.client_id AND s.session_end >= a.first_event - {timeout}
+                GROUP BY a.client_id, a.first_event;
+
+                DELETE FROM {sessions} WHERE EXISTS (
+                    SELECT 1 FROM sessionize_arrived a
+                    WHERE a.client_id = {sessions}.client_id AND {sessions}.session_end >= a.first_event - {timeout}
+                );
+                "#
+            ))
+            .map_err(|e| format!("Failed to reopen sessions: {}", e))?;
+
+            (
+                "JOIN sessionize_reopened r ON r.client_id = CAST(e.client_id AS VARCHAR)",
+                format!("WHERE CAST(e.event_timestamp AS TIMESTAMP) >= r.since {flagged_filter}"),
+            )
+        }
+        None if batch.flagged => ("", "WHERE e.filter_reason IS NULL".to_string()),
+        None => ("", String::new()),
+    };
+
+    conn.execute_batch(&format!(
+        r#"
+        CREATE OR REPLACE TEMP TABLE sessionize_batch AS
+        SELECT
+            CAST(e.client_id AS VARCHAR) AS client_id,
+            CAST(e.event_timestamp AS TIMESTAMP) AS event_timestamp,
+            e.event_name,
+            e.page_location,
+            e.page_referrer
+        FROM {events_table} e
+        {reopened_join}
+        {batch_filter}
+        "#
+    ))
+    .map_err(|e| format!("Failed to read events: {}", e))?;
+
+    conn.execute_batch(&format!(
+        r#"
+        CREATE OR REPLACE TEMP TABLE sessionize_sessions AS
+        WITH tagged AS (
+            SELECT
+                *,
+                NULLIF(url_decode(regexp_extract(page_location, '[?&]utm_source=([^&#]*)', 1)), '') AS utm_source,
+                NULLIF(url_decode(regexp_extract(page_location, '[?&]utm_medium=([^&#]*)', 1)), '') AS utm_medium,
+                NULLIF(url_decode(regexp_extract(page_location, '[?&]utm_campaign=([^&#]*)', 1)), '') AS utm_campaign,
+                NULLIF(regexp_extract(page_location, '[?&]gclid=([^&#]*)', 1), '') AS gclid
+            FROM sessionize_batch
+            WHERE client_id IS NOT NULL AND event_timestamp IS NOT NULL
+        ),
+        campaigns AS (
+            SELECT
+                *,
+                CASE WHEN utm_source IS NOT NULL OR gclid IS NOT NULL
+                    THEN concat_ws('|', utm_source, utm_medium, utm_campaign, gclid IS NOT NULL)
+                END AS campaign_key
+            FROM tagged
+        ),
+        flagged AS (
+            SELECT
+                *,
+                LAG(event_timestamp) OVER (PARTITION BY client_id ORDER BY event_timestamp) AS previous_timestamp,
+                LAST_VALUE(campaign_key IGNORE NULLS) OVER (
+                    PARTITION BY client_id ORDER BY event_timestamp
+                    ROWS BETWEEN UNBOUNDED PRECEDING AND 1 PRECEDING
+                ) AS previous_campaign
+            FROM campaigns
+        ),
+        numbered AS (
+            SELECT
+                *,
+                SUM(CASE
+                    WHEN previous_timestamp IS NULL
+                        OR event_timestamp - previous_timestamp > {timeout}
+                        OR CAST(event_timestamp AS DATE) <> CAST(previous_timestamp AS DATE)
+                        OR (campaign_key IS NOT NULL AND campaign_key IS DISTINCT FROM previous_campaign)
+                    THEN 1 ELSE 0
+                END) OVER (PARTITION BY client_id ORDER BY event_timestamp ROWS UNBOUNDED PRECEDING) AS session_number
+            FROM flagged
+        ),
+        grouped AS (
+            SELECT
+                client_id,
+                MIN(event_timestamp) AS session_start,
+                MAX(event_timestamp) AS session_end,
+                COUNT(*) AS event_count,
+                COUNT(*) FILTER (WHERE event_name = 'page_view') AS page_view_count,
+                first(page_location ORDER BY event_timestamp) FILTER (WHERE page_location IS NOT NULL) AS entry_page,
+                last(page_location ORDER BY event_timestamp) FILTER (WHERE page_location IS NOT NULL) AS exit_page,
+                first(page_referrer ORDER BY event_timestamp) AS referrer,
+                first(utm_source ORDER BY event_timestamp) AS utm_source,
+                first(utm_medium ORDER BY event_timestamp) AS utm_medium,
+                first(utm_campaign ORDER BY event_timestamp) AS utm_campaign,
+                first(gclid ORDER BY event_timestamp) AS gclid
+            FROM numbered
+            GROUP BY client_id, session_number
+        ),
+        attributed AS (
+            SELECT
+                *,
+                date_diff('millisecond', session_start, session_end) / 1000.0 AS duration_seconds,
+                NULLIF(regexp_extract(referrer, '^[A-Za-z]+://([^/:?#]+)', 1), '') AS referrer_host,
+                regexp_extract(entry_page, '^[A-Za-z]+://([^/:?#]+)', 1) AS landing_host
+            FROM grouped
+        )
+        SELECT
+            client_id || '-' || CAST(epoch_us(session_start) AS VARCHAR) AS session_id,
+            client_id,
+            strftime(session_start, '%Y%m%d') AS session_date,
+            session_start,
+            session_end,
+            duration_seconds,
+            entry_page,
+            exit_page,
+            CASE
+                WHEN utm_source IS NOT NULL THEN utm_source
+                WHEN gclid IS NOT NULL THEN 'google'
+                WHEN referrer_host IS NOT NULL AND referrer_host <> landing_host THEN referrer_host
+                ELSE '(direct)'
+            END AS source,
+            CASE
+                WHEN utm_source IS NOT NULL THEN COALESCE(utm_medium, '(not set)')
+                WHEN gclid IS NOT NULL THEN 'cpc'
+                WHEN referrer_host IS NOT NULL AND referrer_host <> landing_host THEN 'referral'
+                ELSE '(none)'
+            END AS medium,
+            utm_campaign AS campaign,
+            event_count,
+            page_view_count,
+            -- GA4 engaged sessions; bounces are the rest, as in GA4's bounceRate
+            duration_seconds >= 10 OR page_view_count >= 2 AS engaged,
+            NOT (duration_seconds >= 10 OR page_view_count >= 2) AS is_bounce
+        FROM attributed;
+
+        INSERT INTO {sessions} BY NAME SELECT * FROM sessionize_sessions;
+
+        INSERT OR REPLACE INTO {WATERMARK_TABLE}
+        SELECT '{events_table}', MAX(CAST({arrival} AS TIMESTAMP))
+        FROM {events_table}
+        HAVING MAX({arrival}) IS NOT NULL;
+
+        -- Daily figures in the shape of ga4_records, to compare with GA4
+        CREATE OR REPLACE VIEW {sessions}_daily AS
+        SELECT
+            session_date AS date,
+            COUNT(DISTINCT client_id) AS active_users,
+            COUNT(*) AS sessions,
+            SUM(page_view_count) AS screen_page_views,
+            AVG(CAST(is_bounce AS DOUBLE)) AS bounce_rate,
+            AVG(duration_seconds) AS average_session_duration
+        FROM {sessions}
+        GROUP BY session_date;
+        "#
+    ))
+    .map_err(|e| format!("Failed to build sessions: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn events_store() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE events (client_id VARCHAR, event_timestamp TIMESTAMP, received_at TIMESTAMP, \
+             event_name VARCHAR, page_location VARCHAR, page_referrer VARCHAR);",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn collect(conn: &Connection, client_id: &str, event_timestamp: &str, received_at: &str) {
+        conn.execute(
+            "INSERT INTO events VALUES (?, CAST(? AS TIMESTAMP), CAST(? AS TIMESTAMP), 'page_view', 'https://example.com/', NULL)",
+            [client_id, event_timestamp, received_at],
+        )
+        .unwrap();
+    }
+
+    fn sessions(conn: &Connection) -> Vec<(String, i64)> {
+        let mut statement = conn
+            .prepare("SELECT CAST(session_start AS VARCHAR), event_count FROM events_sessions ORDER BY session_start, client_id")
+            .unwrap();
+        statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn splits_sessions_after_the_timeout() {
+        let conn = events_store();
+        collect(&conn, "a", "2025-01-01 10:00:00", "2025-01-01 10:00:00");
+        collect(&conn, "a", "2025-01-01 10:20:00", "2025-01-01 10:20:00");
+        collect(&conn, "a", "2025-01-01 11:00:00", "2025-01-01 11:00:00");
+
+        let result = sessionize(&conn, "events", 30).unwrap();
+
+        assert_eq!(result.session_count, 2);
+        assert_eq!(
+            sessions(&conn),
+            vec![("2025-01-01 10:00:00".to_string(), 2), ("2025-01-01 11:00:00".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn sessionizes_backdated_events_arriving_later() {
+        let conn = events_store();
+        collect(&conn, "a", "2025-01-02 10:00:00", "2025-01-02 10:00:00");
+        sessionize(&conn, "events", 30).unwrap();
+
+        // Sent a day late, older than the watermark minus the timeout
+        collect(&conn, "a", "2025-01-01 09:00:00", "2025-01-02 12:00:00");
+        let result = sessionize(&conn, "events", 30).unwrap();
+
+        assert_eq!(result.watermark.as_deref(), Some("2025-01-02 12:00:00"));
+        assert_eq!(
+            sessions(&conn),
+            vec![("2025-01-01 09:00:00".to_string(), 1), ("2025-01-02 10:00:00".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn reopens_the_sessions_late_events_join() {
+        let conn = events_store();
+        collect(&conn, "a", "2025-01-01 10:00:00", "2025-01-01 10:00:00");
+        collect(&conn, "a", "2025-01-01 10:50:00", "2025-01-01 10:50:00");
+        collect(&conn, "b", "2025-01-01 10:00:00", "2025-01-01 10:00:00");
+        sessionize(&conn, "events", 30).unwrap();
+        assert_eq!(sessions(&conn).len(), 3);
+
+        // Bridges the two sessions of client a, client b is left as is
+        collect(&conn, "a", "2025-01-01 10:25:00", "2025-01-01 12:00:00");
+        let result = sessionize(&conn, "events", 30).unwrap();
+
+        assert_eq!(result.event_count, 3);
+        assert_eq!(
+            sessions(&conn),
+            vec![("2025-01-01 10:00:00".to_string(), 3), ("2025-01-01 10:00:00".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_tables() {
+        let conn = events_store();
+        conn.execute_batch("CREATE TABLE partial (client_id VARCHAR);").unwrap();
+
+        assert!(sessionize(&conn, "events; DROP TABLE events", 30).is_err());
+        assert!(sessionize(&conn, "missing", 30).is_err());
+        assert!(sessionize(&conn, "partial", 30).is_err());
+    }
+}
@@ -1,16 +1,74 @@
-use chrono::NaiveDate;
-use duckdb::{Connection, params};
+use chrono::{DateTime, NaiveDate};
+use duckdb::{Connection, appender_params_from_iter, params, params_from_iter, types::{TimeUnit, Value}};
 use serde::Serialize;
 use std::path::PathBuf;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 use uuid::Uuid;
 
+use super::database_service::quote_identifier;
 use super::ga4_service::GA4Record;
 
 const DATA_DIR: &str = "/tmp/ga4_data";
 const LOOKBACK_DAYS: i64 = 2;
 const DEFAULT_BACKFILL_DAYS: i64 = 30;
 
+/// DuckDB file of GA4 connectors, holding `ga4_records`.
+pub const GA4_STORE_FILE: &str = "ga4.duckdb";
+
+/// Column layout of a DuckDB table written by a source.
+#[derive(Debug, Clone, Serialize)]
+pub struct TableSchema {
+    pub name: String,
+    pub columns: Vec<Column>,
+    pub primary_key: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Column {
+    pub name: String,
+    pub data_type: String,
+}
+
+impl TableSchema {
+    pub fn new(name: &str, columns: &[(&str, &str)], primary_key: &[&str]) -> Self {
+        Self {
+            name: name.to_string(),
+            columns: columns
+                .iter()
+                .map(|(name, data_type)| Column {
+                    name: name.to_string(),
+                    data_type: data_type.to_string(),
+                })
+                .collect(),
+            primary_key: primary_key.iter().map(|c| c.to_string()).collect(),
+        }
+    }
+
+    /// Names come from connector configs too, quoted so keywords like `order` stay usable.
+    fn column_list(&self) -> String {
+        self.columns
+            .iter()
+            .map(|c| quote_identifier(&c.name))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn create_table_sql(&self, name: &str, with_primary_key: bool) -> String {
+        let mut definitions: Vec<String> = self
+            .columns
+            .iter()
+            .map(|c| format!("{} {}", quote_identifier(&c.name), c.data_type))
+            .collect();
+
+        if with_primary_key && !self.primary_key.is_empty() {
+            let key: Vec<String> = self.primary_key.iter().map(|k| quote_identifier(k)).collect();
+            definitions.push(format!("PRIMARY KEY ({})", key.join(", ")));
+        }
+
+        format!("CREATE TABLE IF NOT EXISTS {} ({});", quote_identifier(name), definitions.join(", "))
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct StorageResult {
     pub record_count: usize,
@@ -42,33 +100,14 @@ pub fn store(
     let dir = data_dir(project_id, connector_id);
     std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create directory: {}", e))?;
 
-    let db_path = dir.join("ga4.duckdb");
+    let db_path = dir.join(GA4_STORE_FILE);
     debug!(db_path = %db_path.display(), "Opening DuckDB");
 
     let conn = Connection::open(&db_path).map_err(|e| format!("Failed to open DuckDB: {}", e))?;
     debug!("DuckDB connection opened");
 
-    // Create table if not exists with primary key for deduplication
-    conn.execute_batch(
-        r#"
-        CREATE TABLE IF NOT EXISTS ga4_records (
-            date VARCHAR,
-            country VARCHAR,
-            device_category VARCHAR,
-            event_name VARCHAR,
-            browser VARCHAR,
-            operating_system VARCHAR,
-            screen_resolution VARCHAR,
-            active_users BIGINT,
-            sessions BIGINT,
-            screen_page_views BIGINT,
-            bounce_rate DOUBLE,
-            average_session_duration DOUBLE,
-            PRIMARY KEY (date, country, device_category, event_name, browser, operating_system, screen_resolution)
-        );
-        "#,
-    )
-    .map_err(|e| format!("Failed to create table: {}", e))?;
+    migrate_legacy_table(&conn, None)?;
+    create_ga4_table(&conn)?;
     debug!("Table ready");
 
     // Check if table is empty (first sync)
@@ -112,6 +151,386 @@ fn data_dir(project_id: Uuid, connector_id: Uuid) -> PathBuf {
         .join(connector_id.to_string())
 }
 
+/// Whether anything has been written to the connector's DuckDB store.
+pub fn has_data(project_id: Uuid, connector_id: Uuid) -> bool {
+    std::fs::read_dir(data_dir(project_id, connector_id))
+        .map(|mut entries| entries.next().is_some())
+        .unwrap_or(false)
+}
+
+/// Opens a DuckDB file in the connector's data directory, creating it if needed.
+pub fn open_store(project_id: Uuid, connector_id: Uuid, file_name: &str) -> Result<Connection, String> {
+    let dir = data_dir(project_id, connector_id);
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create directory: {}", e))?;
+
+    let db_path = dir.join(file_name);
+    debug!(db_path = %db_path.display(), "Opening DuckDB");
+
+    Connection::open(&db_path).map_err(|e| format!("Failed to open DuckDB: {}", e))
+}
+
+/// Opens a DuckDB file in the project's data directory, for data that belongs to no
+/// connector such as collected events.
+pub fn open_project_store(project_id: Uuid, file_name: &str) -> Result<Connection, String> {
+    let dir = PathBuf::from(DATA_DIR).join(project_id.to_string());
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create directory: {}", e))?;
+
+    let db_path = dir.join(file_name);
+    debug!(db_path = %db_path.display(), "Opening DuckDB");
+
+    Connection::open(&db_path).map_err(|e| format!("Failed to open DuckDB: {}", e))
+}
+
+/// DuckDB value of a `DATE` column.
+pub fn date_value(date: NaiveDate) -> Value {
+    Value::Date32((date - NaiveDate::from_ymd_opt(1970, 1, 1).unwrap()).num_days() as i32)
+}
+
+/// Converts a JSON value to the DuckDB value of a column type. Numbers may come as
+/// strings (e.g. int64 in Google APIs), `TIMESTAMP`s as unix seconds or RFC 3339 and
+/// `DATE`s as `YYYY-MM-DD`; objects and arrays are kept as JSON text.
+pub fn json_value(value: Option<&serde_json::Value>, data_type: &str) -> Value {
+    let Some(value) = value.filter(|v| !v.is_null()) else {
+        return Value::Null;
+    };
+
+    let parsed = match data_type {
+        "BIGINT" => value
+            .as_i64()
+            .or_else(|| value.as_str().and_then(|s| s.parse().ok()))
+            .map(Value::BigInt),
+        "DOUBLE" => value
+            .as_f64()
+            .or_else(|| value.as_str().and_then(|s| s.parse().ok()))
+            .map(Value::Double),
+        "BOOLEAN" => value
+            .as_bool()
+            .or_else(|| value.as_str().and_then(|s| s.parse().ok()))
+            .map(Value::Boolean),
+        "TIMESTAMP" => value
+            .as_i64()
+            .or_else(|| {
+                value
+                    .as_str()
+                    .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+                    .map(|dt| dt.timestamp())
+            })
+            .map(|secs| Value::Timestamp(TimeUnit::Second, secs)),
+        "DATE" => value
+            .as_str()
+            .and_then(|s| NaiveDate::parse_from_str(s.get(..10).unwrap_or(s), "%Y-%m-%d").ok())
+            .map(date_value),
+        _ => Some(match value {
+            serde_json::Value::String(s) => Value::Text(s.clone()),
+            other => Value::Text(other.to_string()),
+        }),
+    };
+
+    parsed.unwrap_or(Value::Null)
+}
+
+/// Creates the table if needed and upserts `rows` on its primary key through a staging table.
+/// Each row holds one value per column, in the order of `schema.columns`.
+pub fn upsert_rows(
+    conn: &Connection,
+    schema: &TableSchema,
+    rows: Vec<Vec<Value>>,
+) -> Result<StorageResult, String> {
+    let record_count = rows.len();
+
+    conn.execute_batch(&schema.create_table_sql(&schema.name, true))
+        .map_err(|e| format!("Failed to create table: {}", e))?;
+    add_missing_columns(conn, schema)?;
+
+    if rows.is_empty() {
+        return Ok(StorageResult {
+            record_count: 0,
+            inserted_count: 0,
+            updated_count: 0,
+        });
+    }
+
+    let staging_name = format!("{}_staging", schema.name);
+    let staging = quote_identifier(&staging_name);
+    conn.execute_batch(&format!(
+        "DROP TABLE IF EXISTS {staging}; {}",
+        schema.create_table_sql(&staging_name, false)
+    ))
+    .map_err(|e| format!("Failed to create staging table: {}", e))?;
+
+    {
+        let mut appender = conn
+            .appender(&staging_name)
+            .map_err(|e| format!("Failed to create staging appender: {}", e))?;
+
+        for row in rows {
+            appender
+                .append_row(appender_params_from_iter(row))
+                .map_err(|e| format!("Failed to append to staging: {}", e))?;
+        }
+    }
+    debug!(records = record_count, table = %schema.name, "Bulk inserted into staging");
+
+    // Rows whose key already exists are replaced, the rest are new
+    let key_match = schema
+        .primary_key
+        .iter()
+        .map(|k| format!("t.{k} IS NOT DISTINCT FROM s.{k}", k = quote_identifier(k)))
+        .collect::<Vec<_>>()
+        .join(" AND ");
+    let updated_count: i64 = conn
+        .query_row(
+            &format!(
+                "SELECT COUNT(*) FROM {staging} s WHERE EXISTS (SELECT 1 FROM {} t WHERE {key_match})",
+                quote_identifier(&schema.name)
+            ),
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to compare with existing rows: {}", e))?;
+
+    let columns = schema.column_list();
+    conn.execute_batch(&format!(
+        "INSERT OR REPLACE INTO {table} ({columns}) SELECT {columns} FROM {staging}; DROP TABLE {staging};",
+        table = quote_identifier(&schema.name),
+    ))
+    .map_err(|e| format!("Failed to merge from staging: {}", e))?;
+
+    let updated_count = updated_count as usize;
+    info!(
+        table = %schema.name,
+        incoming_records = record_count,
+        inserted = record_count - updated_count,
+        updated = updated_count,
+        "Data stored"
+    );
+
+    Ok(StorageResult {
+        record_count,
+        inserted_count: record_count - updated_count,
+        updated_count,
+    })
+}
+
+/// Adds the columns of `schema` that an existing table lacks, at its end.
+fn add_missing_columns(conn: &Connection, schema: &TableSchema) -> Result<(), String> {
+    let migration: String = schema
+        .columns
+        .iter()
+        .map(|c| {
+            format!(
+                "ALTER TABLE {} ADD COLUMN IF NOT EXISTS {} {};",
+                quote_identifier(&schema.name),
+                quote_identifier(&c.name),
+                c.data_type
+            )
+        })
+        .collect();
+    conn.execute_batch(&migration)
+        .map_err(|e| format!("Failed to migrate table: {}", e))
+}
+
+/// Creates the table if needed and appends `rows` as is, for append-only tables.
+///
+/// Columns added to the end of `schema` are added to existing tables, so rows keep
+/// lining up with them.
+pub fn append_rows(conn: &Connection, schema: &TableSchema, rows: Vec<Vec<Value>>) -> Result<usize, String> {
+    conn.execute_batch(&schema.create_table_sql(&schema.name, false))
+        .map_err(|e| format!("Failed to create table: {}", e))?;
+    add_missing_columns(conn, schema)?;
+
+    let record_count = rows.len();
+    let mut appender = conn
+        .appender(&schema.name)
+        .map_err(|e| format!("Failed to create appender: {}", e))?;
+
+    for row in rows {
+        appender
+            .append_row(appender_params_from_iter(row))
+            .map_err(|e| format!("Failed to append row: {}", e))?;
+    }
+    appender.flush().map_err(|e| format!("Failed to flush appender: {}", e))?;
+
+    debug!(records = record_count, table = %schema.name, "Rows appended");
+    Ok(record_count)
+}
+
+/// Latest value of a `DATE` column among the rows where each `(column, value)` filter matches.
+pub fn max_date(
+    project_id: Uuid,
+    connector_id: Uuid,
+    file_name: &str,
+    table: &str,
+    date_column: &str,
+    filters: &[(&str, &str)],
+) -> Option<NaiveDate> {
+    let db_path = data_dir(project_id, connector_id).join(file_name);
+    if !db_path.exists() {
+        return None;
+    }
+
+    let conn = Connection::open(&db_path)
+        .map_err(|e| debug!(error = %e, "Failed to open DuckDB"))
+        .ok()?;
+
+    let conditions = filters
+        .iter()
+        .map(|(column, _)| format!("{column} = ?"))
+        .collect::<Vec<_>>()
+        .join(" AND ");
+    let max_date: Option<String> = conn
+        .query_row(
+            &format!("SELECT CAST(MAX({date_column}) AS VARCHAR) FROM {table} WHERE {conditions}"),
+            params_from_iter(filters.iter().map(|(_, value)| value)),
+            |row| row.get(0),
+        )
+        .ok()
+        .flatten();
+
+    max_date.and_then(|date| NaiveDate::parse_from_str(&date, "%Y-%m-%d").ok())
+}
+
+/// Latest value of a `TIMESTAMP` column as unix seconds, `None` until the table has rows.
+pub fn max_epoch(conn: &Connection, table: &str, column: &str) -> Option<i64> {
+    conn.query_row(&format!("SELECT CAST(epoch(MAX({column})) AS BIGINT) FROM {table}"), [], |row| {
+        row.get(0)
+    })
+    .ok()
+    .flatten()
+}
+
+/// Start of an incremental sync: `LOOKBACK_DAYS` before the latest stored date,
+/// or `DEFAULT_BACKFILL_DAYS` before `today` when nothing is stored yet.
+pub fn incremental_start_date(max_date: Option<NaiveDate>, today: NaiveDate) -> NaiveDate {
+    match max_date {
+        Some(max_date) => {
+            let start = max_date - chrono::Duration::days(LOOKBACK_DAYS);
+            info!(
+                max_date = %max_date,
+                start_date = %start,
+                lookback_days = LOOKBACK_DAYS,
+                "Incremental sync from existing data"
+            );
+            start
+        }
+        None => {
+            info!("No existing records, using default backfill of {} days", DEFAULT_BACKFILL_DAYS);
+            today - chrono::Duration::days(DEFAULT_BACKFILL_DAYS)
+        }
+    }
+}
+
+/// Attributes the rows of a store written before multi-property support, which have no
+/// `property_id`, to the property the connector had selected back then.
+///
+/// Without a known property the rows are parked in `ga4_records_legacy` and merged on a
+/// later call that has one.
+pub fn migrate_legacy_store(
+    project_id: Uuid,
+    connector_id: Uuid,
+    legacy_property_id: Option<&str>,
+) -> Result<(), String> {
+    let db_path = data_dir(project_id, connector_id).join(GA4_STORE_FILE);
+    if !db_path.exists() {
+        return Ok(());
+    }
+
+    let conn = Connection::open(&db_path).map_err(|e| format!("Failed to open DuckDB: {}", e))?;
+    migrate_legacy_table(&conn, legacy_property_id)
+}
+
+fn migrate_legacy_table(conn: &Connection, legacy_property_id: Option<&str>) -> Result<(), String> {
+    if count_rows(
+        conn,
+        "SELECT COUNT(*) FROM information_schema.tables WHERE table_name = 'ga4_records'",
+    )? > 0
+        && count_rows(
+            conn,
+            "SELECT COUNT(*) FROM information_schema.columns WHERE table_name = 'ga4_records' AND column_name = 'property_id'",
+        )? == 0
+    {
+        info!("Legacy ga4_records table without property_id, moving to ga4_records_legacy");
+        conn.execute_batch("ALTER TABLE ga4_records RENAME TO ga4_records_legacy;")
+            .map_err(|e| format!("Failed to rename legacy table: {}", e))?;
+    }
+
+    if count_rows(
+        conn,
+        "SELECT COUNT(*) FROM information_schema.tables WHERE table_name = 'ga4_records_legacy'",
+    )? == 0
+    {
+        return Ok(());
+    }
+
+    let Some(property_id) = legacy_property_id else {
+        warn!("Legacy GA4 rows kept in ga4_records_legacy, no property to attribute them to");
+        return Ok(());
+    };
+
+    create_ga4_table(conn)?;
+
+    // Rows synced since then are fresher, keep them over the legacy ones
+    conn.execute_batch("BEGIN TRANSACTION;")
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    let merged = conn
+        .execute(
+            "INSERT OR IGNORE INTO ga4_records BY NAME SELECT *, ? AS property_id FROM ga4_records_legacy",
+            params![property_id],
+        )
+        .and_then(|merged| conn.execute_batch("DROP TABLE ga4_records_legacy; COMMIT;").map(|_| merged))
+        .map_err(|e| {
+            let _ = conn.execute_batch("ROLLBACK;");
+            format!("Failed to merge legacy table: {}", e)
+        })?;
+
+    info!(property_id = %property_id, merged = merged, "Legacy GA4 rows merged into ga4_records");
+    Ok(())
+}
+
+fn count_rows(conn: &Connection, query: &str) -> Result<i64, String> {
+    conn.query_row(query, [], |row| row.get(0))
+        .map_err(|e| format!("Failed to inspect tables: {}", e))
+}
+
+/// Creates `ga4_records` if needed and adds the columns introduced since.
+fn create_ga4_table(conn: &Connection) -> Result<(), String> {
+    // Primary key for deduplication
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS ga4_records (
+            property_id VARCHAR,
+            date VARCHAR,
+            country VARCHAR,
+            device_category VARCHAR,
+            event_name VARCHAR,
+            browser VARCHAR,
+            operating_system VARCHAR,
+            screen_resolution VARCHAR,
+            active_users BIGINT,
+            sessions BIGINT,
+            screen_page_views BIGINT,
+            bounce_rate DOUBLE,
+            average_session_duration DOUBLE,
+            total_revenue DOUBLE,
+            currency_code VARCHAR,
+            PRIMARY KEY (property_id, date, country, device_category, event_name, browser, operating_system, screen_resolution)
+        );
+        "#,
+    )
+    .map_err(|e| format!("Failed to create table: {}", e))?;
+
+    // Columns added after the initial schema, appended so `SELECT *` merges keep lining up
+    conn.execute_batch(
+        r#"
+        ALTER TABLE ga4_records ADD COLUMN IF NOT EXISTS total_revenue DOUBLE;
+        ALTER TABLE ga4_records ADD COLUMN IF NOT EXISTS currency_code VARCHAR;
+        "#,
+    )
+    .map_err(|e| format!("Failed to migrate table: {}", e))?;
+
+    Ok(())
+}
+
 /// Fast bulk insert using DuckDB appender (for first sync)
 fn bulk_insert(conn: &Connection, records: &[GA4Record]) -> Result<(usize, usize), String> {
     let mut appender = conn
@@ -121,6 +540,7 @@ fn bulk_insert(conn: &Connection, records: &[GA4Record]) -> Result<(usize, usize
     for r in records {
         appender
             .append_row(params![
+                r.property_id,
                 r.date,
                 r.country,
                 r.device_category,
@@ -133,6 +553,8 @@ fn bulk_insert(conn: &Connection, records: &[GA4Record]) -> Result<(usize, usize
                 r.screen_page_views,
                 r.bounce_rate,
                 r.average_session_duration,
+                r.total_revenue,
+                r.currency_code,
             ])
             .map_err(|e| format!("Failed to append record: {}", e))?;
     }
@@ -150,6 +572,7 @@ fn upsert(conn: &Connection, records: &[GA4Record]) -> Result<(usize, usize), St
         r#"
         DROP TABLE IF EXISTS ga4_staging;
         CREATE TABLE ga4_staging (
+            property_id VARCHAR,
             date VARCHAR,
             country VARCHAR,
             device_category VARCHAR,
@@ -161,7 +584,9 @@ fn upsert(conn: &Connection, records: &[GA4Record]) -> Result<(usize, usize), St
             sessions BIGINT,
             screen_page_views BIGINT,
             bounce_rate DOUBLE,
-            average_session_duration DOUBLE
+            average_session_duration DOUBLE,
+            total_revenue DOUBLE,
+            currency_code VARCHAR
         );
         "#,
     )
@@ -177,6 +602,7 @@ fn upsert(conn: &Connection, records: &[GA4Record]) -> Result<(usize, usize), St
         for r in records {
             appender
                 .append_row(params![
+                    r.property_id,
                     r.date,
                     r.country,
                     r.device_category,
@@ -189,6 +615,8 @@ fn upsert(conn: &Connection, records: &[GA4Record]) -> Result<(usize, usize), St
                     r.screen_page_views,
                     r.bounce_rate,
                     r.average_session_duration,
+                    r.total_revenue,
+                    r.currency_code,
                 ])
                 .map_err(|e| format!("Failed to append to staging: {}", e))?;
         }
@@ -209,16 +637,18 @@ fn upsert(conn: &Connection, records: &[GA4Record]) -> Result<(usize, usize), St
     Ok((records.len(), 0))
 }
 
-/// Get the start date for incremental sync.
-/// Returns max_date - LOOKBACK_DAYS if data exists, otherwise today - DEFAULT_BACKFILL_DAYS.
+/// Get the start date for incremental sync of a single property.
+/// Returns max_date - LOOKBACK_DAYS if data exists, otherwise today - DEFAULT_BACKFILL_DAYS,
+/// where `today` is the current date in the property's reporting time zone.
 pub fn get_incremental_start_date(
     project_id: Uuid,
     connector_id: Uuid,
+    property_id: &str,
+    today: NaiveDate,
 ) -> NaiveDate {
-    let today = chrono::Utc::now().date_naive();
     let default_start = today - chrono::Duration::days(DEFAULT_BACKFILL_DAYS);
 
-    let db_path = data_dir(project_id, connector_id).join("ga4.duckdb");
+    let db_path = data_dir(project_id, connector_id).join(GA4_STORE_FILE);
 
     if !db_path.exists() {
         info!("No existing data, using default backfill of {} days", DEFAULT_BACKFILL_DAYS);
@@ -235,7 +665,11 @@ pub fn get_incremental_start_date(
 
     // Get max date from existing data (format: "YYYYMMDD")
     let max_date: Option<String> = conn
-        .query_row("SELECT MAX(date) FROM ga4_records", [], |row| row.get(0))
+        .query_row(
+            "SELECT MAX(date) FROM ga4_records WHERE property_id = ?",
+            params![property_id],
+            |row| row.get(0),
+        )
         .ok();
 
     match max_date {
@@ -264,3 +698,77 @@ pub fn get_incremental_start_date(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn legacy_store() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            r#"
+            CREATE TABLE ga4_records (
+                date VARCHAR, country VARCHAR, device_category VARCHAR, event_name VARCHAR,
+                browser VARCHAR, operating_system VARCHAR, screen_resolution VARCHAR,
+                active_users BIGINT, sessions BIGINT, screen_page_views BIGINT,
+                bounce_rate DOUBLE, average_session_duration DOUBLE,
+                PRIMARY KEY (date, country, device_category, event_name, browser, operating_system, screen_resolution)
+            );
+            INSERT INTO ga4_records VALUES
+                ('20250101', 'France', 'desktop', 'page_view', 'Chrome', 'Linux', '1920x1080', 5, 6, 7, 0.5, 10.0),
+                ('20250102', 'France', 'desktop', 'page_view', 'Chrome', 'Linux', '1920x1080', 1, 2, 3, 0.5, 10.0);
+            "#,
+        )
+        .unwrap();
+        conn
+    }
+
+    fn count(conn: &Connection, query: &str) -> i64 {
+        conn.query_row(query, [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn merges_legacy_rows_into_the_selected_property() {
+        let conn = legacy_store();
+
+        migrate_legacy_table(&conn, Some("properties/123")).unwrap();
+
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM ga4_records WHERE property_id = 'properties/123'"), 2);
+        assert_eq!(
+            count(&conn, "SELECT COUNT(*) FROM information_schema.tables WHERE table_name = 'ga4_records_legacy'"),
+            0
+        );
+    }
+
+    #[test]
+    fn parks_legacy_rows_until_a_property_is_known() {
+        let conn = legacy_store();
+
+        migrate_legacy_table(&conn, None).unwrap();
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM ga4_records_legacy"), 2);
+
+        create_ga4_table(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO ga4_records (property_id, date, country, device_category, event_name, browser, \
+             operating_system, screen_resolution, active_users) \
+             VALUES ('properties/123', '20250102', 'France', 'desktop', 'page_view', 'Chrome', 'Linux', '1920x1080', 9)",
+        )
+        .unwrap();
+
+        migrate_legacy_table(&conn, Some("properties/123")).unwrap();
+
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM ga4_records"), 2);
+        assert_eq!(count(&conn, "SELECT active_users FROM ga4_records WHERE date = '20250102'"), 9);
+    }
+
+    #[test]
+    fn reads_the_latest_epoch_through_the_open_connection() {
+        let conn = Connection::open_in_memory().unwrap();
+        let schema = TableSchema::new("charges", &[("id", "VARCHAR"), ("created", "TIMESTAMP")], &["id"]);
+        assert_eq!(max_epoch(&conn, "charges", "created"), None);
+
+        let row = |id: &str, micros: i64| vec![Value::Text(id.to_string()), Value::Timestamp(TimeUnit::Microsecond, micros)];
+        upsert_rows(&conn, &schema, vec![row("ch_1", 1_000_000), row("ch_2", 5_000_000)]).unwrap();
+        assert_eq!(max_epoch(&conn, "charges", "created"), Some(5));
+    }
+}
@@ -0,0 +1,140 @@
+use serde::Deserialize;
+use tracing::{debug, error, info};
+
+pub const DEFAULT_API_BASE_URL: &str = "https://api.stripe.com";
+
+const PAGE_SIZE: &str = "100";
+
+// Stripe API response types
+#[derive(Debug, Deserialize)]
+struct ListResponse {
+    #[serde(default)]
+    data: Vec<serde_json::Value>,
+    #[serde(default)]
+    has_more: bool,
+}
+
+/// Lists every object of a collection (e.g. `charges`) created at or after `created_gte`,
+/// following `starting_after` pagination.
+pub async fn list_all(
+    base_url: &str,
+    api_key: &str,
+    collection: &str,
+    created_gte: Option<i64>,
+) -> Result<Vec<serde_json::Value>, String> {
+    let url = format!("{}/v1/{}", base_url.trim_end_matches('/'), collection);
+    let client = reqwest::Client::new();
+
+    let mut objects: Vec<serde_json::Value> = Vec::new();
+
+    loop {
+        let mut request = client
+            .get(&url)
+            .bearer_auth(api_key)
+            .query(&[("limit", PAGE_SIZE)]);
+
+        if let Some(created_gte) = created_gte {
+            request = request.query(&[("created[gte]", created_gte)]);
+        }
+
+        // Subscriptions default to non-canceled ones only
+        if collection == "subscriptions" {
+            request = request.query(&[("status", "all")]);
+        }
+
+        if let Some(last_id) = objects.last().and_then(|o| o.get("id")).and_then(|id| id.as_str()) {
+            request = request.query(&[("starting_after", last_id)]);
+        }
+
+        let page: ListResponse = send(request).await?;
+        let has_more = page.has_more && !page.data.is_empty();
+        objects.extend(page.data);
+
+        debug!(collection = %collection, fetched = objects.len(), "Fetched page");
+
+        if !has_more {
+            break;
+        }
+    }
+
+    info!(collection = %collection, count = objects.len(), "Stripe list complete");
+    Ok(objects)
+}
+
+/// Checks the key by fetching the account balance, which every restricted key with read access can see.
+pub async fn verify_key(base_url: &str, api_key: &str) -> Result<(), String> {
+    let request = reqwest::Client::new()
+        .get(format!("{}/v1/balance", base_url.trim_end_matches('/')))
+        .bearer_auth(api_key);
+
+    send::<serde_json::Value>(request).await.map(|_| ())
+}
+
+async fn send<T: serde::de::DeserializeOwned>(request: reqwest::RequestBuilder) -> Result<T, String> {
+    let response = request.send().await.map_err(|e| {
+        error!(error = %e, "Failed to connect to Stripe API");
+        format!("Failed to connect to Stripe API: {}", e)
+    })?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_text = response.text().await.unwrap_or_default();
+        error!(status = %status, error = %error_text, "Stripe API error");
+        return Err(format!("Stripe API error: {} - {}", status, error_text));
+    }
+
+    response.json().await.map_err(|e| {
+        error!(error = %e, "Failed to parse Stripe response");
+        format!("Failed to parse Stripe response: {}", e)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::test_server::serve;
+    use axum::extract::Query;
+    use axum::routing::get;
+    use axum::{Json, Router};
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    /// Three objects served two per page, after `starting_after`.
+    async fn page(Query(query): Query<HashMap<String, String>>) -> Json<serde_json::Value> {
+        let ids = ["sub_1", "sub_2", "sub_3"];
+        let start = match query.get("starting_after") {
+            Some(last) => ids.iter().position(|id| id == last).unwrap() + 1,
+            None => 0,
+        };
+        let data: Vec<_> = ids[start..].iter().take(2).map(|id| json!({ "id": id, "query": query })).collect();
+
+        Json(json!({ "data": data, "has_more": start + 2 < ids.len() }))
+    }
+
+    #[tokio::test]
+    async fn follows_pagination() {
+        let base_url = serve(Router::new().route("/v1/subscriptions", get(page))).await;
+
+        let objects = list_all(&base_url, "sk_test", "subscriptions", Some(1760000000)).await.unwrap();
+
+        let ids: Vec<_> = objects.iter().map(|o| o["id"].as_str().unwrap()).collect();
+        assert_eq!(ids, ["sub_1", "sub_2", "sub_3"]);
+        let query = &objects[2]["query"];
+        assert_eq!(query["starting_after"], "sub_2");
+        assert_eq!(query["created[gte]"], "1760000000");
+        assert_eq!(query["status"], "all");
+        assert_eq!(query["limit"], PAGE_SIZE);
+    }
+
+    #[tokio::test]
+    async fn reports_api_errors() {
+        let router = Router::new().route(
+            "/v1/balance",
+            get(|| async { (axum::http::StatusCode::UNAUTHORIZED, "Invalid API Key provided") }),
+        );
+        let base_url = serve(router).await;
+
+        let error = verify_key(&base_url, "sk_test").await.unwrap_err();
+        assert!(error.contains("401"), "{}", error);
+    }
+}
@@ -0,0 +1,559 @@
+use duckdb::Connection;
+use ipnet::IpNet;
+use regex::Regex;
+use serde::Serialize;
+use std::net::IpAddr;
+use std::sync::LazyLock;
+use strum::Display;
+use tracing::{debug, info, warn};
+
+use super::storage_service::TableSchema;
+use crate::models::traffic_filter::{FilterMode, Ga4SpamCombo, TrafficFilter};
+
+/// Counts of collected events caught by the filters, in the project's event store.
+pub const FILTERED_EVENTS_TABLE: &str = "filtered_events";
+/// GA4 rows without the spam combos, in each GA4 connector store.
+pub const GA4_FILTERED_VIEW: &str = "ga4_records_filtered";
+/// GA4 rows matching a spam combo, with the combo in `filter_reason`.
+pub const GA4_SPAM_VIEW: &str = "ga4_spam_records";
+
+/// Markers of crawlers and automated browsers woothee does not classify as crawlers.
+static BOT_USER_AGENT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)bot\b|crawl|spider|slurp|headless|phantomjs|puppeteer|playwright|selenium|lighthouse|pingdom|uptime|monitor|preview|scrapy",
+    )
+    .unwrap()
+});
+
+/// Well-known referrer spam domains, matched with their subdomains.
+const SPAM_REFERRERS: [&str; 24] = [
+    "100dollars-seo.com",
+    "4webmasters.org",
+    "best-seo-offer.com",
+    "best-seo-solution.com",
+    "blackhatworth.com",
+    "buttons-for-website.com",
+    "buttons-for-your-website.com",
+    "darodar.com",
+    "econom.co",
+    "event-tracking.com",
+    "floating-share-buttons.com",
+    "free-social-buttons.com",
+    "get-free-traffic-now.com",
+    "hulfingtonpost.com",
+    "ilovevitaly.com",
+    "o-o-6-o-o.com",
+    "priceg.com",
+    "rank-checker.online",
+    "semalt.com",
+    "simple-share-buttons.com",
+    "site-auditor.online",
+    "social-buttons.com",
+    "trafficmonetize.org",
+    "videos-for-your-business.com",
+];
+
+/// GA4 dimension values typical of bots: no screen, 1 pixel screens and headless Chrome.
+const GA4_SPAM_COMBOS: [(Option<&str>, Option<&str>, Option<&str>); 5] = [
+    (Some("(not set)"), None, Some("(not set)")),
+    (None, None, Some("0x0")),
+    (None, None, Some("1x1")),
+    (Some("Chrome"), Some("Linux"), Some("800x600")),
+    (Some("Mozilla Compatible Agent"), None, None),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Display)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum FilterReason {
+    BotUserAgent,
+    DatacenterIp,
+    ReferrerSpam,
+}
+
+/// Why an event was caught and what happened to it.
+#[derive(Debug, Clone, Copy)]
+pub struct Filtered {
+    pub reason: FilterReason,
+    pub mode: FilterMode,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FilteredEventCount {
+    pub date: String,
+    pub reason: String,
+    pub mode: String,
+    pub event_count: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FilteredGa4Count {
+    pub property_id: String,
+    pub date: String,
+    pub reason: String,
+    pub sessions: i64,
+    pub screen_page_views: i64,
+}
+
+/// Append-only counts of filtered events per day, reason and mode.
+pub fn filtered_events_schema() -> TableSchema {
+    TableSchema::new(
+        FILTERED_EVENTS_TABLE,
+        &[
+            ("event_date", "VARCHAR"),
+            ("reason", "VARCHAR"),
+            ("mode", "VARCHAR"),
+            ("event_count", "BIGINT"),
+        ],
+        &[],
+    )
+}
+
+/// Lists the filters match traffic against, shared by every project.
+#[derive(Default)]
+pub struct FilterLists {
+    datacenter_ranges: Vec<IpNet>,
+}
+
+impl FilterLists {
+    /// Reads the CIDR ranges in `DATACENTER_IP_RANGES_PATH`, one per line, `#` starting comments.
+    pub fn from_env() -> Self {
+        let Ok(path) = std::env::var("DATACENTER_IP_RANGES_PATH") else {
+            warn!("DATACENTER_IP_RANGES_PATH not set, datacenter IP filtering disabled");
+            return Self::default();
+        };
+
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) => {
+                warn!(path = %path, error = %e, "Failed to read datacenter IP ranges, filtering disabled");
+                return Self::default();
+            }
+        };
+
+        let mut datacenter_ranges = Vec::new();
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            match line.parse::<IpNet>() {
+                Ok(range) => datacenter_ranges.push(range),
+                Err(_) => warn!(line = %line, "Skipping invalid datacenter IP range"),
+            }
+        }
+
+        info!(path = %path, count = datacenter_ranges.len(), "Datacenter IP ranges loaded");
+        Self { datacenter_ranges }
+    }
+
+    /// Reason to filter a request, from what is common to all its events.
+    pub fn check_request(&self, filter: &TrafficFilter, ip: IpAddr, user_agent: Option<&str>) -> Option<FilterReason> {
+        if filter.bot_user_agents && is_bot(user_agent) {
+            return Some(FilterReason::BotUserAgent);
+        }
+        if filter.datacenter_ips && self.datacenter_ranges.iter().any(|range| range.contains(&ip)) {
+            return Some(FilterReason::DatacenterIp);
+        }
+        None
+    }
+}
+
+/// Measurement Protocol requests are authenticated by their `api_secret` and usually sent
+/// server to server, so HTTP libraries and missing User-Agents are expected there.
+fn is_bot(user_agent: Option<&str>) -> bool {
+    match user_agent.map(str::trim) {
+        None | Some("") => false,
+        Some(user_agent) => woothee::is_crawler(user_agent) || BOT_USER_AGENT.is_match(user_agent),
+    }
+}
+
+fn host(url: &str) -> Option<String> {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(|host| host.trim_start_matches("www.").to_lowercase()))
+}
+
+/// Whether the referrer is on the built-in spam list or the project's.
+pub fn is_spam_referrer(filter: &TrafficFilter, referrer: Option<&str>) -> bool {
+    if !filter.referrer_spam {
+        return false;
+    }
+    let Some(host) = referrer.and_then(host) else {
+        return false;
+    };
+
+    SPAM_REFERRERS
+        .iter()
+        .copied()
+        .chain(filter.spam_referrers.iter().map(String::as_str))
+        .map(|domain| domain.trim().trim_start_matches("www.").to_lowercase())
+        .any(|domain| host == domain || host.ends_with(&format!(".{}", domain)))
+}
+
+fn literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+impl Ga4SpamCombo {
+    /// Readable form, used as `filter_reason`.
+    fn label(&self) -> String {
+        [
+            ("browser", &self.browser),
+            ("operating_system", &self.operating_system),
+            ("screen_resolution", &self.screen_resolution),
+        ]
+        .iter()
+        .filter_map(|(column, value)| value.as_ref().map(|value| format!("{}={}", column, value)))
+        .collect::<Vec<_>>()
+        .join(", ")
+    }
+
+    fn condition(&self) -> String {
+        [
+            ("browser", &self.browser),
+            ("operating_system", &self.operating_system),
+            ("screen_resolution", &self.screen_resolution),
+        ]
+        .iter()
+        .filter_map(|(column, value)| value.as_ref().map(|value| format!("{} = {}", column, literal(value))))
+        .collect::<Vec<_>>()
+        .join(" AND ")
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.browser.is_none() && self.operating_system.is_none() && self.screen_resolution.is_none()
+    }
+}
+
+fn ga4_spam_combos(filter: &TrafficFilter) -> Vec<Ga4SpamCombo> {
+    let built_in = GA4_SPAM_COMBOS
+        .iter()
+        .filter(|_| filter.ga4_spam)
+        .map(|(browser, operating_system, screen_resolution)| Ga4SpamCombo {
+            browser: browser.map(str::to_string),
+            operating_system: operating_system.map(str::to_string),
+            screen_resolution: screen_resolution.map(str::to_string),
+        });
+
+    built_in
+        .chain(filter.ga4_spam_combos.iter().filter(|c| !c.is_empty()).cloned())
+        .collect()
+}
+
+fn has_table(conn: &Connection, table: &str) -> bool {
+    conn.query_row(
+        "SELECT COUNT(*) FROM information_schema.tables WHERE table_name = ?",
+        [table],
+        |row| row.get::<_, i64>(0),
+    )
+    .is_ok_and(|count| count > 0)
+}
+
+/// (Re)creates the filtered and spam views over `ga4_records` of a GA4 store.
+pub fn create_ga4_views(conn: &Connection, filter: &TrafficFilter) -> Result<(), String> {
+    if !has_table(conn, "ga4_records") {
+        return Ok(());
+    }
+
+    let combos = ga4_spam_combos(filter);
+    let (is_spam, reason) = if combos.is_empty() {
+        ("false".to_string(), "NULL".to_string())
+    } else {
+        let conditions: Vec<String> = combos.iter().map(|c| format!("({})", c.condition())).collect();
+        let cases: String = combos
+            .iter()
+            .map(|c| format!(" WHEN {} THEN {}", c.condition(), literal(&c.label())))
+            .collect();
+        (
+            format!("COALESCE({}, false)", conditions.join(" OR ")),
+            format!("CASE{} END", cases),
+        )
+    };
+
+    conn.execute_batch(&format!(
+        r#"
+        CREATE OR REPLACE VIEW {GA4_FILTERED_VIEW} AS
+        SELECT * FROM ga4_records WHERE NOT {is_spam};
+
+        CREATE OR REPLACE VIEW {GA4_SPAM_VIEW} AS
+        SELECT *, {reason} AS filter_reason FROM ga4_records WHERE {is_spam};
+        "#
+    ))
+    .map_err(|e| format!("Failed to create GA4 filter views: {}", e))?;
+
+    debug!(combo_count = combos.len(), "GA4 filter views created");
+    Ok(())
+}
+
+/// Filtered collected events per day, reason and mode.
+pub fn filtered_event_counts(conn: &Connection) -> Result<Vec<FilteredEventCount>, String> {
+    if !has_table(conn, FILTERED_EVENTS_TABLE) {
+        return Ok(Vec::new());
+    }
+
+    let mut statement = conn
+        .prepare(&format!(
+            "SELECT event_date, reason, mode, CAST(SUM(event_count) AS BIGINT) FROM {} \
+             GROUP BY ALL ORDER BY event_date, reason, mode",
+            FILTERED_EVENTS_TABLE
+        ))
+        .map_err(|e| format!("Failed to query filtered events: {}", e))?;
+
+    statement
+        .query_map([], |row| {
+            Ok(FilteredEventCount {
+                date: row.get(0)?,
+                reason: row.get(1)?,
+                mode: row.get(2)?,
+                event_count: row.get(3)?,
+            })
+        })
+        .and_then(|rows| rows.collect())
+        .map_err(|e| format!("Failed to query filtered events: {}", e))
+}
+
+/// GA4 sessions and page views matching a spam combo per property, day and combo.
+pub fn filtered_ga4_counts(conn: &Connection) -> Result<Vec<FilteredGa4Count>, String> {
+    if !has_table(conn, GA4_SPAM_VIEW) {
+        return Ok(Vec::new());
+    }
+
+    let mut statement = conn
+        .prepare(&format!(
+            "SELECT property_id, date, filter_reason, \
+             CAST(COALESCE(SUM(sessions), 0) AS BIGINT), CAST(COALESCE(SUM(screen_page_views), 0) AS BIGINT) \
+             FROM {} GROUP BY ALL ORDER BY property_id, date, filter_reason",
+            GA4_SPAM_VIEW
+        ))
+        .map_err(|e| format!("Failed to query GA4 spam rows: {}", e))?;
+
+    statement
+        .query_map([], |row| {
+            Ok(FilteredGa4Count {
+                property_id: row.get(0)?,
+                date: row.get(1)?,
+                reason: row.get(2)?,
+                sessions: row.get(3)?,
+                screen_page_views: row.get(4)?,
+            })
+        })
+        .and_then(|rows| rows.collect())
+        .map_err(|e| format!("Failed to query GA4 spam rows: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ga4_store() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE ga4_records (property_id VARCHAR, date VARCHAR, browser VARCHAR, operating_system VARCHAR, \
+             screen_resolution VARCHAR, sessions BIGINT, screen_page_views BIGINT);
+             INSERT INTO ga4_records VALUES
+                ('1', '2025-01-01', 'Chrome', 'Windows', '1920x1080', 10, 30),
+                ('1', '2025-01-01', 'Chrome', 'Linux', '800x600', 4, 4),
+                ('1', '2025-01-01', 'Safari', 'iOS', '0x0', 2, 2),
+                ('1', '2025-01-02', 'Firefox', NULL, '1280x720', 3, 6),
+                ('1', '2025-01-02', 'Chrome', NULL, '800x600', 1, 1),
+                ('1', '2025-01-02', 'Edge', 'Windows', '1366x768', 5, 5);",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn count(conn: &Connection, view: &str) -> i64 {
+        conn.query_row(&format!("SELECT COUNT(*) FROM {}", view), [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn flags_crawlers_and_automated_browsers() {
+        for user_agent in [
+            "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)",
+            "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) HeadlessChrome/120.0.0.0 Safari/537.36",
+            "Mozilla/5.0 (compatible; AhrefsBot/7.0; +http://ahrefs.com/robot/)",
+            "Chrome-Lighthouse",
+            "Scrapy/2.11 (+https://scrapy.org)",
+        ] {
+            assert!(is_bot(Some(user_agent)), "{}", user_agent);
+        }
+    }
+
+    #[test]
+    fn lets_browsers_and_server_to_server_requests_through() {
+        for user_agent in [
+            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
+            "Mozilla/5.0 (iPhone; CPU iPhone OS 17_1 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.1 Mobile/15E148 Safari/604.1",
+            "curl/8.4.0",
+            "python-requests/2.31.0",
+            "okhttp/4.12.0",
+            "Go-http-client/2.0",
+            "  ",
+        ] {
+            assert!(!is_bot(Some(user_agent)), "{}", user_agent);
+        }
+        assert!(!is_bot(None));
+    }
+
+    #[test]
+    fn checks_user_agents_only_when_enabled() {
+        let lists = FilterLists::default();
+        let ip: IpAddr = "203.0.113.1".parse().unwrap();
+        let mut filter = TrafficFilter::default();
+
+        assert_eq!(
+            lists.check_request(&filter, ip, Some("Googlebot/2.1")),
+            Some(FilterReason::BotUserAgent)
+        );
+        filter.bot_user_agents = false;
+        assert_eq!(lists.check_request(&filter, ip, Some("Googlebot/2.1")), None);
+    }
+
+    #[test]
+    fn matches_spam_referrers_with_subdomains_and_www() {
+        let filter = TrafficFilter::default();
+
+        assert!(is_spam_referrer(&filter, Some("https://semalt.com/")));
+        assert!(is_spam_referrer(&filter, Some("https://www.semalt.com/page")));
+        assert!(is_spam_referrer(&filter, Some("http://free.semalt.com")));
+        assert!(is_spam_referrer(&filter, Some("https://WWW.Darodar.COM/")));
+        assert!(!is_spam_referrer(&filter, Some("https://notsemalt.com/")));
+        assert!(!is_spam_referrer(&filter, Some("https://semalt.com.example.org/")));
+        assert!(!is_spam_referrer(&filter, Some("not a url")));
+        assert!(!is_spam_referrer(&filter, None));
+    }
+
+    #[test]
+    fn matches_project_spam_referrers_and_skips_when_disabled() {
+        let mut filter = TrafficFilter {
+            spam_referrers: vec!["www.spam.example".to_string()],
+            ..TrafficFilter::default()
+        };
+
+        assert!(is_spam_referrer(&filter, Some("https://spam.example/")));
+        assert!(is_spam_referrer(&filter, Some("https://shop.spam.example/")));
+
+        filter.referrer_spam = false;
+        assert!(!is_spam_referrer(&filter, Some("https://spam.example/")));
+        assert!(!is_spam_referrer(&filter, Some("https://semalt.com/")));
+    }
+
+    #[test]
+    fn splits_ga4_rows_on_spam_combos() {
+        let conn = ga4_store();
+        create_ga4_views(&conn, &TrafficFilter::default()).unwrap();
+
+        // Rows missing a dimension a combo sets are kept rather than dropped from both views
+        assert_eq!(count(&conn, GA4_FILTERED_VIEW), 4);
+        assert_eq!(count(&conn, GA4_SPAM_VIEW), 2);
+
+        let reasons: Vec<String> = conn
+            .prepare(&format!("SELECT filter_reason FROM {} ORDER BY filter_reason", GA4_SPAM_VIEW))
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            reasons,
+            vec![
+                "browser=Chrome, operating_system=Linux, screen_resolution=800x600",
+                "screen_resolution=0x0",
+            ]
+        );
+    }
+
+    #[test]
+    fn applies_project_combos_in_place_of_built_in_ones() {
+        let conn = ga4_store();
+        let filter = TrafficFilter {
+            ga4_spam: false,
+            ga4_spam_combos: vec![Ga4SpamCombo {
+                browser: Some("Firefox".to_string()),
+                operating_system: None,
+                screen_resolution: None,
+            }],
+            ..TrafficFilter::default()
+        };
+        create_ga4_views(&conn, &filter).unwrap();
+
+        assert_eq!(count(&conn, GA4_FILTERED_VIEW), 5);
+        assert_eq!(count(&conn, GA4_SPAM_VIEW), 1);
+
+        let no_combos = TrafficFilter {
+            ga4_spam: false,
+            ..TrafficFilter::default()
+        };
+        create_ga4_views(&conn, &no_combos).unwrap();
+        assert_eq!(count(&conn, GA4_FILTERED_VIEW), 6);
+        assert_eq!(count(&conn, GA4_SPAM_VIEW), 0);
+    }
+
+    #[test]
+    fn skips_views_without_ga4_records() {
+        let conn = Connection::open_in_memory().unwrap();
+
+        create_ga4_views(&conn, &TrafficFilter::default()).unwrap();
+        assert!(!has_table(&conn, GA4_FILTERED_VIEW));
+        assert!(filtered_ga4_counts(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn counts_ga4_spam_per_day_and_combo() {
+        let conn = ga4_store();
+        conn.execute_batch("INSERT INTO ga4_records VALUES ('1', '2025-01-02', 'Safari', 'iOS', '0x0', 1, 3);")
+            .unwrap();
+        create_ga4_views(&conn, &TrafficFilter::default()).unwrap();
+
+        let counts: Vec<(String, String, i64, i64)> = filtered_ga4_counts(&conn)
+            .unwrap()
+            .into_iter()
+            .map(|c| (c.date, c.reason, c.sessions, c.screen_page_views))
+            .collect();
+        assert_eq!(
+            counts,
+            vec![
+                (
+                    "2025-01-01".to_string(),
+                    "browser=Chrome, operating_system=Linux, screen_resolution=800x600".to_string(),
+                    4,
+                    4
+                ),
+                ("2025-01-01".to_string(), "screen_resolution=0x0".to_string(), 2, 2),
+                ("2025-01-02".to_string(), "screen_resolution=0x0".to_string(), 1, 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn sums_filtered_events_per_day_reason_and_mode() {
+        let conn = Connection::open_in_memory().unwrap();
+        assert!(filtered_event_counts(&conn).unwrap().is_empty());
+
+        conn.execute_batch(
+            "CREATE TABLE filtered_events (event_date VARCHAR, reason VARCHAR, mode VARCHAR, event_count BIGINT);
+             INSERT INTO filtered_events VALUES
+                ('2025-01-01', 'bot_user_agent', 'drop', 2),
+                ('2025-01-01', 'bot_user_agent', 'drop', 3),
+                ('2025-01-01', 'referrer_spam', 'flag', 1),
+                ('2025-01-02', 'bot_user_agent', 'drop', 4);",
+        )
+        .unwrap();
+
+        let counts: Vec<(String, String, String, i64)> = filtered_event_counts(&conn)
+            .unwrap()
+            .into_iter()
+            .map(|c| (c.date, c.reason, c.mode, c.event_count))
+            .collect();
+        assert_eq!(
+            counts,
+            vec![
+                ("2025-01-01".to_string(), "bot_user_agent".to_string(), "drop".to_string(), 5),
+                ("2025-01-01".to_string(), "referrer_spam".to_string(), "flag".to_string(), 1),
+                ("2025-01-02".to_string(), "bot_user_agent".to_string(), "drop".to_string(), 4),
+            ]
+        );
+    }
+}
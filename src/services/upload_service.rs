@@ -0,0 +1,189 @@
+use calamine::{Data, Reader, open_workbook_auto};
+use chrono::NaiveTime;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+use std::sync::LazyLock;
+use tracing::{debug, info};
+use uuid::Uuid;
+
+use super::storage_service;
+
+pub const STORE_FILE: &str = "uploads.duckdb";
+
+static COLUMN_TYPE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(VARCHAR|BIGINT|INTEGER|DOUBLE|BOOLEAN|DATE|TIME|TIMESTAMP|DECIMAL\(\d{1,2}, ?\d{1,2}\))$").unwrap()
+});
+
+static TABLE_NAME: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[a-z_][a-z0-9_]*$").unwrap());
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode {
+    /// Adds the rows to the table, matching columns by name.
+    #[default]
+    Append,
+    /// Recreates the table from the file.
+    Replace,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileFormat {
+    Csv,
+    Excel,
+}
+
+impl FileFormat {
+    pub fn from_file_name(file_name: &str) -> Option<Self> {
+        let extension = Path::new(file_name).extension()?.to_str()?.to_ascii_lowercase();
+
+        match extension.as_str() {
+            "csv" | "tsv" | "txt" => Some(FileFormat::Csv),
+            "xlsx" | "xlsm" | "xls" | "ods" => Some(FileFormat::Excel),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ColumnInfo {
+    pub name: String,
+    pub data_type: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportResult {
+    pub table: String,
+    pub mode: ImportMode,
+    pub row_count: usize,
+    pub columns: Vec<ColumnInfo>,
+}
+
+/// Checks table name and column type overrides before they are spliced into SQL.
+pub fn validate(table: &str, column_types: &BTreeMap<String, String>) -> Result<(), String> {
+    if !TABLE_NAME.is_match(table) {
+        return Err(format!("Invalid table name '{}'", table));
+    }
+
+    if let Some((column, data_type)) = column_types.iter().find(|(_, t)| !COLUMN_TYPE.is_match(t)) {
+        return Err(format!("Unsupported type '{}' for column '{}'", data_type, column));
+    }
+
+    Ok(())
+}
+
+/// Writes the first sheet of a workbook as CSV, so both formats go through DuckDB's CSV reader.
+pub fn excel_to_csv(source: &Path, destination: &Path) -> Result<(), String> {
+    let mut workbook = open_workbook_auto(source).map_err(|e| format!("Failed to open workbook: {}", e))?;
+
+    let range = workbook
+        .worksheet_range_at(0)
+        .ok_or_else(|| "Workbook has no sheet".to_string())?
+        .map_err(|e| format!("Failed to read sheet: {}", e))?;
+
+    let file = std::fs::File::create(destination).map_err(|e| format!("Failed to create CSV: {}", e))?;
+    let mut writer = std::io::BufWriter::new(file);
+
+    for row in range.rows() {
+        let line = row.iter().map(csv_field).collect::<Vec<_>>().join(",");
+        writeln!(writer, "{}", line).map_err(|e| format!("Failed to write CSV: {}", e))?;
+    }
+
+    writer.flush().map_err(|e| format!("Failed to write CSV: {}", e))
+}
+
+fn csv_field(cell: &Data) -> String {
+    let value = match cell {
+        // Date-only cells are written as dates so DuckDB infers DATE rather than TIMESTAMP
+        Data::DateTime(dt) => match dt.as_datetime() {
+            Some(dt) if dt.time() == NaiveTime::MIN => dt.date().to_string(),
+            Some(dt) => dt.to_string(),
+            None => cell.to_string(),
+        },
+        other => other.to_string(),
+    };
+
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+/// Loads a CSV file into a table of the connector's upload store, inferring the schema
+/// with DuckDB and applying the column type overrides on top.
+pub fn import_csv(
+    project_id: Uuid,
+    connector_id: Uuid,
+    table: &str,
+    csv_path: &Path,
+    column_types: &BTreeMap<String, String>,
+    mode: ImportMode,
+) -> Result<ImportResult, String> {
+    validate(table, column_types)?;
+
+    let conn = storage_service::open_store(project_id, connector_id, STORE_FILE)?;
+
+    let mut options = vec!["header = true".to_string()];
+    if !column_types.is_empty() {
+        let types = column_types
+            .iter()
+            .map(|(column, data_type)| format!("'{}': '{}'", sql_string(column), data_type.to_ascii_uppercase()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        options.push(format!("types = {{{}}}", types));
+    }
+
+    let source = format!(
+        "read_csv_auto('{}', {})",
+        sql_string(&csv_path.to_string_lossy()),
+        options.join(", ")
+    );
+
+    let table_exists: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM information_schema.tables WHERE table_name = ?",
+            [table],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to inspect tables: {}", e))?;
+
+    let sql = if mode == ImportMode::Replace || table_exists == 0 {
+        format!("CREATE OR REPLACE TABLE {} AS SELECT * FROM {}", table, source)
+    } else {
+        format!("INSERT INTO {} BY NAME SELECT * FROM {}", table, source)
+    };
+
+    debug!(sql = %sql, "Importing file");
+    let row_count = conn
+        .execute(&sql, [])
+        .map_err(|e| format!("Failed to import file: {}", e))?;
+
+    let mut statement = conn
+        .prepare(&format!("DESCRIBE {}", table))
+        .map_err(|e| format!("Failed to describe table: {}", e))?;
+    let columns = statement
+        .query_map([], |row| {
+            Ok(ColumnInfo {
+                name: row.get(0)?,
+                data_type: row.get(1)?,
+            })
+        })
+        .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| format!("Failed to describe table: {}", e))?;
+
+    info!(table = %table, mode = ?mode, row_count = row_count, "File imported");
+
+    Ok(ImportResult {
+        table: table.to_string(),
+        mode,
+        row_count,
+        columns,
+    })
+}
+
+fn sql_string(value: &str) -> String {
+    value.replace('\'', "''")
+}
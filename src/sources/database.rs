@@ -0,0 +1,333 @@
+use async_trait::async_trait;
+use duckdb::Connection;
+use serde_json::json;
+use tracing::{debug, error, info, warn};
+
+use super::{
+    AuthKind, Source, Stream, StreamSyncResult, SyncRequest, SyncResult, decrypt_secret, encrypt_secret, parse_config,
+    save_config,
+};
+use crate::api::error::AppError;
+use crate::models::connector::{
+    Connector, ConnectorDetails, ConnectorType, DatabaseEngine, DatabaseQuery, DatabaseTable,
+};
+use crate::services::database_service::{self, Credentials, Replication};
+use crate::services::storage_service::{self, TableSchema};
+use crate::AppState;
+
+/// Replicates tables and custom queries of an external Postgres or MySQL database.
+pub struct DatabaseSource;
+
+struct DatabaseConfig {
+    engine: DatabaseEngine,
+    host: String,
+    port: Option<u16>,
+    database: String,
+    user: String,
+    password: String,
+    tables: Vec<DatabaseTable>,
+    queries: Vec<DatabaseQuery>,
+}
+
+fn database_config(connector: &Connector) -> Result<DatabaseConfig, AppError> {
+    match parse_config(connector)? {
+        ConnectorDetails::Database {
+            engine,
+            host,
+            port,
+            database,
+            user,
+            password,
+            tables,
+            queries,
+        } => Ok(DatabaseConfig {
+            engine,
+            host,
+            port,
+            database,
+            user,
+            password,
+            tables,
+            queries,
+        }),
+        _ => Err(AppError::bad_request("Connector is not a database connector")),
+    }
+}
+
+impl DatabaseConfig {
+    /// Opens the connector's store with the external database attached to it.
+    fn open(&self, connector: &Connector) -> Result<Connection, AppError> {
+        let password = decrypt_secret(&self.password)?;
+
+        let conn = storage_service::open_store(connector.project_id, connector.id, database_service::STORE_FILE)
+            .map_err(AppError::internal)?;
+
+        let credentials = Credentials {
+            engine: self.engine,
+            host: &self.host,
+            port: self.port,
+            database: &self.database,
+            user: &self.user,
+            password: &password,
+        };
+        database_service::load_extension(&conn, self.engine).map_err(|e| {
+            error!(error = %e, "Failed to load database extension");
+            AppError::internal(e)
+        })?;
+        database_service::attach(&conn, &credentials).map_err(|e| {
+            warn!(error = %e, "Database connection failed");
+            AppError::bad_request(e)
+        })?;
+
+        Ok(conn)
+    }
+
+    fn streams(&self, tables: Vec<String>) -> Vec<Stream> {
+        tables
+            .into_iter()
+            .map(|name| Stream {
+                selected: self.tables.iter().any(|t| t.name == name),
+                id: name.clone(),
+                name,
+            })
+            .chain(self.queries.iter().map(|q| Stream {
+                id: q.name.clone(),
+                name: q.name.clone(),
+                selected: true,
+            }))
+            .collect()
+    }
+}
+
+fn list_tables(conn: &Connection) -> Result<Vec<String>, AppError> {
+    database_service::list_tables(conn).map_err(|e| {
+        warn!(error = %e, "Failed to list database tables");
+        AppError::bad_request(e)
+    })
+}
+
+/// Runs blocking DuckDB work, which waits on the external database, off the async runtime.
+async fn run_blocking<T: Send + 'static>(
+    work: impl FnOnce() -> Result<T, AppError> + Send + 'static,
+) -> Result<T, AppError> {
+    tokio::task::spawn_blocking(work).await.map_err(|e| {
+        error!(error = %e, "Database task failed");
+        AppError::internal("Database task failed")
+    })?
+}
+
+/// Tables of the external database.
+async fn available_tables(connector: &Connector) -> Result<Vec<String>, AppError> {
+    let connector = connector.clone();
+    run_blocking(move || {
+        let conn = database_config(&connector)?.open(&connector)?;
+        list_tables(&conn)
+    })
+    .await
+}
+
+#[async_trait]
+impl Source for DatabaseSource {
+    fn connector_type(&self) -> ConnectorType {
+        ConnectorType::Database
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Database"
+    }
+
+    fn auth_kind(&self) -> AuthKind {
+        AuthKind::Config
+    }
+
+    fn config_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "required": ["type", "engine", "host", "database", "user", "password"],
+            "properties": {
+                "type": { "const": "Database" },
+                "engine": { "enum": ["postgres", "mysql"] },
+                "host": { "type": "string", "minLength": 1 },
+                "port": { "type": ["integer", "null"], "minimum": 1 },
+                "database": { "type": "string", "minLength": 1 },
+                "user": { "type": "string", "minLength": 1 },
+                "password": { "type": "string" },
+                "tables": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["name"],
+                        "properties": {
+                            "name": { "type": "string", "pattern": "^[^.]+\\.[^.]+$" },
+                            "cursor_column": { "type": ["string", "null"], "minLength": 1 },
+                            "primary_key": { "type": "array", "items": { "type": "string", "minLength": 1 } }
+                        }
+                    }
+                },
+                "queries": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["name", "sql"],
+                        "properties": {
+                            "name": { "type": "string", "pattern": "^[a-z_][a-z0-9_]*$" },
+                            "sql": { "type": "string", "minLength": 1 },
+                            "cursor_column": { "type": ["string", "null"], "minLength": 1 },
+                            "primary_key": { "type": "array", "items": { "type": "string", "minLength": 1 } }
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    /// Encrypts the password unless the client sent back an already encrypted one.
+    fn prepare_config(&self, mut config: serde_json::Value) -> Result<serde_json::Value, AppError> {
+        encrypt_secret(&mut config, "/password")?;
+        Ok(config)
+    }
+
+    /// Replicated tables mirror the columns of their source.
+    fn storage_schema(&self) -> Vec<TableSchema> {
+        Vec::new()
+    }
+
+    async fn discover(&self, _state: &AppState, connector: &Connector) -> Result<Vec<Stream>, AppError> {
+        let config = database_config(connector)?;
+        let tables = available_tables(connector).await?;
+
+        debug!(count = tables.len(), "Listed database tables");
+        Ok(config.streams(tables))
+    }
+
+    async fn select_streams(
+        &self,
+        state: &AppState,
+        connector: &Connector,
+        stream_ids: Vec<String>,
+    ) -> Result<Vec<Stream>, AppError> {
+        let mut config = database_config(connector)?;
+        let available = available_tables(connector).await?;
+
+        let mut tables: Vec<DatabaseTable> = Vec::with_capacity(stream_ids.len());
+        for id in stream_ids {
+            if config.queries.iter().any(|q| q.name == id) || tables.iter().any(|t| t.name == id) {
+                continue;
+            }
+            if !available.contains(&id) {
+                warn!(table = %id, "Unknown database table");
+                return Err(AppError::bad_request(format!("Unknown table {}", id)));
+            }
+
+            // Keep the cursor and key of tables that stay selected
+            let table = config
+                .tables
+                .iter()
+                .find(|t| t.name == id)
+                .cloned()
+                .unwrap_or(DatabaseTable {
+                    name: id,
+                    cursor_column: None,
+                    primary_key: Vec::new(),
+                });
+            tables.push(table);
+        }
+
+        let mut details = parse_config(connector)?;
+        if let ConnectorDetails::Database { tables: selected, .. } = &mut details {
+            selected.clone_from(&tables);
+        }
+        save_config(state, connector, &details).await?;
+
+        info!(count = tables.len(), "Tables selected successfully");
+        config.tables = tables;
+        Ok(config.streams(available))
+    }
+
+    async fn sync(
+        &self,
+        _state: &AppState,
+        connector: &Connector,
+        request: SyncRequest,
+    ) -> Result<SyncResult, AppError> {
+        let connector = connector.clone();
+        run_blocking(move || replicate_streams(&connector, request)).await
+    }
+}
+
+/// Replicates the selected tables and queries into the store, blocking until done.
+fn replicate_streams(connector: &Connector, request: SyncRequest) -> Result<SyncResult, AppError> {
+    let config = database_config(connector)?;
+
+    let mut replications: Vec<(String, Replication)> = Vec::new();
+    for table in &config.tables {
+        let source = database_service::table_source(&table.name).map_err(AppError::bad_request)?;
+        replications.push((
+            table.name.clone(),
+            Replication {
+                source,
+                table: database_service::table_destination(&table.name),
+                cursor_column: table.cursor_column.as_deref(),
+                primary_key: &table.primary_key,
+                since: None,
+            },
+        ));
+    }
+    for query in &config.queries {
+        replications.push((
+            query.name.clone(),
+            Replication {
+                source: database_service::query_source(config.engine, &query.sql),
+                table: query.name.clone(),
+                cursor_column: query.cursor_column.as_deref(),
+                primary_key: &query.primary_key,
+                since: None,
+            },
+        ));
+    }
+
+    if let Some(names) = &request.streams {
+        if let Some(unknown) = names.iter().find(|n| !replications.iter().any(|(stream, _)| stream == *n)) {
+            warn!(stream = %unknown, "Stream not selected");
+            return Err(AppError::bad_request(format!("Stream {} is not selected", unknown)));
+        }
+        replications.retain(|(stream, _)| names.contains(stream));
+    }
+
+    let mut conn = config.open(connector)?;
+    let mut results = Vec::with_capacity(replications.len());
+
+    for (stream, mut replication) in replications {
+        let start_date = match (request.start_date, replication.cursor_column) {
+            (Some(start_date), Some(column)) => {
+                let temporal = database_service::is_temporal(&conn, &replication.source, column).map_err(|e| {
+                    error!(stream = %stream, error = %e, "Failed to inspect cursor column");
+                    AppError::internal(e)
+                })?;
+                if !temporal {
+                    warn!(stream = %stream, column = %column, "Cursor column is not a date or timestamp, ignoring start date");
+                }
+                temporal.then_some(start_date)
+            }
+            _ => None,
+        };
+        replication.since = start_date.map(|d| d.to_string());
+
+        debug!(stream = %stream, table = %replication.table, "Replicating stream");
+        let stored = database_service::replicate(&mut conn, replication).map_err(|e| {
+            error!(stream = %stream, error = %e, "Replication failed");
+            AppError::internal(e)
+        })?;
+
+        results.push(StreamSyncResult {
+            stream,
+            start_date,
+            currency_code: None,
+            record_count: stored.record_count,
+            inserted_count: stored.inserted_count,
+            updated_count: stored.updated_count,
+        });
+    }
+
+    Ok(SyncResult::from_streams(results))
+}
@@ -0,0 +1,68 @@
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::{AuthKind, Source, Stream, SyncRequest, SyncResult, parse_config};
+use crate::api::error::AppError;
+use crate::models::connector::{Connector, ConnectorDetails, ConnectorType};
+use crate::services::storage_service::TableSchema;
+use crate::AppState;
+
+/// Files pushed through the upload endpoint; there is nothing to pull.
+pub struct FileUploadSource;
+
+#[async_trait]
+impl Source for FileUploadSource {
+    fn connector_type(&self) -> ConnectorType {
+        ConnectorType::FileUpload
+    }
+
+    fn display_name(&self) -> &'static str {
+        "File upload"
+    }
+
+    fn auth_kind(&self) -> AuthKind {
+        AuthKind::Config
+    }
+
+    fn config_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "required": ["type"],
+            "properties": {
+                "type": { "const": "FileUpload" },
+                "table": { "type": "string", "pattern": "^[a-z_][a-z0-9_]*$" },
+                "column_types": {
+                    "type": "object",
+                    "additionalProperties": { "type": "string", "minLength": 1 }
+                }
+            }
+        })
+    }
+
+    /// The table layout is inferred from the uploaded files.
+    fn storage_schema(&self) -> Vec<TableSchema> {
+        Vec::new()
+    }
+
+    async fn discover(&self, _state: &AppState, connector: &Connector) -> Result<Vec<Stream>, AppError> {
+        match parse_config(connector)? {
+            ConnectorDetails::FileUpload { table, .. } => Ok(vec![Stream {
+                id: table.clone(),
+                name: table,
+                selected: true,
+            }]),
+            _ => Err(AppError::bad_request("Connector is not a file upload connector")),
+        }
+    }
+
+    async fn sync(
+        &self,
+        _state: &AppState,
+        _connector: &Connector,
+        _request: SyncRequest,
+    ) -> Result<SyncResult, AppError> {
+        Err(AppError::bad_request(
+            "File upload connectors are loaded through their files endpoint",
+        ))
+    }
+}
@@ -0,0 +1,932 @@
+use async_trait::async_trait;
+use chrono::{Duration, NaiveDate};
+use duckdb::Connection;
+use serde_json::json;
+use tracing::{debug, error, info, warn};
+
+use super::google::fresh_access_token;
+use super::{AuthKind, Source, Stream, StreamSyncResult, SyncRequest, SyncResult, parse_config, save_config};
+use crate::api::error::AppError;
+use crate::models::connector::{Connector, ConnectorDetails, ConnectorType, Ga4Property, OAuthTokens};
+use crate::models::ga4_definition::FieldIssue;
+use crate::models::report_definition::{FunnelSpec, PivotSpec, ReportDefinition, ReportSpec, StandardSpec};
+use crate::services::storage_service::{StorageResult, TableSchema};
+use crate::services::{ga4_service, report_service, storage_service, traffic_filter_service};
+use crate::AppState;
+
+pub const SCOPES: [&str; 2] = [
+    // Admin API (for listing properties)
+    "https://www.googleapis.com/auth/analytics.readonly",
+    // Data API (for running reports)
+    "https://www.googleapis.com/auth/analytics",
+];
+
+pub struct Ga4Source;
+
+#[async_trait]
+impl Source for Ga4Source {
+    fn connector_type(&self) -> ConnectorType {
+        ConnectorType::Ga4
+    }
+
+    fn display_name(&self) -> &'static str {
+        "GA4"
+    }
+
+    fn auth_kind(&self) -> AuthKind {
+        AuthKind::GoogleOAuth {
+            scopes: SCOPES.to_vec(),
+        }
+    }
+
+    fn oauth_config(&self, tokens: OAuthTokens) -> Option<ConnectorDetails> {
+        Some(ConnectorDetails::Ga4 {
+            tokens,
+            properties: Vec::new(),
+            legacy_property_id: None,
+        })
+    }
+
+    fn config_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "required": ["type", "access_token", "token_type"],
+            "properties": {
+                "type": { "const": "Ga4" },
+                "access_token": { "type": "string" },
+                "refresh_token": { "type": ["string", "null"] },
+                "expires_at": { "type": ["string", "null"], "format": "date-time" },
+                "token_type": { "type": "string" },
+                "legacy_property_id": { "type": ["string", "null"] },
+                "properties": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["property_id", "property_name"],
+                        "properties": {
+                            "property_id": { "type": "string", "pattern": "^properties/[0-9]+$" },
+                            "property_name": { "type": "string" },
+                            "time_zone": { "type": ["string", "null"] },
+                            "currency_code": { "type": ["string", "null"] }
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    fn storage_schema(&self) -> Vec<TableSchema> {
+        vec![
+            TableSchema::new(
+                "ga4_records",
+                &[
+                    ("property_id", "VARCHAR"),
+                    ("date", "VARCHAR"),
+                    ("country", "VARCHAR"),
+                    ("device_category", "VARCHAR"),
+                    ("event_name", "VARCHAR"),
+                    ("browser", "VARCHAR"),
+                    ("operating_system", "VARCHAR"),
+                    ("screen_resolution", "VARCHAR"),
+                    ("active_users", "BIGINT"),
+                    ("sessions", "BIGINT"),
+                    ("screen_page_views", "BIGINT"),
+                    ("bounce_rate", "DOUBLE"),
+                    ("average_session_duration", "DOUBLE"),
+                    ("total_revenue", "DOUBLE"),
+                    ("currency_code", "VARCHAR"),
+                ],
+                &[
+                    "property_id",
+                    "date",
+                    "country",
+                    "device_category",
+                    "event_name",
+                    "browser",
+                    "operating_system",
+                    "screen_resolution",
+                ],
+            ),
+            report_service::funnel_results_schema(),
+            report_service::report_results_schema(),
+        ]
+    }
+
+    async fn discover(&self, state: &AppState, connector: &Connector) -> Result<Vec<Stream>, AppError> {
+        let access_token = fresh_access_token(state, connector).await?;
+        let selected = selected_properties(connector)?;
+
+        let streams = ga4_service::list_properties(&access_token)
+            .await
+            .map_err(AppError::internal)?
+            .into_iter()
+            .map(|prop| Stream {
+                selected: selected.iter().any(|p| p.property_id == prop.property),
+                id: prop.property,
+                name: prop.display_name,
+            })
+            .collect();
+
+        Ok(streams)
+    }
+
+    async fn select_streams(
+        &self,
+        state: &AppState,
+        connector: &Connector,
+        stream_ids: Vec<String>,
+    ) -> Result<Vec<Stream>, AppError> {
+        let properties = self.select_properties(state, connector, &stream_ids).await?;
+
+        Ok(properties
+            .into_iter()
+            .map(|p| Stream {
+                id: p.property_id,
+                name: p.property_name,
+                selected: true,
+            })
+            .collect())
+    }
+
+    async fn sync(
+        &self,
+        state: &AppState,
+        connector: &Connector,
+        request: SyncRequest,
+    ) -> Result<SyncResult, AppError> {
+        let project_id = connector.project_id;
+        let connector_id = connector.id;
+
+        // Check and refresh token if expired
+        let access_token = fresh_access_token(state, connector).await?;
+
+        let properties = selected_properties(connector)?;
+
+        // Check at least one property is selected
+        if properties.is_empty() {
+            warn!("No property selected");
+            return Err(AppError::bad_request("No GA4 property selected. Please select a property first."));
+        }
+
+        let properties = backfill_property_details(state, connector, &access_token, properties).await?;
+
+        // Before incremental start dates are read, so older rows count as synced
+        storage_service::migrate_legacy_store(project_id, connector_id, legacy_property_id(connector)?.as_deref())
+            .map_err(AppError::internal)?;
+
+        // Narrow down to the requested subset, if any
+        let properties = match request.streams {
+            Some(property_ids) => {
+                if let Some(unknown) = property_ids
+                    .iter()
+                    .find(|id| !properties.iter().any(|p| &p.property_id == *id))
+                {
+                    warn!(property_id = %unknown, "Property not selected on connector");
+                    return Err(AppError::bad_request(format!(
+                        "Property {} is not selected on this connector",
+                        unknown
+                    )));
+                }
+                properties
+                    .into_iter()
+                    .filter(|p| property_ids.contains(&p.property_id))
+                    .collect()
+            }
+            None => properties,
+        };
+
+        let mut results = Vec::with_capacity(properties.len());
+        let definitions = state.report_definition_repo.find_by_connector(connector_id).await?;
+
+        // Custom definitions only label fields, failing to fetch them should not fail the sync
+        for property in &properties {
+            let property_id = &property.property_id;
+            if let Err(e) = refresh_property_definitions(state, connector, &access_token, property_id).await {
+                warn!(property_id = %property_id, error = %e.message, "Failed to refresh property definitions");
+            }
+        }
+        let property_definitions = state.ga4_definition_repo.find_by_connector(connector_id).await?;
+        for definition in &definitions {
+            for issue in property_definitions.report_issues(definition) {
+                match issue.issue {
+                    FieldIssue::Archived => warn!(
+                        report = %definition.name,
+                        field = %issue.field,
+                        "Report definition uses an archived custom definition"
+                    ),
+                    FieldIssue::Renamed { from } => debug!(
+                        report = %definition.name,
+                        field = %issue.field,
+                        from = %from,
+                        to = %issue.display_name,
+                        "Report definition uses a renamed custom definition"
+                    ),
+                }
+            }
+        }
+
+        for property in properties.clone() {
+            // GA4 dates are in the property's reporting time zone
+            let today = ga4_service::today_in(property.time_zone.as_deref());
+
+            // Calculate start date: use provided, or get incremental start date
+            let start_date = request.start_date.unwrap_or_else(|| {
+                storage_service::get_incremental_start_date(
+                    project_id,
+                    connector_id,
+                    &property.property_id,
+                    today,
+                )
+            });
+
+            debug!(
+                property_id = %property.property_id,
+                start_date = %start_date,
+                "Pulling data for property"
+            );
+
+            // Pull data from GA4
+            let pull_params = ga4_service::PullParams {
+                property_id: property.property_id.clone(),
+                access_token: access_token.clone(),
+                start_date: Some(start_date),
+                time_zone: property.time_zone.clone(),
+                currency_code: property.currency_code.clone(),
+            };
+
+            let records = ga4_service::pull(pull_params)
+                .await
+                .map_err(AppError::internal)?;
+
+            // Store with upsert
+            let result = storage_service::store(project_id, connector_id, records)
+                .map_err(AppError::internal)?;
+
+            results.push(StreamSyncResult {
+                stream: property.property_id,
+                start_date: Some(start_date),
+                currency_code: property.currency_code,
+                record_count: result.record_count,
+                inserted_count: result.inserted_count,
+                updated_count: result.updated_count,
+            });
+        }
+
+        // A failing report definition should not fail the sync of its property
+        let definitions: Vec<ReportDefinition> = definitions
+            .into_iter()
+            .filter(|d| properties.iter().any(|p| p.property_id == d.property_id))
+            .collect();
+        let report_results = run_reports(connector, &access_token, &definitions, &properties, request.start_date).await;
+        for (definition, result) in definitions.iter().zip(report_results) {
+            match result {
+                Ok(result) => results.push(result),
+                Err(e) => warn!(report = %definition.name, error = %e.message, "Failed to run report definition"),
+            }
+        }
+
+        // Spam combos are filtered through views, kept in line with the stored rows
+        let filter = state.traffic_filter_repo.find_by_project(project_id).await?;
+        let conn = storage_service::open_store(project_id, connector_id, storage_service::GA4_STORE_FILE)
+            .map_err(AppError::internal)?;
+        traffic_filter_service::create_ga4_views(&conn, &filter).map_err(AppError::internal)?;
+
+        Ok(SyncResult::from_streams(results))
+    }
+}
+
+/// Standard and pivot reports are fetched this many days at a time.
+const REPORT_CHUNK_DAYS: i64 = 31;
+/// Funnel reports take one request per day, so they go back at most this many days.
+pub const MAX_FUNNEL_DAYS: i64 = 90;
+
+/// Earliest day a funnel report ending `today` covers.
+pub fn earliest_funnel_date(today: NaiveDate) -> NaiveDate {
+    today - Duration::days(MAX_FUNNEL_DAYS - 1)
+}
+
+/// Runs report definitions from `start_date`, or incrementally, up to today in their
+/// property's time zone, and stores their results by date.
+///
+/// Standard reports of a property starting on the same day go through `batchRunReports`
+/// together. Returns one result per definition, in order.
+pub async fn run_reports(
+    connector: &Connector,
+    access_token: &str,
+    definitions: &[ReportDefinition],
+    properties: &[Ga4Property],
+    start_date: Option<NaiveDate>,
+) -> Vec<Result<StreamSyncResult, AppError>> {
+    let mut results: Vec<Option<Result<StreamSyncResult, AppError>>> = definitions.iter().map(|_| None).collect();
+    // Standard reports by property and start date, as indices into `definitions`
+    let mut batches: Vec<(&Ga4Property, NaiveDate, Vec<usize>)> = Vec::new();
+
+    for (i, definition) in definitions.iter().enumerate() {
+        let Some(property) = properties.iter().find(|p| p.property_id == definition.property_id) else {
+            results[i] = Some(Err(AppError::bad_request(format!(
+                "Property {} is not selected on this connector",
+                definition.property_id
+            ))));
+            continue;
+        };
+        let today = ga4_service::today_in(property.time_zone.as_deref());
+
+        match &definition.spec {
+            ReportSpec::Funnel(spec) => {
+                let start_date = start_date.unwrap_or_else(|| {
+                    stored_start_date(connector, definition, report_service::FUNNEL_RESULTS_TABLE, today)
+                });
+                results[i] = Some(run_funnel(connector, access_token, definition, spec, start_date, today).await);
+            }
+            ReportSpec::Pivot(spec) => {
+                let start_date = start_date.unwrap_or_else(|| {
+                    stored_start_date(connector, definition, report_service::REPORT_RESULTS_TABLE, today)
+                });
+                results[i] = Some(run_pivot(connector, access_token, definition, spec, start_date, today).await);
+            }
+            ReportSpec::Standard(_) => {
+                let start_date = start_date.unwrap_or_else(|| {
+                    stored_start_date(connector, definition, report_service::REPORT_RESULTS_TABLE, today)
+                });
+                match batches
+                    .iter_mut()
+                    .find(|(p, start, _)| p.property_id == property.property_id && *start == start_date)
+                {
+                    Some((_, _, indices)) => indices.push(i),
+                    None => batches.push((property, start_date, vec![i])),
+                }
+            }
+        }
+    }
+
+    for (property, start_date, indices) in batches {
+        let today = ga4_service::today_in(property.time_zone.as_deref());
+        let batch: Vec<&ReportDefinition> = indices.iter().map(|&i| &definitions[i]).collect();
+
+        match run_standard_batch(connector, access_token, &property.property_id, &batch, start_date, today).await {
+            Ok(batch_results) => {
+                for (i, result) in indices.into_iter().zip(batch_results) {
+                    results[i] = Some(result);
+                }
+            }
+            Err(e) => {
+                for i in indices {
+                    results[i] = Some(Err(AppError::internal(e.clone())));
+                }
+            }
+        }
+    }
+
+    results
+        .into_iter()
+        .map(|result| result.unwrap_or_else(|| Err(AppError::internal("Report definition was not run"))))
+        .collect()
+}
+
+/// `LOOKBACK_DAYS` before the latest date stored for the definition, or the default backfill.
+/// Results of standard and pivot reports only count under the current filters.
+fn stored_start_date(connector: &Connector, definition: &ReportDefinition, table: &str, today: NaiveDate) -> NaiveDate {
+    let definition_id = definition.id.to_string();
+    let fingerprint = report_service::filter_fingerprint(&definition.spec);
+
+    let mut filters = vec![("definition_id", definition_id.as_str())];
+    if table == report_service::REPORT_RESULTS_TABLE {
+        filters.push(("filter_fingerprint", fingerprint.as_str()));
+    }
+
+    let max_date = storage_service::max_date(
+        connector.project_id,
+        connector.id,
+        storage_service::GA4_STORE_FILE,
+        table,
+        "date",
+        &filters,
+    );
+    storage_service::incremental_start_date(max_date, today)
+}
+
+/// Consecutive ranges of at most `REPORT_CHUNK_DAYS` covering `start_date..=end_date`.
+fn date_chunks(start_date: NaiveDate, end_date: NaiveDate) -> Vec<(NaiveDate, NaiveDate)> {
+    let mut chunks = Vec::new();
+    let mut chunk_start = start_date;
+
+    while chunk_start <= end_date {
+        let chunk_end = (chunk_start + Duration::days(REPORT_CHUNK_DAYS - 1)).min(end_date);
+        chunks.push((chunk_start, chunk_end));
+        chunk_start = chunk_end + Duration::days(1);
+    }
+
+    chunks
+}
+
+/// Pivot report rows from `start_date` to `end_date`, one request per chunk of days.
+async fn pivot_rows(
+    base_url: &str,
+    property_id: &str,
+    access_token: &str,
+    spec: &PivotSpec,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<Vec<ga4_service::ReportRow>, String> {
+    let mut rows = Vec::new();
+    for (chunk_start, chunk_end) in date_chunks(start_date, end_date) {
+        let chunk =
+            ga4_service::run_pivot_report(base_url, property_id, access_token, spec, chunk_start, chunk_end).await?;
+        rows.extend(chunk);
+    }
+    Ok(rows)
+}
+
+/// Rows of standard reports of one property from `start_date` to `end_date`, in the order of
+/// `specs`, batched chunk by chunk.
+async fn standard_rows(
+    base_url: &str,
+    property_id: &str,
+    access_token: &str,
+    specs: &[&StandardSpec],
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<Vec<Vec<ga4_service::ReportRow>>, String> {
+    let mut rows: Vec<Vec<ga4_service::ReportRow>> = vec![Vec::new(); specs.len()];
+    for (chunk_start, chunk_end) in date_chunks(start_date, end_date) {
+        let chunk =
+            ga4_service::batch_run_reports(base_url, property_id, access_token, specs, chunk_start, chunk_end).await?;
+        for (rows, chunk) in rows.iter_mut().zip(chunk) {
+            rows.extend(chunk);
+        }
+    }
+    Ok(rows)
+}
+
+/// Runs `work` on the connector's GA4 store, off the async runtime.
+async fn with_ga4_store<T: Send + 'static>(
+    connector: &Connector,
+    work: impl FnOnce(&Connection) -> Result<T, String> + Send + 'static,
+) -> Result<T, AppError> {
+    let (project_id, connector_id) = (connector.project_id, connector.id);
+
+    tokio::task::spawn_blocking(move || {
+        work(&storage_service::open_store(project_id, connector_id, storage_service::GA4_STORE_FILE)?)
+    })
+    .await
+    .map_err(|e| {
+        error!(error = %e, "GA4 store task failed");
+        AppError::internal("GA4 store task failed")
+    })?
+    .map_err(AppError::internal)
+}
+
+fn report_result(definition: &ReportDefinition, start_date: NaiveDate, stored: StorageResult) -> StreamSyncResult {
+    info!(report = %definition.name, record_count = stored.record_count, "Report definition run");
+
+    StreamSyncResult {
+        stream: format!("report:{}", definition.name),
+        start_date: Some(start_date),
+        currency_code: None,
+        record_count: stored.record_count,
+        inserted_count: stored.inserted_count,
+        updated_count: stored.updated_count,
+    }
+}
+
+async fn run_funnel(
+    connector: &Connector,
+    access_token: &str,
+    definition: &ReportDefinition,
+    spec: &FunnelSpec,
+    start_date: NaiveDate,
+    today: NaiveDate,
+) -> Result<StreamSyncResult, AppError> {
+    let earliest = earliest_funnel_date(today);
+    let start_date = if start_date < earliest {
+        warn!(report = %definition.name, start_date = %start_date, earliest = %earliest, "Funnel start date capped");
+        earliest
+    } else {
+        start_date
+    };
+
+    debug!(report = %definition.name, start_date = %start_date, "Running funnel report");
+
+    // One request per day, funnel results are not broken down by date
+    let mut rows = Vec::new();
+    for date in start_date.iter_days().take_while(|d| *d <= today) {
+        let day = ga4_service::run_funnel_report(ga4_service::FunnelParams {
+            property_id: &definition.property_id,
+            access_token,
+            spec,
+            date,
+        })
+        .await
+        .map_err(AppError::internal)?;
+        rows.extend(day);
+    }
+
+    let (definition_id, property_id) = (definition.id, definition.property_id.clone());
+    let stored =
+        with_ga4_store(connector, move |conn| report_service::store_funnel(conn, definition_id, &property_id, rows))
+            .await?;
+
+    Ok(report_result(definition, start_date, stored))
+}
+
+async fn run_pivot(
+    connector: &Connector,
+    access_token: &str,
+    definition: &ReportDefinition,
+    spec: &PivotSpec,
+    start_date: NaiveDate,
+    today: NaiveDate,
+) -> Result<StreamSyncResult, AppError> {
+    debug!(report = %definition.name, start_date = %start_date, "Running pivot report");
+
+    let rows = pivot_rows(
+        ga4_service::DATA_API_BASE_URL,
+        &definition.property_id,
+        access_token,
+        spec,
+        start_date,
+        today,
+    )
+    .await
+    .map_err(AppError::internal)?;
+
+    let (definition_id, property_id) = (definition.id, definition.property_id.clone());
+    let fingerprint = report_service::filter_fingerprint(&definition.spec);
+    let stored = with_ga4_store(connector, move |conn| {
+        report_service::store_report(conn, definition_id, &property_id, &fingerprint, rows)
+    })
+    .await?;
+
+    Ok(report_result(definition, start_date, stored))
+}
+
+/// Fetches standard reports of one property together, chunk by chunk. Fails as a whole when
+/// a request fails, storage errors are per definition.
+async fn run_standard_batch(
+    connector: &Connector,
+    access_token: &str,
+    property_id: &str,
+    definitions: &[&ReportDefinition],
+    start_date: NaiveDate,
+    today: NaiveDate,
+) -> Result<Vec<Result<StreamSyncResult, AppError>>, String> {
+    let specs: Vec<&StandardSpec> = definitions
+        .iter()
+        .filter_map(|d| match &d.spec {
+            ReportSpec::Standard(spec) => Some(spec),
+            _ => None,
+        })
+        .collect();
+
+    debug!(
+        property_id = %property_id,
+        report_count = specs.len(),
+        start_date = %start_date,
+        "Running standard reports"
+    );
+
+    let rows = standard_rows(ga4_service::DATA_API_BASE_URL, property_id, access_token, &specs, start_date, today).await?;
+
+    let reports: Vec<_> = definitions
+        .iter()
+        .zip(rows)
+        .map(|(definition, rows)| {
+            let fingerprint = report_service::filter_fingerprint(&definition.spec);
+            (definition.id, definition.property_id.clone(), fingerprint, rows)
+        })
+        .collect();
+    let stored = with_ga4_store(connector, move |conn| {
+        Ok(reports
+            .into_iter()
+            .map(|(definition_id, property_id, fingerprint, rows)| {
+                report_service::store_report(conn, definition_id, &property_id, &fingerprint, rows)
+            })
+            .collect::<Vec<_>>())
+    })
+    .await
+    .map_err(|e| e.message)?;
+
+    Ok(definitions
+        .iter()
+        .zip(stored)
+        .map(|(definition, stored)| {
+            stored
+                .map(|stored| report_result(definition, start_date, stored))
+                .map_err(AppError::internal)
+        })
+        .collect())
+}
+
+/// Stores the property's current custom dimensions and metrics, key events and data streams.
+pub async fn refresh_property_definitions(
+    state: &AppState,
+    connector: &Connector,
+    access_token: &str,
+    property_id: &str,
+) -> Result<(), AppError> {
+    let definitions = ga4_service::get_property_definitions(connector.id, access_token, property_id)
+        .await
+        .map_err(AppError::internal)?;
+
+    state
+        .ga4_definition_repo
+        .replace_property(connector.id, property_id, &definitions)
+        .await?;
+
+    debug!(property_id = %property_id, "Property definitions refreshed");
+    Ok(())
+}
+
+/// Fetches the time zone and currency of properties selected before they were tracked,
+/// and persists them on the connector.
+async fn backfill_property_details(
+    state: &AppState,
+    connector: &Connector,
+    access_token: &str,
+    properties: Vec<Ga4Property>,
+) -> Result<Vec<Ga4Property>, AppError> {
+    let is_complete = |p: &Ga4Property| p.time_zone.is_some() && p.currency_code.is_some();
+
+    if properties.iter().all(is_complete) {
+        return Ok(properties);
+    }
+
+    let mut completed = Vec::with_capacity(properties.len());
+    for property in properties {
+        if is_complete(&property) {
+            completed.push(property);
+            continue;
+        }
+
+        debug!(property_id = %property.property_id, "Fetching missing property details");
+        let details = ga4_service::get_property(access_token, &property.property_id)
+            .await
+            .map_err(AppError::internal)?;
+
+        completed.push(Ga4Property {
+            time_zone: details.time_zone,
+            currency_code: details.currency_code,
+            ..property
+        });
+    }
+
+    // Reload the connector, the token may have been refreshed in the meantime
+    let connector = state
+        .connector_repo
+        .find_by_id(connector.id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Connector not found"))?;
+
+    save_selected_properties(state, &connector, completed.clone()).await?;
+
+    info!("Property details backfilled");
+    Ok(completed)
+}
+
+impl Ga4Source {
+    /// Replaces the selected properties of the connector after checking the Google account
+    /// can see them. Ids may be bare (`123456`) or resource names (`properties/123456`).
+    pub async fn select_properties(
+        &self,
+        state: &AppState,
+        connector: &Connector,
+        raw_ids: &[String],
+    ) -> Result<Vec<Ga4Property>, AppError> {
+        let mut property_ids: Vec<String> = Vec::with_capacity(raw_ids.len());
+        for raw in raw_ids {
+            let property_id = ga4_service::normalize_property_id(raw).ok_or_else(|| {
+                warn!(property_id = %raw, "Invalid property id");
+                AppError::bad_request(format!(
+                    "Invalid property id '{}', expected 'properties/<number>' or '<number>'",
+                    raw
+                ))
+            })?;
+            if !property_ids.contains(&property_id) {
+                property_ids.push(property_id);
+            }
+        }
+
+        let access_token = fresh_access_token(state, connector).await?;
+
+        // Only accept properties the connected Google account can actually see
+        let accessible = ga4_service::list_properties(&access_token)
+            .await
+            .map_err(AppError::internal)?;
+
+        if let Some(missing) = property_ids
+            .iter()
+            .find(|id| !accessible.iter().any(|p| &p.property == *id))
+        {
+            warn!(property_id = %missing, "Property not accessible");
+            return Err(AppError::bad_request(format!(
+                "Property {} is not accessible with this Google account",
+                missing
+            )));
+        }
+
+        let mut properties: Vec<Ga4Property> = Vec::with_capacity(property_ids.len());
+        for property_id in property_ids {
+            let details = ga4_service::get_property(&access_token, &property_id)
+                .await
+                .map_err(AppError::internal)?;
+
+            properties.push(Ga4Property {
+                property_id,
+                property_name: details.display_name,
+                time_zone: details.time_zone,
+                currency_code: details.currency_code,
+            });
+        }
+
+        // Reload the connector, the token may have been refreshed above
+        let connector = state
+            .connector_repo
+            .find_by_id(connector.id)
+            .await?
+            .ok_or_else(|| AppError::not_found("Connector not found"))?;
+
+        save_selected_properties(state, &connector, properties.clone()).await?;
+
+        info!(count = properties.len(), "Properties selected successfully");
+        Ok(properties)
+    }
+}
+
+fn selected_properties(connector: &Connector) -> Result<Vec<Ga4Property>, AppError> {
+    match parse_config(connector)? {
+        ConnectorDetails::Ga4 { properties, .. } => Ok(properties),
+        _ => Err(AppError::bad_request("Connector is not a GA4 connector")),
+    }
+}
+
+fn legacy_property_id(connector: &Connector) -> Result<Option<String>, AppError> {
+    match parse_config(connector)? {
+        ConnectorDetails::Ga4 { legacy_property_id, .. } => Ok(legacy_property_id),
+        _ => Err(AppError::bad_request("Connector is not a GA4 connector")),
+    }
+}
+
+async fn save_selected_properties(
+    state: &AppState,
+    connector: &Connector,
+    selected: Vec<Ga4Property>,
+) -> Result<Connector, AppError> {
+    let mut config = parse_config(connector)?;
+    if let ConnectorDetails::Ga4 { properties, .. } = &mut config {
+        *properties = selected;
+    }
+
+    save_config(state, connector, &config).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::report_definition::Pivot;
+    use crate::services::test_server::serve;
+    use axum::extract::{Path, State};
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use serde_json::Value;
+    use std::sync::{Arc, Mutex};
+
+    /// Date range and `date` pivot limit of each call, with the method called.
+    type Calls = Arc<Mutex<Vec<(String, String, String, Option<i64>)>>>;
+
+    /// Returns one row dated on the first day of the requested range, for every report.
+    async fn report(State(calls): State<Calls>, Path(call): Path<String>, Json(body): Json<Value>) -> Json<Value> {
+        let method = call.rsplit(':').next().unwrap().to_string();
+        let requests = match method.as_str() {
+            "batchRunReports" => body["requests"].as_array().unwrap().clone(),
+            _ => vec![body],
+        };
+        let range = &requests[0]["dateRanges"][0];
+        let (start, end) = (range["startDate"].as_str().unwrap(), range["endDate"].as_str().unwrap());
+        let date_limit = requests[0]["pivots"][0]["limit"].as_i64();
+        calls
+            .lock()
+            .unwrap()
+            .push((method.clone(), start.to_string(), end.to_string(), date_limit));
+
+        let response = json!({
+            "dimensionHeaders": [{ "name": "date" }],
+            "metricHeaders": [{ "name": "sessions" }],
+            "rows": [{ "dimensionValues": [{ "value": start.replace('-', "") }], "metricValues": [{ "value": "1" }] }],
+        });
+        match method.as_str() {
+            "batchRunReports" => Json(json!({ "reports": vec![response; requests.len()] })),
+            _ => Json(response),
+        }
+    }
+
+    async fn stub() -> (String, Calls) {
+        let calls = Calls::default();
+        let router = Router::new()
+            .route("/properties/{call}", post(report))
+            .with_state(calls.clone());
+
+        (serve(router).await, calls)
+    }
+
+    fn date(value: &str) -> NaiveDate {
+        value.parse().unwrap()
+    }
+
+    fn range(start: &str, end: &str) -> (String, String) {
+        (start.to_string(), end.to_string())
+    }
+
+    #[test]
+    fn chunks_dates_by_report_chunk_days() {
+        assert_eq!(
+            date_chunks(date("2026-01-01"), date("2026-03-05")),
+            vec![
+                (date("2026-01-01"), date("2026-01-31")),
+                (date("2026-02-01"), date("2026-03-03")),
+                (date("2026-03-04"), date("2026-03-05")),
+            ]
+        );
+        assert_eq!(
+            date_chunks(date("2026-01-01"), date("2026-01-01")),
+            vec![(date("2026-01-01"), date("2026-01-01"))]
+        );
+        assert!(date_chunks(date("2026-01-02"), date("2026-01-01")).is_empty());
+    }
+
+    #[test]
+    fn caps_funnels_to_max_funnel_days() {
+        let today = date("2026-10-19");
+        let earliest = earliest_funnel_date(today);
+
+        assert_eq!(earliest.iter_days().take_while(|d| *d <= today).count() as i64, MAX_FUNNEL_DAYS);
+    }
+
+    #[tokio::test]
+    async fn runs_pivot_reports_chunk_by_chunk() {
+        let (base_url, calls) = stub().await;
+        let spec = PivotSpec {
+            dimensions: vec!["country".to_string()],
+            metrics: vec!["sessions".to_string()],
+            pivots: vec![Pivot {
+                field_names: vec!["country".to_string()],
+                limit: None,
+            }],
+            dimension_filter: None,
+            metric_filter: None,
+        };
+
+        let rows = pivot_rows(&base_url, "properties/123", "token", &spec, date("2026-01-01"), date("2026-03-05"))
+            .await
+            .unwrap();
+
+        let dates: Vec<String> = rows.iter().map(|r| r.date.to_string()).collect();
+        assert_eq!(dates, vec!["2026-01-01", "2026-02-01", "2026-03-04"]);
+        // The date pivot keeps every day of its chunk
+        let pivot = |start: &str, end: &str, limit| ("runPivotReport".to_string(), start.to_string(), end.to_string(), Some(limit));
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec![
+                pivot("2026-01-01", "2026-01-31", 31),
+                pivot("2026-02-01", "2026-03-03", 31),
+                pivot("2026-03-04", "2026-03-05", 2),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn joins_standard_report_chunks_per_report() {
+        let (base_url, calls) = stub().await;
+        let specs = [
+            StandardSpec {
+                dimensions: Vec::new(),
+                metrics: vec!["sessions".to_string()],
+                dimension_filter: None,
+                metric_filter: None,
+            },
+            StandardSpec {
+                dimensions: vec!["country".to_string()],
+                metrics: vec!["sessions".to_string()],
+                dimension_filter: None,
+                metric_filter: None,
+            },
+        ];
+        let specs: Vec<&StandardSpec> = specs.iter().collect();
+
+        let rows = standard_rows(&base_url, "properties/123", "token", &specs, date("2026-01-01"), date("2026-02-10"))
+            .await
+            .unwrap();
+
+        assert_eq!(rows.len(), 2);
+        for report in &rows {
+            let dates: Vec<String> = report.iter().map(|r| r.date.to_string()).collect();
+            assert_eq!(dates, vec!["2026-01-01", "2026-02-01"]);
+        }
+        let ranges: Vec<(String, String)> = calls
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(_, start, end, _)| (start.clone(), end.clone()))
+            .collect();
+        assert_eq!(ranges, vec![range("2026-01-01", "2026-01-31"), range("2026-02-01", "2026-02-10")]);
+    }
+}
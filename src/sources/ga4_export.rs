@@ -0,0 +1,192 @@
+use async_trait::async_trait;
+use serde_json::json;
+use tracing::{debug, error, warn};
+
+use super::{AuthKind, Source, Stream, StreamSyncResult, SyncRequest, SyncResult, parse_config};
+use crate::api::error::{AppError, FieldError};
+use crate::models::connector::{Connector, ConnectorDetails, ConnectorType};
+use crate::services::ga4_export_service::{self, ExportLocations, STORE_FILE};
+use crate::services::storage_service::{self, TableSchema};
+use crate::AppState;
+
+/// Files of the GA4 BigQuery export (`events_*` and `events_intraday_*` tables dumped to
+/// NDJSON or Parquet), one stream per file.
+pub struct Ga4ExportSource {
+    locations: ExportLocations,
+}
+
+impl Ga4ExportSource {
+    /// Allows the locations configured by `GA4_EXPORT_ROOT` and `GA4_EXPORT_REMOTE_PREFIXES`.
+    pub fn from_env() -> Self {
+        Self {
+            locations: ExportLocations::from_env(),
+        }
+    }
+
+    /// The connector path resolved against the allowed locations, checked again on every
+    /// use since the allowed locations may have changed since the config was stored.
+    fn export_path(&self, connector: &Connector) -> Result<String, AppError> {
+        let path = match parse_config(connector)? {
+            ConnectorDetails::Ga4Export { path } => path,
+            _ => return Err(AppError::bad_request("Connector is not a GA4 export connector")),
+        };
+
+        self.locations.resolve(&path).map_err(|e| {
+            warn!(path = %path, error = %e, "GA4 export path is not allowed");
+            AppError::bad_request(e)
+        })
+    }
+}
+
+/// Export files matched by the path, without the ones outside the allowed locations.
+fn list_files(
+    locations: &ExportLocations,
+    conn: &duckdb::Connection,
+    path: &str,
+) -> Result<Vec<(String, bool)>, String> {
+    let mut files = ga4_export_service::list_files(conn, path)?;
+    files.retain(|(file, _)| locations.contains(file));
+    Ok(files)
+}
+
+/// Runs DuckDB work, which may read remote files, off the async runtime.
+async fn run_blocking<T: Send + 'static>(
+    work: impl FnOnce() -> Result<T, AppError> + Send + 'static,
+) -> Result<T, AppError> {
+    tokio::task::spawn_blocking(work).await.map_err(|e| {
+        error!(error = %e, "GA4 export task failed");
+        AppError::internal("GA4 export task failed")
+    })?
+}
+
+/// Imports the files not imported yet, or the requested ones, blocking until done.
+fn import_files(
+    locations: &ExportLocations,
+    connector: &Connector,
+    path: &str,
+    streams: Option<Vec<String>>,
+) -> Result<SyncResult, AppError> {
+    let conn = storage_service::open_store(connector.project_id, connector.id, STORE_FILE)
+        .map_err(AppError::internal)?;
+
+    let files = list_files(locations, &conn, path).map_err(AppError::bad_request)?;
+
+    let files: Vec<String> = match streams {
+        Some(requested) => {
+            if let Some(unknown) = requested.iter().find(|r| !files.iter().any(|(file, _)| file == *r)) {
+                warn!(file = %unknown, "Unknown GA4 export file");
+                return Err(AppError::bad_request(format!("No export file {} under {}", unknown, path)));
+            }
+            requested
+        }
+        None => files
+            .into_iter()
+            .filter(|(_, imported)| !imported)
+            .map(|(file, _)| file)
+            .collect(),
+    };
+
+    debug!(file_count = files.len(), "Importing GA4 export files");
+
+    let mut results = Vec::with_capacity(files.len());
+    for file in files {
+        let row_count = ga4_export_service::import_file(&conn, &file).map_err(AppError::internal)?;
+
+        results.push(StreamSyncResult {
+            stream: file,
+            start_date: None,
+            currency_code: None,
+            record_count: row_count,
+            inserted_count: row_count,
+            updated_count: 0,
+        });
+    }
+
+    Ok(SyncResult::from_streams(results))
+}
+
+#[async_trait]
+impl Source for Ga4ExportSource {
+    fn connector_type(&self) -> ConnectorType {
+        ConnectorType::Ga4Export
+    }
+
+    fn display_name(&self) -> &'static str {
+        "GA4 BigQuery export"
+    }
+
+    fn auth_kind(&self) -> AuthKind {
+        AuthKind::Config
+    }
+
+    fn config_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "required": ["type", "path"],
+            "properties": {
+                "type": { "const": "Ga4Export" },
+                "path": { "type": "string", "minLength": 1 }
+            }
+        })
+    }
+
+    fn prepare_config(&self, config: serde_json::Value) -> Result<serde_json::Value, AppError> {
+        let ConnectorDetails::Ga4Export { path } = serde_json::from_value(config.clone())
+            .map_err(|e| AppError::bad_request(e.to_string()))?
+        else {
+            return Err(AppError::bad_request("Config is not a GA4 export config"));
+        };
+
+        if let Err(e) = self.locations.resolve(&path) {
+            return Err(AppError::validation(
+                "Invalid config for connector type GA4_EXPORT",
+                vec![FieldError::new("path", e)],
+            ));
+        }
+
+        Ok(config)
+    }
+
+    fn storage_schema(&self) -> Vec<TableSchema> {
+        vec![ga4_export_service::events_schema()]
+    }
+
+    /// Lists the export files, the ones not imported yet are selected.
+    async fn discover(&self, _state: &AppState, connector: &Connector) -> Result<Vec<Stream>, AppError> {
+        let path = self.export_path(connector)?;
+        let (project_id, connector_id) = (connector.project_id, connector.id);
+        let locations = self.locations.clone();
+
+        let files = run_blocking(move || {
+            let conn = storage_service::open_store(project_id, connector_id, STORE_FILE).map_err(AppError::internal)?;
+            list_files(&locations, &conn, &path).map_err(|e| {
+                warn!(error = %e, "Failed to list GA4 export files");
+                AppError::bad_request(e)
+            })
+        })
+        .await?;
+
+        Ok(files
+            .into_iter()
+            .map(|(file, imported)| Stream {
+                name: file.rsplit('/').next().unwrap_or(&file).to_string(),
+                id: file,
+                selected: !imported,
+            })
+            .collect())
+    }
+
+    /// Imports the files not imported yet, or re-imports the requested ones.
+    async fn sync(
+        &self,
+        _state: &AppState,
+        connector: &Connector,
+        request: SyncRequest,
+    ) -> Result<SyncResult, AppError> {
+        let path = self.export_path(connector)?;
+        let locations = self.locations.clone();
+        let connector = connector.clone();
+
+        run_blocking(move || import_files(&locations, &connector, &path, request.streams)).await
+    }
+}
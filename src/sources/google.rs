@@ -0,0 +1,39 @@
+use tracing::{error, info};
+
+use super::{parse_config, save_config};
+use crate::api::error::AppError;
+use crate::models::connector::Connector;
+use crate::services::oauth_service;
+use crate::AppState;
+
+/// Returns a usable access token for a Google connector, refreshing and persisting it when expired.
+pub async fn fresh_access_token(state: &AppState, connector: &Connector) -> Result<String, AppError> {
+    let mut config = parse_config(connector)?;
+    let tokens = config
+        .oauth_tokens_mut()
+        .ok_or_else(|| AppError::bad_request("Connector does not use OAuth"))?;
+
+    if !oauth_service::is_token_expired(tokens.expires_at) {
+        return Ok(tokens.access_token.clone());
+    }
+
+    let refresh_token = tokens.refresh_token.clone().ok_or_else(|| {
+        error!("Token expired and no refresh token available");
+        AppError::unauthorized("Token expired and no refresh token. Please re-authenticate.")
+    })?;
+
+    let new_token = oauth_service::refresh_token(&state.oauth_client, &refresh_token)
+        .await
+        .map_err(AppError::internal)?;
+
+    tokens.access_token = new_token.access_token.clone();
+    tokens.expires_at = new_token.expires_at;
+    if let Some(refresh_token) = new_token.refresh_token {
+        tokens.refresh_token = Some(refresh_token);
+    }
+
+    save_config(state, connector, &config).await?;
+
+    info!("Connector updated with refreshed token");
+    Ok(new_token.access_token)
+}
@@ -0,0 +1,492 @@
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use duckdb::types::Value;
+use serde_json::json;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use super::google::fresh_access_token;
+use super::{AuthKind, Source, Stream, StreamSyncResult, SyncRequest, SyncResult, parse_config, save_config};
+use crate::api::error::AppError;
+use crate::models::connector::{Connector, ConnectorDetails, ConnectorType, GoogleAdsCustomer, OAuthTokens};
+use crate::services::google_ads_service::{self, Credentials};
+use crate::services::storage_service::{self, StorageResult, TableSchema};
+use crate::services::ga4_service;
+use crate::AppState;
+
+pub const SCOPES: [&str; 1] = ["https://www.googleapis.com/auth/adwords"];
+
+const STORE_FILE: &str = "google_ads.duckdb";
+
+/// A GAQL performance report stored in its own table, one row per date and entity.
+struct Report {
+    table: &'static str,
+    resource: &'static str,
+    /// `(column, GAQL field, DuckDB type)`; `customer_id` and `date` come first implicitly.
+    fields: &'static [(&'static str, &'static str, &'static str)],
+    primary_key: &'static [&'static str],
+}
+
+const METRICS: [(&str, &str, &str); 5] = [
+    ("impressions", "metrics.impressions", "BIGINT"),
+    ("clicks", "metrics.clicks", "BIGINT"),
+    ("cost_micros", "metrics.cost_micros", "BIGINT"),
+    ("conversions", "metrics.conversions", "DOUBLE"),
+    ("conversions_value", "metrics.conversions_value", "DOUBLE"),
+];
+
+const REPORTS: [Report; 3] = [
+    Report {
+        table: "google_ads_campaigns",
+        resource: "campaign",
+        fields: &[
+            ("campaign_id", "campaign.id", "VARCHAR"),
+            ("campaign_name", "campaign.name", "VARCHAR"),
+            ("campaign_status", "campaign.status", "VARCHAR"),
+        ],
+        primary_key: &["customer_id", "date", "campaign_id"],
+    },
+    Report {
+        table: "google_ads_ad_groups",
+        resource: "ad_group",
+        fields: &[
+            ("campaign_id", "campaign.id", "VARCHAR"),
+            ("ad_group_id", "ad_group.id", "VARCHAR"),
+            ("ad_group_name", "ad_group.name", "VARCHAR"),
+            ("ad_group_status", "ad_group.status", "VARCHAR"),
+        ],
+        primary_key: &["customer_id", "date", "ad_group_id"],
+    },
+    Report {
+        table: "google_ads_keywords",
+        resource: "keyword_view",
+        fields: &[
+            ("campaign_id", "campaign.id", "VARCHAR"),
+            ("ad_group_id", "ad_group.id", "VARCHAR"),
+            ("criterion_id", "ad_group_criterion.criterion_id", "VARCHAR"),
+            ("keyword_text", "ad_group_criterion.keyword.text", "VARCHAR"),
+            ("match_type", "ad_group_criterion.keyword.match_type", "VARCHAR"),
+        ],
+        primary_key: &["customer_id", "date", "ad_group_id", "criterion_id"],
+    },
+];
+
+impl Report {
+    fn fields(&self) -> impl Iterator<Item = &(&'static str, &'static str, &'static str)> {
+        self.fields.iter().chain(METRICS.iter())
+    }
+
+    fn schema(&self) -> TableSchema {
+        let columns: Vec<(&str, &str)> = [("customer_id", "VARCHAR"), ("date", "DATE")]
+            .into_iter()
+            .chain(self.fields().map(|(column, _, data_type)| (*column, *data_type)))
+            .chain([("currency_code", "VARCHAR")])
+            .collect();
+
+        TableSchema::new(self.table, &columns, self.primary_key)
+    }
+
+    fn query(&self, start_date: NaiveDate, end_date: NaiveDate) -> String {
+        let fields: Vec<&str> = std::iter::once("segments.date")
+            .chain(self.fields().map(|(_, field, _)| *field))
+            .collect();
+
+        format!(
+            "SELECT {} FROM {} WHERE segments.date BETWEEN '{}' AND '{}'",
+            fields.join(", "),
+            self.resource,
+            start_date,
+            end_date
+        )
+    }
+
+    /// Maps a searchStream result to a row in `schema()` column order.
+    fn row(&self, customer: &GoogleAdsCustomer, result: &serde_json::Value) -> Option<Vec<Value>> {
+        let date = result
+            .pointer("/segments/date")
+            .and_then(|v| v.as_str())
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())?;
+
+        let mut row = vec![
+            Value::Text(customer.customer_id.clone()),
+            storage_service::date_value(date),
+        ];
+        row.extend(
+            self.fields()
+                .map(|(_, field, data_type)| to_value(result.pointer(&json_pointer(field)), data_type)),
+        );
+        row.push(
+            customer
+                .currency_code
+                .clone()
+                .map(Value::Text)
+                .unwrap_or(Value::Null),
+        );
+
+        Some(row)
+    }
+}
+
+/// `ad_group_criterion.keyword.match_type` -> `/adGroupCriterion/keyword/matchType`,
+/// the REST API returns fields in lower camel case.
+fn json_pointer(field: &str) -> String {
+    field
+        .split('.')
+        .map(|part| {
+            let mut segment = String::with_capacity(part.len());
+            let mut upper = false;
+            for c in part.chars() {
+                if c == '_' {
+                    upper = true;
+                } else if upper {
+                    segment.push(c.to_ascii_uppercase());
+                    upper = false;
+                } else {
+                    segment.push(c);
+                }
+            }
+            format!("/{}", segment)
+        })
+        .collect()
+}
+
+/// Metrics missing from a result are zero, e.g. `conversions` on a day without any.
+fn to_value(value: Option<&serde_json::Value>, data_type: &str) -> Value {
+    match (value, data_type) {
+        (None, "BIGINT") => Value::BigInt(0),
+        (None, "DOUBLE") => Value::Double(0.0),
+        (value, data_type) => storage_service::json_value(value, data_type),
+    }
+}
+
+pub struct GoogleAdsSource {
+    base_url: String,
+}
+
+impl GoogleAdsSource {
+    /// Uses `GOOGLE_ADS_API_BASE_URL` when set, e.g. to point at a local stub.
+    pub fn from_env() -> Self {
+        Self {
+            base_url: std::env::var("GOOGLE_ADS_API_BASE_URL")
+                .unwrap_or_else(|_| google_ads_service::DEFAULT_API_BASE_URL.to_string()),
+        }
+    }
+}
+
+struct AdsConfig {
+    developer_token: String,
+    login_customer_id: Option<String>,
+    customers: Vec<GoogleAdsCustomer>,
+}
+
+impl AdsConfig {
+    fn credentials<'a>(&'a self, access_token: &'a str) -> Credentials<'a> {
+        Credentials {
+            access_token,
+            developer_token: &self.developer_token,
+            login_customer_id: self.login_customer_id.as_deref(),
+        }
+    }
+}
+
+fn ads_config(connector: &Connector) -> Result<AdsConfig, AppError> {
+    match parse_config(connector)? {
+        ConnectorDetails::GoogleAds {
+            developer_token,
+            login_customer_id,
+            customers,
+            ..
+        } => {
+            let developer_token = developer_token.filter(|t| !t.is_empty()).ok_or_else(|| {
+                warn!("Missing developer token");
+                AppError::bad_request("Set developer_token on the connector config first")
+            })?;
+
+            Ok(AdsConfig {
+                developer_token,
+                login_customer_id,
+                customers,
+            })
+        }
+        _ => Err(AppError::bad_request("Connector is not a Google Ads connector")),
+    }
+}
+
+#[async_trait]
+impl Source for GoogleAdsSource {
+    fn connector_type(&self) -> ConnectorType {
+        ConnectorType::GoogleAds
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Google Ads"
+    }
+
+    fn auth_kind(&self) -> AuthKind {
+        AuthKind::GoogleOAuth {
+            scopes: SCOPES.to_vec(),
+        }
+    }
+
+    fn oauth_config(&self, tokens: OAuthTokens) -> Option<ConnectorDetails> {
+        Some(ConnectorDetails::GoogleAds {
+            tokens,
+            developer_token: None,
+            login_customer_id: None,
+            customers: Vec::new(),
+        })
+    }
+
+    fn config_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "required": ["type", "access_token", "token_type"],
+            "properties": {
+                "type": { "const": "GoogleAds" },
+                "access_token": { "type": "string" },
+                "refresh_token": { "type": ["string", "null"] },
+                "expires_at": { "type": ["string", "null"], "format": "date-time" },
+                "token_type": { "type": "string" },
+                "developer_token": { "type": ["string", "null"] },
+                "login_customer_id": { "type": ["string", "null"], "pattern": "^[0-9]{10}$" },
+                "customers": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["customer_id", "customer_name"],
+                        "properties": {
+                            "customer_id": { "type": "string", "pattern": "^[0-9]{10}$" },
+                            "customer_name": { "type": "string" },
+                            "time_zone": { "type": ["string", "null"] },
+                            "currency_code": { "type": ["string", "null"] }
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    fn storage_schema(&self) -> Vec<TableSchema> {
+        REPORTS.iter().map(Report::schema).collect()
+    }
+
+    async fn discover(&self, state: &AppState, connector: &Connector) -> Result<Vec<Stream>, AppError> {
+        let access_token = fresh_access_token(state, connector).await?;
+        let config = ads_config(connector)?;
+        let credentials = config.credentials(&access_token);
+
+        let customer_ids = google_ads_service::list_accessible_customers(&self.base_url, &credentials)
+            .await
+            .map_err(AppError::internal)?;
+
+        let mut streams = Vec::with_capacity(customer_ids.len());
+        for customer_id in customer_ids {
+            let name = match google_ads_service::get_customer(&self.base_url, &credentials, &customer_id).await {
+                Ok(details) => details.descriptive_name,
+                Err(e) => {
+                    // Accounts the user lost access to still show up in the list
+                    warn!(customer_id = %customer_id, error = %e, "Could not fetch customer details");
+                    customer_id.clone()
+                }
+            };
+
+            streams.push(Stream {
+                selected: config.customers.iter().any(|c| c.customer_id == customer_id),
+                id: customer_id,
+                name,
+            });
+        }
+
+        Ok(streams)
+    }
+
+    async fn select_streams(
+        &self,
+        state: &AppState,
+        connector: &Connector,
+        stream_ids: Vec<String>,
+    ) -> Result<Vec<Stream>, AppError> {
+        let mut customer_ids: Vec<String> = Vec::with_capacity(stream_ids.len());
+        for raw in &stream_ids {
+            let customer_id = google_ads_service::normalize_customer_id(raw).ok_or_else(|| {
+                warn!(customer_id = %raw, "Invalid customer id");
+                AppError::bad_request(format!("Invalid customer id '{}', expected ten digits", raw))
+            })?;
+            if !customer_ids.contains(&customer_id) {
+                customer_ids.push(customer_id);
+            }
+        }
+
+        let access_token = fresh_access_token(state, connector).await?;
+        let config = ads_config(connector)?;
+        let credentials = config.credentials(&access_token);
+
+        let mut customers = Vec::with_capacity(customer_ids.len());
+        for customer_id in customer_ids {
+            // Also proves the account is reachable with these credentials
+            let details = google_ads_service::get_customer(&self.base_url, &credentials, &customer_id)
+                .await
+                .map_err(|e| {
+                    warn!(customer_id = %customer_id, error = %e, "Customer not accessible");
+                    AppError::bad_request(format!(
+                        "Customer {} is not accessible with this Google account",
+                        customer_id
+                    ))
+                })?;
+
+            if details.manager {
+                return Err(AppError::bad_request(format!(
+                    "Customer {} is a manager account, select its client accounts instead",
+                    customer_id
+                )));
+            }
+
+            customers.push(GoogleAdsCustomer {
+                customer_id,
+                customer_name: details.descriptive_name,
+                time_zone: details.time_zone,
+                currency_code: details.currency_code,
+            });
+        }
+
+        // Reload the connector, the token may have been refreshed above
+        let connector = state
+            .connector_repo
+            .find_by_id(connector.id)
+            .await?
+            .ok_or_else(|| AppError::not_found("Connector not found"))?;
+
+        let mut config = parse_config(&connector)?;
+        if let ConnectorDetails::GoogleAds { customers: selected, .. } = &mut config {
+            selected.clone_from(&customers);
+        }
+        save_config(state, &connector, &config).await?;
+
+        info!(count = customers.len(), "Customers selected successfully");
+        Ok(customers
+            .into_iter()
+            .map(|c| Stream {
+                id: c.customer_id,
+                name: c.customer_name,
+                selected: true,
+            })
+            .collect())
+    }
+
+    async fn sync(
+        &self,
+        state: &AppState,
+        connector: &Connector,
+        request: SyncRequest,
+    ) -> Result<SyncResult, AppError> {
+        let project_id = connector.project_id;
+        let connector_id = connector.id;
+
+        let access_token = fresh_access_token(state, connector).await?;
+        let config = ads_config(connector)?;
+
+        if config.customers.is_empty() {
+            warn!("No customer selected");
+            return Err(AppError::bad_request("No Google Ads customer selected. Please select a customer first."));
+        }
+
+        let customers: Vec<&GoogleAdsCustomer> = match &request.streams {
+            Some(customer_ids) => {
+                if let Some(unknown) = customer_ids
+                    .iter()
+                    .find(|id| !config.customers.iter().any(|c| &c.customer_id == *id))
+                {
+                    warn!(customer_id = %unknown, "Customer not selected on connector");
+                    return Err(AppError::bad_request(format!(
+                        "Customer {} is not selected on this connector",
+                        unknown
+                    )));
+                }
+                config
+                    .customers
+                    .iter()
+                    .filter(|c| customer_ids.contains(&c.customer_id))
+                    .collect()
+            }
+            None => config.customers.iter().collect(),
+        };
+
+        let credentials = config.credentials(&access_token);
+        let mut results = Vec::with_capacity(customers.len());
+
+        for customer in customers {
+            // Segments are dated in the account time zone
+            let today = ga4_service::today_in(customer.time_zone.as_deref());
+
+            let start_date = request.start_date.unwrap_or_else(|| {
+                let max_date = storage_service::max_date(
+                    project_id,
+                    connector_id,
+                    STORE_FILE,
+                    REPORTS[0].table,
+                    "date",
+                    &[("customer_id", customer.customer_id.as_str())],
+                );
+                storage_service::incremental_start_date(max_date, today)
+            });
+
+            debug!(customer_id = %customer.customer_id, start_date = %start_date, "Pulling data for customer");
+
+            let mut result = StreamSyncResult {
+                stream: customer.customer_id.clone(),
+                start_date: Some(start_date),
+                currency_code: customer.currency_code.clone(),
+                record_count: 0,
+                inserted_count: 0,
+                updated_count: 0,
+            };
+
+            let mut tables = Vec::with_capacity(REPORTS.len());
+            for report in &REPORTS {
+                let report_rows = google_ads_service::search_stream(
+                    &self.base_url,
+                    &credentials,
+                    &customer.customer_id,
+                    &report.query(start_date, today),
+                )
+                .await
+                .map_err(AppError::internal)?;
+
+                let rows = report_rows.iter().filter_map(|r| report.row(customer, r)).collect();
+                tables.push((report.schema(), rows));
+            }
+
+            for stored in store(project_id, connector_id, tables).await? {
+                result.record_count += stored.record_count;
+                result.inserted_count += stored.inserted_count;
+                result.updated_count += stored.updated_count;
+            }
+
+            info!(customer_id = %customer.customer_id, record_count = result.record_count, "Customer pulled");
+            results.push(result);
+        }
+
+        Ok(SyncResult::from_streams(results))
+    }
+}
+
+/// Upserts the rows of each table in one write, on the blocking pool.
+async fn store(
+    project_id: Uuid,
+    connector_id: Uuid,
+    tables: Vec<(TableSchema, Vec<Vec<Value>>)>,
+) -> Result<Vec<StorageResult>, AppError> {
+    tokio::task::spawn_blocking(move || {
+        let conn = storage_service::open_store(project_id, connector_id, STORE_FILE)?;
+        tables
+            .into_iter()
+            .map(|(schema, rows)| storage_service::upsert_rows(&conn, &schema, rows))
+            .collect::<Result<Vec<_>, String>>()
+    })
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Google Ads store task failed");
+        AppError::internal("Google Ads store task failed")
+    })?
+    .map_err(AppError::internal)
+}
@@ -0,0 +1,280 @@
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use tracing::{debug, info, warn};
+
+use super::web_analytics::{self, Breakdown, Metrics};
+use super::{
+    AuthKind, Source, Stream, StreamSyncResult, SyncRequest, SyncResult, decrypt_secret, encrypt_secret, parse_config,
+    save_config,
+};
+use crate::api::error::AppError;
+use crate::models::connector::{Connector, ConnectorDetails, ConnectorType};
+use crate::services::matomo_service::{self, Site, number};
+use crate::services::storage_service::TableSchema;
+use crate::AppState;
+
+const STORE_FILE: &str = "matomo.duckdb";
+const TOTALS_TABLE: &str = "matomo_totals";
+const BREAKDOWNS_TABLE: &str = "matomo_breakdowns";
+
+/// Breakdown dimensions and the Reporting API methods they come from.
+const REPORTS: [(&str, &str); 4] = [
+    ("country", "UserCountry.getCountry"),
+    ("device_category", "DevicesDetection.getType"),
+    ("browser", "DevicesDetection.getBrowsers"),
+    ("operating_system", "DevicesDetection.getOsFamilies"),
+];
+
+pub struct MatomoSource;
+
+struct MatomoConfig {
+    base_url: String,
+    token_auth: String,
+    sites: Vec<String>,
+}
+
+fn matomo_config(connector: &Connector) -> Result<MatomoConfig, AppError> {
+    match parse_config(connector)? {
+        ConnectorDetails::Matomo {
+            base_url,
+            token_auth,
+            sites,
+        } => Ok(MatomoConfig {
+            base_url,
+            token_auth: decrypt_secret(&token_auth)?,
+            sites,
+        }),
+        _ => Err(AppError::bad_request("Connector is not a Matomo connector")),
+    }
+}
+
+/// Metrics of an `API.get` day.
+fn totals_metrics(day: &Value) -> Metrics {
+    Metrics {
+        active_users: number(day, "nb_uniq_visitors") as i64,
+        sessions: number(day, "nb_visits") as i64,
+        screen_page_views: number(day, "nb_pageviews") as i64,
+        bounce_rate: number(day, "bounce_rate") / 100.0,
+        average_session_duration: number(day, "avg_time_on_site"),
+    }
+}
+
+/// Metrics of a breakdown row. Page views are not tracked per visit dimension, actions
+/// (page views plus downloads and outlinks) are the closest figure.
+fn row_metrics(row: &Value) -> Metrics {
+    let visits = number(row, "nb_visits");
+    let per_visit = |metric: &str| if visits > 0.0 { number(row, metric) / visits } else { 0.0 };
+
+    Metrics {
+        active_users: number(row, "nb_uniq_visitors") as i64,
+        sessions: visits as i64,
+        screen_page_views: number(row, "nb_actions") as i64,
+        bounce_rate: per_visit("bounce_count"),
+        average_session_duration: per_visit("sum_visit_length"),
+    }
+}
+
+impl MatomoSource {
+    async fn sites(&self, config: &MatomoConfig) -> Result<Vec<Site>, AppError> {
+        matomo_service::list_sites(&config.base_url, &config.token_auth)
+            .await
+            .map_err(|e| {
+                warn!(error = %e, "Failed to list Matomo sites");
+                AppError::bad_request(e)
+            })
+    }
+}
+
+#[async_trait]
+impl Source for MatomoSource {
+    fn connector_type(&self) -> ConnectorType {
+        ConnectorType::Matomo
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Matomo"
+    }
+
+    fn auth_kind(&self) -> AuthKind {
+        AuthKind::Config
+    }
+
+    fn config_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "required": ["type", "base_url", "token_auth"],
+            "properties": {
+                "type": { "const": "Matomo" },
+                "base_url": { "type": "string", "pattern": "^https?://" },
+                "token_auth": { "type": "string", "minLength": 1 },
+                "sites": { "type": "array", "items": { "type": "string", "pattern": "^[0-9]+$" } }
+            }
+        })
+    }
+
+    fn prepare_config(&self, mut config: serde_json::Value) -> Result<serde_json::Value, AppError> {
+        encrypt_secret(&mut config, "/token_auth")?;
+        Ok(config)
+    }
+
+    fn storage_schema(&self) -> Vec<TableSchema> {
+        vec![
+            web_analytics::totals_schema(TOTALS_TABLE),
+            web_analytics::breakdowns_schema(BREAKDOWNS_TABLE),
+        ]
+    }
+
+    async fn discover(&self, _state: &AppState, connector: &Connector) -> Result<Vec<Stream>, AppError> {
+        let config = matomo_config(connector)?;
+
+        Ok(self
+            .sites(&config)
+            .await?
+            .into_iter()
+            .map(|site| Stream {
+                selected: config.sites.contains(&site.idsite),
+                id: site.idsite,
+                name: site.name,
+            })
+            .collect())
+    }
+
+    async fn select_streams(
+        &self,
+        state: &AppState,
+        connector: &Connector,
+        stream_ids: Vec<String>,
+    ) -> Result<Vec<Stream>, AppError> {
+        let config = matomo_config(connector)?;
+        let available = self.sites(&config).await?;
+
+        let mut selected: Vec<Site> = Vec::with_capacity(stream_ids.len());
+        for id in stream_ids {
+            let Some(site) = available.iter().find(|s| s.idsite == id) else {
+                warn!(site_id = %id, "Site not accessible");
+                return Err(AppError::bad_request(format!("Site {} is not accessible with this token", id)));
+            };
+            if !selected.iter().any(|s| s.idsite == id) {
+                selected.push(site.clone());
+            }
+        }
+
+        let mut details = parse_config(connector)?;
+        if let ConnectorDetails::Matomo { sites, .. } = &mut details {
+            *sites = selected.iter().map(|s| s.idsite.clone()).collect();
+        }
+        save_config(state, connector, &details).await?;
+
+        info!(count = selected.len(), "Sites selected successfully");
+        Ok(selected
+            .into_iter()
+            .map(|site| Stream {
+                id: site.idsite,
+                name: site.name,
+                selected: true,
+            })
+            .collect())
+    }
+
+    async fn sync(
+        &self,
+        _state: &AppState,
+        connector: &Connector,
+        request: SyncRequest,
+    ) -> Result<SyncResult, AppError> {
+        let config = matomo_config(connector)?;
+
+        if config.sites.is_empty() {
+            warn!("No site selected");
+            return Err(AppError::bad_request("No Matomo site selected. Please select a site first."));
+        }
+
+        let sites = match request.streams {
+            Some(sites) => {
+                if let Some(unknown) = sites.iter().find(|s| !config.sites.contains(s)) {
+                    warn!(site_id = %unknown, "Site not selected on connector");
+                    return Err(AppError::bad_request(format!("Site {} is not selected on this connector", unknown)));
+                }
+                sites
+            }
+            None => config.sites.clone(),
+        };
+
+        let (project_id, connector_id) = (connector.project_id, connector.id);
+        let time_zones: HashMap<String, Option<String>> = self
+            .sites(&config)
+            .await?
+            .into_iter()
+            .map(|site| (site.idsite, site.timezone))
+            .collect();
+        let mut results = Vec::with_capacity(sites.len());
+
+        for site_id in sites {
+            // Days are cut in the site's time zone
+            let today = matomo_service::today(time_zones.get(&site_id).and_then(|tz| tz.as_deref()));
+            let start_date = match request.start_date {
+                Some(start_date) => start_date,
+                None => {
+                    let site = site_id.clone();
+                    web_analytics::with_store(project_id, connector_id, STORE_FILE, move |conn| {
+                        Ok(web_analytics::start_date(conn, TOTALS_TABLE, &site, today))
+                    })
+                    .await?
+                }
+            };
+
+            debug!(site_id = %site_id, start_date = %start_date, "Pulling Matomo site");
+
+            // Each report covers the whole range, one entry per day
+            let totals = matomo_service::daily(&config.base_url, &config.token_auth, &site_id, "API.get", start_date, today)
+                .await
+                .map_err(AppError::internal)?
+                .into_iter()
+                .map(|(date, day)| web_analytics::totals_row(&site_id, date, &totals_metrics(&day)))
+                .collect();
+
+            let mut breakdowns = Vec::new();
+            for (dimension, method) in REPORTS {
+                let days = matomo_service::daily(&config.base_url, &config.token_auth, &site_id, method, start_date, today)
+                    .await
+                    .map_err(AppError::internal)?;
+
+                for (date, rows) in days {
+                    for row in rows.as_array().into_iter().flatten() {
+                        let label = row.get("label").and_then(Value::as_str).unwrap_or_default();
+                        // Country labels are localized, their ISO code is not
+                        let breakdown = match dimension {
+                            "country" => {
+                                let code = row.get("code").and_then(Value::as_str).unwrap_or_default();
+                                Breakdown::country(code, row_metrics(row))
+                            }
+                            "device_category" => {
+                                Breakdown::new(dimension, web_analytics::device_category(label), row_metrics(row))
+                            }
+                            _ => Breakdown::new(dimension, label.to_string(), row_metrics(row)),
+                        };
+                        breakdowns.push(web_analytics::breakdown_row(&site_id, date, &breakdown));
+                    }
+                }
+            }
+
+            let stored = web_analytics::with_store(project_id, connector_id, STORE_FILE, move |conn| {
+                web_analytics::store(conn, TOTALS_TABLE, BREAKDOWNS_TABLE, totals, breakdowns)
+            })
+            .await?;
+
+            info!(site_id = %site_id, record_count = stored.record_count, "Site pulled");
+            results.push(StreamSyncResult {
+                stream: site_id,
+                start_date: Some(start_date),
+                currency_code: None,
+                record_count: stored.record_count,
+                inserted_count: stored.inserted_count,
+                updated_count: stored.updated_count,
+            });
+        }
+
+        Ok(SyncResult::from_streams(results))
+    }
+}
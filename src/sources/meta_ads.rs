@@ -0,0 +1,435 @@
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use duckdb::types::Value;
+use serde_json::json;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use super::{AuthKind, Source, Stream, StreamSyncResult, SyncRequest, SyncResult, parse_config, save_config};
+use crate::api::error::AppError;
+use crate::models::connector::{Connector, ConnectorDetails, ConnectorType, MetaAdAccount, OAuthTokens};
+use crate::services::meta_ads_service::{self, InsightsQuery};
+use crate::services::storage_service::{self, StorageResult, TableSchema};
+use crate::services::{ga4_service, oauth_service};
+use crate::AppState;
+
+pub const SCOPES: [&str; 1] = ["ads_read"];
+
+const STORE_FILE: &str = "meta_ads.duckdb";
+
+/// Breakdowns that can be configured; each gets its own column, empty when not requested.
+pub const BREAKDOWNS: [&str; 8] = [
+    "age",
+    "gender",
+    "country",
+    "region",
+    "publisher_platform",
+    "platform_position",
+    "device_platform",
+    "impression_device",
+];
+
+/// An insights level stored in its own table, one row per date, entity and breakdown values.
+struct Level {
+    level: &'static str,
+    table: &'static str,
+    /// Entity fields, the first one being the entity id.
+    fields: &'static [&'static str],
+}
+
+const LEVELS: [Level; 3] = [
+    Level {
+        level: "campaign",
+        table: "meta_ads_campaigns",
+        fields: &["campaign_id", "campaign_name"],
+    },
+    Level {
+        level: "adset",
+        table: "meta_ads_adsets",
+        fields: &["adset_id", "adset_name", "campaign_id"],
+    },
+    Level {
+        level: "ad",
+        table: "meta_ads_ads",
+        fields: &["ad_id", "ad_name", "adset_id", "campaign_id"],
+    },
+];
+
+const METRICS: [&str; 6] = ["impressions", "clicks", "spend", "reach", "actions", "action_values"];
+
+impl Level {
+    fn schema(&self) -> TableSchema {
+        let mut columns: Vec<(&str, &str)> = vec![("account_id", "VARCHAR"), ("date", "DATE")];
+        columns.extend(self.fields.iter().map(|f| (*f, "VARCHAR")));
+        columns.push(("breakdowns", "VARCHAR"));
+        columns.extend(BREAKDOWNS.iter().map(|b| (*b, "VARCHAR")));
+        columns.extend([
+            ("impressions", "BIGINT"),
+            ("clicks", "BIGINT"),
+            ("spend", "DOUBLE"),
+            ("reach", "BIGINT"),
+            ("purchases", "DOUBLE"),
+            ("purchase_value", "DOUBLE"),
+            ("actions", "VARCHAR"),
+            ("currency_code", "VARCHAR"),
+        ]);
+
+        let mut primary_key = vec!["account_id", "date", self.fields[0], "breakdowns"];
+        primary_key.extend(BREAKDOWNS);
+
+        TableSchema::new(self.table, &columns, &primary_key)
+    }
+
+    fn query<'a>(&'a self, breakdowns: &'a [String], since: NaiveDate, until: NaiveDate) -> InsightsQuery<'a> {
+        InsightsQuery {
+            level: self.level,
+            fields: self.fields.iter().chain(METRICS.iter()).copied().collect(),
+            breakdowns,
+            since,
+            until,
+        }
+    }
+
+    /// Maps an insights row to a row in `schema()` column order.
+    fn row(&self, account: &MetaAdAccount, breakdowns: &[String], insight: &serde_json::Value) -> Option<Vec<Value>> {
+        let text = |field: &str| insight.get(field).and_then(|v| v.as_str());
+        let number = |field: &str| text(field).and_then(|v| v.parse::<f64>().ok()).unwrap_or(0.0);
+
+        let date = text("date_start").and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())?;
+
+        let mut row = vec![
+            Value::Text(account.account_id.clone()),
+            storage_service::date_value(date),
+        ];
+        row.extend(self.fields.iter().map(|f| Value::Text(text(f).unwrap_or_default().to_string())));
+        row.push(Value::Text(breakdowns.join(",")));
+        row.extend(
+            BREAKDOWNS
+                .iter()
+                .map(|b| Value::Text(text(b).unwrap_or_default().to_string())),
+        );
+        row.extend([
+            Value::BigInt(number("impressions") as i64),
+            Value::BigInt(number("clicks") as i64),
+            Value::Double(number("spend")),
+            Value::BigInt(number("reach") as i64),
+            Value::Double(action_total(insight.get("actions"), "purchase")),
+            Value::Double(action_total(insight.get("action_values"), "purchase")),
+            insight
+                .get("actions")
+                .map(|a| Value::Text(a.to_string()))
+                .unwrap_or(Value::Null),
+            account
+                .currency_code
+                .clone()
+                .map(Value::Text)
+                .unwrap_or(Value::Null),
+        ]);
+
+        Some(row)
+    }
+}
+
+/// Sums an `actions` style list (`[{"action_type": "...", "value": "1"}]`) for one action type.
+fn action_total(actions: Option<&serde_json::Value>, action_type: &str) -> f64 {
+    actions
+        .and_then(|a| a.as_array())
+        .map(|actions| {
+            actions
+                .iter()
+                .filter(|a| a.get("action_type").and_then(|t| t.as_str()) == Some(action_type))
+                .filter_map(|a| a.get("value").and_then(|v| v.as_str()).and_then(|v| v.parse::<f64>().ok()))
+                .sum()
+        })
+        .unwrap_or(0.0)
+}
+
+pub struct MetaAdsSource {
+    base_url: String,
+}
+
+impl MetaAdsSource {
+    /// Uses `META_API_BASE_URL` when set, e.g. to point at a mock server.
+    pub fn from_env() -> Self {
+        Self {
+            base_url: std::env::var("META_API_BASE_URL")
+                .unwrap_or_else(|_| meta_ads_service::DEFAULT_API_BASE_URL.to_string()),
+        }
+    }
+}
+
+struct MetaConfig {
+    access_token: String,
+    ad_accounts: Vec<MetaAdAccount>,
+    breakdowns: Vec<String>,
+}
+
+/// Meta tokens cannot be refreshed, an expired one means going through OAuth again.
+fn meta_config(connector: &Connector) -> Result<MetaConfig, AppError> {
+    match parse_config(connector)? {
+        ConnectorDetails::MetaAds {
+            tokens,
+            ad_accounts,
+            breakdowns,
+        } => {
+            if oauth_service::is_token_expired(tokens.expires_at) {
+                warn!("Meta token expired");
+                return Err(AppError::unauthorized("Meta token expired. Please re-authenticate."));
+            }
+
+            Ok(MetaConfig {
+                access_token: tokens.access_token,
+                ad_accounts,
+                breakdowns,
+            })
+        }
+        _ => Err(AppError::bad_request("Connector is not a Meta Ads connector")),
+    }
+}
+
+#[async_trait]
+impl Source for MetaAdsSource {
+    fn connector_type(&self) -> ConnectorType {
+        ConnectorType::MetaAds
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Meta Ads"
+    }
+
+    fn auth_kind(&self) -> AuthKind {
+        AuthKind::MetaOAuth {
+            scopes: SCOPES.to_vec(),
+        }
+    }
+
+    fn oauth_config(&self, tokens: OAuthTokens) -> Option<ConnectorDetails> {
+        Some(ConnectorDetails::MetaAds {
+            tokens,
+            ad_accounts: Vec::new(),
+            breakdowns: Vec::new(),
+        })
+    }
+
+    fn config_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "required": ["type", "access_token", "token_type"],
+            "properties": {
+                "type": { "const": "MetaAds" },
+                "access_token": { "type": "string" },
+                "refresh_token": { "type": ["string", "null"] },
+                "expires_at": { "type": ["string", "null"], "format": "date-time" },
+                "token_type": { "type": "string" },
+                "ad_accounts": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["account_id", "account_name"],
+                        "properties": {
+                            "account_id": { "type": "string", "pattern": "^act_[0-9]+$" },
+                            "account_name": { "type": "string" },
+                            "time_zone": { "type": ["string", "null"] },
+                            "currency_code": { "type": ["string", "null"] }
+                        }
+                    }
+                },
+                "breakdowns": {
+                    "type": "array",
+                    "items": { "enum": BREAKDOWNS }
+                }
+            }
+        })
+    }
+
+    fn storage_schema(&self) -> Vec<TableSchema> {
+        LEVELS.iter().map(Level::schema).collect()
+    }
+
+    async fn discover(&self, _state: &AppState, connector: &Connector) -> Result<Vec<Stream>, AppError> {
+        let config = meta_config(connector)?;
+
+        let streams = meta_ads_service::list_ad_accounts(&self.base_url, &config.access_token)
+            .await
+            .map_err(AppError::internal)?
+            .into_iter()
+            .map(|account| Stream {
+                selected: config.ad_accounts.iter().any(|a| a.account_id == account.id),
+                id: account.id,
+                name: account.name,
+            })
+            .collect();
+
+        Ok(streams)
+    }
+
+    async fn select_streams(
+        &self,
+        state: &AppState,
+        connector: &Connector,
+        stream_ids: Vec<String>,
+    ) -> Result<Vec<Stream>, AppError> {
+        let config = meta_config(connector)?;
+
+        let accessible = meta_ads_service::list_ad_accounts(&self.base_url, &config.access_token)
+            .await
+            .map_err(AppError::internal)?;
+
+        let mut ad_accounts: Vec<MetaAdAccount> = Vec::with_capacity(stream_ids.len());
+        for raw in stream_ids {
+            // Accept the bare numeric id as shown in Ads Manager
+            let account_id = if raw.starts_with("act_") { raw } else { format!("act_{}", raw) };
+
+            let account = accessible.iter().find(|a| a.id == account_id).ok_or_else(|| {
+                warn!(account_id = %account_id, "Ad account not accessible");
+                AppError::bad_request(format!(
+                    "Ad account {} is not accessible with this Meta account",
+                    account_id
+                ))
+            })?;
+
+            if !ad_accounts.iter().any(|a| a.account_id == account_id) {
+                ad_accounts.push(MetaAdAccount {
+                    account_id,
+                    account_name: account.name.clone(),
+                    time_zone: account.timezone_name.clone(),
+                    currency_code: account.currency.clone(),
+                });
+            }
+        }
+
+        let mut config = parse_config(connector)?;
+        if let ConnectorDetails::MetaAds { ad_accounts: selected, .. } = &mut config {
+            selected.clone_from(&ad_accounts);
+        }
+        save_config(state, connector, &config).await?;
+
+        info!(count = ad_accounts.len(), "Ad accounts selected successfully");
+        Ok(ad_accounts
+            .into_iter()
+            .map(|a| Stream {
+                id: a.account_id,
+                name: a.account_name,
+                selected: true,
+            })
+            .collect())
+    }
+
+    async fn sync(
+        &self,
+        _state: &AppState,
+        connector: &Connector,
+        request: SyncRequest,
+    ) -> Result<SyncResult, AppError> {
+        let project_id = connector.project_id;
+        let connector_id = connector.id;
+
+        let config = meta_config(connector)?;
+
+        if config.ad_accounts.is_empty() {
+            warn!("No ad account selected");
+            return Err(AppError::bad_request("No Meta ad account selected. Please select an ad account first."));
+        }
+
+        let accounts: Vec<&MetaAdAccount> = match &request.streams {
+            Some(account_ids) => {
+                if let Some(unknown) = account_ids
+                    .iter()
+                    .find(|id| !config.ad_accounts.iter().any(|a| &a.account_id == *id))
+                {
+                    warn!(account_id = %unknown, "Ad account not selected on connector");
+                    return Err(AppError::bad_request(format!(
+                        "Ad account {} is not selected on this connector",
+                        unknown
+                    )));
+                }
+                config
+                    .ad_accounts
+                    .iter()
+                    .filter(|a| account_ids.contains(&a.account_id))
+                    .collect()
+            }
+            None => config.ad_accounts.iter().collect(),
+        };
+
+        let mut results = Vec::with_capacity(accounts.len());
+
+        for account in accounts {
+            // Insights are dated in the ad account time zone
+            let today = ga4_service::today_in(account.time_zone.as_deref());
+
+            let start_date = request.start_date.unwrap_or_else(|| {
+                let max_date = storage_service::max_date(
+                    project_id,
+                    connector_id,
+                    STORE_FILE,
+                    LEVELS[0].table,
+                    "date",
+                    &[("account_id", account.account_id.as_str())],
+                );
+                storage_service::incremental_start_date(max_date, today)
+            });
+
+            debug!(account_id = %account.account_id, start_date = %start_date, "Pulling data for ad account");
+
+            let mut result = StreamSyncResult {
+                stream: account.account_id.clone(),
+                start_date: Some(start_date),
+                currency_code: account.currency_code.clone(),
+                record_count: 0,
+                inserted_count: 0,
+                updated_count: 0,
+            };
+
+            let mut tables = Vec::with_capacity(LEVELS.len());
+            for level in &LEVELS {
+                let insights = meta_ads_service::pull_insights(
+                    &self.base_url,
+                    &config.access_token,
+                    &account.account_id,
+                    &level.query(&config.breakdowns, start_date, today),
+                )
+                .await
+                .map_err(AppError::internal)?;
+
+                let rows = insights
+                    .iter()
+                    .filter_map(|i| level.row(account, &config.breakdowns, i))
+                    .collect();
+                tables.push((level.schema(), rows));
+            }
+
+            for stored in store(project_id, connector_id, tables).await? {
+                result.record_count += stored.record_count;
+                result.inserted_count += stored.inserted_count;
+                result.updated_count += stored.updated_count;
+            }
+
+            info!(account_id = %account.account_id, record_count = result.record_count, "Ad account pulled");
+            results.push(result);
+        }
+
+        Ok(SyncResult::from_streams(results))
+    }
+}
+
+/// Upserts the rows of each table in one write, on the blocking pool.
+async fn store(
+    project_id: Uuid,
+    connector_id: Uuid,
+    tables: Vec<(TableSchema, Vec<Vec<Value>>)>,
+) -> Result<Vec<StorageResult>, AppError> {
+    tokio::task::spawn_blocking(move || {
+        let conn = storage_service::open_store(project_id, connector_id, STORE_FILE)?;
+        tables
+            .into_iter()
+            .map(|(schema, rows)| storage_service::upsert_rows(&conn, &schema, rows))
+            .collect::<Result<Vec<_>, String>>()
+    })
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Meta Ads store task failed");
+        AppError::internal("Meta Ads store task failed")
+    })?
+    .map_err(AppError::internal)
+}
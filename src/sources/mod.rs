@@ -0,0 +1,248 @@
+pub mod countries;
+pub mod database;
+pub mod file_upload;
+pub mod ga4;
+pub mod ga4_export;
+pub mod google;
+pub mod google_ads;
+pub mod matomo;
+pub mod meta_ads;
+pub mod plausible;
+pub mod rest_api;
+pub mod schema;
+pub mod search_console;
+pub mod stripe;
+pub mod web_analytics;
+
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::error;
+
+use crate::api::error::{AppError, FieldError};
+use crate::models::connector::{Connector, ConnectorDetails, ConnectorType, OAuthTokens};
+use crate::services::crypto_service;
+use crate::services::storage_service::TableSchema;
+use crate::AppState;
+
+/// How a source obtains its credentials.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AuthKind {
+    /// Google OAuth2 authorization code flow with the given scopes.
+    GoogleOAuth { scopes: Vec<&'static str> },
+    /// Meta (Facebook Login) OAuth2 flow with the given scopes.
+    MetaOAuth { scopes: Vec<&'static str> },
+    /// Secrets supplied directly in the connector config.
+    Config,
+}
+
+/// Something a source can sync independently, e.g. a GA4 property.
+#[derive(Debug, Clone, Serialize)]
+pub struct Stream {
+    pub id: String,
+    pub name: String,
+    pub selected: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SyncRequest {
+    /// Overrides the incremental start date.
+    #[serde(default)]
+    pub start_date: Option<NaiveDate>,
+    /// Subset of the selected streams to sync; all of them when omitted.
+    #[serde(default)]
+    pub streams: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StreamSyncResult {
+    pub stream: String,
+    pub start_date: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currency_code: Option<String>,
+    pub record_count: usize,
+    pub inserted_count: usize,
+    pub updated_count: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncResult {
+    pub record_count: usize,
+    pub inserted_count: usize,
+    pub updated_count: usize,
+    pub streams: Vec<StreamSyncResult>,
+}
+
+impl SyncResult {
+    pub fn from_streams(streams: Vec<StreamSyncResult>) -> Self {
+        Self {
+            record_count: streams.iter().map(|s| s.record_count).sum(),
+            inserted_count: streams.iter().map(|s| s.inserted_count).sum(),
+            updated_count: streams.iter().map(|s| s.updated_count).sum(),
+            streams,
+        }
+    }
+}
+
+/// A data source that can be plugged in as a connector type.
+#[async_trait]
+pub trait Source: Send + Sync {
+    fn connector_type(&self) -> ConnectorType;
+
+    /// Human readable name, e.g. for default connector names.
+    fn display_name(&self) -> &'static str;
+
+    fn auth_kind(&self) -> AuthKind;
+
+    /// Initial config of a connector created through the OAuth flow.
+    fn oauth_config(&self, _tokens: OAuthTokens) -> Option<ConnectorDetails> {
+        None
+    }
+
+    /// JSON Schema of the connector `config`.
+    fn config_schema(&self) -> serde_json::Value;
+
+    /// Checks a config against `config_schema` and the typed `ConnectorDetails` variant.
+    fn validate_config(&self, config: &serde_json::Value) -> Vec<FieldError> {
+        let mut errors = schema::validate(&self.config_schema(), config);
+
+        if errors.is_empty() {
+            match serde_json::from_value::<ConnectorDetails>(config.clone()) {
+                Ok(details) if details.connector_type() == self.connector_type() => {}
+                Ok(_) => errors.push(FieldError::new("type", "does not match connector_type")),
+                Err(e) => errors.push(FieldError::new("$", e.to_string())),
+            }
+        }
+
+        errors
+    }
+
+    /// Runs checks beyond `config_schema` and adjusts a validated config before it is
+    /// stored, e.g. to encrypt secrets.
+    fn prepare_config(&self, config: serde_json::Value) -> Result<serde_json::Value, AppError> {
+        Ok(config)
+    }
+
+    /// DuckDB tables written by `sync`.
+    fn storage_schema(&self) -> Vec<TableSchema>;
+
+    /// Lists the streams available to the connector, flagging the selected ones.
+    async fn discover(&self, state: &AppState, connector: &Connector) -> Result<Vec<Stream>, AppError>;
+
+    /// Replaces the selected streams, returning them once validated.
+    async fn select_streams(
+        &self,
+        _state: &AppState,
+        _connector: &Connector,
+        _stream_ids: Vec<String>,
+    ) -> Result<Vec<Stream>, AppError> {
+        Err(AppError::bad_request(format!(
+            "{} connectors do not support stream selection",
+            self.display_name()
+        )))
+    }
+
+    /// Incrementally pulls the selected streams into the connector's DuckDB store.
+    async fn sync(
+        &self,
+        state: &AppState,
+        connector: &Connector,
+        request: SyncRequest,
+    ) -> Result<SyncResult, AppError>;
+}
+
+/// Sources keyed by the connector type they handle.
+#[derive(Default)]
+pub struct SourceRegistry {
+    sources: HashMap<ConnectorType, Arc<dyn Source>>,
+}
+
+impl SourceRegistry {
+    pub fn new() -> Self {
+        let mut registry = Self::default();
+        registry.register(Arc::new(ga4::Ga4Source));
+        registry.register(Arc::new(search_console::SearchConsoleSource::from_env()));
+        registry.register(Arc::new(google_ads::GoogleAdsSource::from_env()));
+        registry.register(Arc::new(meta_ads::MetaAdsSource::from_env()));
+        registry.register(Arc::new(stripe::StripeSource::from_env()));
+        registry.register(Arc::new(file_upload::FileUploadSource));
+        registry.register(Arc::new(database::DatabaseSource));
+        registry.register(Arc::new(rest_api::RestApiSource));
+        registry.register(Arc::new(plausible::PlausibleSource));
+        registry.register(Arc::new(matomo::MatomoSource));
+        registry.register(Arc::new(ga4_export::Ga4ExportSource::from_env()));
+        registry
+    }
+
+    pub fn register(&mut self, source: Arc<dyn Source>) {
+        self.sources.insert(source.connector_type(), source);
+    }
+
+    pub fn get(&self, connector_type: &ConnectorType) -> Option<Arc<dyn Source>> {
+        self.sources.get(connector_type).cloned()
+    }
+
+    pub fn all(&self) -> Vec<Arc<dyn Source>> {
+        let mut sources: Vec<_> = self.sources.values().cloned().collect();
+        sources.sort_by_key(|s| s.connector_type().to_string());
+        sources
+    }
+}
+
+pub fn parse_config(connector: &Connector) -> Result<ConnectorDetails, AppError> {
+    serde_json::from_value(connector.config.clone())
+        .map_err(|_| AppError::internal("Invalid connector config"))
+}
+
+/// Encrypts the config secret at the JSON `pointer`, e.g. `/api_key`, unless the client
+/// sent back an already encrypted one.
+pub fn encrypt_secret(config: &mut serde_json::Value, pointer: &str) -> Result<(), AppError> {
+    if let Some(secret) = config.pointer_mut(pointer)
+        && let Some(plaintext) = secret.as_str().filter(|s| !crypto_service::is_encrypted(s))
+    {
+        let encrypted = crypto_service::encrypt(plaintext).map_err(|e| {
+            error!(error = %e, pointer, "Failed to encrypt connector secret");
+            AppError::internal(e)
+        })?;
+        *secret = serde_json::Value::String(encrypted);
+    }
+
+    Ok(())
+}
+
+/// Decrypts a secret stored by `encrypt_secret`. Configs saved before their secrets were
+/// encrypted hold plaintext, which is used as is until the connector is next updated.
+pub fn decrypt_secret(value: &str) -> Result<String, AppError> {
+    if !crypto_service::is_encrypted(value) {
+        return Ok(value.to_string());
+    }
+
+    crypto_service::decrypt(value).map_err(|e| {
+        error!(error = %e, "Failed to decrypt connector secret");
+        AppError::internal("Failed to decrypt connector secret")
+    })
+}
+
+/// Persists a new config on the connector, keeping everything else as is.
+pub async fn save_config(
+    state: &AppState,
+    connector: &Connector,
+    config: &ConnectorDetails,
+) -> Result<Connector, AppError> {
+    let updated_connector = Connector {
+        id: connector.id,
+        project_id: connector.project_id,
+        name: connector.name.clone(),
+        connector_type: connector.connector_type.clone(),
+        config: serde_json::to_value(config).unwrap(),
+    };
+
+    state
+        .connector_repo
+        .update(&updated_connector)
+        .await
+        .map_err(AppError::from)
+}
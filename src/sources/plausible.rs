@@ -0,0 +1,205 @@
+use async_trait::async_trait;
+use serde_json::json;
+use tracing::{debug, info, warn};
+
+use super::web_analytics::{self, Breakdown, Metrics};
+use super::{
+    AuthKind, Source, Stream, StreamSyncResult, SyncRequest, SyncResult, decrypt_secret, encrypt_secret, parse_config,
+};
+use crate::api::error::AppError;
+use crate::models::connector::{Connector, ConnectorDetails, ConnectorType};
+use crate::services::plausible_service::{self, PlausibleMetrics};
+use crate::services::storage_service::TableSchema;
+use crate::services::ga4_service;
+use crate::AppState;
+
+const STORE_FILE: &str = "plausible.duckdb";
+const TOTALS_TABLE: &str = "plausible_totals";
+const BREAKDOWNS_TABLE: &str = "plausible_breakdowns";
+
+/// Breakdown dimensions and the Plausible properties they come from.
+const PROPERTIES: [(&str, &str); 4] = [
+    ("country", "visit:country"),
+    ("device_category", "visit:device"),
+    ("browser", "visit:browser"),
+    ("operating_system", "visit:os"),
+];
+
+pub struct PlausibleSource;
+
+struct PlausibleConfig {
+    base_url: String,
+    api_key: String,
+    sites: Vec<String>,
+}
+
+fn plausible_config(connector: &Connector) -> Result<PlausibleConfig, AppError> {
+    match parse_config(connector)? {
+        ConnectorDetails::Plausible {
+            base_url,
+            api_key,
+            sites,
+        } => Ok(PlausibleConfig {
+            base_url,
+            api_key: decrypt_secret(&api_key)?,
+            sites,
+        }),
+        _ => Err(AppError::bad_request("Connector is not a Plausible connector")),
+    }
+}
+
+fn metrics(metrics: &PlausibleMetrics) -> Metrics {
+    Metrics {
+        active_users: metrics.visitors.unwrap_or_default() as i64,
+        sessions: metrics.visits.unwrap_or_default() as i64,
+        screen_page_views: metrics.pageviews.unwrap_or_default() as i64,
+        bounce_rate: metrics.bounce_rate.unwrap_or_default() / 100.0,
+        average_session_duration: metrics.visit_duration.unwrap_or_default(),
+    }
+}
+
+#[async_trait]
+impl Source for PlausibleSource {
+    fn connector_type(&self) -> ConnectorType {
+        ConnectorType::Plausible
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Plausible"
+    }
+
+    fn auth_kind(&self) -> AuthKind {
+        AuthKind::Config
+    }
+
+    fn config_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "required": ["type", "api_key"],
+            "properties": {
+                "type": { "const": "Plausible" },
+                "base_url": { "type": "string", "pattern": "^https?://" },
+                "api_key": { "type": "string", "minLength": 1 },
+                "sites": { "type": "array", "items": { "type": "string", "minLength": 1 } }
+            }
+        })
+    }
+
+    fn prepare_config(&self, mut config: serde_json::Value) -> Result<serde_json::Value, AppError> {
+        encrypt_secret(&mut config, "/api_key")?;
+        Ok(config)
+    }
+
+    fn storage_schema(&self) -> Vec<TableSchema> {
+        vec![
+            web_analytics::totals_schema(TOTALS_TABLE),
+            web_analytics::breakdowns_schema(BREAKDOWNS_TABLE),
+        ]
+    }
+
+    /// The Stats API cannot list sites, the configured ones are the streams.
+    async fn discover(&self, _state: &AppState, connector: &Connector) -> Result<Vec<Stream>, AppError> {
+        let config = plausible_config(connector)?;
+
+        Ok(config
+            .sites
+            .into_iter()
+            .map(|site| Stream {
+                id: site.clone(),
+                name: site,
+                selected: true,
+            })
+            .collect())
+    }
+
+    async fn sync(
+        &self,
+        _state: &AppState,
+        connector: &Connector,
+        request: SyncRequest,
+    ) -> Result<SyncResult, AppError> {
+        let config = plausible_config(connector)?;
+
+        if config.sites.is_empty() {
+            warn!("No site configured");
+            return Err(AppError::bad_request("No Plausible site configured on this connector"));
+        }
+
+        let sites = match request.streams {
+            Some(sites) => {
+                if let Some(unknown) = sites.iter().find(|s| !config.sites.contains(s)) {
+                    warn!(site_id = %unknown, "Site not configured on connector");
+                    return Err(AppError::bad_request(format!("Site {} is not configured on this connector", unknown)));
+                }
+                sites
+            }
+            None => config.sites,
+        };
+
+        let (project_id, connector_id) = (connector.project_id, connector.id);
+        // The Stats API does not expose the site time zone, so today is the UTC date. Sites
+        // ahead of UTC get their latest day on the next pull, which re-reads the lookback days.
+        let today = ga4_service::today_in(None);
+        let mut results = Vec::with_capacity(sites.len());
+
+        for site_id in sites {
+            let start_date = match request.start_date {
+                Some(start_date) => start_date,
+                None => {
+                    let site = site_id.clone();
+                    web_analytics::with_store(project_id, connector_id, STORE_FILE, move |conn| {
+                        Ok(web_analytics::start_date(conn, TOTALS_TABLE, &site, today))
+                    })
+                    .await?
+                }
+            };
+
+            debug!(site_id = %site_id, start_date = %start_date, "Pulling Plausible site");
+
+            let mut totals = Vec::new();
+            let mut breakdowns = Vec::new();
+
+            for date in start_date.iter_days().take_while(|d| *d <= today) {
+                let aggregate = plausible_service::aggregate(&config.base_url, &config.api_key, &site_id, date)
+                    .await
+                    .map_err(AppError::internal)?;
+                totals.push(web_analytics::totals_row(&site_id, date, &metrics(&aggregate)));
+
+                for (dimension, property) in PROPERTIES {
+                    let rows = plausible_service::breakdown(&config.base_url, &config.api_key, &site_id, date, property)
+                        .await
+                        .map_err(AppError::internal)?;
+
+                    breakdowns.extend(rows.iter().map(|(value, row_metrics)| {
+                        // Countries come as ISO codes
+                        let breakdown = match dimension {
+                            "country" => Breakdown::country(value, metrics(row_metrics)),
+                            "device_category" => {
+                                Breakdown::new(dimension, web_analytics::device_category(value), metrics(row_metrics))
+                            }
+                            _ => Breakdown::new(dimension, value.clone(), metrics(row_metrics)),
+                        };
+                        web_analytics::breakdown_row(&site_id, date, &breakdown)
+                    }));
+                }
+            }
+
+            let stored = web_analytics::with_store(project_id, connector_id, STORE_FILE, move |conn| {
+                web_analytics::store(conn, TOTALS_TABLE, BREAKDOWNS_TABLE, totals, breakdowns)
+            })
+            .await?;
+
+            info!(site_id = %site_id, record_count = stored.record_count, "Site pulled");
+            results.push(StreamSyncResult {
+                stream: site_id,
+                start_date: Some(start_date),
+                currency_code: None,
+                record_count: stored.record_count,
+                inserted_count: stored.inserted_count,
+                updated_count: stored.updated_count,
+            });
+        }
+
+        Ok(SyncResult::from_streams(results))
+    }
+}
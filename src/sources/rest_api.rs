@@ -0,0 +1,576 @@
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use duckdb::Connection;
+use duckdb::types::Value;
+use serde_json::json;
+use std::collections::HashSet;
+use tracing::{debug, error, info, warn};
+
+use super::{
+    AuthKind, Source, Stream, StreamSyncResult, SyncRequest, SyncResult, decrypt_secret, encrypt_secret, parse_config,
+};
+use crate::api::error::{AppError, FieldError};
+use crate::models::connector::{Connector, ConnectorDetails, ConnectorType, RestAuth, RestEndpoint, RestPagination};
+use crate::services::rest_api_service::{self, Api};
+use crate::services::storage_service::{self, StorageResult, TableSchema};
+use crate::AppState;
+
+const STORE_FILE: &str = "rest_api.duckdb";
+const CURSOR_TABLE: &str = "rest_api_cursors";
+/// Secrets of the `auth` variants, encrypted before the config is stored.
+const AUTH_SECRETS: [&str; 4] = ["/auth/token", "/auth/password", "/auth/value", "/auth/client_secret"];
+
+/// Pulls the endpoints declared in the connector config, one table per endpoint.
+pub struct RestApiSource;
+
+fn endpoint_schema(endpoint: &RestEndpoint) -> TableSchema {
+    let columns: Vec<(&str, &str)> = endpoint
+        .fields
+        .iter()
+        .map(|f| (f.name.as_str(), f.data_type.as_str()))
+        .collect();
+    let primary_key: Vec<&str> = endpoint.primary_key.iter().map(String::as_str).collect();
+
+    TableSchema::new(&endpoint.name, &columns, &primary_key)
+}
+
+/// Highest cursor value stored for each endpoint, in the format the API returned it.
+fn cursor_schema() -> TableSchema {
+    TableSchema::new(CURSOR_TABLE, &[("endpoint", "VARCHAR"), ("value", "VARCHAR")], &["endpoint"])
+}
+
+fn stored_cursor(conn: &Connection, endpoint: &str) -> Option<String> {
+    conn.query_row(
+        &format!("SELECT value FROM {} WHERE endpoint = ?", CURSOR_TABLE),
+        [endpoint],
+        |row| row.get(0),
+    )
+    .ok()
+}
+
+fn cursor_string(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(s) if !s.is_empty() => Some(s.clone()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// Numeric cursors compare as numbers, anything else (e.g. ISO timestamps) as text.
+fn is_after(value: &str, other: &str) -> bool {
+    match (value.parse::<f64>(), other.parse::<f64>()) {
+        (Ok(a), Ok(b)) => a > b,
+        _ => value > other,
+    }
+}
+
+/// Cross-field checks the JSON Schema cannot express.
+fn endpoint_errors(index: usize, endpoint: &RestEndpoint) -> Vec<FieldError> {
+    let path = format!("endpoints[{}]", index);
+    let mut errors = Vec::new();
+    let has_field = |name: &str| endpoint.fields.iter().any(|f| f.name == name);
+
+    let mut names = HashSet::new();
+    for field in &endpoint.fields {
+        if !names.insert(field.name.as_str()) {
+            errors.push(FieldError::new(format!("{}.fields", path), format!("duplicate field {}", field.name)));
+        }
+    }
+
+    if endpoint.primary_key.is_empty() {
+        errors.push(FieldError::new(format!("{}.primary_key", path), "must not be empty"));
+    }
+    for key in endpoint.primary_key.iter().filter(|k| !has_field(k)) {
+        errors.push(FieldError::new(format!("{}.primary_key", path), format!("unknown field {}", key)));
+    }
+
+    if let Some(cursor) = endpoint.cursor.as_ref().filter(|c| !has_field(&c.field)) {
+        errors.push(FieldError::new(format!("{}.cursor.field", path), format!("unknown field {}", cursor.field)));
+    }
+
+    let mut json_paths = vec![(format!("{}.records_path", path), endpoint.records_path.as_deref())];
+    if let RestPagination::Cursor { next_cursor_path, .. } = &endpoint.pagination {
+        json_paths.push((format!("{}.pagination.next_cursor_path", path), Some(next_cursor_path.as_str())));
+    }
+    for (i, field) in endpoint.fields.iter().enumerate() {
+        json_paths.push((format!("{}.fields[{}].path", path, i), field.path.as_deref()));
+    }
+    for (field, json_path) in json_paths {
+        if let Some(Err(e)) = json_path.map(rest_api_service::parse_path) {
+            errors.push(FieldError::new(field, e));
+        }
+    }
+
+    errors
+}
+
+/// Query parameter carrying the endpoint's cursor, `start_date` overriding the stored one.
+fn cursor_params(conn: &Connection, endpoint: &RestEndpoint, start_date: Option<NaiveDate>) -> Vec<(String, String)> {
+    let Some(cursor) = &endpoint.cursor else {
+        return Vec::new();
+    };
+
+    start_date
+        .map(|d| d.to_string())
+        .or_else(|| stored_cursor(conn, &endpoint.name))
+        .map(|since| vec![(cursor.param.clone(), since)])
+        .unwrap_or_default()
+}
+
+/// Upserts the records of an endpoint and stores the highest cursor value among them.
+fn store_records(
+    conn: &Connection,
+    endpoint: &RestEndpoint,
+    records: &[serde_json::Value],
+) -> Result<StorageResult, AppError> {
+    let field_paths = endpoint
+        .fields
+        .iter()
+        .map(|f| f.path.as_deref().map(rest_api_service::parse_path).transpose())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(AppError::bad_request)?;
+
+    let cursor_index = endpoint
+        .cursor
+        .as_ref()
+        .and_then(|c| endpoint.fields.iter().position(|f| f.name == c.field));
+
+    let mut latest = cursor_index.and_then(|_| stored_cursor(conn, &endpoint.name));
+    let rows: Vec<Vec<Value>> = records
+        .iter()
+        .map(|record| {
+            let values: Vec<Option<&serde_json::Value>> = endpoint
+                .fields
+                .iter()
+                .zip(&field_paths)
+                .map(|(field, path)| match path {
+                    Some(path) => rest_api_service::select(path, record),
+                    None => record.get(&field.name),
+                })
+                .collect();
+
+            if let Some(value) = cursor_index.and_then(|i| values[i]).and_then(cursor_string)
+                && latest.as_deref().is_none_or(|l| is_after(&value, l))
+            {
+                latest = Some(value);
+            }
+
+            endpoint
+                .fields
+                .iter()
+                .zip(values)
+                .map(|(field, value)| storage_service::json_value(value, &field.data_type))
+                .collect()
+        })
+        .collect();
+
+    let stored = storage_service::upsert_rows(conn, &endpoint_schema(endpoint), rows).map_err(AppError::internal)?;
+
+    if let Some(latest) = latest {
+        storage_service::upsert_rows(
+            conn,
+            &cursor_schema(),
+            vec![vec![Value::Text(endpoint.name.clone()), Value::Text(latest)]],
+        )
+        .map_err(AppError::internal)?;
+    }
+
+    Ok(stored)
+}
+
+/// Runs DuckDB work off the async runtime.
+async fn run_blocking<T: Send + 'static>(
+    work: impl FnOnce() -> Result<T, AppError> + Send + 'static,
+) -> Result<T, AppError> {
+    tokio::task::spawn_blocking(work).await.map_err(|e| {
+        error!(error = %e, "REST API store task failed");
+        AppError::internal("REST API store task failed")
+    })?
+}
+
+/// `auth` with its secret decrypted.
+fn decrypted(auth: RestAuth) -> Result<RestAuth, AppError> {
+    Ok(match auth {
+        RestAuth::None => RestAuth::None,
+        RestAuth::Bearer { token } => RestAuth::Bearer {
+            token: decrypt_secret(&token)?,
+        },
+        RestAuth::Basic { username, password } => RestAuth::Basic {
+            username,
+            password: decrypt_secret(&password)?,
+        },
+        RestAuth::ApiKey { header, value } => RestAuth::ApiKey {
+            header,
+            value: decrypt_secret(&value)?,
+        },
+        RestAuth::OAuthClientCredentials {
+            token_url,
+            client_id,
+            client_secret,
+            scopes,
+        } => RestAuth::OAuthClientCredentials {
+            token_url,
+            client_id,
+            client_secret: decrypt_secret(&client_secret)?,
+            scopes,
+        },
+    })
+}
+
+#[async_trait]
+impl Source for RestApiSource {
+    fn connector_type(&self) -> ConnectorType {
+        ConnectorType::RestApi
+    }
+
+    fn display_name(&self) -> &'static str {
+        "REST API"
+    }
+
+    fn auth_kind(&self) -> AuthKind {
+        AuthKind::Config
+    }
+
+    fn config_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "required": ["type", "base_url", "endpoints"],
+            "properties": {
+                "type": { "const": "RestApi" },
+                "base_url": { "type": "string", "pattern": "^https?://" },
+                "auth": {
+                    "type": "object",
+                    "required": ["type"],
+                    "properties": {
+                        "type": { "enum": ["none", "bearer", "basic", "api_key", "oauth_client_credentials"] },
+                        "token": { "type": "string", "minLength": 1 },
+                        "username": { "type": "string" },
+                        "password": { "type": "string" },
+                        "header": { "type": "string", "minLength": 1 },
+                        "value": { "type": "string", "minLength": 1 },
+                        "token_url": { "type": "string", "pattern": "^https?://" },
+                        "client_id": { "type": "string", "minLength": 1 },
+                        "client_secret": { "type": "string", "minLength": 1 },
+                        "scopes": { "type": "array", "items": { "type": "string" } }
+                    }
+                },
+                "headers": { "type": "object", "additionalProperties": { "type": "string" } },
+                "endpoints": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["name", "path", "fields", "primary_key"],
+                        "properties": {
+                            "name": { "type": "string", "pattern": "^[a-z_][a-z0-9_]*$" },
+                            "path": { "type": "string", "pattern": "^/" },
+                            "params": { "type": "object", "additionalProperties": { "type": "string" } },
+                            "records_path": { "type": ["string", "null"], "pattern": "^\\$" },
+                            "pagination": {
+                                "type": "object",
+                                "required": ["type"],
+                                "properties": {
+                                    "type": { "enum": ["none", "offset", "cursor", "link_header"] },
+                                    "offset_param": { "type": "string", "minLength": 1 },
+                                    "limit_param": { "type": "string", "minLength": 1 },
+                                    "limit": { "type": "integer", "minimum": 1 },
+                                    "cursor_param": { "type": "string", "minLength": 1 },
+                                    "next_cursor_path": { "type": "string", "pattern": "^\\$" }
+                                }
+                            },
+                            "fields": {
+                                "type": "array",
+                                "items": {
+                                    "type": "object",
+                                    "required": ["name"],
+                                    "properties": {
+                                        "name": { "type": "string", "pattern": "^[a-z_][a-z0-9_]*$" },
+                                        "path": { "type": ["string", "null"], "pattern": "^\\$" },
+                                        "data_type": { "enum": ["VARCHAR", "BIGINT", "DOUBLE", "BOOLEAN", "TIMESTAMP", "DATE"] }
+                                    }
+                                }
+                            },
+                            "primary_key": { "type": "array", "items": { "type": "string" } },
+                            "cursor": {
+                                "type": ["object", "null"],
+                                "required": ["field", "param"],
+                                "properties": {
+                                    "field": { "type": "string" },
+                                    "param": { "type": "string", "minLength": 1 }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    fn prepare_config(&self, mut config: serde_json::Value) -> Result<serde_json::Value, AppError> {
+        let ConnectorDetails::RestApi { endpoints, .. } = serde_json::from_value(config.clone())
+            .map_err(|e| AppError::bad_request(e.to_string()))?
+        else {
+            return Err(AppError::bad_request("Config is not a REST API config"));
+        };
+
+        let mut errors: Vec<FieldError> = endpoints
+            .iter()
+            .enumerate()
+            .flat_map(|(i, endpoint)| endpoint_errors(i, endpoint))
+            .collect();
+
+        let mut names = HashSet::new();
+        for endpoint in &endpoints {
+            if !names.insert(endpoint.name.as_str()) || endpoint.name == CURSOR_TABLE {
+                errors.push(FieldError::new("endpoints", format!("duplicate or reserved endpoint name {}", endpoint.name)));
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(AppError::validation("Invalid config for connector type REST_API", errors));
+        }
+
+        for pointer in AUTH_SECRETS {
+            encrypt_secret(&mut config, pointer)?;
+        }
+
+        Ok(config)
+    }
+
+    fn storage_schema(&self) -> Vec<TableSchema> {
+        vec![cursor_schema()]
+    }
+
+    async fn discover(&self, _state: &AppState, connector: &Connector) -> Result<Vec<Stream>, AppError> {
+        match parse_config(connector)? {
+            ConnectorDetails::RestApi { endpoints, .. } => Ok(endpoints
+                .into_iter()
+                .map(|e| Stream {
+                    id: e.name.clone(),
+                    name: e.name,
+                    selected: true,
+                })
+                .collect()),
+            _ => Err(AppError::bad_request("Connector is not a REST API connector")),
+        }
+    }
+
+    async fn sync(
+        &self,
+        _state: &AppState,
+        connector: &Connector,
+        request: SyncRequest,
+    ) -> Result<SyncResult, AppError> {
+        let ConnectorDetails::RestApi {
+            base_url,
+            auth,
+            headers,
+            mut endpoints,
+        } = parse_config(connector)?
+        else {
+            return Err(AppError::bad_request("Connector is not a REST API connector"));
+        };
+        let auth = decrypted(auth)?;
+
+        if let Some(names) = &request.streams {
+            if let Some(unknown) = names.iter().find(|n| !endpoints.iter().any(|e| &e.name == *n)) {
+                warn!(stream = %unknown, "Unknown REST API endpoint");
+                return Err(AppError::bad_request(format!("Unknown endpoint {}", unknown)));
+            }
+            endpoints.retain(|e| names.contains(&e.name));
+        }
+
+        let access_token = match &auth {
+            RestAuth::OAuthClientCredentials {
+                token_url,
+                client_id,
+                client_secret,
+                scopes,
+            } => Some(
+                rest_api_service::client_credentials_token(token_url, client_id, client_secret, scopes)
+                    .await
+                    .map_err(|e| {
+                        warn!(error = %e, "Failed to obtain REST API token");
+                        AppError::unauthorized(e)
+                    })?,
+            ),
+            _ => None,
+        };
+
+        let api = Api {
+            base_url: &base_url,
+            auth: &auth,
+            headers: &headers,
+            access_token,
+        };
+
+        let (project_id, connector_id) = (connector.project_id, connector.id);
+        let open_store =
+            move || storage_service::open_store(project_id, connector_id, STORE_FILE).map_err(AppError::internal);
+
+        let mut results = Vec::with_capacity(endpoints.len());
+
+        for endpoint in &endpoints {
+            let (cursor_endpoint, start_date) = (endpoint.clone(), request.start_date);
+            let params = run_blocking(move || Ok(cursor_params(&open_store()?, &cursor_endpoint, start_date))).await?;
+
+            debug!(endpoint = %endpoint.name, params = ?params, "Pulling REST API endpoint");
+            let records = rest_api_service::fetch_records(&api, endpoint, params)
+                .await
+                .map_err(|e| {
+                    warn!(endpoint = %endpoint.name, error = %e, "REST API pull failed");
+                    AppError::internal(e)
+                })?;
+
+            let store_endpoint = endpoint.clone();
+            let stored = run_blocking(move || store_records(&open_store()?, &store_endpoint, &records)).await?;
+
+            info!(endpoint = %endpoint.name, record_count = stored.record_count, "Endpoint pulled");
+            results.push(StreamSyncResult {
+                stream: endpoint.name.clone(),
+                start_date: request.start_date,
+                currency_code: None,
+                record_count: stored.record_count,
+                inserted_count: stored.inserted_count,
+                updated_count: stored.updated_count,
+            });
+        }
+
+        Ok(SyncResult::from_streams(results))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::test_server::serve;
+    use axum::extract::Query;
+    use axum::routing::get;
+    use axum::{Json, Router};
+    use std::collections::{BTreeMap, HashMap};
+    use std::sync::{Arc, Mutex};
+
+    /// Orders updated since `updated_since`, recording the value of each request.
+    async fn stub(requests: Arc<Mutex<Vec<Option<String>>>>) -> String {
+        let router = Router::new().route(
+            "/orders",
+            get(move |Query(query): Query<HashMap<String, String>>| async move {
+                let since = query.get("updated_since").cloned();
+                requests.lock().unwrap().push(since.clone());
+
+                let orders = [
+                    json!({ "id": 1, "status": "paid", "updated_at": "2026-10-01T10:00:00Z" }),
+                    json!({ "id": 2, "status": "paid", "updated_at": "2026-10-03T10:00:00Z" }),
+                ];
+                Json(json!({
+                    "orders": orders
+                        .into_iter()
+                        .filter(|o| since.as_deref().is_none_or(|s| o["updated_at"].as_str().unwrap() >= s))
+                        .collect::<Vec<_>>()
+                }))
+            }),
+        );
+
+        serve(router).await
+    }
+
+    fn orders() -> RestEndpoint {
+        serde_json::from_value(json!({
+            "name": "orders",
+            "path": "/orders",
+            "records_path": "$.orders[*]",
+            "fields": [
+                { "name": "id", "data_type": "BIGINT" },
+                { "name": "status" },
+                { "name": "updated_at" },
+            ],
+            "primary_key": ["id"],
+            "cursor": { "field": "updated_at", "param": "updated_since" },
+        }))
+        .unwrap()
+    }
+
+    /// Pulls and stores the endpoint the way `sync` does.
+    async fn sync_endpoint(
+        conn: &Connection,
+        api: &Api<'_>,
+        endpoint: &RestEndpoint,
+        start_date: Option<NaiveDate>,
+    ) -> StorageResult {
+        let params = cursor_params(conn, endpoint, start_date);
+        let records = rest_api_service::fetch_records(api, endpoint, params).await.unwrap();
+        store_records(conn, endpoint, &records).ok().unwrap()
+    }
+
+    #[tokio::test]
+    async fn resumes_from_the_stored_cursor() {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let base_url = stub(requests.clone()).await;
+        let headers = BTreeMap::new();
+        let api = Api {
+            base_url: &base_url,
+            auth: &RestAuth::None,
+            headers: &headers,
+            access_token: None,
+        };
+        let conn = Connection::open_in_memory().unwrap();
+        let endpoint = orders();
+
+        let first = sync_endpoint(&conn, &api, &endpoint, None).await;
+        assert_eq!((first.record_count, first.inserted_count), (2, 2));
+        assert_eq!(stored_cursor(&conn, "orders").as_deref(), Some("2026-10-03T10:00:00Z"));
+
+        let second = sync_endpoint(&conn, &api, &endpoint, None).await;
+        assert_eq!((second.record_count, second.updated_count), (1, 1));
+
+        let start_date = "2026-09-30".parse().ok();
+        let third = sync_endpoint(&conn, &api, &endpoint, start_date).await;
+        assert_eq!(third.record_count, 2);
+        // An older start date never moves the stored cursor back
+        assert_eq!(stored_cursor(&conn, "orders").as_deref(), Some("2026-10-03T10:00:00Z"));
+
+        assert_eq!(
+            *requests.lock().unwrap(),
+            [None, Some("2026-10-03T10:00:00Z".to_string()), Some("2026-09-30".to_string())]
+        );
+    }
+
+    #[test]
+    fn stores_fields_named_after_keywords() {
+        let conn = Connection::open_in_memory().unwrap();
+        let endpoint: RestEndpoint = serde_json::from_value(json!({
+            "name": "order",
+            "path": "/order",
+            "fields": [
+                { "name": "select", "data_type": "BIGINT" },
+                { "name": "group" },
+                { "name": "from" },
+                { "name": "limit", "data_type": "BIGINT" },
+            ],
+            "primary_key": ["select"],
+            "cursor": { "field": "from", "param": "since" },
+        }))
+        .unwrap();
+        let records = [
+            json!({ "select": 1, "group": "a", "from": "2026-10-01", "limit": 10 }),
+            json!({ "select": 2, "group": "b", "from": "2026-10-02", "limit": 20 }),
+        ];
+
+        let first = store_records(&conn, &endpoint, &records).ok().unwrap();
+        assert_eq!(first.inserted_count, 2);
+        let second = store_records(&conn, &endpoint, &records[1..]).ok().unwrap();
+        assert_eq!(second.updated_count, 1);
+
+        assert_eq!(cursor_params(&conn, &endpoint, None), [("since".to_string(), "2026-10-02".to_string())]);
+        let total: i64 = conn
+            .query_row(r#"SELECT SUM("limit") FROM "order""#, [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(total, 30);
+    }
+
+    #[test]
+    fn compares_numeric_cursors_as_numbers() {
+        assert!(is_after("10", "9"));
+        assert!(!is_after("9", "10"));
+        assert!(is_after("2026-10-10", "2026-10-09"));
+    }
+}
@@ -0,0 +1,231 @@
+use regex::Regex;
+use serde_json::Value;
+
+use crate::api::error::FieldError;
+
+/// Validates a value against the subset of JSON Schema used by source config schemas:
+/// `type`, `const`, `enum`, `required`, `properties`, `additionalProperties`, `items`,
+/// `minLength`, `minimum` and `pattern`.
+pub fn validate(schema: &Value, value: &Value) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    validate_at(schema, value, "", &mut errors);
+    errors
+}
+
+fn validate_at(schema: &Value, value: &Value, path: &str, errors: &mut Vec<FieldError>) {
+    let field = if path.is_empty() { "$" } else { path };
+
+    if let Some(expected) = schema.get("type") {
+        let allowed: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+
+        if !allowed.is_empty() && !allowed.iter().any(|t| matches_type(t, value)) {
+            errors.push(FieldError::new(field, format!("expected {}", allowed.join(" or "))));
+            return;
+        }
+    }
+
+    if let Some(expected) = schema.get("const")
+        && value != expected
+    {
+        errors.push(FieldError::new(field, format!("must be {}", expected)));
+    }
+
+    if let Some(Value::Array(options)) = schema.get("enum")
+        && !options.contains(value)
+    {
+        let options: Vec<String> = options.iter().map(|o| o.to_string()).collect();
+        errors.push(FieldError::new(field, format!("must be one of {}", options.join(", "))));
+    }
+
+    if let Value::String(s) = value {
+        if let Some(min) = schema.get("minLength").and_then(Value::as_u64)
+            && (s.chars().count() as u64) < min
+        {
+            errors.push(FieldError::new(field, format!("must be at least {} characters", min)));
+        }
+
+        if let Some(pattern) = schema.get("pattern").and_then(Value::as_str)
+            && let Ok(re) = Regex::new(pattern)
+            && !re.is_match(s)
+        {
+            errors.push(FieldError::new(field, format!("must match {}", pattern)));
+        }
+    }
+
+    if let Some(n) = value.as_f64()
+        && let Some(min) = schema.get("minimum").and_then(Value::as_f64)
+        && n < min
+    {
+        errors.push(FieldError::new(field, format!("must be at least {}", min)));
+    }
+
+    if let Value::Object(map) = value {
+        if let Some(Value::Array(required)) = schema.get("required") {
+            for name in required.iter().filter_map(Value::as_str) {
+                if !map.contains_key(name) {
+                    errors.push(FieldError::new(join(path, name), "is required"));
+                }
+            }
+        }
+
+        if let Some(Value::Object(properties)) = schema.get("properties") {
+            for (name, property_schema) in properties {
+                if let Some(property_value) = map.get(name) {
+                    validate_at(property_schema, property_value, &join(path, name), errors);
+                }
+            }
+        }
+
+        // Either a schema for the other properties or `false` to reject them
+        if let Some(additional_schema) = schema.get("additionalProperties").filter(|s| s.is_object() || s == &false) {
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (name, property_value) in map {
+                if properties.is_some_and(|p| p.contains_key(name)) {
+                    continue;
+                }
+
+                if additional_schema.is_object() {
+                    validate_at(additional_schema, property_value, &join(path, name), errors);
+                } else {
+                    errors.push(FieldError::new(join(path, name), "is not allowed"));
+                }
+            }
+        }
+    }
+
+    if let (Value::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+        for (i, item) in items.iter().enumerate() {
+            validate_at(item_schema, item, &format!("{}[{}]", path, i), errors);
+        }
+    }
+}
+
+fn matches_type(expected: &str, value: &Value) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn join(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// `field: message` of each error, in order.
+    fn errors(schema: &Value, value: Value) -> Vec<String> {
+        validate(schema, &value)
+            .into_iter()
+            .map(|e| format!("{}: {}", e.field, e.message))
+            .collect()
+    }
+
+    fn config_schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["kind", "url"],
+            "properties": {
+                "kind": { "const": "REST_API" },
+                "url": { "type": "string", "pattern": "^https?://", "minLength": 10 },
+                "method": { "enum": ["GET", "POST"] },
+                "page_size": { "type": "integer", "minimum": 1 },
+                "tables": { "type": "array", "items": { "type": "string", "minLength": 1 } },
+                "headers": { "type": "object", "additionalProperties": { "type": "string" } },
+                "since": { "type": ["string", "null"] },
+            },
+        })
+    }
+
+    #[test]
+    fn accepts_a_valid_config() {
+        let config = json!({
+            "kind": "REST_API",
+            "url": "https://api.example.com",
+            "method": "GET",
+            "page_size": 1,
+            "tables": ["orders"],
+            "headers": { "Accept": "application/json" },
+            "since": null,
+            "extra": true,
+        });
+
+        assert!(errors(&config_schema(), config).is_empty());
+    }
+
+    #[test]
+    fn reports_each_invalid_field_by_path() {
+        let config = json!({
+            "kind": "DATABASE",
+            "url": "ftp://x",
+            "method": "PUT",
+            "page_size": 0,
+            "tables": ["orders", ""],
+            "headers": { "Accept": 1 },
+            "since": 3,
+        });
+
+        // Properties are checked in key order
+        assert_eq!(
+            errors(&config_schema(), config),
+            [
+                "headers.Accept: expected string".to_string(),
+                r#"kind: must be "REST_API""#.to_string(),
+                r#"method: must be one of "GET", "POST""#.to_string(),
+                "page_size: must be at least 1".to_string(),
+                "since: expected string or null".to_string(),
+                "tables[1]: must be at least 1 characters".to_string(),
+                "url: must be at least 10 characters".to_string(),
+                "url: must match ^https?://".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_missing_required_fields() {
+        assert_eq!(errors(&config_schema(), json!({})), ["kind: is required", "url: is required"]);
+        assert_eq!(errors(&config_schema(), json!([])), ["$: expected object"]);
+    }
+
+    #[test]
+    fn checks_integers_and_minimum_edges() {
+        let schema = config_schema();
+        let config = |page_size: Value| {
+            json!({ "kind": "REST_API", "url": "https://api.example.com", "page_size": page_size })
+        };
+
+        assert!(errors(&schema, config(json!(1))).is_empty());
+        assert_eq!(errors(&schema, config(json!(1.5))), ["page_size: expected integer"]);
+        assert_eq!(errors(&schema, config(json!(-1))), ["page_size: must be at least 1"]);
+    }
+
+    #[test]
+    fn rejects_additional_properties_when_false() {
+        let schema = json!({
+            "type": "object",
+            "properties": { "name": { "type": "string" } },
+            "additionalProperties": false,
+        });
+
+        assert!(errors(&schema, json!({ "name": "orders" })).is_empty());
+        assert_eq!(errors(&schema, json!({ "name": "orders", "extra": 1 })), ["extra: is not allowed"]);
+        assert!(errors(&json!({ "additionalProperties": true }), json!({ "extra": 1 })).is_empty());
+    }
+}
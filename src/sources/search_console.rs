@@ -0,0 +1,293 @@
+use async_trait::async_trait;
+use duckdb::types::Value;
+use serde_json::json;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use super::google::fresh_access_token;
+use super::{AuthKind, Source, Stream, StreamSyncResult, SyncRequest, SyncResult, parse_config, save_config};
+use crate::api::error::AppError;
+use crate::models::connector::{Connector, ConnectorDetails, ConnectorType, OAuthTokens};
+use crate::services::search_console_service::{self, SearchConsoleRecord, Site};
+use crate::services::storage_service::{self, StorageResult, TableSchema};
+use crate::services::ga4_service;
+use crate::AppState;
+
+pub const SCOPES: [&str; 1] = ["https://www.googleapis.com/auth/webmasters.readonly"];
+
+const STORE_FILE: &str = "search_console.duckdb";
+const TABLE: &str = "search_console_records";
+
+pub struct SearchConsoleSource {
+    base_url: String,
+}
+
+impl SearchConsoleSource {
+    /// Uses `SEARCH_CONSOLE_API_BASE_URL` when set, e.g. to point at a mock server.
+    pub fn from_env() -> Self {
+        Self {
+            base_url: std::env::var("SEARCH_CONSOLE_API_BASE_URL")
+                .unwrap_or_else(|_| search_console_service::DEFAULT_API_BASE_URL.to_string()),
+        }
+    }
+
+    /// Sites the account can read data of; unverified ones have no search analytics.
+    async fn readable_sites(&self, access_token: &str) -> Result<Vec<Site>, AppError> {
+        let sites = search_console_service::list_sites(&self.base_url, access_token)
+            .await
+            .map_err(AppError::internal)?;
+
+        Ok(sites
+            .into_iter()
+            .filter(|site| site.permission_level != "siteUnverifiedUser")
+            .collect())
+    }
+}
+
+#[async_trait]
+impl Source for SearchConsoleSource {
+    fn connector_type(&self) -> ConnectorType {
+        ConnectorType::SearchConsole
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Search Console"
+    }
+
+    fn auth_kind(&self) -> AuthKind {
+        AuthKind::GoogleOAuth {
+            scopes: SCOPES.to_vec(),
+        }
+    }
+
+    fn oauth_config(&self, tokens: OAuthTokens) -> Option<ConnectorDetails> {
+        Some(ConnectorDetails::SearchConsole {
+            tokens,
+            sites: Vec::new(),
+        })
+    }
+
+    fn config_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "required": ["type", "access_token", "token_type"],
+            "properties": {
+                "type": { "const": "SearchConsole" },
+                "access_token": { "type": "string" },
+                "refresh_token": { "type": ["string", "null"] },
+                "expires_at": { "type": ["string", "null"], "format": "date-time" },
+                "token_type": { "type": "string" },
+                "sites": {
+                    "type": "array",
+                    "items": { "type": "string", "minLength": 1 }
+                }
+            }
+        })
+    }
+
+    fn storage_schema(&self) -> Vec<TableSchema> {
+        vec![TableSchema::new(
+            TABLE,
+            &[
+                ("site_url", "VARCHAR"),
+                ("date", "DATE"),
+                ("query", "VARCHAR"),
+                ("page", "VARCHAR"),
+                ("country", "VARCHAR"),
+                ("device", "VARCHAR"),
+                ("clicks", "BIGINT"),
+                ("impressions", "BIGINT"),
+                ("ctr", "DOUBLE"),
+                ("position", "DOUBLE"),
+            ],
+            &["site_url", "date", "query", "page", "country", "device"],
+        )]
+    }
+
+    async fn discover(&self, state: &AppState, connector: &Connector) -> Result<Vec<Stream>, AppError> {
+        let access_token = fresh_access_token(state, connector).await?;
+        let selected = selected_sites(connector)?;
+
+        let streams = self
+            .readable_sites(&access_token)
+            .await?
+            .into_iter()
+            .map(|site| Stream {
+                selected: selected.contains(&site.site_url),
+                name: site.site_url.clone(),
+                id: site.site_url,
+            })
+            .collect();
+
+        Ok(streams)
+    }
+
+    async fn select_streams(
+        &self,
+        state: &AppState,
+        connector: &Connector,
+        stream_ids: Vec<String>,
+    ) -> Result<Vec<Stream>, AppError> {
+        let access_token = fresh_access_token(state, connector).await?;
+        let accessible = self.readable_sites(&access_token).await?;
+
+        let mut sites: Vec<String> = Vec::with_capacity(stream_ids.len());
+        for site_url in stream_ids {
+            if !accessible.iter().any(|s| s.site_url == site_url) {
+                warn!(site_url = %site_url, "Site not accessible");
+                return Err(AppError::bad_request(format!(
+                    "Site {} is not accessible with this Google account",
+                    site_url
+                )));
+            }
+            if !sites.contains(&site_url) {
+                sites.push(site_url);
+            }
+        }
+
+        // Reload the connector, the token may have been refreshed above
+        let connector = state
+            .connector_repo
+            .find_by_id(connector.id)
+            .await?
+            .ok_or_else(|| AppError::not_found("Connector not found"))?;
+
+        let mut config = parse_config(&connector)?;
+        if let ConnectorDetails::SearchConsole { sites: selected, .. } = &mut config {
+            selected.clone_from(&sites);
+        }
+        save_config(state, &connector, &config).await?;
+
+        info!(count = sites.len(), "Sites selected successfully");
+        Ok(sites
+            .into_iter()
+            .map(|site_url| Stream {
+                name: site_url.clone(),
+                id: site_url,
+                selected: true,
+            })
+            .collect())
+    }
+
+    async fn sync(
+        &self,
+        state: &AppState,
+        connector: &Connector,
+        request: SyncRequest,
+    ) -> Result<SyncResult, AppError> {
+        let project_id = connector.project_id;
+        let connector_id = connector.id;
+
+        let access_token = fresh_access_token(state, connector).await?;
+        let sites = selected_sites(connector)?;
+
+        if sites.is_empty() {
+            warn!("No site selected");
+            return Err(AppError::bad_request("No Search Console site selected. Please select a site first."));
+        }
+
+        let sites = match request.streams {
+            Some(site_urls) => {
+                if let Some(unknown) = site_urls.iter().find(|url| !sites.contains(url)) {
+                    warn!(site_url = %unknown, "Site not selected on connector");
+                    return Err(AppError::bad_request(format!(
+                        "Site {} is not selected on this connector",
+                        unknown
+                    )));
+                }
+                site_urls
+            }
+            None => sites,
+        };
+
+        let schema = self.storage_schema().remove(0);
+        let today = ga4_service::today_in(Some(search_console_service::TIME_ZONE));
+        let mut results = Vec::with_capacity(sites.len());
+
+        for site_url in sites {
+            let start_date = request.start_date.unwrap_or_else(|| {
+                let max_date = storage_service::max_date(
+                    project_id,
+                    connector_id,
+                    STORE_FILE,
+                    TABLE,
+                    "date",
+                    &[("site_url", site_url.as_str())],
+                );
+                storage_service::incremental_start_date(max_date, today)
+            });
+
+            debug!(site_url = %site_url, start_date = %start_date, "Pulling data for site");
+
+            let records =
+                search_console_service::pull_days(&self.base_url, &access_token, &site_url, start_date, today)
+                    .await
+                    .map_err(AppError::internal)?;
+            let stored = store(project_id, connector_id, schema.clone(), records).await?;
+
+            info!(site_url = %site_url, record_count = stored.record_count, "Site pulled");
+            results.push(StreamSyncResult {
+                stream: site_url,
+                start_date: Some(start_date),
+                currency_code: None,
+                record_count: stored.record_count,
+                inserted_count: stored.inserted_count,
+                updated_count: stored.updated_count,
+            });
+        }
+
+        Ok(SyncResult::from_streams(results))
+    }
+}
+
+fn selected_sites(connector: &Connector) -> Result<Vec<String>, AppError> {
+    match parse_config(connector)? {
+        ConnectorDetails::SearchConsole { sites, .. } => Ok(sites),
+        _ => Err(AppError::bad_request("Connector is not a Search Console connector")),
+    }
+}
+
+/// Upserts the records of a site in one write, on the blocking pool.
+async fn store(
+    project_id: Uuid,
+    connector_id: Uuid,
+    schema: TableSchema,
+    records: Vec<SearchConsoleRecord>,
+) -> Result<StorageResult, AppError> {
+    tokio::task::spawn_blocking(move || write_records(project_id, connector_id, &schema, records))
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Search Console store task failed");
+            AppError::internal("Search Console store task failed")
+        })?
+        .map_err(AppError::internal)
+}
+
+fn write_records(
+    project_id: Uuid,
+    connector_id: Uuid,
+    schema: &TableSchema,
+    records: Vec<SearchConsoleRecord>,
+) -> Result<StorageResult, String> {
+    let conn = storage_service::open_store(project_id, connector_id, STORE_FILE)?;
+
+    let rows = records
+        .into_iter()
+        .map(|r| {
+            vec![
+                Value::Text(r.site_url),
+                storage_service::date_value(r.date),
+                Value::Text(r.query),
+                Value::Text(r.page),
+                Value::Text(r.country),
+                Value::Text(r.device),
+                Value::BigInt(r.clicks),
+                Value::BigInt(r.impressions),
+                Value::Double(r.ctr),
+                Value::Double(r.position),
+            ]
+        })
+        .collect();
+
+    storage_service::upsert_rows(&conn, schema, rows)
+}
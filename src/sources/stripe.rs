@@ -0,0 +1,268 @@
+use async_trait::async_trait;
+use serde_json::json;
+use tracing::{debug, error, info, warn};
+
+use super::{
+    AuthKind, Source, Stream, StreamSyncResult, SyncRequest, SyncResult, decrypt_secret, encrypt_secret, parse_config,
+};
+use crate::api::error::AppError;
+use crate::models::connector::{Connector, ConnectorDetails, ConnectorType};
+use crate::services::storage_service::{self, TableSchema};
+use crate::services::stripe_service;
+use crate::AppState;
+
+const STORE_FILE: &str = "stripe.duckdb";
+
+/// Runs DuckDB work off the async runtime.
+async fn run_blocking<T: Send + 'static>(
+    work: impl FnOnce() -> Result<T, AppError> + Send + 'static,
+) -> Result<T, AppError> {
+    tokio::task::spawn_blocking(work).await.map_err(|e| {
+        error!(error = %e, "Stripe store task failed");
+        AppError::internal("Stripe store task failed")
+    })?
+}
+
+/// A Stripe collection stored in its own table keyed by object id.
+struct Collection {
+    name: &'static str,
+    table: &'static str,
+    /// `(column, DuckDB type)`, read from the object field of the same name.
+    fields: &'static [(&'static str, &'static str)],
+}
+
+const COLLECTIONS: [Collection; 4] = [
+    Collection {
+        name: "charges",
+        table: "stripe_charges",
+        fields: &[
+            ("id", "VARCHAR"),
+            ("created", "TIMESTAMP"),
+            ("customer", "VARCHAR"),
+            ("invoice", "VARCHAR"),
+            ("payment_intent", "VARCHAR"),
+            ("amount", "BIGINT"),
+            ("amount_captured", "BIGINT"),
+            ("amount_refunded", "BIGINT"),
+            ("currency", "VARCHAR"),
+            ("status", "VARCHAR"),
+            ("paid", "BOOLEAN"),
+            ("refunded", "BOOLEAN"),
+            ("description", "VARCHAR"),
+            ("metadata", "VARCHAR"),
+        ],
+    },
+    Collection {
+        name: "invoices",
+        table: "stripe_invoices",
+        fields: &[
+            ("id", "VARCHAR"),
+            ("created", "TIMESTAMP"),
+            ("customer", "VARCHAR"),
+            ("subscription", "VARCHAR"),
+            ("status", "VARCHAR"),
+            ("currency", "VARCHAR"),
+            ("subtotal", "BIGINT"),
+            ("total", "BIGINT"),
+            ("amount_due", "BIGINT"),
+            ("amount_paid", "BIGINT"),
+            ("amount_remaining", "BIGINT"),
+            ("period_start", "TIMESTAMP"),
+            ("period_end", "TIMESTAMP"),
+            ("metadata", "VARCHAR"),
+        ],
+    },
+    Collection {
+        name: "subscriptions",
+        table: "stripe_subscriptions",
+        fields: &[
+            ("id", "VARCHAR"),
+            ("created", "TIMESTAMP"),
+            ("customer", "VARCHAR"),
+            ("status", "VARCHAR"),
+            ("currency", "VARCHAR"),
+            ("current_period_start", "TIMESTAMP"),
+            ("current_period_end", "TIMESTAMP"),
+            ("cancel_at_period_end", "BOOLEAN"),
+            ("canceled_at", "TIMESTAMP"),
+            ("ended_at", "TIMESTAMP"),
+            ("metadata", "VARCHAR"),
+        ],
+    },
+    Collection {
+        name: "customers",
+        table: "stripe_customers",
+        fields: &[
+            ("id", "VARCHAR"),
+            ("created", "TIMESTAMP"),
+            ("email", "VARCHAR"),
+            ("name", "VARCHAR"),
+            ("currency", "VARCHAR"),
+            ("delinquent", "BOOLEAN"),
+            ("metadata", "VARCHAR"),
+        ],
+    },
+];
+
+impl Collection {
+    fn schema(&self) -> TableSchema {
+        TableSchema::new(self.table, self.fields, &["id"])
+    }
+}
+
+pub struct StripeSource {
+    base_url: String,
+}
+
+impl StripeSource {
+    /// Uses `STRIPE_API_BASE_URL` when set, e.g. to point at stripe-mock.
+    pub fn from_env() -> Self {
+        Self {
+            base_url: std::env::var("STRIPE_API_BASE_URL")
+                .unwrap_or_else(|_| stripe_service::DEFAULT_API_BASE_URL.to_string()),
+        }
+    }
+
+    /// The connector config overrides the server-wide base URL.
+    fn base_url<'a>(&'a self, config_base_url: &'a Option<String>) -> &'a str {
+        config_base_url.as_deref().unwrap_or(&self.base_url)
+    }
+}
+
+fn stripe_config(connector: &Connector) -> Result<(String, Option<String>), AppError> {
+    match parse_config(connector)? {
+        ConnectorDetails::Stripe { api_key, base_url } => Ok((decrypt_secret(&api_key)?, base_url)),
+        _ => Err(AppError::bad_request("Connector is not a Stripe connector")),
+    }
+}
+
+#[async_trait]
+impl Source for StripeSource {
+    fn connector_type(&self) -> ConnectorType {
+        ConnectorType::Stripe
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Stripe"
+    }
+
+    fn auth_kind(&self) -> AuthKind {
+        AuthKind::Config
+    }
+
+    fn config_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "required": ["type", "api_key"],
+            "properties": {
+                "type": { "const": "Stripe" },
+                "api_key": { "type": "string", "pattern": "^((sk|rk)_(test|live)_[A-Za-z0-9]+|enc:v1:.+)$" },
+                "base_url": { "type": ["string", "null"], "pattern": "^https?://" }
+            }
+        })
+    }
+
+    fn prepare_config(&self, mut config: serde_json::Value) -> Result<serde_json::Value, AppError> {
+        encrypt_secret(&mut config, "/api_key")?;
+        Ok(config)
+    }
+
+    fn storage_schema(&self) -> Vec<TableSchema> {
+        COLLECTIONS.iter().map(Collection::schema).collect()
+    }
+
+    async fn discover(&self, _state: &AppState, connector: &Connector) -> Result<Vec<Stream>, AppError> {
+        let (api_key, base_url) = stripe_config(connector)?;
+
+        stripe_service::verify_key(self.base_url(&base_url), &api_key)
+            .await
+            .map_err(|e| {
+                warn!(error = %e, "Stripe key check failed");
+                AppError::bad_request("Stripe rejected the API key")
+            })?;
+
+        Ok(COLLECTIONS
+            .iter()
+            .map(|c| Stream {
+                id: c.name.to_string(),
+                name: c.name.to_string(),
+                selected: true,
+            })
+            .collect())
+    }
+
+    async fn sync(
+        &self,
+        _state: &AppState,
+        connector: &Connector,
+        request: SyncRequest,
+    ) -> Result<SyncResult, AppError> {
+        let project_id = connector.project_id;
+        let connector_id = connector.id;
+
+        let (api_key, base_url) = stripe_config(connector)?;
+        let base_url = self.base_url(&base_url);
+
+        let collections: Vec<&Collection> = match &request.streams {
+            Some(names) => {
+                if let Some(unknown) = names.iter().find(|n| !COLLECTIONS.iter().any(|c| c.name == n.as_str())) {
+                    warn!(stream = %unknown, "Unknown Stripe stream");
+                    return Err(AppError::bad_request(format!("Unknown Stripe stream {}", unknown)));
+                }
+                COLLECTIONS.iter().filter(|c| names.iter().any(|n| n == c.name)).collect()
+            }
+            None => COLLECTIONS.iter().collect(),
+        };
+
+        let open_store =
+            move || storage_service::open_store(project_id, connector_id, STORE_FILE).map_err(AppError::internal);
+
+        let mut results = Vec::with_capacity(collections.len());
+
+        for collection in collections {
+            // Objects created at the cursor second are fetched again, the upsert dedups them
+            let created_gte = match request.start_date {
+                Some(start_date) => Some(start_date.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp()),
+                None => {
+                    let table = collection.table;
+                    run_blocking(move || Ok(storage_service::max_epoch(&open_store()?, table, "created"))).await?
+                }
+            };
+
+            debug!(collection = %collection.name, created_gte = ?created_gte, "Pulling Stripe collection");
+
+            let objects = stripe_service::list_all(base_url, &api_key, collection.name, created_gte)
+                .await
+                .map_err(AppError::internal)?;
+
+            let rows: Vec<_> = objects
+                .iter()
+                .map(|object| {
+                    collection
+                        .fields
+                        .iter()
+                        .map(|(field, data_type)| storage_service::json_value(object.get(*field), data_type))
+                        .collect()
+                })
+                .collect();
+
+            let schema = collection.schema();
+            let stored = run_blocking(move || {
+                storage_service::upsert_rows(&open_store()?, &schema, rows).map_err(AppError::internal)
+            })
+            .await?;
+
+            info!(collection = %collection.name, record_count = stored.record_count, "Collection pulled");
+            results.push(StreamSyncResult {
+                stream: collection.name.to_string(),
+                start_date: request.start_date,
+                currency_code: None,
+                record_count: stored.record_count,
+                inserted_count: stored.inserted_count,
+                updated_count: stored.updated_count,
+            });
+        }
+
+        Ok(SyncResult::from_streams(results))
+    }
+}
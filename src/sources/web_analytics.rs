@@ -0,0 +1,198 @@
+//! Tables shared by the Plausible and Matomo sources. Column names and types follow
+//! `ga4_records` so the tools can be queried side by side.
+
+use chrono::NaiveDate;
+use duckdb::Connection;
+use duckdb::types::Value;
+use tracing::error;
+use uuid::Uuid;
+
+use super::countries;
+use crate::api::error::AppError;
+use crate::services::storage_service::{self, StorageResult, TableSchema};
+
+/// GA4's value for a missing dimension.
+const NOT_SET: &str = "(not set)";
+
+/// Dimensions broken down one at a time; GA4 column names.
+pub const BREAKDOWNS: [&str; 4] = ["country", "device_category", "browser", "operating_system"];
+
+const METRIC_COLUMNS: [(&str, &str); 5] = [
+    ("active_users", "BIGINT"),
+    ("sessions", "BIGINT"),
+    ("screen_page_views", "BIGINT"),
+    ("bounce_rate", "DOUBLE"),
+    ("average_session_duration", "DOUBLE"),
+];
+
+/// Metrics of a day or a dimension value, in GA4 terms.
+#[derive(Debug, Default, Clone)]
+pub struct Metrics {
+    /// Unique visitors.
+    pub active_users: i64,
+    /// Visits.
+    pub sessions: i64,
+    pub screen_page_views: i64,
+    /// Fraction of bounced visits, 0 to 1 like GA4.
+    pub bounce_rate: f64,
+    /// Seconds.
+    pub average_session_duration: f64,
+}
+
+/// One value of one of the `BREAKDOWNS` dimensions on a day.
+#[derive(Debug, Clone)]
+pub struct Breakdown {
+    pub dimension: &'static str,
+    pub value: String,
+    /// ISO country code of `country` rows, like GA4's `countryId`.
+    pub country_id: Option<String>,
+    pub metrics: Metrics,
+}
+
+impl Breakdown {
+    pub fn new(dimension: &'static str, value: String, metrics: Metrics) -> Self {
+        Self {
+            dimension,
+            value,
+            country_id: None,
+            metrics,
+        }
+    }
+
+    /// A `country` row from an ISO code, named as in GA4, e.g. `US` is `United States`.
+    pub fn country(code: &str, metrics: Metrics) -> Self {
+        let code = code.trim().to_ascii_uppercase();
+        let (value, country_id) = match countries::ga4_name(&code) {
+            Some(name) => (name.to_string(), code),
+            None if code.is_empty() || code == "XX" || code == "ZZ" => (NOT_SET.to_string(), NOT_SET.to_string()),
+            None => (code.clone(), code),
+        };
+
+        Self {
+            dimension: "country",
+            value,
+            country_id: Some(country_id),
+            metrics,
+        }
+    }
+}
+
+/// Daily totals, one row per site and date.
+pub fn totals_schema(table: &str) -> TableSchema {
+    let mut columns = vec![("site_id", "VARCHAR"), ("date", "VARCHAR")];
+    columns.extend(METRIC_COLUMNS);
+
+    TableSchema::new(table, &columns, &["site_id", "date"])
+}
+
+/// Daily breakdowns, one row per site, date and dimension value. `breakdown` names the
+/// dimension of the row; the other dimension columns are empty. `country_id` comes with
+/// `country`.
+pub fn breakdowns_schema(table: &str) -> TableSchema {
+    let mut columns = vec![("site_id", "VARCHAR"), ("date", "VARCHAR"), ("breakdown", "VARCHAR")];
+    columns.extend(BREAKDOWNS.iter().map(|d| (*d, "VARCHAR")));
+    columns.extend(METRIC_COLUMNS);
+    // Added after the initial schema, so last
+    columns.push(("country_id", "VARCHAR"));
+
+    let mut primary_key = vec!["site_id", "date", "breakdown"];
+    primary_key.extend(BREAKDOWNS);
+
+    TableSchema::new(table, &columns, &primary_key)
+}
+
+/// `YYYYMMDD`, the format of `ga4_records.date`.
+pub fn ga4_date(date: NaiveDate) -> String {
+    date.format("%Y%m%d").to_string()
+}
+
+fn metric_values(metrics: &Metrics) -> [Value; 5] {
+    [
+        Value::BigInt(metrics.active_users),
+        Value::BigInt(metrics.sessions),
+        Value::BigInt(metrics.screen_page_views),
+        Value::Double(metrics.bounce_rate),
+        Value::Double(metrics.average_session_duration),
+    ]
+}
+
+pub fn totals_row(site_id: &str, date: NaiveDate, metrics: &Metrics) -> Vec<Value> {
+    let mut row = vec![Value::Text(site_id.to_string()), Value::Text(ga4_date(date))];
+    row.extend(metric_values(metrics));
+    row
+}
+
+pub fn breakdown_row(site_id: &str, date: NaiveDate, breakdown: &Breakdown) -> Vec<Value> {
+    let mut row = vec![
+        Value::Text(site_id.to_string()),
+        Value::Text(ga4_date(date)),
+        Value::Text(breakdown.dimension.to_string()),
+    ];
+    row.extend(BREAKDOWNS.iter().map(|d| {
+        Value::Text(if *d == breakdown.dimension {
+            breakdown.value.clone()
+        } else {
+            String::new()
+        })
+    }));
+    row.extend(metric_values(&breakdown.metrics));
+    row.push(Value::Text(breakdown.country_id.clone().unwrap_or_default()));
+    row
+}
+
+/// GA4 device category of a device type, e.g. Matomo's `Smartphone` is `mobile`.
+pub fn device_category(device: &str) -> String {
+    match device.to_ascii_lowercase().as_str() {
+        "smartphone" | "phablet" | "feature phone" => "mobile".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Runs `work` on the connector's store, off the async runtime.
+pub async fn with_store<T: Send + 'static>(
+    project_id: Uuid,
+    connector_id: Uuid,
+    store_file: &'static str,
+    work: impl FnOnce(&Connection) -> Result<T, String> + Send + 'static,
+) -> Result<T, AppError> {
+    tokio::task::spawn_blocking(move || work(&storage_service::open_store(project_id, connector_id, store_file)?))
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Store task failed");
+            AppError::internal("Store task failed")
+        })?
+        .map_err(AppError::internal)
+}
+
+/// Incremental start date of a site, from the latest day in its totals table.
+pub fn start_date(conn: &Connection, table: &str, site_id: &str, today: NaiveDate) -> NaiveDate {
+    let max_date = conn
+        .query_row(
+            &format!("SELECT MAX(date) FROM {} WHERE site_id = ?", table),
+            [site_id],
+            |row| row.get::<_, Option<String>>(0),
+        )
+        .ok()
+        .flatten()
+        .and_then(|date| NaiveDate::parse_from_str(&date, "%Y%m%d").ok());
+
+    storage_service::incremental_start_date(max_date, today)
+}
+
+/// Upserts the daily totals and breakdown rows of a site, counting both.
+pub fn store(
+    conn: &Connection,
+    totals_table: &str,
+    breakdowns_table: &str,
+    totals: Vec<Vec<Value>>,
+    breakdowns: Vec<Vec<Value>>,
+) -> Result<StorageResult, String> {
+    let totals = storage_service::upsert_rows(conn, &totals_schema(totals_table), totals)?;
+    let breakdowns = storage_service::upsert_rows(conn, &breakdowns_schema(breakdowns_table), breakdowns)?;
+
+    Ok(StorageResult {
+        record_count: totals.record_count + breakdowns.record_count,
+        inserted_count: totals.inserted_count + breakdowns.inserted_count,
+        updated_count: totals.updated_count + breakdowns.updated_count,
+    })
+}